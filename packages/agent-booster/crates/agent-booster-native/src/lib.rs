@@ -1,12 +1,20 @@
 #![deny(clippy::all)]
 
-use agent_booster::{AgentBooster, Config, EditRequest, EditResult, Language};
+use agent_booster::patch::{self, Hunk, Patch};
+use agent_booster::workspace::Workspace;
+use agent_booster::{
+    AgentBooster, Config, EditRequest, EditResult, Language, MergeStrategy, TransactionEdit,
+    TransactionRequest, TransactionResult, WorkspaceCandidate, WorkspaceEditRequest,
+    WorkspaceEditResult,
+};
 use napi::bindgen_prelude::*;
 use napi_derive::napi;
 
 #[napi]
 pub struct AgentBoosterNative {
     inner: AgentBooster,
+    /// Index of the last workspace edited, refreshed instead of rebuilt
+    workspace: Option<Workspace>,
 }
 
 #[napi(object)]
@@ -15,6 +23,10 @@ pub struct JsConfig {
     pub confidence_threshold: Option<f64>,
     /// Maximum number of chunks to consider
     pub max_chunks: Option<u32>,
+    /// Include a unified diff and hunks in every edit result
+    pub include_patch: Option<bool>,
+    /// YAML or TOML files of extra transformation templates
+    pub template_files: Option<Vec<String>>,
 }
 
 impl From<JsConfig> for Config {
@@ -26,6 +38,12 @@ impl From<JsConfig> for Config {
         if let Some(max_chunks) = js_config.max_chunks {
             config.max_chunks = max_chunks as usize;
         }
+        if let Some(include_patch) = js_config.include_patch {
+            config.include_patch = include_patch;
+        }
+        if let Some(template_files) = js_config.template_files {
+            config.template_files = template_files.into_iter().map(Into::into).collect();
+        }
         config
     }
 }
@@ -68,6 +86,90 @@ pub struct JsEditResult {
     pub strategy: String,
     /// Metadata about the edit
     pub metadata: JsEditMetadata,
+    /// Unified diff of the edit, if `includePatch` is set
+    pub unified_diff: Option<String>,
+    /// Changed regions of the edit, if `includePatch` is set
+    pub hunks: Option<Vec<JsHunk>>,
+}
+
+#[napi(object)]
+pub struct JsHunk {
+    /// Byte range `[start, end)` of the original code that is replaced
+    pub original_range: Vec<u32>,
+    /// Lines `[start, end)` of the original code that are replaced (0-indexed)
+    pub original_lines: Vec<u32>,
+    /// Lines `[start, end)` of the merged code that replace them (0-indexed)
+    pub merged_lines: Vec<u32>,
+    /// Original text that is replaced
+    pub original_text: String,
+    /// Text that replaces it
+    pub replacement: String,
+    /// Unchanged lines just before the hunk
+    pub context_before: String,
+    /// Unchanged lines just after the hunk
+    pub context_after: String,
+    /// Node type of the chunk the edit matched, if it matched one
+    pub node_type: Option<String>,
+    /// Strategy used for the edit
+    pub strategy: String,
+}
+
+fn js_range(range: std::ops::Range<usize>) -> Vec<u32> {
+    vec![range.start as u32, range.end as u32]
+}
+
+fn range(js_range: &[u32]) -> Result<std::ops::Range<usize>> {
+    match js_range {
+        [start, end] if start <= end => Ok(*start as usize..*end as usize),
+        _ => Err(napi::Error::from_reason("ranges must be [start, end]")),
+    }
+}
+
+impl From<Hunk> for JsHunk {
+    fn from(hunk: Hunk) -> Self {
+        JsHunk {
+            original_range: js_range(hunk.original_range),
+            original_lines: js_range(hunk.original_lines),
+            merged_lines: js_range(hunk.merged_lines),
+            original_text: hunk.original_text,
+            replacement: hunk.replacement,
+            context_before: hunk.context_before,
+            context_after: hunk.context_after,
+            node_type: hunk.node_type,
+            strategy: hunk.strategy.as_str().to_string(),
+        }
+    }
+}
+
+impl TryFrom<JsHunk> for Hunk {
+    type Error = napi::Error;
+
+    fn try_from(js_hunk: JsHunk) -> Result<Self> {
+        let strategy = [
+            MergeStrategy::ExactReplace,
+            MergeStrategy::FuzzyReplace,
+            MergeStrategy::InsertAfter,
+            MergeStrategy::InsertBefore,
+            MergeStrategy::Append,
+        ]
+        .into_iter()
+        .find(|s| s.as_str() == js_hunk.strategy)
+        .ok_or_else(|| {
+            napi::Error::from_reason(format!("unknown strategy: {}", js_hunk.strategy))
+        })?;
+
+        Ok(Hunk {
+            original_range: range(&js_hunk.original_range)?,
+            original_lines: range(&js_hunk.original_lines)?,
+            merged_lines: range(&js_hunk.merged_lines)?,
+            original_text: js_hunk.original_text,
+            replacement: js_hunk.replacement,
+            context_before: js_hunk.context_before,
+            context_after: js_hunk.context_after,
+            node_type: js_hunk.node_type,
+            strategy,
+        })
+    }
 }
 
 #[napi(object)]
@@ -84,6 +186,14 @@ pub struct JsEditMetadata {
 
 impl From<EditResult> for JsEditResult {
     fn from(result: EditResult) -> Self {
+        let (unified_diff, hunks) = match result.patch {
+            Some(patch) => (
+                Some(patch.unified_diff),
+                Some(patch.hunks.into_iter().map(JsHunk::from).collect()),
+            ),
+            None => (None, None),
+        };
+
         JsEditResult {
             merged_code: result.merged_code,
             confidence: result.confidence as f64,
@@ -94,6 +204,206 @@ impl From<EditResult> for JsEditResult {
                 syntax_valid: result.metadata.syntax_valid,
                 processing_time_ms: result.metadata.processing_time_ms.map(|t| t as f64),
             },
+            unified_diff,
+            hunks,
+        }
+    }
+}
+
+#[napi(object)]
+pub struct JsBatchItem {
+    /// Edit result, if the edit succeeded
+    pub result: Option<JsEditResult>,
+    /// Why the edit failed
+    pub error: Option<String>,
+}
+
+impl From<std::result::Result<EditResult, String>> for JsBatchItem {
+    fn from(item: std::result::Result<EditResult, String>) -> Self {
+        match item {
+            Ok(result) => JsBatchItem {
+                result: Some(JsEditResult::from(result)),
+                error: None,
+            },
+            Err(error) => JsBatchItem {
+                result: None,
+                error: Some(error),
+            },
+        }
+    }
+}
+
+#[napi(object)]
+pub struct JsTransactionRequest {
+    /// Original source code
+    pub original_code: String,
+    /// Edit snippets to apply, in order
+    pub edit_snippets: Vec<String>,
+    /// Programming language ("javascript" or "typescript")
+    pub language: String,
+    /// Optional confidence threshold for every edit (0.0 - 1.0)
+    pub confidence_threshold: Option<f64>,
+}
+
+impl TryFrom<JsTransactionRequest> for TransactionRequest {
+    type Error = napi::Error;
+
+    fn try_from(js_req: JsTransactionRequest) -> Result<Self> {
+        let language = Language::from_str(&js_req.language)
+            .map_err(|e| napi::Error::from_reason(e.to_string()))?;
+
+        Ok(TransactionRequest {
+            original_code: js_req.original_code,
+            edit_snippets: js_req.edit_snippets,
+            language,
+            confidence_threshold: js_req.confidence_threshold.unwrap_or(0.5) as f32,
+        })
+    }
+}
+
+#[napi(object)]
+pub struct JsTransactionResult {
+    /// Code after applying every edit, or the original code
+    pub merged_code: String,
+    /// Whether all edits were applied
+    pub committed: bool,
+    /// Whether the combined result passed syntax validation; unset if the
+    /// transaction was rolled back before it was validated
+    pub syntax_valid: Option<bool>,
+    /// Report for each edit, in request order
+    pub edits: Vec<JsTransactionEdit>,
+}
+
+#[napi(object)]
+pub struct JsTransactionEdit {
+    /// Position of the snippet in the request
+    pub index: u32,
+    /// Strategy the edit was merged with, if it matched
+    pub strategy: Option<String>,
+    /// Confidence score (0.0 - 1.0)
+    pub confidence: f64,
+    /// Byte range `[start, end)` of the original code the edit rewrites
+    pub original_range: Option<Vec<u32>>,
+    /// Byte range `[start, end)` of the replacement in the merged code
+    pub merged_range: Option<Vec<u32>>,
+    /// Why the edit could not be applied
+    pub error: Option<String>,
+}
+
+impl From<TransactionEdit> for JsTransactionEdit {
+    fn from(edit: TransactionEdit) -> Self {
+        let range = |r: std::ops::Range<usize>| vec![r.start as u32, r.end as u32];
+        JsTransactionEdit {
+            index: edit.index as u32,
+            strategy: edit.strategy.map(|s| s.as_str().to_string()),
+            confidence: edit.confidence as f64,
+            original_range: edit.original_range.map(range),
+            merged_range: edit.merged_range.map(range),
+            error: edit.error,
+        }
+    }
+}
+
+impl From<TransactionResult> for JsTransactionResult {
+    fn from(result: TransactionResult) -> Self {
+        JsTransactionResult {
+            merged_code: result.merged_code,
+            committed: result.committed,
+            syntax_valid: result.syntax_valid,
+            edits: result.edits.into_iter().map(JsTransactionEdit::from).collect(),
+        }
+    }
+}
+
+#[napi(object)]
+pub struct JsWorkspaceEditRequest {
+    /// Edit snippet to apply
+    pub edit_snippet: String,
+    /// Free-form description of the target, e.g. "the user service"
+    pub target_hint: Option<String>,
+    /// Only consider files in this language
+    pub language: Option<String>,
+    /// Optional confidence threshold (0.0 - 1.0)
+    pub confidence_threshold: Option<f64>,
+}
+
+impl TryFrom<JsWorkspaceEditRequest> for WorkspaceEditRequest {
+    type Error = napi::Error;
+
+    fn try_from(js_req: JsWorkspaceEditRequest) -> Result<Self> {
+        let language = js_req
+            .language
+            .map(|l| Language::from_str(&l))
+            .transpose()
+            .map_err(|e| napi::Error::from_reason(e.to_string()))?;
+
+        Ok(WorkspaceEditRequest {
+            edit_snippet: js_req.edit_snippet,
+            target_hint: js_req.target_hint,
+            language,
+            confidence_threshold: js_req.confidence_threshold.unwrap_or(0.5) as f32,
+        })
+    }
+}
+
+#[napi(object)]
+pub struct JsWorkspaceEditResult {
+    /// File the edit was applied to, relative to the workspace root
+    pub path: Option<String>,
+    /// The edit, applied to that file's code (the file itself is not written)
+    pub edit: Option<JsEditResult>,
+    /// Whether several files matched about equally well
+    pub ambiguous: bool,
+    /// Best locations for the snippet, best first
+    pub candidates: Vec<JsWorkspaceCandidate>,
+}
+
+#[napi(object)]
+pub struct JsWorkspaceCandidate {
+    /// File path, relative to the workspace root
+    pub path: String,
+    /// Language of the file
+    pub language: String,
+    /// Node type of the matched chunk
+    pub node_type: String,
+    /// Line range `[start, end]` of the chunk (0-indexed, inclusive)
+    pub lines: Vec<u32>,
+    /// Similarity of the chunk to the snippet (0.0 - 1.0)
+    pub similarity: f64,
+    /// How well the path matches the target hint (0.0 - 1.0)
+    pub path_score: f64,
+    /// Combined ranking score (0.0 - 1.0)
+    pub score: f64,
+}
+
+impl From<WorkspaceCandidate> for JsWorkspaceCandidate {
+    fn from(candidate: WorkspaceCandidate) -> Self {
+        JsWorkspaceCandidate {
+            path: candidate.path.to_string_lossy().into_owned(),
+            language: candidate.language.as_str().to_string(),
+            node_type: candidate.chunk.node_type,
+            lines: vec![
+                candidate.chunk.start_line as u32,
+                candidate.chunk.end_line as u32,
+            ],
+            similarity: candidate.similarity as f64,
+            path_score: candidate.path_score as f64,
+            score: candidate.score as f64,
+        }
+    }
+}
+
+impl From<WorkspaceEditResult> for JsWorkspaceEditResult {
+    fn from(result: WorkspaceEditResult) -> Self {
+        JsWorkspaceEditResult {
+            path: result.path.map(|p| p.to_string_lossy().into_owned()),
+            edit: result.edit.map(JsEditResult::from),
+            ambiguous: result.ambiguous,
+            candidates: result
+                .candidates
+                .into_iter()
+                .map(JsWorkspaceCandidate::from)
+                .collect(),
         }
     }
 }
@@ -107,7 +417,10 @@ impl AgentBoosterNative {
         let inner = AgentBooster::new(config)
             .map_err(|e| napi::Error::from_reason(e.to_string()))?;
 
-        Ok(Self { inner })
+        Ok(Self {
+            inner,
+            workspace: None,
+        })
     }
 
     /// Apply a single edit to code
@@ -141,6 +454,84 @@ impl AgentBoosterNative {
         Ok(results.into_iter().map(JsEditResult::from).collect())
     }
 
+    /// Apply multiple edits on all cores
+    ///
+    /// Returns one item per request, in order; a failed edit only fails its own item.
+    #[napi]
+    pub fn batch_apply_parallel(&self, requests: Vec<JsEditRequest>) -> Vec<JsBatchItem> {
+        let mut items: Vec<Option<std::result::Result<EditResult, String>>> = Vec::new();
+        let mut edit_requests = Vec::with_capacity(requests.len());
+        for request in requests {
+            match EditRequest::try_from(request) {
+                Ok(request) => {
+                    edit_requests.push(request);
+                    items.push(None);
+                }
+                Err(e) => items.push(Some(Err(e.reason))),
+            }
+        }
+
+        let mut results = self.inner.batch_apply_parallel(edit_requests).into_iter();
+        items
+            .into_iter()
+            .map(|item| {
+                let item = item.unwrap_or_else(|| {
+                    results
+                        .next()
+                        .expect("one result per valid request")
+                        .map_err(|e| e.to_string())
+                });
+                JsBatchItem::from(item)
+            })
+            .collect()
+    }
+
+    /// Apply an edit to the best matching file under `root`
+    ///
+    /// Ambiguous matches are not applied; the result lists the candidates instead.
+    /// The index of `root` is kept between calls and only changed files are
+    /// read again.
+    #[napi]
+    pub fn apply_workspace_edit(
+        &mut self,
+        root: String,
+        request: JsWorkspaceEditRequest,
+    ) -> Result<JsWorkspaceEditResult> {
+        let request = WorkspaceEditRequest::try_from(request)?;
+        let indexed = match self.workspace.take() {
+            Some(mut workspace) if workspace.root() == std::path::Path::new(&root) => {
+                workspace.refresh().map(|_| workspace)
+            }
+            _ => Workspace::index(&root),
+        };
+        let workspace = self
+            .workspace
+            .insert(indexed.map_err(|e| napi::Error::from_reason(e.to_string()))?);
+
+        let result = self
+            .inner
+            .apply_workspace_edit(workspace, request)
+            .map_err(|e| napi::Error::from_reason(e.to_string()))?;
+
+        Ok(JsWorkspaceEditResult::from(result))
+    }
+
+    /// Apply several edits to one file, all or nothing
+    #[napi]
+    pub fn apply_transaction(
+        &mut self,
+        request: JsTransactionRequest,
+    ) -> Result<JsTransactionResult> {
+        let transaction = TransactionRequest::try_from(request)?;
+
+        let result = self
+            .inner
+            .apply_transaction(transaction)
+            .map_err(|e| napi::Error::from_reason(e.to_string()))?;
+
+        Ok(JsTransactionResult::from(result))
+    }
+
     /// Get the current configuration
     #[napi]
     pub fn get_config(&self) -> JsConfig {
@@ -148,13 +539,23 @@ impl AgentBoosterNative {
         JsConfig {
             confidence_threshold: Some(config.confidence_threshold as f64),
             max_chunks: Some(config.max_chunks as u32),
+            include_patch: Some(config.include_patch),
+            template_files: Some(
+                config
+                    .template_files
+                    .iter()
+                    .map(|p| p.to_string_lossy().into_owned())
+                    .collect(),
+            ),
         }
     }
 
     /// Update the configuration
     #[napi]
-    pub fn set_config(&mut self, config: JsConfig) {
-        self.inner.set_config(Config::from(config));
+    pub fn set_config(&mut self, config: JsConfig) -> Result<()> {
+        self.inner
+            .set_config(Config::from(config))
+            .map_err(|e| napi::Error::from_reason(e.to_string()))
     }
 }
 
@@ -174,6 +575,23 @@ pub fn apply_edit_simple(
     })
 }
 
+/// Apply the hunks of an edit result to a copy of the original code
+///
+/// The copy may have drifted; hunks are placed by their context.
+#[napi]
+pub fn apply_patch(code: String, hunks: Vec<JsHunk>) -> Result<String> {
+    let hunks = hunks
+        .into_iter()
+        .map(Hunk::try_from)
+        .collect::<Result<Vec<_>>>()?;
+    let patch = Patch {
+        unified_diff: String::new(),
+        hunks,
+    };
+
+    patch::apply_patch(&code, &patch).map_err(|e| napi::Error::from_reason(e.to_string()))
+}
+
 /// Get version information
 #[napi]
 pub fn version() -> String {
@@ -202,6 +202,18 @@ impl WasmEditResult {
         self.inner.metadata.processing_time_ms
     }
 
+    /// Unified diff of the edit, if the config enables `include_patch`
+    #[wasm_bindgen(getter)]
+    pub fn unified_diff(&self) -> Option<String> {
+        self.inner.patch.as_ref().map(|p| p.unified_diff.clone())
+    }
+
+    /// Apply this edit to a copy of the original code that may have drifted
+    pub fn apply_to(&self, code: &str) -> Result<String, JsValue> {
+        self.inner.apply_to(code)
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
     /// Convert to JSON string
     pub fn to_json(&self) -> Result<String, JsValue> {
         serde_json::to_string(&self.inner)
@@ -245,6 +257,16 @@ impl WasmConfig {
         self.inner.max_chunks = max;
     }
 
+    #[wasm_bindgen(getter)]
+    pub fn include_patch(&self) -> bool {
+        self.inner.include_patch
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_include_patch(&mut self, include: bool) {
+        self.inner.include_patch = include;
+    }
+
     /// Create from JSON string
     pub fn from_json(json: &str) -> Result<WasmConfig, JsValue> {
         let inner: Config = serde_json::from_str(json)
@@ -269,7 +291,6 @@ impl Default for WasmConfig {
 #[wasm_bindgen]
 pub struct AgentBoosterWasm {
     booster: agent_booster::AgentBooster,
-    config: Config,
 }
 
 #[wasm_bindgen]
@@ -277,25 +298,18 @@ impl AgentBoosterWasm {
     /// Create a new AgentBooster instance with default config
     #[wasm_bindgen(constructor)]
     pub fn new() -> Result<AgentBoosterWasm, JsValue> {
-        let config = Config::default();
-        let booster = agent_booster::AgentBooster::new(config.clone())
+        let booster = agent_booster::AgentBooster::new(Config::default())
             .map_err(|e| JsValue::from_str(&e.to_string()))?;
 
-        Ok(Self {
-            booster,
-            config,
-        })
+        Ok(Self { booster })
     }
 
     /// Create with custom config
     pub fn with_config(config: WasmConfig) -> Result<AgentBoosterWasm, JsValue> {
-        let booster = agent_booster::AgentBooster::new(config.inner.clone())
+        let booster = agent_booster::AgentBooster::new(config.inner)
             .map_err(|e| JsValue::from_str(&e.to_string()))?;
 
-        Ok(Self {
-            booster,
-            config: config.inner,
-        })
+        Ok(Self { booster })
     }
 
     /// Parse a language string into WasmLanguage
@@ -323,7 +337,7 @@ impl AgentBoosterWasm {
             original_code: original_code.to_string(),
             edit_snippet: edit_snippet.to_string(),
             language: language.into(),
-            confidence_threshold: self.config.confidence_threshold,
+            confidence_threshold: self.booster.config().confidence_threshold,
         };
 
         let result = self.booster.apply_edit(request)
@@ -350,13 +364,17 @@ impl AgentBoosterWasm {
     /// Get current configuration
     pub fn get_config(&self) -> WasmConfig {
         WasmConfig {
-            inner: self.config.clone(),
+            inner: self.booster.config().clone(),
         }
     }
 
     /// Update configuration
-    pub fn set_config(&mut self, config: WasmConfig) {
-        self.config = config.inner;
+    ///
+    /// On error the previous configuration is kept.
+    pub fn set_config(&mut self, config: WasmConfig) -> Result<(), JsValue> {
+        self.booster
+            .set_config(config.inner)
+            .map_err(|e| JsValue::from_str(&e.to_string()))
     }
 
     /// Get library version
@@ -431,11 +449,29 @@ mod tests {
 
     #[wasm_bindgen_test]
     fn test_agent_booster_creation() {
-        let booster = AgentBoosterWasm::new();
+        let booster = AgentBoosterWasm::new().unwrap();
         let config = booster.get_config();
         assert_eq!(config.confidence_threshold(), 0.5);
     }
 
+    #[wasm_bindgen_test]
+    fn test_set_config_reaches_booster() {
+        let mut booster = AgentBoosterWasm::new().unwrap();
+        let mut config = WasmConfig::new();
+        config.set_include_patch(true);
+        booster.set_config(config).unwrap();
+        assert!(booster.get_config().include_patch());
+
+        let result = booster
+            .apply_edit(
+                "function foo() {\n    return 1;\n}\n",
+                "function foo() {\n    return 2;\n}",
+                WasmLanguage::JavaScript,
+            )
+            .unwrap();
+        assert!(result.unified_diff().is_some());
+    }
+
     #[wasm_bindgen_test]
     fn test_parse_language() {
         let result = AgentBoosterWasm::parse_language("javascript");
@@ -0,0 +1,59 @@
+use agent_booster::{AgentBooster, EditRequest, Language};
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+
+/// One edit per "file", each file holding a few functions
+fn requests(count: usize) -> Vec<EditRequest> {
+    (0..count)
+        .map(|i| {
+            let original_code = (0..8)
+                .map(|f| {
+                    format!(
+                        "function handler{f}(input) {{\n    const value = input * {i};\n    return value + {f};\n}}\n"
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            EditRequest {
+                original_code,
+                edit_snippet: format!(
+                    "function handler{f}(input) {{\n    const value = input * {i};\n    if (value < 0) throw new Error('negative');\n    return value + {f};\n}}",
+                    f = i % 8
+                ),
+                language: Language::JavaScript,
+                confidence_threshold: 0.5,
+            }
+        })
+        .collect()
+}
+
+fn bench_batch(c: &mut Criterion) {
+    let mut group = c.benchmark_group("batch_apply");
+
+    for count in [16, 128] {
+        let batch = requests(count);
+        group.throughput(Throughput::Elements(count as u64));
+
+        let mut booster = AgentBooster::default();
+        group.bench_function(format!("sequential/{count}"), |b| {
+            b.iter_batched(
+                || batch.clone(),
+                |batch| black_box(booster.batch_apply(batch)),
+                BatchSize::SmallInput,
+            )
+        });
+
+        let booster = AgentBooster::default();
+        group.bench_function(format!("parallel/{count}"), |b| {
+            b.iter_batched(
+                || batch.clone(),
+                |batch| black_box(booster.batch_apply_parallel(batch)),
+                BatchSize::SmallInput,
+            )
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_batch);
+criterion_main!(benches);
@@ -0,0 +1,296 @@
+//! Line-level alignment between an edit snippet and existing code
+//!
+//! [`align_lines`] pairs up similar lines in order (a weighted longest
+//! common subsequence), which is what fuzzy replacement uses to find the
+//! region of a chunk a snippet rewrites. The indentation helpers move
+//! snippet lines onto the target's indentation, converting between tabs and
+//! indent widths.
+
+use std::collections::HashMap;
+use strsim::normalized_levenshtein;
+
+/// Minimum similarity for two lines to be paired
+pub const LINE_MATCH_THRESHOLD: f64 = 0.5;
+
+/// Minimum similarity for a line next to aligned lines to still correspond
+pub const EDGE_MATCH_THRESHOLD: f64 = 0.3;
+
+/// Largest similarity matrix aligned optimally; bigger inputs only pair
+/// identical lines
+const MAX_ALIGN_CELLS: usize = 250_000;
+
+/// A pair of aligned lines
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LinePair {
+    /// Line index in the first sequence
+    pub a: usize,
+    /// Line index in the second sequence
+    pub b: usize,
+    /// Similarity of the two lines (0.0 - 1.0)
+    pub similarity: f32,
+}
+
+/// Similarity of two lines, ignoring indentation; blank lines never match
+pub fn line_similarity(a: &str, b: &str) -> f64 {
+    let (a, b) = (a.trim(), b.trim());
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    if a == b {
+        return 1.0;
+    }
+    normalized_levenshtein(a, b)
+}
+
+/// Align two sequences of lines, maximizing the total similarity of pairs
+///
+/// Pairs are in order in both sequences and only lines at least
+/// [`LINE_MATCH_THRESHOLD`] similar are paired. Inputs with more than
+/// [`MAX_ALIGN_CELLS`] line pairs fall back to [`align_identical`].
+pub fn align_lines(a: &[&str], b: &[&str]) -> Vec<LinePair> {
+    let (n, m) = (a.len(), b.len());
+    if n * m > MAX_ALIGN_CELLS {
+        return align_identical(a, b);
+    }
+    let similarity: Vec<Vec<f64>> = a
+        .iter()
+        .map(|x| b.iter().map(|y| line_similarity(x, y)).collect())
+        .collect();
+
+    // score[i][j]: best alignment of a[i..] and b[j..]
+    let mut score = vec![vec![0.0_f64; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            let mut best = score[i + 1][j].max(score[i][j + 1]);
+            if similarity[i][j] >= LINE_MATCH_THRESHOLD {
+                best = best.max(score[i + 1][j + 1] + similarity[i][j]);
+            }
+            score[i][j] = best;
+        }
+    }
+
+    let mut pairs = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        let paired = score[i + 1][j + 1] + similarity[i][j];
+        if similarity[i][j] >= LINE_MATCH_THRESHOLD && score[i][j] == paired {
+            pairs.push(LinePair {
+                a: i,
+                b: j,
+                similarity: similarity[i][j] as f32,
+            });
+            i += 1;
+            j += 1;
+        } else if score[i][j] == score[i + 1][j] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    pairs
+}
+
+/// Pair each line of `b` with the next identical line of `a`, ignoring
+/// indentation
+fn align_identical(a: &[&str], b: &[&str]) -> Vec<LinePair> {
+    let mut positions: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (i, line) in a.iter().enumerate() {
+        let line = line.trim();
+        if !line.is_empty() {
+            positions.entry(line).or_default().push(i);
+        }
+    }
+
+    let mut pairs = Vec::new();
+    let mut next = 0;
+    for (j, line) in b.iter().enumerate() {
+        let Some(found) = positions.get(line.trim()) else {
+            continue;
+        };
+        if let Some(&i) = found.get(found.partition_point(|&i| i < next)) {
+            pairs.push(LinePair {
+                a: i,
+                b: j,
+                similarity: 1.0,
+            });
+            next = i + 1;
+        }
+    }
+    pairs
+}
+
+/// One level of indentation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndentUnit {
+    /// A tab character
+    Tab,
+    /// A number of spaces
+    Spaces(usize),
+}
+
+impl IndentUnit {
+    /// Indentation unit of `code`: tabs if most indented lines start with a
+    /// tab, else the most common step between a line and a deeper next line
+    pub fn detect<'a>(lines: impl IntoIterator<Item = &'a str>) -> Option<Self> {
+        let (mut tabs, mut spaces) = (0, 0);
+        let mut steps: HashMap<usize, usize> = HashMap::new();
+        let mut previous = 0;
+        for line in lines.into_iter().filter(|l| !l.trim().is_empty()) {
+            let indent = leading_whitespace(line);
+            if indent.starts_with('\t') {
+                tabs += 1;
+            } else if !indent.is_empty() {
+                spaces += 1;
+            }
+            if !indent.contains('\t') && indent.len() > previous {
+                *steps.entry(indent.len() - previous).or_default() += 1;
+            }
+            previous = indent.len();
+        }
+
+        if tabs > 0 && tabs >= spaces {
+            return Some(IndentUnit::Tab);
+        }
+        steps
+            .into_iter()
+            .max_by_key(|&(step, count)| (count, std::cmp::Reverse(step)))
+            .map(|(step, _)| IndentUnit::Spaces(step))
+    }
+
+    /// Levels and leftover spaces of `indent` written in this unit
+    fn levels(self, indent: &str) -> (usize, usize) {
+        match self {
+            IndentUnit::Tab => {
+                let tabs = indent.chars().filter(|&c| c == '\t').count();
+                (tabs, indent.len() - tabs)
+            }
+            IndentUnit::Spaces(width) => {
+                let columns: usize = indent
+                    .chars()
+                    .map(|c| if c == '\t' { width } else { 1 })
+                    .sum();
+                (columns / width, columns % width)
+            }
+        }
+    }
+
+    /// Indentation of `levels` levels plus `extra` spaces in this unit
+    fn render(self, levels: usize, extra: usize) -> String {
+        let level = match self {
+            IndentUnit::Tab => "\t".to_string(),
+            IndentUnit::Spaces(width) => " ".repeat(width),
+        };
+        format!("{}{}", level.repeat(levels), " ".repeat(extra))
+    }
+}
+
+/// Leading whitespace of a line
+pub fn leading_whitespace(line: &str) -> &str {
+    &line[..line.len() - line.trim_start().len()]
+}
+
+/// Smallest indentation (in bytes) among non-blank lines
+pub fn base_indent(lines: &[&str]) -> usize {
+    lines
+        .iter()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| leading_whitespace(l).len())
+        .min()
+        .unwrap_or(0)
+}
+
+/// Move a line from a base indentation of `from` bytes onto `prefix`
+///
+/// With `units`, indentation beyond the base is converted from the first
+/// unit (the line's) to the second (the target's).
+pub fn reindent(
+    line: &str,
+    from: usize,
+    prefix: &str,
+    units: (Option<IndentUnit>, Option<IndentUnit>),
+) -> String {
+    if line.trim().is_empty() {
+        return String::new();
+    }
+    let indent = leading_whitespace(line);
+    let strip = indent.len().min(from);
+    let relative = match units {
+        (Some(source), Some(target)) if source != target => {
+            let (levels, extra) = source.levels(&indent[strip..]);
+            target.render(levels, extra)
+        }
+        _ => indent[strip..].to_string(),
+    };
+    format!("{}{}{}", prefix, relative, line.trim_start())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_align_lines() {
+        let chunk = ["function f() {", "    a();", "    b(1);", "    c();", "}"];
+        let snippet = ["b(2);", "c();"];
+
+        let pairs = align_lines(&chunk, &snippet);
+        assert_eq!(
+            pairs.iter().map(|p| (p.a, p.b)).collect::<Vec<_>>(),
+            vec![(2, 0), (3, 1)]
+        );
+        assert!(pairs[0].similarity < 1.0);
+        assert_eq!(pairs[1].similarity, 1.0);
+    }
+
+    #[test]
+    fn test_unrelated_lines_are_not_paired() {
+        let pairs = align_lines(&["const total = items.length;"], &["}"]);
+        assert!(pairs.is_empty());
+        assert!(align_lines(&["", "x"], &["", "y"]).is_empty());
+    }
+
+    #[test]
+    fn test_large_inputs_pair_identical_lines() {
+        let a: Vec<String> = (0..1000).map(|i| format!("    line {};", i)).collect();
+        let a: Vec<&str> = a.iter().map(String::as_str).collect();
+        let b = ["line 10;", "line 11 changed;", "line 999;", "line 5;"];
+        let b: Vec<&str> = b.iter().cycle().take(300).copied().collect();
+
+        let pairs = align_lines(&a, &b);
+        assert_eq!(
+            pairs.iter().map(|p| (p.a, p.b)).collect::<Vec<_>>(),
+            vec![(10, 0), (999, 2)]
+        );
+    }
+
+    #[test]
+    fn test_detect_indent_unit() {
+        let detect = |code: &str| IndentUnit::detect(code.lines());
+        assert_eq!(detect("a {\n\tb {\n\t\tc\n\t}\n}"), Some(IndentUnit::Tab));
+        assert_eq!(detect("a:\n  b:\n    c\n  d"), Some(IndentUnit::Spaces(2)));
+        assert_eq!(
+            detect("  f() {\n      g();\n  }\n  /**\n   * doc\n   */\n  h() {\n      i();\n  }"),
+            Some(IndentUnit::Spaces(4))
+        );
+        assert_eq!(detect("a\nb"), None);
+    }
+
+    #[test]
+    fn test_reindent() {
+        let keep = (None, None);
+        assert_eq!(reindent("    return x;", 4, "\t", keep), "\treturn x;");
+        assert_eq!(reindent("      nested();", 4, "", keep), "  nested();");
+        assert_eq!(reindent("   ", 4, "    ", keep), "");
+        assert_eq!(base_indent(&["  a", "", "    b"]), 2);
+    }
+
+    #[test]
+    fn test_reindent_converts_units() {
+        let to_tabs = (Some(IndentUnit::Spaces(4)), Some(IndentUnit::Tab));
+        assert_eq!(reindent("        return x;", 4, "\t", to_tabs), "\t\treturn x;");
+        assert_eq!(reindent("          .map(f)", 4, "", to_tabs), "\t  .map(f)");
+
+        let to_two = (Some(IndentUnit::Tab), Some(IndentUnit::Spaces(2)));
+        assert_eq!(reindent("\t\t\tx", 1, "  ", to_two), "      x");
+    }
+}
@@ -0,0 +1,190 @@
+//! Declared identifiers of code snippets and chunks
+//!
+//! Only declarations at the outermost indentation of the code are reported,
+//! so a function's locals are not mistaken for top-level symbols. Matching is
+//! line-based and works the same with the tree-sitter and lite parsers.
+//!
+//! In Go, methods are reported as `Receiver.name`; in Java and C++, where
+//! functions can be overloaded, callables are reported as `name/arity`.
+
+use crate::alignment::{base_indent, leading_whitespace};
+use crate::models::Language;
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+/// Compile a table of declaration patterns; the last capture group is the name
+fn patterns(sources: &[&str]) -> Vec<Regex> {
+    sources
+        .iter()
+        .map(|source| Regex::new(source).expect("invalid declaration pattern"))
+        .collect()
+}
+
+static JAVASCRIPT: Lazy<Vec<Regex>> = Lazy::new(|| {
+    patterns(&[
+        r"^(?:export\s+)?(?:default\s+)?(?:declare\s+)?(?:async\s+)?function\s*\*?\s*([A-Za-z_$][\w$]*)",
+        r"^(?:export\s+)?(?:default\s+)?(?:declare\s+)?(?:abstract\s+)?class\s+([A-Za-z_$][\w$]*)",
+        r"^(?:export\s+)?(?:declare\s+)?(?:const|let|var)\s+([A-Za-z_$][\w$]*)",
+        r"^(?:export\s+)?(?:declare\s+)?(?:interface|type|enum|namespace)\s+([A-Za-z_$][\w$]*)",
+        r"^import\s+([A-Za-z_$][\w$]*)\s+from\b",
+        // Methods: `name(...) {`, with any modifiers
+        r"^(?:(?:public|private|protected|static|async|readonly|override|get|set)\s+)*\*?([A-Za-z_$][\w$]*)\s*(?:<[^>]*>)?\s*\([^)]*\)\s*(?::[^{]*)?\{",
+    ])
+});
+
+static PYTHON: Lazy<Vec<Regex>> =
+    Lazy::new(|| patterns(&[r"^(?:async\s+)?def\s+(\w+)", r"^class\s+(\w+)"]));
+
+static RUST: Lazy<Vec<Regex>> = Lazy::new(|| {
+    patterns(&[
+        r#"^(?:pub(?:\([^)]*\))?\s+)?(?:(?:const|async|unsafe)\s+|extern\s+"[^"]*"\s+)*fn\s+(\w+)"#,
+        r"^(?:pub(?:\([^)]*\))?\s+)?(?:struct|enum|union|trait|type|mod)\s+(\w+)",
+        r"^(?:pub(?:\([^)]*\))?\s+)?(?:const|static)\s+(?:mut\s+)?(\w+)",
+        r"^macro_rules!\s*(\w+)",
+    ])
+});
+
+static GO: Lazy<Vec<Regex>> = Lazy::new(|| {
+    patterns(&[
+        r"^func\s+(\w+)",
+        r"^type\s+(\w+)",
+        r"^(?:var|const)\s+(\w+)",
+    ])
+});
+
+/// Go method, keyed by its receiver type
+static GO_METHOD: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^func\s+\(\s*(?:\w+\s+)?\*?(\w+)[^)]*\)\s*(\w+)").unwrap());
+
+static JAVA: Lazy<Vec<Regex>> = Lazy::new(|| {
+    patterns(&[
+        r"^(?:(?:public|private|protected|static|final|abstract|sealed|strictfp)\s+)*(?:class|interface|enum|record|@interface)\s+(\w+)",
+    ])
+});
+
+static C: Lazy<Vec<Regex>> = Lazy::new(|| {
+    patterns(&[
+        r"^(?:typedef\s+)?(?:struct|union|enum(?:\s+class)?|class|namespace)\s+(\w+)\s*(?::[^{]*)?\{",
+        r"^#\s*define\s+(\w+)",
+    ])
+});
+
+/// Callables of Java and C-family languages: `name(params) {`
+static CALLABLE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^[\w\s\*&<>,\[\]:~.?@]*?((?:\w+::)*~?\w+)\s*\(([^)]*)\)\s*(?:const\s*)?(?:noexcept\s*)?(?:override\s*)?(?:throws[^{]*)?\{")
+        .unwrap()
+});
+
+/// Words that look like a callable or method head but are not declarations
+const KEYWORDS: &[&str] = &[
+    "if", "for", "while", "switch", "catch", "with", "return", "function", "do", "else", "try",
+    "new", "sizeof",
+];
+
+/// Identifiers declared at the outermost level of `code`, in order
+pub fn declared_names(code: &str, language: Language) -> Vec<String> {
+    let lines: Vec<&str> = code.lines().collect();
+    let indent = base_indent(&lines);
+
+    let mut names: Vec<String> = Vec::new();
+    for line in lines {
+        if line.trim().is_empty() || leading_whitespace(line).len() != indent {
+            continue;
+        }
+        if let Some(name) = declaration(line.trim(), language) {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+    }
+    names
+}
+
+/// Name declared by a single line, if any
+fn declaration(line: &str, language: Language) -> Option<String> {
+    let by_table = |table: &[Regex]| {
+        table.iter().find_map(|pattern| {
+            let captures = pattern.captures(line)?;
+            let name = captures.get(captures.len() - 1)?.as_str();
+            (!KEYWORDS.contains(&name)).then(|| name.to_string())
+        })
+    };
+
+    match language {
+        Language::JavaScript | Language::TypeScript => by_table(&JAVASCRIPT),
+        Language::Python => by_table(&PYTHON),
+        Language::Rust => by_table(&RUST),
+        Language::Go => GO_METHOD
+            .captures(line)
+            .map(|c| format!("{}.{}", &c[1], &c[2]))
+            .or_else(|| by_table(&GO)),
+        Language::Java => by_table(&JAVA).or_else(|| callable(line, true)),
+        Language::C => by_table(&C).or_else(|| callable(line, false)),
+        Language::Cpp => by_table(&C).or_else(|| callable(line, true)),
+    }
+}
+
+/// Function or method definition, keyed by arity where overloads are allowed
+fn callable(line: &str, overloads: bool) -> Option<String> {
+    let captures = CALLABLE.captures(line)?;
+    let name = &captures[1];
+    if KEYWORDS.contains(&name) {
+        return None;
+    }
+    if !overloads {
+        return Some(name.to_string());
+    }
+
+    let params = captures[2].trim();
+    let arity = if params.is_empty() || params == "void" {
+        0
+    } else {
+        params.split(',').count()
+    };
+    Some(format!("{}/{}", name, arity))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_javascript_declarations() {
+        let code = "const x = 1;\nexport async function load(url) {\n    let data = 2;\n}\nclass Cache {}\nimport fs from 'fs';\nif (x) {\n}";
+        assert_eq!(
+            declared_names(code, Language::JavaScript),
+            vec!["x", "load", "Cache", "fs"]
+        );
+
+        let method = "    async fetch(id) {\n        return id;\n    }";
+        assert_eq!(declared_names(method, Language::TypeScript), vec!["fetch"]);
+        assert!(declared_names("main();\nconsole.log(x);", Language::JavaScript).is_empty());
+    }
+
+    #[test]
+    fn test_only_outermost_declarations() {
+        let code = "def area(radius):\n    def square(x):\n        return x * x\n    return square(radius)";
+        assert_eq!(declared_names(code, Language::Python), vec!["area"]);
+    }
+
+    #[test]
+    fn test_go_methods_include_receiver() {
+        let code = "func (c Circle) Area() float64 {\n}\nfunc (s *Square) Area() float64 {\n}\nfunc NewCircle() Circle {\n}";
+        assert_eq!(
+            declared_names(code, Language::Go),
+            vec!["Circle.Area", "Square.Area", "NewCircle"]
+        );
+    }
+
+    #[test]
+    fn test_overloads_are_distinct() {
+        let code = "public int add(int a, int b) {\n}\npublic double add(double a) {\n}\nfor (int i = 0; i < n; i++) {\n}";
+        assert_eq!(declared_names(code, Language::Java), vec!["add/2", "add/1"]);
+
+        let code = "struct point {\n};\nint manhattan(struct point p) {\n}";
+        assert_eq!(
+            declared_names(code, Language::C),
+            vec!["point", "manhattan"]
+        );
+    }
+}
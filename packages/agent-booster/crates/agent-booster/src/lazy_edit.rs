@@ -0,0 +1,429 @@
+//! Lazy edits: snippets that elide unchanged code with marker comments
+//!
+//! Agents usually abbreviate an edit instead of repeating a whole function:
+//!
+//! ```text
+//! function foo() {
+//!     // ... existing code ...
+//!     newLine();
+//! }
+//! ```
+//!
+//! Each marker stands for the original lines between the concrete lines
+//! around it. [`merge_lazy_edit`] aligns those anchor lines with the matched
+//! chunk, keeps the elided regions of the original and splices in the rest
+//! of the snippet.
+
+use crate::alignment::{base_indent, leading_whitespace, reindent, IndentUnit};
+use crate::models::Language;
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+/// Comment text of an elision marker: a bare `...`, `... existing code ...`,
+/// `rest of the function` or `keep existing methods`
+///
+/// Only these canonical forms count, so ordinary comments that merely start
+/// with words like "existing" or "remaining" are kept as code.
+static MARKER_BODY: Lazy<Regex> = Lazy::new(|| {
+    let dots = r"(?:\.{3}|…)";
+    let phrase = r"(?:(?:existing|unchanged)\s+code|rest\s+of\s+(?:the\s+)?(?:function|method|class|file|module|body)|keep\s+existing\s+[a-z]+)";
+    Regex::new(&format!(
+        r"(?i)^(?:{dots}|{dots}\s*{phrase}\s*{dots}|{phrase})$"
+    ))
+    .unwrap()
+});
+
+/// Whether `line` is an elision marker such as `// ... existing code ...`
+pub fn is_marker(line: &str, language: Language) -> bool {
+    let line = line.trim();
+    let body = match language {
+        Language::Python => line.strip_prefix('#'),
+        _ => line.strip_prefix("//").or_else(|| {
+            line.strip_prefix("/*")
+                .and_then(|body| body.strip_suffix("*/"))
+        }),
+    };
+    body.is_some_and(|body| MARKER_BODY.is_match(body.trim()))
+}
+
+/// Whether the snippet elides code with markers
+pub fn has_markers(snippet: &str, language: Language) -> bool {
+    snippet.lines().any(|line| is_marker(line, language))
+}
+
+/// The snippet without its marker lines, for similarity matching
+pub fn strip_markers(snippet: &str, language: Language) -> String {
+    snippet
+        .lines()
+        .filter(|line| !is_marker(line, language))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Result of merging a lazy snippet into a chunk
+#[derive(Debug, Clone, PartialEq)]
+pub struct LazyMerge {
+    /// The chunk with the snippet applied
+    pub code: String,
+    /// Fraction of marker sides backed by an anchor line (0.0 - 1.0)
+    pub anchor_score: f32,
+}
+
+/// Merge a snippet with elision markers into `target`
+///
+/// `target` must start at the beginning of a line so indentation can be
+/// compared. Each run of concrete snippet lines replaces the original lines
+/// from its first to its last anchor; what lies between two runs is kept.
+/// Returns `None` when the snippet has no markers or no line of it can be
+/// found in `target`.
+pub fn merge_lazy_edit(target: &str, snippet: &str, language: Language) -> Option<LazyMerge> {
+    let segments = segments(snippet, language);
+    if segments.len() < 2 {
+        return None;
+    }
+
+    let original: Vec<&str> = target.lines().collect();
+    let original = Lines::new(&original);
+    let concrete: Vec<&str> = segments.iter().flatten().copied().collect();
+    let snippet_indent = base_indent(&concrete);
+    let units = (
+        IndentUnit::detect(concrete.iter().copied()),
+        IndentUnit::detect(original.lines.iter().copied()),
+    );
+
+    let ranges = align(&segments, &original, snippet_indent);
+    if ranges.iter().all(Option::is_none) {
+        return None;
+    }
+
+    let last = segments.len() - 1;
+    let mut anchored_sides = 0;
+    for marker in 0..last {
+        let before = ranges[..=marker].iter().any(Option::is_some) || segments[0].is_empty();
+        let after = ranges[marker + 1..].iter().any(Option::is_some) || segments[last].is_empty();
+        anchored_sides += before as usize + after as usize;
+    }
+    let anchor_score = anchored_sides as f32 / (2 * last) as f32;
+
+    // Unanchored segments go right before the next anchored one, so the
+    // elided region in front of them stays as large as possible
+    let resolved: Vec<(usize, usize)> = (0..segments.len())
+        .map(|i| match ranges[i] {
+            Some(range) => range,
+            None if i == 0 => (0, 0),
+            None => {
+                let next = ranges[i + 1..]
+                    .iter()
+                    .flatten()
+                    .map(|(start, _)| *start)
+                    .next()
+                    .unwrap_or(original.len());
+                (next, next)
+            }
+        })
+        .collect();
+
+    let prefix = leading_whitespace(original.lines.first().copied().unwrap_or(""));
+    let mut out: Vec<String> = Vec::new();
+    let mut kept_until = 0;
+    for (segment, (start, end)) in segments.iter().zip(resolved) {
+        let start = start.max(kept_until);
+        out.extend(
+            original.lines[kept_until..start]
+                .iter()
+                .map(|l| l.to_string()),
+        );
+        out.extend(
+            segment
+                .iter()
+                .map(|line| reindent(line, snippet_indent, prefix, units)),
+        );
+        kept_until = end.max(start);
+    }
+    out.extend(original.lines[kept_until..].iter().map(|l| l.to_string()));
+
+    let mut code = out.join("\n");
+    if target.ends_with('\n') {
+        code.push('\n');
+    }
+    Some(LazyMerge { code, anchor_score })
+}
+
+/// Runs of concrete lines between markers; consecutive markers count as one
+/// and blank lines next to a marker are dropped
+fn segments(snippet: &str, language: Language) -> Vec<Vec<&str>> {
+    let mut segments = vec![Vec::new()];
+    for line in snippet.lines() {
+        if is_marker(line, language) {
+            if !segments.last().is_some_and(Vec::is_empty) || segments.len() == 1 {
+                segments.push(Vec::new());
+            }
+        } else {
+            segments.last_mut().unwrap().push(line);
+        }
+    }
+    for segment in &mut segments {
+        while segment.last().is_some_and(|l| l.trim().is_empty()) {
+            segment.pop();
+        }
+        let blank = segment.iter().take_while(|l| l.trim().is_empty()).count();
+        segment.drain(..blank);
+    }
+    segments
+}
+
+/// Original lines with their indentation relative to the first line
+struct Lines<'a> {
+    lines: &'a [&'a str],
+    indent: usize,
+}
+
+impl<'a> Lines<'a> {
+    fn new(lines: &'a [&'a str]) -> Self {
+        let indent = lines.first().map_or(0, |l| leading_whitespace(l).len());
+        Self { lines, indent }
+    }
+
+    fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// Position of `line` in `from..to`, preferring the same relative
+    /// indentation and otherwise any line with the same text
+    fn find(
+        &self,
+        line: &str,
+        relative: usize,
+        from: usize,
+        to: usize,
+        reverse: bool,
+    ) -> Option<usize> {
+        let text = line.trim();
+        if text.is_empty() || from >= to {
+            return None;
+        }
+        let candidates = || {
+            let range = from..to;
+            let order: Box<dyn Iterator<Item = usize>> = if reverse {
+                Box::new(range.rev())
+            } else {
+                Box::new(range)
+            };
+            order.filter(move |&i| self.lines[i].trim() == text)
+        };
+        candidates()
+            .find(|&i| relative_indent(self.lines[i], self.indent) == relative)
+            .or_else(|| candidates().next())
+    }
+
+    /// Start of the run of snippet lines ending at original line `last`
+    ///
+    /// Walks back while the snippet and the original agree line by line and
+    /// returns the earliest line with some text in that run (or the last
+    /// line), as indexes into the segment and the original.
+    fn tail_run(&self, segment: &[&str], last: usize, from: usize) -> (usize, usize) {
+        let mut start = (segment.len() - 1, last);
+        let mut k = segment.len() - 1;
+        let mut at = last;
+        while k > 0 && at > from && segment[k - 1].trim() == self.lines[at - 1].trim() {
+            k -= 1;
+            at -= 1;
+            if has_text(segment[k]) {
+                start = (k, at);
+            }
+        }
+        start
+    }
+}
+
+/// Original range `start..end` each segment replaces, if it is anchored
+///
+/// A segment starts at the first of its lines found in the original. Lines
+/// without any text (a bare `}`) say too little to anchor new code and are
+/// only used at the edges of a segment. The last segment ends where the
+/// chunk ends.
+fn align(
+    segments: &[Vec<&str>],
+    original: &Lines,
+    snippet_indent: usize,
+) -> Vec<Option<(usize, usize)>> {
+    let n = original.len();
+    let last_segment = segments.len() - 1;
+    let mut cursor = 0;
+    let mut ranges = Vec::with_capacity(segments.len());
+
+    for (i, segment) in segments.iter().enumerate() {
+        let Some(last_line) = segment.last() else {
+            // Empty edge segment: the snippet starts or ends with a marker
+            let at = if i == 0 { 0 } else { n };
+            ranges.push(Some((at, at)));
+            cursor = at;
+            continue;
+        };
+        let last_rel = relative_indent(last_line, snippet_indent);
+        let leading = |lines: usize, to: usize| {
+            segment[..lines].iter().enumerate().find_map(|(k, line)| {
+                if k > 0 && k + 1 < segment.len() && !has_text(line) {
+                    return None;
+                }
+                let relative = relative_indent(line, snippet_indent);
+                original
+                    .find(line, relative, cursor, to, false)
+                    .map(|at| (k, at))
+            })
+        };
+
+        let range = if i == last_segment && i > 0 {
+            // The snippet's closing lines match the end of the chunk
+            original
+                .find(last_line, last_rel, cursor, n, true)
+                .map(|last| {
+                    let (k, tail) = original.tail_run(segment, last, cursor);
+                    let start = leading(k, tail).map_or(tail, |(_, at)| at);
+                    (start, last + 1)
+                })
+                .or_else(|| leading(segment.len(), n).map(|(_, at)| (at, at + 1)))
+        } else {
+            leading(segment.len(), n).map(|(k, first)| {
+                let last = if k + 1 < segment.len() {
+                    original.find(last_line, last_rel, first + 1, n, false)
+                } else {
+                    None
+                };
+                (first, last.unwrap_or(first) + 1)
+            })
+        };
+
+        if let Some((_, end)) = range {
+            cursor = end;
+        }
+        ranges.push(range);
+    }
+    ranges
+}
+
+fn has_text(line: &str) -> bool {
+    line.chars().any(char::is_alphanumeric)
+}
+
+fn relative_indent(line: &str, base: usize) -> usize {
+    leading_whitespace(line).len().saturating_sub(base)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_marker_recognition() {
+        let js = Language::JavaScript;
+        assert!(is_marker("    // ... existing code ...", js));
+        assert!(is_marker("// ...", js));
+        assert!(is_marker("/* ... rest of the class ... */", js));
+        assert!(is_marker("// rest of the function", js));
+        assert!(is_marker("# ... existing code ...", Language::Python));
+
+        assert!(!is_marker("// TODO: handle errors", js));
+        assert!(!is_marker("# ... existing code ...", js));
+        assert!(!is_marker("// ... existing code ...", Language::Python));
+        assert!(!is_marker("const rest = [...items];", js));
+        assert!(is_marker("// keep existing methods", js));
+        assert!(is_marker("// … unchanged code …", js));
+    }
+
+    #[test]
+    fn test_ordinary_comments_are_not_markers() {
+        let js = Language::JavaScript;
+        for comment in [
+            "// previous implementation was buggy",
+            "// remaining items are processed below",
+            "// existing users keep settings",
+            "// ...and then return",
+            "// ... comprehensive error types",
+            "// unchanged since v1",
+            "// rest of the items are ignored",
+            "/* existing code paths rely on this */",
+        ] {
+            assert!(!is_marker(comment, js), "{comment}");
+        }
+        assert!(!is_marker(
+            "# remaining arguments are passed through",
+            Language::Python
+        ));
+
+        let target = "function foo() {\n    a();\n}";
+        let snippet = "function foo() {\n    // previous implementation was buggy\n    a();\n}";
+        assert!(!has_markers(snippet, js));
+        assert!(merge_lazy_edit(target, snippet, js).is_none());
+    }
+
+    #[test]
+    fn test_append_after_existing_code() {
+        let target = "function foo() {\n    a();\n    b();\n}";
+        let snippet = "function foo() {\n    // ... existing code ...\n    newLine();\n}";
+
+        let merged = merge_lazy_edit(target, snippet, Language::JavaScript).unwrap();
+        assert_eq!(
+            merged.code,
+            "function foo() {\n    a();\n    b();\n    newLine();\n}"
+        );
+        assert_eq!(merged.anchor_score, 1.0);
+    }
+
+    #[test]
+    fn test_multiple_markers() {
+        let target = "class Cart {\n    add(item) {\n        this.items.push(item);\n    }\n\n    total() {\n        return 0;\n    }\n\n    clear() {\n        this.items = [];\n    }\n}";
+        let snippet = "class Cart {\n    // ... existing code ...\n    total() {\n        return this.items.length;\n    }\n    // ... existing code ...\n}";
+
+        let merged = merge_lazy_edit(target, snippet, Language::JavaScript).unwrap();
+        assert_eq!(
+            merged.code,
+            "class Cart {\n    add(item) {\n        this.items.push(item);\n    }\n\n    total() {\n        return this.items.length;\n    }\n\n    clear() {\n        this.items = [];\n    }\n}"
+        );
+    }
+
+    #[test]
+    fn test_nested_blocks() {
+        let target = "function foo() {\n  if (x) {\n    a();\n  }\n  b();\n}";
+        let snippet = "function foo() {\n  if (x) {\n    // ... existing code ...\n    extra();\n  }\n  // ... existing code ...\n}";
+
+        let merged = merge_lazy_edit(target, snippet, Language::JavaScript).unwrap();
+        assert_eq!(
+            merged.code,
+            "function foo() {\n  if (x) {\n    a();\n    extra();\n  }\n  b();\n}"
+        );
+    }
+
+    #[test]
+    fn test_new_method_between_markers_is_inserted() {
+        let target = "class A {\n    one() {\n        return 1;\n    }\n}";
+        let snippet =
+            "class A {\n    // ... existing code ...\n    two() {\n        return 2;\n    }\n}";
+
+        let merged = merge_lazy_edit(target, snippet, Language::JavaScript).unwrap();
+        assert_eq!(
+            merged.code,
+            "class A {\n    one() {\n        return 1;\n    }\n    two() {\n        return 2;\n    }\n}"
+        );
+    }
+
+    #[test]
+    fn test_reindents_snippet_to_target() {
+        let target = "    def area(self):\n        x = 1\n        return x\n";
+        let snippet = "def area(self):\n    # ... existing code ...\n    log(x)\n    return x";
+
+        let merged = merge_lazy_edit(target, snippet, Language::Python).unwrap();
+        assert_eq!(
+            merged.code,
+            "    def area(self):\n        x = 1\n        log(x)\n        return x\n"
+        );
+    }
+
+    #[test]
+    fn test_no_anchors() {
+        let target = "function foo() {\n    a();\n}";
+        assert!(merge_lazy_edit(target, "// ...\nunrelated();", Language::JavaScript).is_some());
+        assert!(merge_lazy_edit(target, "bar();\n// ...\nbaz();", Language::JavaScript).is_none());
+        assert!(merge_lazy_edit(target, "foo();", Language::JavaScript).is_none());
+    }
+}
@@ -4,9 +4,21 @@
 //! the best matching location in existing code using tree-sitter parsing and text
 //! similarity algorithms.
 
+pub mod alignment;
+pub mod declarations;
+pub mod lazy_edit;
 pub mod merge;
 pub mod models;
+pub mod patch;
+pub mod pool;
+#[cfg(feature = "tree-sitter-parser")]
+pub mod query_templates;
 pub mod templates;
+pub mod transaction;
+pub mod user_templates;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod workspace;
 
 #[cfg(feature = "tree-sitter-parser")]
 pub mod parser;
@@ -21,7 +33,8 @@ pub mod similarity;
 
 pub use models::{
     AgentBoosterError, CodeChunk, Config, EditMetadata, EditRequest, EditResult, Language,
-    MergeStrategy, Result,
+    MergeStrategy, Result, TransactionEdit, TransactionRequest, TransactionResult,
+    WorkspaceCandidate, WorkspaceEditRequest, WorkspaceEditResult,
 };
 pub use templates::TemplateEngine;
 
@@ -33,14 +46,24 @@ pub struct AgentBooster {
     parser: parser::Parser,
     merger: merge::Merger,
     config: Config,
+    templates: Vec<user_templates::UserTemplate>,
+    /// Workers for `batch_apply_parallel`, kept until the config changes
+    #[cfg(feature = "parallel")]
+    pool: pool::WorkerPool,
 }
 
 impl AgentBooster {
     /// Create a new AgentBooster instance with the given configuration
+    ///
+    /// Fails with `ConfigError` if a file in `config.template_files` can't be
+    /// read or holds an invalid template.
     pub fn new(config: Config) -> Result<Self> {
         Ok(Self {
             parser: parser::Parser::new()?,
             merger: merge::Merger::new()?,
+            templates: user_templates::load_templates(&config.template_files)?,
+            #[cfg(feature = "parallel")]
+            pool: pool::WorkerPool::new(config.clone()),
             config,
         })
     }
@@ -50,18 +73,43 @@ impl AgentBooster {
         #[cfg(not(target_arch = "wasm32"))]
         let start_time = Instant::now();
 
-        // PHASE 1: Try template-based transformation first (bypasses similarity matching)
-        if let Some(template_match) = TemplateEngine::try_template_transform(&request.original_code, &request.edit_snippet) {
-            let syntax_valid = self.parser.validate_syntax(&template_match.transformed_code, request.language);
+        // Snippets with `// ... existing code ...` markers are merged around the markers
+        let lazy = lazy_edit::has_markers(&request.edit_snippet, request.language);
+
+        // PHASE 1: Try template-based transformation first (bypasses similarity matching).
+        // A match that is not valid syntax or not confident enough gives way to the next.
+        let template_matches = if lazy {
+            Vec::new()
+        } else {
+            TemplateEngine::rank_templates(
+                &request.original_code,
+                &request.edit_snippet,
+                request.language,
+                &self.templates,
+            )
+        };
+        for template_match in template_matches {
             let confidence_f32 = template_match.confidence as f32;
+            if confidence_f32 < request.confidence_threshold {
+                // Ranked best first, so the rest are below the threshold too
+                break;
+            }
+            let syntax_valid = self.parser.validate_syntax(&template_match.transformed_code, request.language);
 
-            if confidence_f32 >= request.confidence_threshold && syntax_valid {
+            if syntax_valid {
                 #[cfg(not(target_arch = "wasm32"))]
                 let processing_time_ms = Some(start_time.elapsed().as_millis() as u64);
 
                 #[cfg(target_arch = "wasm32")]
                 let processing_time_ms = None;
 
+                let patch = self.patch(
+                    &request.original_code,
+                    &template_match.transformed_code,
+                    None,
+                    MergeStrategy::ExactReplace,
+                );
+
                 return Ok(EditResult {
                     merged_code: template_match.transformed_code,
                     confidence: confidence_f32,
@@ -72,6 +120,7 @@ impl AgentBooster {
                         syntax_valid,
                         processing_time_ms,
                     },
+                    patch,
                 });
             }
         }
@@ -95,20 +144,37 @@ impl AgentBooster {
 
         let chunks_found = chunks.len();
 
-        // Find best matching chunk
-        let best_match = similarity::SimilarityMatcher::find_best_match(&request.edit_snippet, &chunks)
-            .ok_or_else(|| AgentBoosterError::LowConfidence(0.0))?;
-
-        let best_similarity = best_match.similarity;
-
-        // Apply merge
-        let merge_result = self.merger.merge(
-            &request.original_code,
-            &request.edit_snippet,
-            &best_match,
-            request.language,
-            request.confidence_threshold,
-        )?;
+        let (merge_result, best_similarity) = if lazy {
+            // Match without the markers and try the closest few chunks
+            let stripped = lazy_edit::strip_markers(&request.edit_snippet, request.language);
+            let candidates = similarity::SimilarityMatcher::find_top_k_matches(&stripped, &chunks, 3);
+            let best_similarity = candidates.first().map_or(0.0, |c| c.similarity);
+
+            let merge_result = self.merger.merge_lazy(
+                &request.original_code,
+                &request.edit_snippet,
+                &candidates,
+                request.language,
+                request.confidence_threshold,
+            )?;
+            (merge_result, best_similarity)
+        } else {
+            // Find best matching chunk
+            let best_match = similarity::SimilarityMatcher::find_best_match(&request.edit_snippet, &chunks)
+                .ok_or_else(|| AgentBoosterError::LowConfidence(0.0))?;
+
+            let best_similarity = best_match.similarity;
+
+            // Apply merge
+            let merge_result = self.merger.merge(
+                &request.original_code,
+                &request.edit_snippet,
+                &best_match,
+                request.language,
+                request.confidence_threshold,
+            )?;
+            (merge_result, best_similarity)
+        };
 
         #[cfg(not(target_arch = "wasm32"))]
         let processing_time_ms = Some(start_time.elapsed().as_millis() as u64);
@@ -116,7 +182,32 @@ impl AgentBooster {
         #[cfg(target_arch = "wasm32")]
         let processing_time_ms = None; // No timing in WASM
 
-        Ok(EditResult {
+        Ok(self.edit_result(
+            &request.original_code,
+            merge_result,
+            chunks_found,
+            best_similarity,
+            processing_time_ms,
+        ))
+    }
+
+    /// Edit result of a merge, with a patch if the config enables `include_patch`
+    fn edit_result(
+        &self,
+        original_code: &str,
+        merge_result: merge::MergeResult,
+        chunks_found: usize,
+        best_similarity: f32,
+        processing_time_ms: Option<u64>,
+    ) -> EditResult {
+        let patch = self.patch(
+            original_code,
+            &merge_result.code,
+            merge_result.node_type.as_deref(),
+            merge_result.strategy,
+        );
+
+        EditResult {
             merged_code: merge_result.code,
             confidence: merge_result.confidence,
             strategy: merge_result.strategy,
@@ -126,10 +217,24 @@ impl AgentBooster {
                 syntax_valid: merge_result.syntax_valid,
                 processing_time_ms,
             },
-        })
+            patch,
+        }
     }
 
-    /// Apply multiple edits in batch (sequentially for now)
+    /// Patch for an edit's result, if the configuration asks for one
+    fn patch(
+        &self,
+        original_code: &str,
+        merged_code: &str,
+        node_type: Option<&str>,
+        strategy: MergeStrategy,
+    ) -> Option<patch::Patch> {
+        self.config
+            .include_patch
+            .then(|| patch::Patch::new(original_code, merged_code, node_type, strategy))
+    }
+
+    /// Apply multiple edits in batch, one after another
     pub fn batch_apply(&mut self, requests: Vec<EditRequest>) -> Result<Vec<EditResult>> {
         requests
             .into_iter()
@@ -137,14 +242,208 @@ impl AgentBooster {
             .collect()
     }
 
+    /// Apply multiple edits in parallel
+    ///
+    /// Each thread merges with its own parser from a [`pool::WorkerPool`],
+    /// which is kept across calls and replaced when the configuration changes.
+    /// Results come back in input order, one per request, so a failed edit
+    /// doesn't abort the rest. Apart from `processing_time_ms` they are the
+    /// same as applying each request with [`AgentBooster::apply_edit`].
+    #[cfg(feature = "parallel")]
+    pub fn batch_apply_parallel(&self, requests: Vec<EditRequest>) -> Vec<Result<EditResult>> {
+        self.pool.batch_apply(requests)
+    }
+
+    /// Apply several edits to one file, all or nothing
+    ///
+    /// Every snippet is matched against the original code, so byte offsets
+    /// from one edit stay valid for the others; the edits are then applied
+    /// together with their offsets rebased. Nothing is applied if an edit
+    /// fails to match, two edits rewrite overlapping code, or the combined
+    /// result fails syntax validation.
+    pub fn apply_transaction(&mut self, request: TransactionRequest) -> Result<TransactionResult> {
+        let mut edits = Vec::with_capacity(request.edit_snippets.len());
+        let mut changes = Vec::with_capacity(request.edit_snippets.len());
+
+        for (index, snippet) in request.edit_snippets.iter().enumerate() {
+            let result = self.apply_edit(EditRequest {
+                original_code: request.original_code.clone(),
+                edit_snippet: snippet.clone(),
+                language: request.language,
+                confidence_threshold: request.confidence_threshold,
+            });
+
+            let mut edit = TransactionEdit {
+                index,
+                strategy: None,
+                confidence: 0.0,
+                original_range: None,
+                merged_range: None,
+                error: None,
+            };
+            match result {
+                Ok(result) => {
+                    let change =
+                        transaction::Change::between(&request.original_code, &result.merged_code);
+                    edit.strategy = Some(result.strategy);
+                    edit.confidence = result.confidence;
+                    edit.original_range = Some(change.range.clone());
+                    changes.push(change);
+                }
+                Err(e) => edit.error = Some(e.to_string()),
+            }
+            edits.push(edit);
+        }
+
+        let rolled_back = |edits, syntax_valid| TransactionResult {
+            merged_code: request.original_code.clone(),
+            committed: false,
+            syntax_valid,
+            edits,
+        };
+
+        if edits.iter().any(|e| e.error.is_some()) {
+            return Ok(rolled_back(edits, None));
+        }
+
+        if let Some((first, second)) = transaction::find_overlap(&changes) {
+            edits[second].error = Some(AgentBoosterError::OverlappingEdit(first).to_string());
+            return Ok(rolled_back(edits, None));
+        }
+
+        let (merged_code, ranges) = transaction::apply_changes(&request.original_code, &changes);
+
+        // Each edit was validated on its own; the combination is validated once
+        let language = request.language;
+        if !self.parser.validate_syntax(&merged_code, language) {
+            let blamed = transaction::blame(&request.original_code, &changes, |code| {
+                self.parser.validate_syntax(code, language)
+            });
+            for i in blamed {
+                edits[i].error = Some(AgentBoosterError::InvalidSyntax.to_string());
+            }
+            return Ok(rolled_back(edits, Some(false)));
+        }
+
+        for (edit, range) in edits.iter_mut().zip(ranges) {
+            edit.merged_range = Some(range);
+        }
+
+        Ok(TransactionResult {
+            merged_code,
+            committed: true,
+            syntax_valid: Some(true),
+            edits,
+        })
+    }
+
+    /// Apply an edit to the best matching file of a workspace
+    ///
+    /// Candidates are ranked by [`workspace::Workspace::rank`] and the edit is
+    /// merged into the best one's chunk, without searching its file again. If
+    /// another file scores about as well, nothing is applied and the result is
+    /// marked ambiguous so the caller can choose from the candidates. Files on
+    /// disk are never written.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn apply_workspace_edit(
+        &mut self,
+        workspace: &workspace::Workspace,
+        request: WorkspaceEditRequest,
+    ) -> Result<WorkspaceEditResult> {
+        let start_time = Instant::now();
+        let candidates = workspace.rank(
+            &request.edit_snippet,
+            request.language,
+            request.target_hint.as_deref(),
+            workspace::MAX_CANDIDATES,
+        );
+        let best = candidates
+            .first()
+            .ok_or(AgentBoosterError::LowConfidence(0.0))?;
+
+        if workspace::is_ambiguous(&candidates) {
+            return Ok(WorkspaceEditResult {
+                path: None,
+                edit: None,
+                ambiguous: true,
+                candidates,
+            });
+        }
+
+        let file = workspace
+            .file(&best.path)
+            .expect("candidates come from indexed files");
+        // The best file's ranked chunks, best first
+        let matches: Vec<similarity::SearchResult> = candidates
+            .iter()
+            .filter(|c| c.path == best.path)
+            .map(|c| similarity::SearchResult {
+                chunk: c.chunk.clone(),
+                similarity: c.similarity,
+                chunk_index: file
+                    .chunks
+                    .iter()
+                    .position(|chunk| {
+                        (chunk.start_byte, chunk.end_byte) == (c.chunk.start_byte, c.chunk.end_byte)
+                    })
+                    .unwrap_or(0),
+            })
+            .collect();
+        let merge_result = if lazy_edit::has_markers(&request.edit_snippet, file.language) {
+            self.merger.merge_lazy(
+                &file.code,
+                &request.edit_snippet,
+                &matches,
+                file.language,
+                request.confidence_threshold,
+            )?
+        } else {
+            self.merger.merge(
+                &file.code,
+                &request.edit_snippet,
+                &matches[0],
+                file.language,
+                request.confidence_threshold,
+            )?
+        };
+        let edit = self.edit_result(
+            &file.code,
+            merge_result,
+            file.chunks.len(),
+            best.similarity,
+            Some(start_time.elapsed().as_millis() as u64),
+        );
+
+        Ok(WorkspaceEditResult {
+            path: Some(file.path.clone()),
+            edit: Some(edit),
+            ambiguous: false,
+            candidates,
+        })
+    }
+
     /// Get the current configuration
     pub fn config(&self) -> &Config {
         &self.config
     }
 
     /// Update the configuration
-    pub fn set_config(&mut self, config: Config) {
+    ///
+    /// Template files are reloaded and parallel workers are replaced; on error
+    /// the previous configuration is kept.
+    pub fn set_config(&mut self, config: Config) -> Result<()> {
+        self.templates = user_templates::load_templates(&config.template_files)?;
+        #[cfg(feature = "parallel")]
+        {
+            self.pool = pool::WorkerPool::new(config.clone());
+        }
         self.config = config;
+        Ok(())
+    }
+
+    /// Templates loaded from `Config::template_files`
+    pub fn user_templates(&self) -> &[user_templates::UserTemplate] {
+        &self.templates
     }
 }
 
@@ -249,6 +548,8 @@ interface Product {
         let mut booster = AgentBooster::new(Config {
             confidence_threshold: 0.9, // High threshold
             max_chunks: 50,
+            include_patch: false,
+            template_files: Vec::new(),
         })
         .unwrap();
 
@@ -290,6 +591,282 @@ interface Product {
         assert!(results[1].merged_code.contains("return 4"));
     }
 
+    fn transaction(snippets: &[&str]) -> TransactionRequest {
+        TransactionRequest {
+            original_code: "function foo() {\n    return 1;\n}\n\nfunction bar() {\n    return 2;\n}\n\nfunction baz() {\n    return 3;\n}\n".to_string(),
+            edit_snippets: snippets.iter().map(|s| s.to_string()).collect(),
+            language: Language::JavaScript,
+            confidence_threshold: 0.5,
+        }
+    }
+
+    #[test]
+    fn test_transaction_applies_all_edits() {
+        let mut booster = AgentBooster::default();
+        let result = booster
+            .apply_transaction(transaction(&[
+                "function baz() {\n    return 30;\n}",
+                "function foo() {\n    return 10;\n}",
+            ]))
+            .unwrap();
+
+        assert!(result.committed);
+        assert_eq!(result.syntax_valid, Some(true));
+        assert_eq!(
+            result.merged_code,
+            "function foo() {\n    return 10;\n}\n\nfunction bar() {\n    return 2;\n}\n\nfunction baz() {\n    return 30;\n}\n"
+        );
+        let replaced = |i: usize| &result.merged_code[result.edits[i].merged_range.clone().unwrap()];
+        assert_eq!(replaced(0), "    return 30;");
+        assert_eq!(replaced(1), "    return 10;");
+    }
+
+    #[test]
+    fn test_transaction_rolls_back_overlapping_edits() {
+        let mut booster = AgentBooster::default();
+        let result = booster
+            .apply_transaction(transaction(&[
+                "function bar() {\n    return 20;\n}",
+                "function bar() {\n    return 21;\n}",
+            ]))
+            .unwrap();
+
+        assert!(!result.committed);
+        assert_eq!(result.syntax_valid, None);
+        assert_eq!(result.merged_code, transaction(&[]).original_code);
+        assert!(result.edits[0].error.is_none());
+        assert!(result.edits[1].error.as_deref().unwrap().contains("edit 0"));
+    }
+
+    #[test]
+    fn test_transaction_rolls_back_on_failed_edit() {
+        let mut booster = AgentBooster::default();
+        let result = booster
+            .apply_transaction(transaction(&[
+                "function foo() {\n    return 10;\n}",
+                "completely unrelated text",
+            ]))
+            .unwrap();
+
+        assert!(!result.committed);
+        assert_eq!(result.syntax_valid, None);
+        assert_eq!(result.merged_code, transaction(&[]).original_code);
+        assert!(result.edits[0].original_range.is_some());
+        assert!(result.edits[1].error.is_some());
+        assert!(result.edits.iter().all(|e| e.merged_range.is_none()));
+    }
+
+    #[test]
+    fn test_patch_output() {
+        let original = "function foo() {\n    return 1;\n}\n\nfunction bar() {\n    return 2;\n}\n";
+        let request = EditRequest {
+            original_code: original.to_string(),
+            edit_snippet: "function bar() {\n    return 20;\n}".to_string(),
+            language: Language::JavaScript,
+            confidence_threshold: 0.5,
+        };
+
+        let mut booster = AgentBooster::default();
+        assert!(booster.apply_edit(request.clone()).unwrap().patch.is_none());
+
+        booster
+            .set_config(Config {
+                include_patch: true,
+                ..Config::default()
+            })
+            .unwrap();
+        let result = booster.apply_edit(request).unwrap();
+        let patch = result.patch.as_ref().unwrap();
+
+        assert_eq!(patch.hunks.len(), 1);
+        assert_eq!(patch.hunks[0].original_lines, 5..6);
+        assert_eq!(patch.hunks[0].replacement, "    return 20;\n");
+        assert_eq!(patch.hunks[0].node_type.as_deref(), Some("function_declaration"));
+        assert_eq!(patch.hunks[0].strategy, result.strategy);
+        assert!(patch.unified_diff.contains("-    return 2;\n+    return 20;\n"));
+
+        // The patch still applies after unrelated changes to the file
+        let drifted = format!("// header\n{}", original.replace("return 1;", "return 10;"));
+        assert_eq!(
+            result.apply_to(&drifted).unwrap(),
+            format!("// header\n{}", result.merged_code.replace("return 1;", "return 10;"))
+        );
+    }
+
+    #[test]
+    fn test_user_templates_from_config() {
+        let fixtures =
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/templates");
+        let mut booster = AgentBooster::new(Config {
+            template_files: vec![fixtures.join("house.yaml"), fixtures.join("house.toml")],
+            ..Config::default()
+        })
+        .unwrap();
+        assert_eq!(booster.user_templates().len(), 3);
+
+        let original = "function load(id) {\n  console.info(\"loading\", id);\n  return store.get(id);\n}\n";
+        let result = booster
+            .apply_edit(EditRequest {
+                original_code: original.to_string(),
+                edit_snippet: "logger.info(\"loading\", id);".to_string(),
+                language: Language::JavaScript,
+                confidence_threshold: 0.5,
+            })
+            .unwrap();
+        assert_eq!(result.merged_code, original.replace("console.info", "logger.info"));
+        assert_eq!(result.strategy, MergeStrategy::ExactReplace);
+        assert_eq!(result.confidence, 0.88);
+
+        // A broken file is reported with its path, and the old templates stay
+        let invalid = Config {
+            template_files: vec![fixtures.join("invalid.yaml")],
+            ..Config::default()
+        };
+        let error = AgentBooster::new(invalid.clone()).err().unwrap().to_string();
+        assert!(error.contains("invalid.yaml: template `broken-placeholder`"), "{}", error);
+        assert!(error.contains("`${modul}`, which the pattern does not capture"));
+        assert!(booster.set_config(invalid).is_err());
+        assert_eq!(booster.user_templates().len(), 3);
+    }
+
+    #[test]
+    fn test_invalid_template_match_falls_through_to_next() {
+        use user_templates::{parse_templates, TemplateFormat};
+
+        let mut booster = AgentBooster::new(Config::default()).unwrap();
+        // The better template also swallows the closing brace
+        booster.templates = parse_templates(
+            r#"
+templates:
+  - name: greedy-rename
+    match:
+      regex: 'a\(\);\n\}'
+    replace: "b();"
+    confidence: 0.95
+  - name: rename
+    match:
+      regex: 'a\(\);'
+    replace: "b();"
+    confidence: 0.9
+"#,
+            TemplateFormat::Yaml,
+        )
+        .unwrap();
+
+        let original = "function f() {\n  a();\n}\n";
+        let result = booster
+            .apply_edit(EditRequest {
+                original_code: original.to_string(),
+                edit_snippet: "b();".to_string(),
+                language: Language::JavaScript,
+                confidence_threshold: 0.5,
+            })
+            .unwrap();
+        assert_eq!(result.merged_code, "function f() {\n  b();\n}\n");
+        assert_eq!(result.strategy, MergeStrategy::ExactReplace);
+        assert_eq!(result.confidence, 0.9);
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_batch_apply_parallel_matches_sequential() {
+        let requests: Vec<EditRequest> = (0..40)
+            .map(|i| EditRequest {
+                original_code: format!("function f{i}() {{\n    return {i};\n}}\n"),
+                edit_snippet: if i % 7 == 3 {
+                    "completely unrelated words".to_string()
+                } else {
+                    format!("function f{i}() {{\n    return {};\n}}", i * 10)
+                },
+                language: Language::JavaScript,
+                confidence_threshold: if i % 7 == 3 { 0.99 } else { 0.5 },
+            })
+            .collect();
+
+        let mut booster = AgentBooster::default();
+        let parallel = booster.batch_apply_parallel(requests.clone());
+        assert_eq!(parallel.len(), requests.len());
+        assert!(parallel[3].is_err() && parallel[4].is_ok());
+
+        for (request, result) in requests.into_iter().zip(parallel) {
+            match (booster.apply_edit(request), result) {
+                (Ok(expected), Ok(actual)) => {
+                    assert_eq!(actual.merged_code, expected.merged_code);
+                    assert_eq!(actual.strategy, expected.strategy);
+                    assert_eq!(actual.confidence, expected.confidence);
+                }
+                (Err(expected), Err(actual)) => {
+                    assert_eq!(actual.to_string(), expected.to_string())
+                }
+                (expected, actual) => panic!("expected {:?}, got {:?}", expected, actual),
+            }
+        }
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_parallel_workers_kept_until_config_changes() {
+        let request = EditRequest {
+            original_code: "function foo() { return 1; }".to_string(),
+            edit_snippet: "function foo() { return 2; }".to_string(),
+            language: Language::JavaScript,
+            confidence_threshold: 0.5,
+        };
+        let mut booster = AgentBooster::default();
+
+        booster.batch_apply_parallel(vec![request.clone()]);
+        assert_eq!(booster.pool.idle_workers(), 1);
+        booster.batch_apply_parallel(vec![request]);
+        assert_eq!(booster.pool.idle_workers(), 1);
+
+        booster
+            .set_config(Config::default().with_include_patch(true))
+            .unwrap();
+        assert_eq!(booster.pool.idle_workers(), 0);
+    }
+
+    #[test]
+    fn test_lazy_edit_keeps_existing_code() {
+        let mut booster = AgentBooster::new(Config::default()).unwrap();
+
+        let original = r#"
+class Calculator {
+    add(a, b) {
+        return a + b;
+    }
+
+    divide(a, b) {
+        const result = a / b;
+        return result;
+    }
+}
+"#;
+
+        let edit = r#"
+    divide(a, b) {
+        // ... existing code ...
+        console.log(result);
+        return result;
+    }
+"#;
+
+        let request = EditRequest {
+            original_code: original.to_string(),
+            edit_snippet: edit.to_string(),
+            language: Language::JavaScript,
+            confidence_threshold: 0.5,
+        };
+
+        let result = booster.apply_edit(request).unwrap();
+
+        assert!(result.merged_code.contains(
+            "        const result = a / b;\n        console.log(result);\n        return result;"
+        ));
+        assert!(result.merged_code.contains("return a + b;"));
+        assert!(!result.merged_code.contains("existing code"));
+        assert_eq!(result.strategy, MergeStrategy::FuzzyReplace);
+    }
+
     #[test]
     fn test_empty_file() {
         let mut booster = AgentBooster::new(Config::default()).unwrap();
@@ -1,4 +1,7 @@
-use crate::models::{AgentBoosterError, Language, MergeStrategy, Result};
+use crate::alignment::{self, align_lines, IndentUnit};
+use crate::declarations;
+use crate::lazy_edit;
+use crate::models::{AgentBoosterError, CodeChunk, Language, MergeStrategy, Result};
 use crate::parser::Parser;
 use crate::similarity::SearchResult;
 use crate::templates::TemplateEngine;
@@ -24,8 +27,19 @@ impl Merger {
         language: Language,
         confidence_threshold: f32,
     ) -> Result<MergeResult> {
+        // Snippets with `// ... existing code ...` markers only spell out what changes
+        if lazy_edit::has_markers(edit_snippet, language) {
+            return self.merge_lazy(
+                original_code,
+                edit_snippet,
+                std::slice::from_ref(best_match),
+                language,
+                confidence_threshold,
+            );
+        }
+
         // PHASE 1: Try template-based transformation first
-        if let Some(template_match) = TemplateEngine::try_template_transform(original_code, edit_snippet) {
+        if let Some(template_match) = TemplateEngine::try_template_transform(original_code, edit_snippet, language) {
             // Template matched! Use template transformation
             let syntax_valid = self.parser.validate_syntax(&template_match.transformed_code, language);
             let confidence_f32 = template_match.confidence as f32;
@@ -36,22 +50,42 @@ impl Merger {
                     strategy: MergeStrategy::ExactReplace, // Templates are exact transformations
                     confidence: confidence_f32,
                     syntax_valid,
+                    node_type: None,
                 });
             }
         }
 
         // PHASE 2: Fall back to similarity-based merge
         // Select strategy based on similarity
-        let strategy = Self::select_strategy(best_match.similarity, confidence_threshold);
+        let mut strategy = Self::select_strategy(best_match.similarity, confidence_threshold);
+
+        // Insertions must not add a second declaration of an existing symbol
+        let redeclared = match strategy {
+            MergeStrategy::InsertAfter | MergeStrategy::InsertBefore => {
+                self.redeclared_chunk(original_code, edit_snippet, best_match, language)?
+            }
+            _ => None,
+        };
 
         // Apply the merge
-        let merged_code = self.apply_merge(original_code, edit_snippet, best_match, strategy)?;
+        let mut node_type = best_match.chunk.node_type.clone();
+        let (merged_code, score) = match redeclared {
+            Some(declaration) => {
+                node_type = declaration.chunk.node_type.clone();
+                // Replace the existing declaration instead; the shared name
+                // pins the location, so it counts as a full match
+                strategy = MergeStrategy::FuzzyReplace;
+                let code = self.apply_exact_replace(original_code, edit_snippet, &declaration)?;
+                (code, (best_match.similarity + 1.0) / 2.0)
+            }
+            None => self.apply_merge(original_code, edit_snippet, best_match, strategy)?,
+        };
 
         // Validate syntax
         let syntax_valid = self.parser.validate_syntax(&merged_code, language);
 
         // Calculate final confidence
-        let confidence = Self::calculate_confidence(best_match.similarity, strategy, syntax_valid);
+        let confidence = Self::calculate_confidence(score, strategy, syntax_valid);
 
         // Check if confidence meets threshold
         if confidence < confidence_threshold {
@@ -67,9 +101,137 @@ impl Merger {
             strategy,
             confidence,
             syntax_valid,
+            node_type: Some(node_type),
         })
     }
 
+    /// Merge a snippet with elision markers into the first candidate it aligns with
+    ///
+    /// Candidates are tried in order. The elided regions of the chunk are kept
+    /// and confidence comes from how many markers have anchor lines on both sides.
+    pub fn merge_lazy(
+        &mut self,
+        original_code: &str,
+        edit_snippet: &str,
+        candidates: &[SearchResult],
+        language: Language,
+        confidence_threshold: f32,
+    ) -> Result<MergeResult> {
+        // Failure of the best aligned candidate, reported if none succeeds
+        let mut failure = None;
+
+        for candidate in candidates {
+            let chunk = &candidate.chunk;
+            let start = chunk_line_start(original_code, chunk);
+
+            let Some(merged) = lazy_edit::merge_lazy_edit(
+                &original_code[start..chunk.end_byte],
+                edit_snippet,
+                language,
+            ) else {
+                continue;
+            };
+
+            let code = format!(
+                "{}{}{}",
+                &original_code[..start],
+                merged.code,
+                &original_code[chunk.end_byte..]
+            );
+            let syntax_valid = self.parser.validate_syntax(&code, language);
+            let confidence =
+                Self::calculate_confidence(merged.anchor_score, MergeStrategy::FuzzyReplace, syntax_valid);
+
+            if confidence >= confidence_threshold && syntax_valid {
+                return Ok(MergeResult {
+                    code,
+                    strategy: MergeStrategy::FuzzyReplace,
+                    confidence,
+                    syntax_valid,
+                    node_type: Some(chunk.node_type.clone()),
+                });
+            }
+            failure.get_or_insert(if confidence < confidence_threshold {
+                AgentBoosterError::LowConfidence(confidence)
+            } else {
+                AgentBoosterError::InvalidSyntax
+            });
+        }
+
+        Err(failure.unwrap_or(AgentBoosterError::LowConfidence(0.0)))
+    }
+
+    /// Declaration an insertion of `edit_snippet` next to `best_match` would repeat
+    ///
+    /// A symbol declared by the matched chunk, or by a chunk inside it, gives the
+    /// chunk to replace. A symbol declared by a neighbouring chunk, or more than
+    /// one clashing symbol, rejects the edit.
+    fn redeclared_chunk(
+        &mut self,
+        original_code: &str,
+        edit_snippet: &str,
+        best_match: &SearchResult,
+        language: Language,
+    ) -> Result<Option<SearchResult>> {
+        let names = declarations::declared_names(edit_snippet, language);
+        if names.is_empty() {
+            return Ok(None);
+        }
+
+        let matched = &best_match.chunk;
+        let chunks = match self.parser.parse(original_code, language) {
+            Ok(tree) => self.parser.extract_chunks(&tree, original_code),
+            Err(_) => Vec::new(),
+        };
+
+        // Symbol a chunk declares, read from the start of its first line
+        let clash = |chunk: &CodeChunk| {
+            let start = chunk_line_start(original_code, chunk);
+            declarations::declared_names(&original_code[start..chunk.end_byte], language)
+                .into_iter()
+                .next()
+                .filter(|name| names.contains(name))
+        };
+
+        // Outermost chunks come first, so a redeclared symbol maps to its whole declaration
+        let mut clashes: Vec<(String, SearchResult)> = Vec::new();
+        let inside = chunks.iter().enumerate().filter(|(_, c)| {
+            c.start_byte >= matched.start_byte
+                && c.end_byte <= matched.end_byte
+                && (c.start_byte, c.end_byte) != (matched.start_byte, matched.end_byte)
+        });
+        for (chunk_index, chunk) in std::iter::once((best_match.chunk_index, matched)).chain(inside) {
+            if let Some(name) = clash(chunk) {
+                if clashes.iter().all(|(seen, _)| *seen != name) {
+                    let declaration = SearchResult {
+                        chunk: chunk.clone(),
+                        similarity: best_match.similarity,
+                        chunk_index,
+                    };
+                    clashes.push((name, declaration));
+                }
+            }
+        }
+
+        let before = chunks
+            .iter()
+            .filter(|c| c.end_byte <= matched.start_byte)
+            .max_by_key(|c| (c.end_byte, std::cmp::Reverse(c.start_byte)));
+        let after = chunks
+            .iter()
+            .filter(|c| c.start_byte >= matched.end_byte)
+            .min_by_key(|c| (c.start_byte, std::cmp::Reverse(c.end_byte)));
+        if let Some(name) = before.into_iter().chain(after).find_map(&clash) {
+            return Err(AgentBoosterError::DuplicateDeclaration(name));
+        }
+
+        let mut clashes = clashes.into_iter();
+        match (clashes.next(), clashes.next()) {
+            (_, Some((name, _))) => Err(AgentBoosterError::DuplicateDeclaration(name)),
+            (declaration, None) => Ok(declaration.map(|(_, d)| d)),
+        }
+    }
+
     /// Select merge strategy based on similarity score
     fn select_strategy(similarity: f32, threshold: f32) -> MergeStrategy {
         match similarity {
@@ -83,28 +245,36 @@ impl Merger {
     }
 
     /// Apply the actual merge operation
+    ///
+    /// Returns the merged code and the score confidence is derived from: the
+    /// match similarity, blended with the line alignment for fuzzy replaces.
     fn apply_merge(
         &self,
         original_code: &str,
         edit_snippet: &str,
         best_match: &SearchResult,
         strategy: MergeStrategy,
-    ) -> Result<String> {
-        match strategy {
+    ) -> Result<(String, f32)> {
+        let similarity = best_match.similarity;
+        let code = match strategy {
             MergeStrategy::ExactReplace => {
-                self.apply_exact_replace(original_code, edit_snippet, best_match)
+                self.apply_exact_replace(original_code, edit_snippet, best_match)?
             }
             MergeStrategy::FuzzyReplace => {
-                self.apply_fuzzy_replace(original_code, edit_snippet, best_match)
+                let (code, alignment) =
+                    self.apply_fuzzy_replace(original_code, edit_snippet, best_match)?;
+                let score = alignment.map_or(similarity, |a| (similarity + a) / 2.0);
+                return Ok((code, score));
             }
             MergeStrategy::InsertAfter => {
-                self.apply_insert_after(original_code, edit_snippet, best_match)
+                self.apply_insert_after(original_code, edit_snippet, best_match)?
             }
             MergeStrategy::InsertBefore => {
-                self.apply_insert_before(original_code, edit_snippet, best_match)
+                self.apply_insert_before(original_code, edit_snippet, best_match)?
             }
-            MergeStrategy::Append => Ok(self.apply_append(original_code, edit_snippet)),
-        }
+            MergeStrategy::Append => self.apply_append(original_code, edit_snippet),
+        };
+        Ok((code, similarity))
     }
 
     /// Replace exact match with edit snippet
@@ -123,16 +293,110 @@ impl Merger {
         Ok(format!("{}{}{}", before, edit_snippet, after))
     }
 
-    /// Replace with fuzzy matching (similar to exact but more lenient)
+    /// Replace only the region of the chunk the snippet aligns with
+    ///
+    /// Lines of the chunk before the first and after the last aligned line are
+    /// kept, and the snippet is moved onto the region's indentation. Returns the
+    /// merged code with the alignment score (0.0 - 1.0), or `None` if no line
+    /// aligned and the whole chunk was replaced.
     fn apply_fuzzy_replace(
         &self,
         original_code: &str,
         edit_snippet: &str,
         best_match: &SearchResult,
-    ) -> Result<String> {
-        // For now, fuzzy replace is the same as exact replace
-        // In a more advanced version, this could handle partial replacements
-        self.apply_exact_replace(original_code, edit_snippet, best_match)
+    ) -> Result<(String, Option<f32>)> {
+        let chunk = &best_match.chunk;
+        let start = chunk_line_start(original_code, chunk);
+        let target = &original_code[start..chunk.end_byte];
+
+        let chunk_lines: Vec<&str> = target.lines().collect();
+        let snippet_lines: Vec<&str> = edit_snippet.lines().collect();
+        let pairs = align_lines(&chunk_lines, &snippet_lines);
+
+        let (Some(first), Some(last)) = (pairs.first(), pairs.last()) else {
+            // Nothing lines up, so the snippet stands in for the whole chunk
+            let code = self.apply_exact_replace(original_code, edit_snippet, best_match)?;
+            return Ok((code, None));
+        };
+        let mut matched: f32 = pairs.iter().map(|p| p.similarity).sum();
+
+        // Unaligned snippet lines at either edge take over the chunk lines in
+        // the same position while those are still loosely similar
+        let loose_similarity =
+            |a: usize, b: usize| alignment::line_similarity(chunk_lines[a], snippet_lines[b]);
+        let (mut region_start, mut snippet_start) = (first.a, first.b);
+        while region_start > 0 && snippet_start > 0 {
+            let similarity = loose_similarity(region_start - 1, snippet_start - 1);
+            if similarity < alignment::EDGE_MATCH_THRESHOLD {
+                break;
+            }
+            matched += similarity as f32;
+            region_start -= 1;
+            snippet_start -= 1;
+        }
+        let (mut region_end, mut snippet_end) = (last.a, last.b);
+        while region_end + 1 < chunk_lines.len() && snippet_end + 1 < snippet_lines.len() {
+            let similarity = loose_similarity(region_end + 1, snippet_end + 1);
+            if similarity < alignment::EDGE_MATCH_THRESHOLD {
+                break;
+            }
+            matched += similarity as f32;
+            region_end += 1;
+            snippet_end += 1;
+        }
+
+        // A snippet that opens like the chunk and ends on the chunk's closing
+        // line rewrites all of it, even if that line also closes an inner block
+        let closes_chunk = chunk_lines.last().map(|l| l.trim())
+            == snippet_lines.last().map(|l| l.trim());
+        if region_start == 0
+            && snippet_start == 0
+            && snippet_end + 1 == snippet_lines.len()
+            && closes_chunk
+        {
+            region_end = chunk_lines.len() - 1;
+        }
+        let region = &chunk_lines[region_start..=region_end];
+
+        let region_indent = alignment::base_indent(region);
+        let prefix = region
+            .iter()
+            .map(|l| alignment::leading_whitespace(l))
+            .find(|ws| ws.len() == region_indent)
+            .unwrap_or("");
+        let snippet_indent = alignment::base_indent(&snippet_lines);
+        let units = (
+            IndentUnit::detect(snippet_lines.iter().copied()),
+            IndentUnit::detect(original_code.lines()),
+        );
+
+        let mut lines: Vec<String> = chunk_lines[..region_start].iter().map(|l| l.to_string()).collect();
+        lines.extend(
+            snippet_lines
+                .iter()
+                .map(|line| alignment::reindent(line, snippet_indent, prefix, units)),
+        );
+        lines.extend(chunk_lines[region_end + 1..].iter().map(|l| l.to_string()));
+
+        let mut merged = lines.join("\n");
+        if target.ends_with('\n') {
+            merged.push('\n');
+        }
+
+        // Dice-style score: matched similarity over the lines on both sides
+        let non_blank = |lines: &[&str]| lines.iter().filter(|l| !l.trim().is_empty()).count();
+        let total = non_blank(&snippet_lines) + non_blank(region);
+        let score = (2.0 * matched / total.max(1) as f32).min(1.0);
+
+        Ok((
+            format!(
+                "{}{}{}",
+                &original_code[..start],
+                merged,
+                &original_code[chunk.end_byte..]
+            ),
+            Some(score),
+        ))
     }
 
     /// Insert edit snippet after matched chunk
@@ -200,6 +464,20 @@ impl Default for Merger {
     }
 }
 
+/// Byte offset the chunk's first line starts at, if only indentation precedes it
+///
+/// Merges that compare indentation start here rather than at the chunk itself.
+fn chunk_line_start(original_code: &str, chunk: &CodeChunk) -> usize {
+    let line_start = original_code[..chunk.start_byte]
+        .rfind('\n')
+        .map_or(0, |i| i + 1);
+    if original_code[line_start..chunk.start_byte].trim().is_empty() {
+        line_start
+    } else {
+        chunk.start_byte
+    }
+}
+
 /// Result of a merge operation
 #[derive(Debug, Clone)]
 pub struct MergeResult {
@@ -207,6 +485,8 @@ pub struct MergeResult {
     pub strategy: MergeStrategy,
     pub confidence: f32,
     pub syntax_valid: bool,
+    /// Node type of the chunk the snippet was merged into, if any
+    pub node_type: Option<String>,
 }
 
 #[cfg(test)]
@@ -311,4 +591,115 @@ mod tests {
             _ => panic!("Should return LowConfidence error"),
         }
     }
+
+    /// Chunk of `original` that spans the given text
+    fn match_in(original: &str, chunk: &str, similarity: f32) -> SearchResult {
+        let mut test_match = create_test_match(chunk, similarity);
+        test_match.chunk.start_byte = original.find(chunk).unwrap();
+        test_match.chunk.end_byte = test_match.chunk.start_byte + chunk.len();
+        test_match
+    }
+
+    #[test]
+    fn test_fuzzy_replace_keeps_unmatched_lines() {
+        let merger = Merger::new().unwrap();
+        let chunk = "function total(items) {\n    let sum = 0;\n    for (const item of items) {\n        sum += item.price;\n    }\n    return sum;\n}";
+        let original = format!("{}\n", chunk);
+        let edit = "for (const item of items) {\n    sum += item.price * item.quantity;\n}";
+
+        let (code, score) = merger
+            .apply_fuzzy_replace(&original, edit, &match_in(&original, chunk, 0.6))
+            .unwrap();
+
+        assert_eq!(
+            code,
+            "function total(items) {\n    let sum = 0;\n    for (const item of items) {\n        sum += item.price * item.quantity;\n    }\n    return sum;\n}\n"
+        );
+        let score = score.unwrap();
+        assert!(score > 0.8 && score < 1.0);
+    }
+
+    #[test]
+    fn test_fuzzy_replace_adapts_indentation() {
+        let merger = Merger::new().unwrap();
+        let chunk = "add(a, b) {\n\t\treturn a + b;\n\t}";
+        let original = format!("class Calc {{\n\t{}\n}}", chunk);
+        let edit = "add(a, b) {\n    return a + b + 0;\n}";
+
+        let (code, _) = merger
+            .apply_fuzzy_replace(&original, edit, &match_in(&original, chunk, 0.8))
+            .unwrap();
+
+        assert_eq!(code, "class Calc {\n\tadd(a, b) {\n\t\treturn a + b + 0;\n\t}\n}");
+    }
+
+    #[test]
+    fn test_fuzzy_replace_rewrites_whole_chunk() {
+        let merger = Merger::new().unwrap();
+        let original = "function f(x) {\n  if (x) {\n    a();\n  }\n  return b;\n}";
+        let edit = "function f(x) {\n  a();\n}";
+
+        let (code, _) = merger
+            .apply_fuzzy_replace(original, edit, &match_in(original, original, 0.6))
+            .unwrap();
+
+        assert_eq!(code, edit);
+    }
+
+    #[test]
+    fn test_fuzzy_confidence_uses_alignment() {
+        let mut merger = Merger::new().unwrap();
+        let chunk = "function scale(x) {\n    const factor = 2;\n    return x * factor;\n}";
+        let original = format!("{}\nfunction other() {{ return 0; }}", chunk);
+
+        // A snippet that lines up with the chunk scores above its similarity
+        let aligned = merger
+            .merge(
+                &original,
+                "function scale(x) {\n    const factor = 3;\n    return x * factor;\n}",
+                &match_in(&original, chunk, 0.6),
+                Language::JavaScript,
+                0.3,
+            )
+            .unwrap();
+        assert_eq!(aligned.strategy, MergeStrategy::FuzzyReplace);
+        assert!(aligned.confidence > 0.6 * 0.95);
+        assert!(aligned.code.contains("function other()"));
+
+        // One that only shares the outline of the chunk scores below it
+        let unaligned = merger
+            .merge(
+                &original,
+                "function scale(x) {\n    const unrelated = require('fs');\n    console.log(unrelated);\n}",
+                &match_in(&original, chunk, 0.6),
+                Language::JavaScript,
+                0.1,
+            )
+            .unwrap();
+        assert!(unaligned.confidence < 0.6 * 0.95);
+    }
+
+    #[test]
+    fn test_fuzzy_replace_takes_over_loose_edge_lines() {
+        let merger = Merger::new().unwrap();
+        let original = "function add(a, b) {\n  return a + b;\n}\n";
+        let edit = "function add(a: number, b: number): number {\n  return a + b;\n}";
+
+        let (code, _) = merger
+            .apply_fuzzy_replace(original, edit, &match_in(original, original.trim_end(), 0.6))
+            .unwrap();
+        assert_eq!(code, format!("{}\n", edit));
+
+        // Without a single aligned line the whole chunk is replaced
+        let original = "function foo(){}";
+        let (code, score) = merger
+            .apply_fuzzy_replace(
+                original,
+                "function foo(){console.log('called')}",
+                &match_in(original, original, 0.6),
+            )
+            .unwrap();
+        assert_eq!(code, "function foo(){console.log('called')}");
+        assert_eq!(score, None);
+    }
 }
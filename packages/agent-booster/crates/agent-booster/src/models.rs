@@ -1,4 +1,7 @@
+use crate::patch::Patch;
 use serde::{Deserialize, Serialize};
+use std::ops::Range;
+use std::path::PathBuf;
 
 /// Supported programming languages
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -28,6 +31,21 @@ impl Language {
         }
     }
 
+    /// Language of a source file, by its extension
+    pub fn from_extension(ext: &str) -> Option<Self> {
+        match ext.to_lowercase().as_str() {
+            "js" | "jsx" | "mjs" | "cjs" => Some(Language::JavaScript),
+            "ts" | "tsx" | "mts" | "cts" => Some(Language::TypeScript),
+            "py" | "pyi" => Some(Language::Python),
+            "rs" => Some(Language::Rust),
+            "go" => Some(Language::Go),
+            "java" => Some(Language::Java),
+            "c" | "h" => Some(Language::C),
+            "cc" | "cpp" | "cxx" | "hh" | "hpp" | "hxx" => Some(Language::Cpp),
+            _ => None,
+        }
+    }
+
     pub fn as_str(&self) -> &'static str {
         match self {
             Language::JavaScript => "javascript",
@@ -123,6 +141,23 @@ pub struct EditResult {
     pub strategy: MergeStrategy,
     /// Metadata about the edit
     pub metadata: EditMetadata,
+    /// What the edit changed, when `Config::include_patch` is set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub patch: Option<Patch>,
+}
+
+impl EditResult {
+    /// Apply this edit's patch to another copy of the original code
+    ///
+    /// The copy may have drifted; hunks are placed by their context.
+    pub fn apply_to(&self, code: &str) -> Result<String> {
+        let patch = self.patch.as_ref().ok_or_else(|| {
+            AgentBoosterError::ConfigError(
+                "edit result has no patch (enable `include_patch`)".to_string(),
+            )
+        })?;
+        crate::patch::apply_patch(code, patch)
+    }
 }
 
 /// Metadata about an edit operation
@@ -139,8 +174,110 @@ pub struct EditMetadata {
     pub processing_time_ms: Option<u64>,
 }
 
+/// Request to apply several edits to one file as a single transaction
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransactionRequest {
+    /// Original source code
+    pub original_code: String,
+    /// Edit snippets to apply, in order
+    pub edit_snippets: Vec<String>,
+    /// Programming language
+    pub language: Language,
+    /// Minimum confidence threshold for every edit (0.0 - 1.0)
+    #[serde(default = "default_confidence_threshold")]
+    pub confidence_threshold: f32,
+}
+
+/// Result of a transaction
+///
+/// When the transaction is not committed, `merged_code` is the original code
+/// and the failing edits carry an error.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransactionResult {
+    /// Code after applying every edit, or the original code
+    pub merged_code: String,
+    /// Whether all edits were applied
+    pub committed: bool,
+    /// Whether the combined result passed syntax validation; `None` if the
+    /// transaction was rolled back before it was validated
+    pub syntax_valid: Option<bool>,
+    /// Report for each edit, in request order
+    pub edits: Vec<TransactionEdit>,
+}
+
+/// Report for one edit of a transaction
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransactionEdit {
+    /// Position of the snippet in the request
+    pub index: usize,
+    /// Strategy the edit was merged with, if it matched
+    pub strategy: Option<MergeStrategy>,
+    /// Confidence score (0.0 - 1.0)
+    pub confidence: f32,
+    /// Byte range of the original code the edit rewrites
+    pub original_range: Option<Range<usize>>,
+    /// Byte range of the edit's replacement in the merged code, if committed
+    pub merged_range: Option<Range<usize>>,
+    /// Why the edit could not be applied
+    pub error: Option<String>,
+}
+
+/// Request to apply an edit somewhere in a workspace
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkspaceEditRequest {
+    /// Edit snippet to apply
+    pub edit_snippet: String,
+    /// Free-form description of the target, e.g. "the user service"
+    #[serde(default)]
+    pub target_hint: Option<String>,
+    /// Only consider files in this language
+    #[serde(default)]
+    pub language: Option<Language>,
+    /// Minimum confidence threshold (0.0 - 1.0)
+    #[serde(default = "default_confidence_threshold")]
+    pub confidence_threshold: f32,
+}
+
+/// Result of a workspace edit
+///
+/// When the best locations are too close to call, nothing is applied:
+/// `ambiguous` is set and the caller picks from `candidates`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkspaceEditResult {
+    /// File the edit was applied to, relative to the workspace root
+    pub path: Option<PathBuf>,
+    /// The edit, applied to that file's code (the file itself is not written)
+    pub edit: Option<EditResult>,
+    /// Whether several files matched about equally well
+    pub ambiguous: bool,
+    /// Best locations for the snippet, best first
+    pub candidates: Vec<WorkspaceCandidate>,
+}
+
+/// A chunk of a workspace file ranked against a snippet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkspaceCandidate {
+    /// File path, relative to the workspace root
+    pub path: PathBuf,
+    /// Language of the file
+    pub language: Language,
+    /// The matched chunk
+    pub chunk: CodeChunk,
+    /// Similarity of the chunk to the snippet (0.0 - 1.0)
+    pub similarity: f32,
+    /// How well the path matches the target hint (0.0 - 1.0)
+    pub path_score: f32,
+    /// Combined ranking score (0.0 - 1.0)
+    pub score: f32,
+}
+
 /// Configuration for AgentBooster
+///
+/// Start from `Config::default()` and chain the `with_*` methods; new options
+/// may be added, so the struct can't be built with a literal outside this
+/// crate.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct Config {
     /// Minimum confidence threshold for applying edits
     #[serde(default = "default_confidence_threshold")]
@@ -148,6 +285,12 @@ pub struct Config {
     /// Maximum number of chunks to consider
     #[serde(default = "default_max_chunks")]
     pub max_chunks: usize,
+    /// Include a unified diff and hunks in every `EditResult`
+    #[serde(default)]
+    pub include_patch: bool,
+    /// YAML or TOML files of extra transformation templates
+    #[serde(default)]
+    pub template_files: Vec<PathBuf>,
 }
 
 fn default_max_chunks() -> usize {
@@ -159,10 +302,38 @@ impl Default for Config {
         Self {
             confidence_threshold: default_confidence_threshold(),
             max_chunks: default_max_chunks(),
+            include_patch: false,
+            template_files: Vec::new(),
         }
     }
 }
 
+impl Config {
+    /// Set the minimum confidence threshold
+    pub fn with_confidence_threshold(mut self, threshold: f32) -> Self {
+        self.confidence_threshold = threshold;
+        self
+    }
+
+    /// Set the maximum number of chunks to consider
+    pub fn with_max_chunks(mut self, max_chunks: usize) -> Self {
+        self.max_chunks = max_chunks;
+        self
+    }
+
+    /// Include a patch in every `EditResult`
+    pub fn with_include_patch(mut self, include_patch: bool) -> Self {
+        self.include_patch = include_patch;
+        self
+    }
+
+    /// Set the files of extra transformation templates
+    pub fn with_template_files(mut self, template_files: Vec<PathBuf>) -> Self {
+        self.template_files = template_files;
+        self
+    }
+}
+
 /// Errors that can occur in AgentBooster
 #[derive(Debug, thiserror::Error)]
 pub enum AgentBoosterError {
@@ -175,6 +346,15 @@ pub enum AgentBoosterError {
     #[error("Merge resulted in invalid syntax")]
     InvalidSyntax,
 
+    #[error("Edit would redeclare `{0}`")]
+    DuplicateDeclaration(String),
+
+    #[error("Edit overlaps edit {0} of the same transaction")]
+    OverlappingEdit(usize),
+
+    #[error("Hunk {0} of the patch does not match the code")]
+    PatchConflict(usize),
+
     #[error("Unsupported language: {0}")]
     UnsupportedLanguage(String),
 
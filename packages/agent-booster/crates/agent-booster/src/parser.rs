@@ -1,40 +1,164 @@
 use crate::models::{AgentBoosterError, CodeChunk, Language, Result};
-use tree_sitter::{Parser as TSParser, Tree};
+use std::collections::HashMap;
+use tree_sitter::{Node, Parser as TSParser, Tree};
+
+/// Every language a grammar can be compiled in for
+const LANGUAGES: [Language; 8] = [
+    Language::JavaScript,
+    Language::TypeScript,
+    Language::Python,
+    Language::Rust,
+    Language::Go,
+    Language::Java,
+    Language::C,
+    Language::Cpp,
+];
+
+const JS_CHUNKS: &[&str] = &[
+    "function_declaration",
+    "function_expression",
+    "arrow_function",
+    "method_definition",
+    "class_declaration",
+    "class",
+    "interface_declaration",
+    "type_alias_declaration",
+    "enum_declaration",
+    "variable_declaration",
+    "export_statement",
+    "import_statement",
+];
+
+const PYTHON_CHUNKS: &[&str] = &[
+    "function_definition",
+    "class_definition",
+    "decorated_definition",
+];
+
+const RUST_CHUNKS: &[&str] = &[
+    "function_item",
+    "impl_item",
+    "struct_item",
+    "enum_item",
+    "union_item",
+    "trait_item",
+    "mod_item",
+    "type_item",
+    "macro_definition",
+];
+
+const GO_CHUNKS: &[&str] = &[
+    "function_declaration",
+    "method_declaration",
+    "type_declaration",
+];
+
+const JAVA_CHUNKS: &[&str] = &[
+    "class_declaration",
+    "interface_declaration",
+    "enum_declaration",
+    "record_declaration",
+    "method_declaration",
+    "constructor_declaration",
+];
+
+const C_CHUNKS: &[&str] = &[
+    "function_definition",
+    "struct_specifier",
+    "union_specifier",
+    "enum_specifier",
+    "type_definition",
+];
+
+const CPP_CHUNKS: &[&str] = &[
+    "function_definition",
+    "struct_specifier",
+    "union_specifier",
+    "enum_specifier",
+    "type_definition",
+    "class_specifier",
+    "namespace_definition",
+    "template_declaration",
+];
+
+/// Node types extracted as chunks for `language`
+///
+/// C and C++ `*_specifier` nodes only count when they have a body, so a
+/// `struct point p;` declaration is not mistaken for the struct itself.
+pub fn chunk_node_types(language: Language) -> &'static [&'static str] {
+    match language {
+        Language::JavaScript | Language::TypeScript => JS_CHUNKS,
+        Language::Python => PYTHON_CHUNKS,
+        Language::Rust => RUST_CHUNKS,
+        Language::Go => GO_CHUNKS,
+        Language::Java => JAVA_CHUNKS,
+        Language::C => C_CHUNKS,
+        Language::Cpp => CPP_CHUNKS,
+    }
+}
+
+/// Grammar for `language`, if it is compiled into this build
+pub(crate) fn grammar(language: Language) -> Option<tree_sitter::Language> {
+    match language {
+        Language::JavaScript => Some(tree_sitter_javascript::language()),
+        Language::TypeScript => Some(tree_sitter_typescript::language_typescript()),
+        #[cfg(feature = "lang-python")]
+        Language::Python => Some(tree_sitter_python::language()),
+        #[cfg(feature = "lang-rust")]
+        Language::Rust => Some(tree_sitter_rust::language()),
+        #[cfg(feature = "lang-go")]
+        Language::Go => Some(tree_sitter_go::language()),
+        #[cfg(feature = "lang-java")]
+        Language::Java => Some(tree_sitter_java::language()),
+        #[cfg(feature = "lang-c")]
+        Language::C => Some(tree_sitter_c::language()),
+        #[cfg(feature = "lang-cpp")]
+        Language::Cpp => Some(tree_sitter_cpp::language()),
+        #[allow(unreachable_patterns)]
+        _ => None,
+    }
+}
 
 /// Parser for extracting code chunks using tree-sitter
+///
+/// JavaScript and TypeScript are always available. The other grammars are
+/// compiled in with the `lang-*` features (or `all-languages`); parsing a
+/// language without its grammar fails with `UnsupportedLanguage`.
 pub struct Parser {
-    js_parser: TSParser,
-    ts_parser: TSParser,
+    parsers: HashMap<Language, TSParser>,
 }
 
 impl Parser {
-    /// Create a new parser with JavaScript and TypeScript support
+    /// Create a new parser with every grammar compiled into this build
     pub fn new() -> Result<Self> {
-        let mut js_parser = TSParser::new();
-        js_parser
-            .set_language(&tree_sitter_javascript::language())
-            .map_err(|e| AgentBoosterError::ParseError(e.to_string()))?;
+        let mut parsers = HashMap::new();
+        for language in LANGUAGES {
+            if let Some(grammar) = grammar(language) {
+                let mut parser = TSParser::new();
+                parser
+                    .set_language(&grammar)
+                    .map_err(|e| AgentBoosterError::ParseError(e.to_string()))?;
+                parsers.insert(language, parser);
+            }
+        }
 
-        let mut ts_parser = TSParser::new();
-        ts_parser
-            .set_language(&tree_sitter_typescript::language_typescript())
-            .map_err(|e| AgentBoosterError::ParseError(e.to_string()))?;
+        Ok(Self { parsers })
+    }
 
-        Ok(Self {
-            js_parser,
-            ts_parser,
-        })
+    /// Whether a grammar for `language` is compiled in
+    pub fn supports(&self, language: Language) -> bool {
+        self.parsers.contains_key(&language)
     }
 
     /// Parse code into a tree-sitter Tree
     pub fn parse(&mut self, code: &str, language: Language) -> Result<Tree> {
-        let parser = match language {
-            Language::JavaScript => &mut self.js_parser,
-            Language::TypeScript => &mut self.ts_parser,
-            // For other languages in native build, fall back to JavaScript parser
-            // (This is only used when tree-sitter feature is enabled, which is native-only)
-            _ => &mut self.js_parser,
-        };
+        let parser = self.parsers.get_mut(&language).ok_or_else(|| {
+            AgentBoosterError::UnsupportedLanguage(format!(
+                "{} (enable the `lang-{}` feature)",
+                language.as_str(),
+                language.as_str()
+            ))
+        })?;
 
         parser
             .parse(code, None)
@@ -45,45 +169,43 @@ impl Parser {
     pub fn extract_chunks(&self, tree: &Tree, code: &str) -> Vec<CodeChunk> {
         let mut chunks = Vec::new();
         let root = tree.root_node();
+        let node_types = chunk_node_types(self.language_of(tree));
 
-        self.extract_chunks_recursive(root, code, None, &mut chunks);
+        Self::extract_chunks_recursive(root, code, node_types, None, &mut chunks);
 
         // Sort by start position
         chunks.sort_by_key(|c| c.start_byte);
         chunks
     }
 
+    /// Language whose grammar produced `tree`
+    fn language_of(&self, tree: &Tree) -> Language {
+        let tree_language = tree.language();
+        self.parsers
+            .iter()
+            .find(|(_, parser)| parser.language().as_ref() == Some(&*tree_language))
+            .map(|(language, _)| *language)
+            .unwrap_or(Language::JavaScript)
+    }
+
     fn extract_chunks_recursive(
-        &self,
-        node: tree_sitter::Node,
+        node: Node,
         code: &str,
+        node_types: &[&str],
         parent_type: Option<String>,
         chunks: &mut Vec<CodeChunk>,
     ) {
         let node_type = node.kind();
 
         // Extract meaningful code blocks
-        let is_chunk = matches!(
-            node_type,
-            "function_declaration"
-                | "function_expression"
-                | "arrow_function"
-                | "method_definition"
-                | "class_declaration"
-                | "class"
-                | "interface_declaration"
-                | "type_alias_declaration"
-                | "enum_declaration"
-                | "variable_declaration"
-                | "export_statement"
-                | "import_statement"
-        );
+        let is_chunk = node_types.contains(&node_type)
+            && (!node_type.ends_with("_specifier") || node.child_by_field_name("body").is_some());
 
         if is_chunk && !node.is_error() {
             let start_byte = node.start_byte();
             let end_byte = node.end_byte();
 
-            if let Ok(chunk_code) = std::str::from_utf8(&code.as_bytes()[start_byte..end_byte]) {
+            if let Some(chunk_code) = code.get(start_byte..end_byte) {
                 let start_line = node.start_position().row;
                 let end_line = node.end_position().row;
 
@@ -103,16 +225,29 @@ impl Parser {
         let current_type = Some(node_type.to_string());
         let mut cursor = node.walk();
         for child in node.children(&mut cursor) {
-            self.extract_chunks_recursive(child, code, current_type.clone(), chunks);
+            Self::extract_chunks_recursive(child, code, node_types, current_type.clone(), chunks);
         }
     }
 
     /// Validate that code has valid syntax
+    ///
+    /// Any `ERROR` or `MISSING` node in the language's own grammar fails
+    /// validation, as does a language without a compiled-in grammar.
     pub fn validate_syntax(&mut self, code: &str, language: Language) -> bool {
-        match self.parse(code, language) {
-            Ok(tree) => !tree.root_node().has_error(),
-            Err(_) => false,
-        }
+        matches!(self.find_syntax_error(code, language), Ok(None))
+    }
+
+    /// Position (row, column; 0-indexed) of the first syntax error, if any
+    pub fn find_syntax_error(
+        &mut self,
+        code: &str,
+        language: Language,
+    ) -> Result<Option<(usize, usize)>> {
+        let tree = self.parse(code, language)?;
+        Ok(first_error(tree.root_node()).map(|node| {
+            let position = node.start_position();
+            (position.row, position.column)
+        }))
     }
 
     /// Extract full file as a single chunk (fallback for small files)
@@ -130,6 +265,19 @@ impl Parser {
     }
 }
 
+/// First error or missing node in document order
+fn first_error(node: Node) -> Option<Node> {
+    if node.is_error() || node.is_missing() {
+        return Some(node);
+    }
+    if !node.has_error() {
+        return None;
+    }
+    let mut cursor = node.walk();
+    let children: Vec<Node> = node.children(&mut cursor).collect();
+    children.into_iter().find_map(first_error)
+}
+
 impl Default for Parser {
     fn default() -> Self {
         Self::new().expect("Failed to initialize parser")
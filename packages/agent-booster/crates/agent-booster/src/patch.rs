@@ -0,0 +1,373 @@
+//! Patches describing what a merge changed
+//!
+//! A [`Patch`] is a line diff of the original and merged code, both as a
+//! unified diff and as structured [`Hunk`]s. Hunks keep a few unchanged lines
+//! of context, so [`apply_patch`] can still place them in a copy of the file
+//! that has drifted since the edit was made.
+
+use crate::models::{AgentBoosterError, MergeStrategy, Result};
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+/// Unchanged lines kept around each hunk
+pub const CONTEXT_LINES: usize = 3;
+
+/// Largest line matrix diffed line by line; bigger regions become one hunk
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+/// Changes made by an edit
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Patch {
+    /// Unified diff of the original and merged code
+    pub unified_diff: String,
+    /// Changed regions, in order
+    pub hunks: Vec<Hunk>,
+}
+
+/// One changed region
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Hunk {
+    /// Byte range of the original code that is replaced
+    pub original_range: Range<usize>,
+    /// Lines of the original code that are replaced (0-indexed)
+    pub original_lines: Range<usize>,
+    /// Lines of the merged code that replace them (0-indexed)
+    pub merged_lines: Range<usize>,
+    /// Original text that is replaced
+    pub original_text: String,
+    /// Text that replaces it
+    pub replacement: String,
+    /// Unchanged lines just before the hunk
+    pub context_before: String,
+    /// Unchanged lines just after the hunk
+    pub context_after: String,
+    /// Node type of the chunk the edit matched, if it matched one
+    pub node_type: Option<String>,
+    /// Strategy used for the edit
+    pub strategy: MergeStrategy,
+}
+
+impl Patch {
+    /// Diff `original` against `merged`, attributing every hunk to one edit
+    pub fn new(
+        original: &str,
+        merged: &str,
+        node_type: Option<&str>,
+        strategy: MergeStrategy,
+    ) -> Self {
+        let a: Vec<&str> = original.split_inclusive('\n').collect();
+        let b: Vec<&str> = merged.split_inclusive('\n').collect();
+
+        // Byte offset of every line start in the original, plus the end
+        let mut offsets = vec![0];
+        offsets.extend(a.iter().scan(0, |end, line| {
+            *end += line.len();
+            Some(*end)
+        }));
+
+        let regions = diff_lines(&a, &b);
+        let hunks = regions
+            .iter()
+            .enumerate()
+            .map(|(i, (old, new))| {
+                let previous_end = if i == 0 { 0 } else { regions[i - 1].0.end };
+                let next_start = regions.get(i + 1).map_or(a.len(), |r| r.0.start);
+                let before = old.start.saturating_sub(CONTEXT_LINES).max(previous_end);
+                let after = (old.end + CONTEXT_LINES).min(next_start);
+
+                Hunk {
+                    original_range: offsets[old.start]..offsets[old.end],
+                    original_lines: old.clone(),
+                    merged_lines: new.clone(),
+                    original_text: a[old.clone()].concat(),
+                    replacement: b[new.clone()].concat(),
+                    context_before: a[before..old.start].concat(),
+                    context_after: a[old.end..after].concat(),
+                    node_type: node_type.map(str::to_string),
+                    strategy,
+                }
+            })
+            .collect();
+
+        Self {
+            unified_diff: unified_diff(&a, &b, &regions),
+            hunks,
+        }
+    }
+
+    /// Whether the edit changed nothing
+    pub fn is_empty(&self) -> bool {
+        self.hunks.is_empty()
+    }
+}
+
+/// Changed regions between two line sequences, as `(original, merged)` line ranges
+fn diff_lines(a: &[&str], b: &[&str]) -> Vec<(Range<usize>, Range<usize>)> {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let (a_mid, b_mid) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+
+    if a_mid.is_empty() && b_mid.is_empty() {
+        return Vec::new();
+    }
+    if a_mid.len() * b_mid.len() > MAX_DIFF_CELLS {
+        return vec![(prefix..a.len() - suffix, prefix..b.len() - suffix)];
+    }
+
+    // lcs[i][j]: longest common subsequence of a_mid[i..] and b_mid[j..]
+    let (n, m) = (a_mid.len(), b_mid.len());
+    let mut lcs = vec![vec![0u32; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if a_mid[i] == b_mid[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut regions = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    let mut open: Option<(usize, usize)> = None;
+    while i < n || j < m {
+        if i < n && j < m && a_mid[i] == b_mid[j] {
+            if let Some((si, sj)) = open.take() {
+                regions.push((prefix + si..prefix + i, prefix + sj..prefix + j));
+            }
+            i += 1;
+            j += 1;
+            continue;
+        }
+        open.get_or_insert((i, j));
+        if j < m && (i == n || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            j += 1;
+        } else {
+            i += 1;
+        }
+    }
+    if let Some((si, sj)) = open {
+        regions.push((prefix + si..prefix + n, prefix + sj..prefix + m));
+    }
+    regions
+}
+
+/// Render regions as a unified diff, merging hunks whose context touches
+fn unified_diff(a: &[&str], b: &[&str], regions: &[(Range<usize>, Range<usize>)]) -> String {
+    if regions.is_empty() {
+        return String::new();
+    }
+
+    let mut diff = String::from("--- original\n+++ merged\n");
+    let mut k = 0;
+    while k < regions.len() {
+        // Extend the group while the gap to the next region fits in the context
+        let mut last = k;
+        while last + 1 < regions.len()
+            && regions[last + 1].0.start - regions[last].0.end <= 2 * CONTEXT_LINES
+        {
+            last += 1;
+        }
+
+        let (first_old, first_new) = &regions[k];
+        let lead = first_old.start.min(CONTEXT_LINES);
+        let (old_start, new_start) = (first_old.start - lead, first_new.start - lead);
+        let trail = (a.len() - regions[last].0.end).min(CONTEXT_LINES);
+        let old_end = regions[last].0.end + trail;
+        let new_end = regions[last].1.end + trail;
+
+        diff.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            hunk_range(old_start, old_end - old_start),
+            hunk_range(new_start, new_end - new_start)
+        ));
+
+        let mut old_line = old_start;
+        for (old, new) in &regions[k..=last] {
+            push_lines(&mut diff, ' ', &a[old_line..old.start]);
+            push_lines(&mut diff, '-', &a[old.clone()]);
+            push_lines(&mut diff, '+', &b[new.clone()]);
+            old_line = old.end;
+        }
+        push_lines(&mut diff, ' ', &a[old_line..old_end]);
+
+        k = last + 1;
+    }
+    diff
+}
+
+/// `start,count` of a hunk header, 1-indexed
+fn hunk_range(start: usize, count: usize) -> String {
+    // An empty range names the line before it
+    let start = if count == 0 { start } else { start + 1 };
+    if count == 1 {
+        start.to_string()
+    } else {
+        format!("{},{}", start, count)
+    }
+}
+
+fn push_lines(diff: &mut String, prefix: char, lines: &[&str]) {
+    for line in lines {
+        diff.push(prefix);
+        diff.push_str(line);
+        if !line.ends_with('\n') {
+            diff.push_str("\n\\ No newline at end of file\n");
+        }
+    }
+}
+
+/// Apply a patch to `code`, which may have drifted from the patch's original
+///
+/// Each hunk is located by its original text and surrounding context, nearest
+/// to where it was in the original. If that fails, context lines are dropped
+/// from the outside in, as `patch` does with its fuzz factor. A hunk that
+/// cannot be placed fails the whole patch.
+pub fn apply_patch(code: &str, patch: &Patch) -> Result<String> {
+    let target: Vec<&str> = code.split_inclusive('\n').collect();
+
+    let mut merged = String::with_capacity(code.len());
+    let mut cursor = 0;
+    // How far the target has shifted from the original, in lines
+    let mut drift: isize = 0;
+
+    for (index, hunk) in patch.hunks.iter().enumerate() {
+        let before: Vec<&str> = hunk.context_before.split_inclusive('\n').collect();
+        let removed: Vec<&str> = hunk.original_text.split_inclusive('\n').collect();
+        let after: Vec<&str> = hunk.context_after.split_inclusive('\n').collect();
+        let expected = (hunk.original_lines.start as isize + drift).max(0) as usize;
+
+        let start = (0..=CONTEXT_LINES)
+            .find_map(|fuzz| {
+                let before = &before[fuzz.min(before.len())..];
+                let after = &after[..after.len() - fuzz.min(after.len())];
+                let block: Vec<&str> = [before, &removed[..], after].concat();
+
+                locate(
+                    &target,
+                    &block,
+                    cursor,
+                    expected.saturating_sub(before.len()),
+                )
+                .map(|at| at + before.len())
+            })
+            .ok_or(AgentBoosterError::PatchConflict(index))?;
+
+        merged.push_str(&target[cursor..start].concat());
+        merged.push_str(&hunk.replacement);
+        cursor = start + removed.len();
+        drift = start as isize - hunk.original_lines.start as isize;
+    }
+    merged.push_str(&target[cursor..].concat());
+
+    Ok(merged)
+}
+
+/// Position at or after `from` where `block` occurs in `target`, nearest `near`
+fn locate(target: &[&str], block: &[&str], from: usize, near: usize) -> Option<usize> {
+    if block.is_empty() {
+        return Some(near.clamp(from, target.len()));
+    }
+    if target.len() < block.len() {
+        return None;
+    }
+
+    let same =
+        |x: &str, y: &str| x.trim_end_matches(['\n', '\r']) == y.trim_end_matches(['\n', '\r']);
+    (from..=target.len() - block.len())
+        .filter(|&at| block.iter().zip(&target[at..]).all(|(x, y)| same(x, y)))
+        .min_by_key(|&at| at.abs_diff(near))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ORIGINAL: &str = "function a() {\n    return 1;\n}\n\nfunction b() {\n    return 2;\n}\n\nfunction c() {\n    return 3;\n}\n";
+
+    fn patch(merged: &str) -> Patch {
+        Patch::new(
+            ORIGINAL,
+            merged,
+            Some("function_declaration"),
+            MergeStrategy::FuzzyReplace,
+        )
+    }
+
+    #[test]
+    fn test_hunks() {
+        let merged = ORIGINAL.replace("return 2;", "return 20;");
+        let patch = patch(&merged);
+
+        assert_eq!(patch.hunks.len(), 1);
+        let hunk = &patch.hunks[0];
+        assert_eq!(hunk.original_lines, 5..6);
+        assert_eq!(hunk.merged_lines, 5..6);
+        assert_eq!(&ORIGINAL[hunk.original_range.clone()], "    return 2;\n");
+        assert_eq!(hunk.replacement, "    return 20;\n");
+        assert_eq!(hunk.context_before, "}\n\nfunction b() {\n");
+        assert_eq!(hunk.context_after, "}\n\nfunction c() {\n");
+        assert_eq!(hunk.node_type.as_deref(), Some("function_declaration"));
+
+        assert!(Patch::new(ORIGINAL, ORIGINAL, None, MergeStrategy::ExactReplace).is_empty());
+    }
+
+    #[test]
+    fn test_unified_diff() {
+        let merged = ORIGINAL
+            .replace("return 1;", "return 10;")
+            .replace("return 3;", "return 30;\n    // done");
+        let diff = patch(&merged).unified_diff;
+
+        assert_eq!(
+            diff,
+            "--- original\n+++ merged\n\
+             @@ -1,5 +1,5 @@\n function a() {\n-    return 1;\n+    return 10;\n }\n \n function b() {\n\
+             @@ -7,5 +7,6 @@\n }\n \n function c() {\n-    return 3;\n+    return 30;\n+    // done\n }\n"
+        );
+
+        let diff = Patch::new("a\nb", "a\nc", None, MergeStrategy::ExactReplace).unified_diff;
+        assert_eq!(
+            diff,
+            "--- original\n+++ merged\n@@ -1,2 +1,2 @@\n a\n-b\n\\ No newline at end of file\n+c\n\\ No newline at end of file\n"
+        );
+    }
+
+    #[test]
+    fn test_apply_patch_to_drifted_code() {
+        let merged = ORIGINAL.replace("return 3;", "return 30;");
+        let patch = patch(&merged);
+
+        assert_eq!(apply_patch(ORIGINAL, &patch).unwrap(), merged);
+
+        // Lines added above the hunk shift it down
+        let drifted = format!("import x from 'x';\n\n{}", ORIGINAL);
+        assert_eq!(
+            apply_patch(&drifted, &patch).unwrap(),
+            format!("import x from 'x';\n\n{}", merged)
+        );
+
+        // A changed context line is tolerated
+        let drifted = ORIGINAL.replace("function c() {", "function c(x) {");
+        assert_eq!(
+            apply_patch(&drifted, &patch).unwrap(),
+            merged.replace("function c() {", "function c(x) {")
+        );
+    }
+
+    #[test]
+    fn test_apply_patch_conflict() {
+        let patch = patch(&ORIGINAL.replace("return 3;", "return 30;"));
+        let drifted = ORIGINAL.replace("return 3;", "return 4;");
+
+        assert!(matches!(
+            apply_patch(&drifted, &patch),
+            Err(AgentBoosterError::PatchConflict(0))
+        ));
+    }
+}
@@ -0,0 +1,112 @@
+//! Pool of edit workers for applying edits from several threads
+//!
+//! Parsers are mutable, so each thread needs its own. A [`WorkerPool`] hands
+//! out idle workers and creates new ones on demand, so a batch ends up with
+//! about one worker per thread, reused across batches.
+//!
+//! Workers keep no state between edits, so which worker handles an edit never
+//! changes its result.
+
+use crate::{AgentBooster, AgentBoosterError, Config, EditRequest, EditResult, Result};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Mutex;
+
+/// Thread-safe pool of [`AgentBooster`] workers sharing one configuration
+pub struct WorkerPool {
+    config: Config,
+    idle: Mutex<Vec<AgentBooster>>,
+}
+
+impl WorkerPool {
+    pub fn new(config: Config) -> Self {
+        Self {
+            config,
+            idle: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Apply one edit on an idle worker
+    ///
+    /// A panic while editing is reported as an `InternalError` for this edit
+    /// only; the worker that panicked is discarded.
+    pub fn apply_edit(&self, request: EditRequest) -> Result<EditResult> {
+        let mut worker = match self.checkout() {
+            Some(worker) => worker,
+            None => AgentBooster::new(self.config.clone())?,
+        };
+
+        match panic::catch_unwind(AssertUnwindSafe(|| worker.apply_edit(request))) {
+            Ok(result) => {
+                self.checkin(worker);
+                result
+            }
+            Err(payload) => {
+                let message = payload
+                    .downcast_ref::<&str>()
+                    .map(|s| s.to_string())
+                    .or_else(|| payload.downcast_ref::<String>().cloned())
+                    .unwrap_or_else(|| "unknown panic".to_string());
+                Err(AgentBoosterError::InternalError(format!(
+                    "edit panicked: {}",
+                    message
+                )))
+            }
+        }
+    }
+
+    /// Apply edits on the rayon thread pool
+    ///
+    /// Results are in the same order as `requests`, and a failed edit only
+    /// fails its own entry.
+    #[cfg(feature = "parallel")]
+    pub fn batch_apply(&self, requests: Vec<EditRequest>) -> Vec<Result<EditResult>> {
+        requests
+            .into_par_iter()
+            .map(|request| self.apply_edit(request))
+            .collect()
+    }
+
+    /// Number of workers waiting to be reused
+    pub fn idle_workers(&self) -> usize {
+        self.lock().len()
+    }
+
+    fn checkout(&self) -> Option<AgentBooster> {
+        self.lock().pop()
+    }
+
+    fn checkin(&self, worker: AgentBooster) {
+        self.lock().push(worker);
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<AgentBooster>> {
+        // Workers are only pushed and popped under the lock, so a poisoned
+        // lock still holds a consistent list
+        self.idle.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Language;
+
+    #[test]
+    fn test_workers_are_reused() {
+        let pool = WorkerPool::new(Config::default());
+        for _ in 0..3 {
+            let result = pool
+                .apply_edit(EditRequest {
+                    original_code: "function foo() { return 1; }".to_string(),
+                    edit_snippet: "function foo() { return 2; }".to_string(),
+                    language: Language::JavaScript,
+                    confidence_threshold: 0.5,
+                })
+                .unwrap();
+            assert!(result.merged_code.contains("return 2"));
+        }
+        assert_eq!(pool.idle_workers(), 1);
+    }
+}
@@ -0,0 +1,363 @@
+//! Builtin templates matched on syntax trees
+//!
+//! The regex detection in `templates.rs` reads raw text, so comments, strings
+//! and multiline signatures throw it off, and it returns the whole snippet as
+//! the new code. Here each template pairs two tree-sitter queries: one finds
+//! the node of the original code to rewrite, the other the node of the edit
+//! that replaces it. Nodes pair up by their `@name` capture. Shape queries
+//! then check what the nodes contain, at any depth: the original must contain
+//! `input_shape`, and the edit must add `edit_shape`. Only the original node's
+//! byte range is rewritten, so the rest of the file is kept.
+//!
+//! JavaScript and TypeScript are both parsed with the TypeScript grammar, so a
+//! JavaScript file can take a snippet that adds types.
+
+use crate::alignment::{leading_whitespace, reindent, IndentUnit};
+use crate::models::Language;
+use crate::templates::{squash, TemplateMatch};
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::ops::Range;
+use tree_sitter::{Node, Parser, Query, QueryCursor, Tree};
+
+/// Functions, methods and function-valued variables, by name
+const FUNCTION: &str = r#"[
+  (function_declaration name: (identifier) @name)
+  (generator_function_declaration name: (identifier) @name)
+  (method_definition name: (_) @name)
+  (variable_declarator
+    name: (identifier) @name
+    value: [(arrow_function) (function_expression)])
+] @target"#;
+
+const CLASS: &str = "(class_declaration name: (_) @name) @target";
+
+/// `JSON.parse(...)` call, for the `value` or `right` side of a binding
+const JSON_PARSE_CALL: &str = r#"(call_expression
+  function: (member_expression
+    object: (identifier) @object (#eq? @object "JSON")
+    property: (property_identifier) @method (#eq? @method "parse")))"#;
+
+const TRY: &str = "(try_statement) @shape";
+
+const AWAIT: &str = "(await_expression) @shape";
+
+const THEN_CALL: &str = r#"(call_expression
+  function: (member_expression
+    property: (property_identifier) @method (#eq? @method "then"))) @shape"#;
+
+const DIVISION: &str = r#"(binary_expression operator: "/") @shape"#;
+
+const PROPERTY_RETURN: &str = "(return_statement (member_expression)) @shape";
+
+const THROW_GUARD: &str = r#"(if_statement
+  consequence: [(throw_statement) (statement_block (throw_statement))]) @shape"#;
+
+const NULL_GUARD: &str = r#"(if_statement
+  consequence: [
+    (return_statement (null))
+    (statement_block (return_statement (null)))
+  ]) @shape"#;
+
+const ACCESSIBILITY: &str = "(accessibility_modifier) @shape";
+
+/// A builtin template as a pair of tree-sitter queries
+struct QueryTemplate {
+    name: &'static str,
+    /// Name reported instead when the edit node is async
+    async_name: Option<&'static str>,
+    confidence: f64,
+    /// Nodes of the original code the template rewrites
+    input: Query,
+    /// Nodes of the edit that replace them
+    edit: Query,
+    /// Must occur in the original node
+    input_shape: Option<Query>,
+    /// Must occur more often in the edit node than in the original node
+    edit_shape: Option<Query>,
+    /// Replace the original node with the whole snippet, not just the edit node
+    whole_snippet: bool,
+}
+
+impl QueryTemplate {
+    fn new(name: &'static str, confidence: f64, input: &str, edit: &str) -> Self {
+        Self {
+            name,
+            async_name: None,
+            confidence,
+            input: query(input),
+            edit: query(edit),
+            input_shape: None,
+            edit_shape: None,
+            whole_snippet: false,
+        }
+    }
+
+    fn input_shape(mut self, shape: &str) -> Self {
+        self.input_shape = Some(query(shape));
+        self
+    }
+
+    fn edit_shape(mut self, shape: &str) -> Self {
+        self.edit_shape = Some(query(shape));
+        self
+    }
+}
+
+fn grammar() -> tree_sitter::Language {
+    tree_sitter_typescript::language_typescript()
+}
+
+fn query(source: &str) -> Query {
+    Query::new(&grammar(), source).expect("builtin template query should compile")
+}
+
+/// Builtin templates, in the order they are tried
+static TEMPLATES: Lazy<Vec<QueryTemplate>> = Lazy::new(|| {
+    let json_declaration = ["lexical_declaration", "variable_declaration"]
+        .map(|kind| {
+            format!(
+                "({} (variable_declarator name: (identifier) @name value: {})) @target",
+                kind, JSON_PARSE_CALL
+            )
+        })
+        .join("\n");
+    let json_try = format!(
+        "(try_statement body: (statement_block (expression_statement \
+         (assignment_expression left: (identifier) @name right: {})))) @target",
+        JSON_PARSE_CALL
+    );
+
+    vec![
+        QueryTemplate {
+            whole_snippet: true,
+            ..QueryTemplate::new("try-catch-json-parse", 0.9, &json_declaration, &json_try)
+        },
+        QueryTemplate {
+            async_name: Some("try-catch-async-function"),
+            ..QueryTemplate::new("try-catch-function", 0.85, FUNCTION, FUNCTION).edit_shape(TRY)
+        },
+        QueryTemplate::new("null-check-return", 0.85, FUNCTION, FUNCTION)
+            .input_shape(PROPERTY_RETURN)
+            .edit_shape(NULL_GUARD),
+        QueryTemplate::new("validation-division", 0.9, FUNCTION, FUNCTION)
+            .input_shape(DIVISION)
+            .edit_shape(THROW_GUARD),
+        QueryTemplate::new("class-to-typescript", 0.8, CLASS, CLASS).edit_shape(ACCESSIBILITY),
+        QueryTemplate::new("promise-to-async-await", 0.85, FUNCTION, FUNCTION)
+            .input_shape(THEN_CALL)
+            .edit_shape(AWAIT),
+    ]
+});
+
+/// A node found by a template's `input` or `edit` query
+struct Target<'tree> {
+    node: Node<'tree>,
+    name: Option<String>,
+}
+
+/// Rewrite `input` towards `edit` with the first builtin template that applies
+///
+/// Only JavaScript and TypeScript have builtin templates.
+pub fn detect(input: &str, edit: &str, language: Language) -> Option<TemplateMatch> {
+    if !matches!(language, Language::JavaScript | Language::TypeScript) {
+        return None;
+    }
+
+    let input_tree = parse(input)?;
+    let snippet = Snippet::parse(edit)?;
+    TEMPLATES
+        .iter()
+        .find_map(|template| apply(template, input, &input_tree, &snippet))
+}
+
+/// Parsed edit snippet
+///
+/// Methods on their own don't parse, so a snippet with syntax errors is
+/// parsed again inside a class body.
+struct Snippet {
+    source: String,
+    tree: Tree,
+    /// The snippet itself, within `source`
+    range: Range<usize>,
+}
+
+impl Snippet {
+    const WRAPPER: (&'static str, &'static str) = ("class __Snippet {\n", "\n}");
+
+    fn parse(edit: &str) -> Option<Self> {
+        let tree = parse(edit)?;
+        if tree.root_node().has_error() {
+            let (open, close) = Self::WRAPPER;
+            let source = format!("{}{}{}", open, edit, close);
+            if let Some(wrapped) = parse(&source).filter(|t| !t.root_node().has_error()) {
+                return Some(Self {
+                    source,
+                    tree: wrapped,
+                    range: open.len()..open.len() + edit.len(),
+                });
+            }
+        }
+        Some(Self {
+            source: edit.to_string(),
+            tree,
+            range: 0..edit.len(),
+        })
+    }
+}
+
+fn apply(
+    template: &QueryTemplate,
+    input: &str,
+    input_tree: &Tree,
+    snippet: &Snippet,
+) -> Option<TemplateMatch> {
+    let (edit, edit_tree) = (snippet.source.as_str(), &snippet.tree);
+    let inputs = targets(&template.input, input_tree.root_node(), input);
+    if inputs.is_empty() {
+        return None;
+    }
+
+    for replacement in targets(&template.edit, edit_tree.root_node(), edit) {
+        for target in &inputs {
+            if target.name != replacement.name {
+                continue;
+            }
+            if let Some(shape) = &template.input_shape {
+                if count(shape, target.node, input) == 0 {
+                    continue;
+                }
+            }
+            if let Some(shape) = &template.edit_shape {
+                if count(shape, replacement.node, edit) <= count(shape, target.node, input) {
+                    continue;
+                }
+            }
+
+            let range = if template.whole_snippet {
+                let text = &edit[snippet.range.clone()];
+                let start = snippet.range.start + text.len() - text.trim_start().len();
+                start..snippet.range.start + text.trim_end().len()
+            } else {
+                replacement.node.byte_range()
+            };
+            let new_text = moved(edit, range, input, target.node.start_byte());
+            let old_text = &input[target.node.byte_range()];
+            if squash(&new_text) == squash(old_text) {
+                continue;
+            }
+
+            let mut transformed_code = String::with_capacity(input.len() + new_text.len());
+            transformed_code.push_str(&input[..target.node.start_byte()]);
+            transformed_code.push_str(&new_text);
+            transformed_code.push_str(&input[target.node.end_byte()..]);
+
+            let name = match template.async_name {
+                Some(name) if is_async(replacement.node) => name,
+                _ => template.name,
+            };
+            return Some(TemplateMatch {
+                template_name: name.to_string(),
+                matched_code: old_text.to_string(),
+                transformed_code,
+                confidence: template.confidence,
+            });
+        }
+    }
+
+    None
+}
+
+fn parse(code: &str) -> Option<Tree> {
+    let mut parser = Parser::new();
+    parser.set_language(&grammar()).ok()?;
+    parser.parse(code, None)
+}
+
+/// `@target` nodes of `query` under `node`, in document order
+fn targets<'tree>(query: &Query, node: Node<'tree>, code: &str) -> Vec<Target<'tree>> {
+    let target_index = query.capture_index_for_name("target");
+    let name_index = query.capture_index_for_name("name");
+
+    let mut found: HashMap<usize, Target<'tree>> = HashMap::new();
+    let mut cursor = QueryCursor::new();
+    for m in cursor.matches(query, node, code.as_bytes()) {
+        let Some(target) = m.captures.iter().find(|c| Some(c.index) == target_index) else {
+            continue;
+        };
+        let name = m
+            .captures
+            .iter()
+            .find(|c| Some(c.index) == name_index)
+            .map(|c| code[c.node.byte_range()].to_string());
+        found.entry(target.node.id()).or_insert(Target {
+            node: target.node,
+            name,
+        });
+    }
+
+    let mut targets: Vec<Target> = found.into_values().collect();
+    targets.sort_by_key(|t| (t.node.start_byte(), std::cmp::Reverse(t.node.end_byte())));
+    targets
+}
+
+/// Number of nodes matching a shape query under `node`
+fn count(shape: &Query, node: Node, code: &str) -> usize {
+    QueryCursor::new()
+        .matches(shape, node, code.as_bytes())
+        .count()
+}
+
+/// Whether a function node, or the function a variable is bound to, is async
+fn is_async(node: Node) -> bool {
+    let function = node.child_by_field_name("value").unwrap_or(node);
+    let mut cursor = function.walk();
+    let mut children = function.children(&mut cursor);
+    children.any(|c| c.kind() == "async")
+}
+
+/// Text of `source[range]`, reindented to sit at byte `at` of `target`
+fn moved(source: &str, range: Range<usize>, target: &str, at: usize) -> String {
+    let indent_of = |code: &str, offset: usize| {
+        let line_start = code[..offset].rfind('\n').map_or(0, |i| i + 1);
+        leading_whitespace(&code[line_start..]).to_string()
+    };
+    let from = indent_of(source, range.start).len();
+    let prefix = indent_of(target, at);
+    let units = (
+        IndentUnit::detect(source.lines()),
+        IndentUnit::detect(target.lines()),
+    );
+
+    let mut lines = source[range].split('\n');
+    let mut text = lines.next().unwrap_or_default().to_string();
+    for line in lines {
+        text.push('\n');
+        text.push_str(&reindent(line, from, &prefix, units));
+    }
+    text
+}
+
+/// Code with all whitespace removed, for layout-insensitive comparison
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_queries_compile() {
+        assert_eq!(TEMPLATES.len(), 6);
+    }
+
+    #[test]
+    fn test_moved_reindents_continuation_lines() {
+        let edit = "class A {\n    run() {\n        go();\n    }\n}";
+        let start = edit.find("run").unwrap();
+        let end = edit.rfind("    }").unwrap() + 5;
+        let target = "function f() {\n  x();\n}";
+
+        assert_eq!(
+            moved(edit, start..end, target, target.find("x").unwrap()),
+            "run() {\n    go();\n  }"
+        );
+    }
+}
@@ -7,6 +7,8 @@
 
 use regex::Regex;
 use once_cell::sync::Lazy;
+use crate::models::Language;
+use crate::user_templates::UserTemplate;
 
 #[derive(Debug, Clone)]
 pub struct TransformationTemplate {
@@ -271,7 +273,32 @@ impl TemplateEngine {
     }
 
     /// Try all template-based transformations
-    pub fn try_template_transform(input: &str, edit: &str) -> Option<TemplateMatch> {
+    ///
+    /// With the tree-sitter parser, templates match on syntax trees and only
+    /// the matched node is rewritten (see `query_templates`). Builds without
+    /// it fall back to the regex detection in [`Self::try_regex_transform`].
+    pub fn try_template_transform(
+        input: &str,
+        edit: &str,
+        language: Language,
+    ) -> Option<TemplateMatch> {
+        #[cfg(feature = "tree-sitter-parser")]
+        {
+            crate::query_templates::detect(input, edit, language)
+        }
+
+        #[cfg(not(feature = "tree-sitter-parser"))]
+        {
+            let _ = language;
+            Self::try_regex_transform(input, edit)
+        }
+    }
+
+    /// Try the regex-based transformations on raw text
+    ///
+    /// These return the whole snippet as the transformed code, and are easily
+    /// confused by comments, strings and multiline signatures.
+    pub fn try_regex_transform(input: &str, edit: &str) -> Option<TemplateMatch> {
         // First detect which template matches
         if let Some(template_match) = Self::detect_template(input, edit) {
             return Some(template_match);
@@ -334,6 +361,39 @@ impl TemplateEngine {
 
         None
     }
+
+    /// Matches of the builtin templates and `user` templates, best first
+    ///
+    /// Matches are ranked by confidence; on a tie the builtin match comes
+    /// first, then user templates in the order they were loaded.
+    pub fn rank_templates(
+        input: &str,
+        edit: &str,
+        language: Language,
+        user: &[UserTemplate],
+    ) -> Vec<TemplateMatch> {
+        let mut matches: Vec<TemplateMatch> = Self::try_template_transform(input, edit, language)
+            .into_iter()
+            .chain(user.iter().filter_map(|t| t.apply(input, edit, language)))
+            .collect();
+        matches.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+        matches
+    }
+
+    /// Best match of the builtin templates and `user` templates
+    pub fn try_template_transform_with(
+        input: &str,
+        edit: &str,
+        language: Language,
+        user: &[UserTemplate],
+    ) -> Option<TemplateMatch> {
+        Self::rank_templates(input, edit, language, user).into_iter().next()
+    }
+}
+
+/// `code` without whitespace, for comparing snippets regardless of formatting
+pub(crate) fn squash(code: &str) -> String {
+    code.chars().filter(|c| !c.is_whitespace()).collect()
 }
 
 #[cfg(test)]
@@ -425,4 +485,36 @@ try {
         let template_match = result.unwrap();
         assert_eq!(template_match.template_name, "validation-division");
     }
+
+    #[test]
+    fn test_user_templates_rank_by_confidence() {
+        use crate::user_templates::{parse_templates, TemplateFormat};
+
+        let input = "const data = JSON.parse(raw);";
+        let edit = "let data;\ntry {\n  data = JSON.parse(raw);\n} catch (e) {\n  data = null;\n}";
+        let user = |confidence: f64| {
+            let source = format!(
+                r#"
+templates:
+  - name: guarded-json-parse
+    match:
+      regex: '(?:const|let|var) (?P<name>\w+) = JSON\.parse\((?P<src>[^)]+)\);'
+    replace: "let ${{name}};\ntry {{\n  ${{name}} = JSON.parse(${{src}});\n}} catch (e) {{\n  ${{name}} = null;\n}}"
+    confidence: {}
+"#,
+                confidence
+            );
+            parse_templates(&source, TemplateFormat::Yaml).unwrap()
+        };
+
+        let ranked = TemplateEngine::rank_templates(input, edit, Language::JavaScript, &user(0.95));
+        assert_eq!(ranked.len(), 2);
+        assert_eq!(ranked[0].template_name, "guarded-json-parse");
+        assert_eq!(ranked[0].transformed_code, edit);
+        assert_eq!(ranked[1].template_name, "try-catch-json-parse");
+
+        let best =
+            TemplateEngine::try_template_transform_with(input, edit, Language::JavaScript, &user(0.8));
+        assert_eq!(best.unwrap().template_name, "try-catch-json-parse");
+    }
 }
@@ -0,0 +1,209 @@
+//! Applying several edits to one file at once
+//!
+//! Each edit is merged against the original code on its own and reduced to a
+//! [`Change`]: the lines of the original it rewrites and the text that
+//! replaces them. Changes that don't overlap can then be applied together, with
+//! later offsets shifted by the edits before them.
+
+use std::ops::Range;
+
+/// Replacement of one byte range of the original code
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    /// Byte range of the original code that is replaced
+    pub range: Range<usize>,
+    /// Text that replaces it
+    pub replacement: String,
+}
+
+impl Change {
+    /// Change that turns `original` into `merged`, widened to whole lines
+    ///
+    /// Edits to the same line therefore always overlap. Lines inserted or
+    /// removed between other lines keep their range on line boundaries.
+    pub fn between(original: &str, merged: &str) -> Self {
+        let (a, b) = (original.as_bytes(), merged.as_bytes());
+        if a == b {
+            return Self {
+                range: a.len()..a.len(),
+                replacement: String::new(),
+            };
+        }
+
+        let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+        let max_suffix = a.len().min(b.len()) - prefix;
+        let suffix = a
+            .iter()
+            .rev()
+            .zip(b.iter().rev())
+            .take(max_suffix)
+            .take_while(|(x, y)| x == y)
+            .count();
+
+        let at_line_start = |i: usize| i == 0 || a[i - 1] == b'\n';
+        let mut start = prefix;
+        while !at_line_start(start) {
+            start -= 1;
+        }
+        let mut end = a.len() - suffix;
+        if !at_line_start(end) {
+            end += a[end..].iter().take_while(|&&c| c != b'\n').count();
+        }
+
+        Self {
+            range: start..end,
+            replacement: merged[start..b.len() - (a.len() - end)].to_string(),
+        }
+    }
+
+    /// Whether both changes rewrite some of the same code
+    ///
+    /// An insertion overlaps a replacement only if it falls strictly inside it,
+    /// and two insertions at the same offset never overlap.
+    pub fn overlaps(&self, other: &Change) -> bool {
+        self.range.start < other.range.end && other.range.start < self.range.end
+    }
+}
+
+/// First pair of overlapping changes, by position in `changes`
+pub fn find_overlap(changes: &[Change]) -> Option<(usize, usize)> {
+    (0..changes.len()).find_map(|j| {
+        (0..j)
+            .find(|&i| changes[i].overlaps(&changes[j]))
+            .map(|i| (i, j))
+    })
+}
+
+/// Apply non-overlapping changes to `original`
+///
+/// Returns the new code and, for each change in order, the byte range its
+/// replacement occupies in it. Insertions at the same offset keep their order.
+pub fn apply_changes(original: &str, changes: &[Change]) -> (String, Vec<Range<usize>>) {
+    let mut order: Vec<usize> = (0..changes.len()).collect();
+    order.sort_by_key(|&i| (changes[i].range.start, changes[i].range.end, i));
+
+    let mut code = String::with_capacity(original.len());
+    let mut ranges = vec![0..0; changes.len()];
+    let mut copied = 0;
+    for i in order {
+        let change = &changes[i];
+        code.push_str(&original[copied..change.range.start]);
+        let start = code.len();
+        code.push_str(&change.replacement);
+        ranges[i] = start..code.len();
+        copied = change.range.end;
+    }
+    code.push_str(&original[copied..]);
+
+    (code, ranges)
+}
+
+/// Changes to blame when `original` with all `changes` applied doesn't parse
+///
+/// A change is blamed if the code parses without it. If no single change is
+/// responsible, all of them are.
+pub fn blame(
+    original: &str,
+    changes: &[Change],
+    mut parses: impl FnMut(&str) -> bool,
+) -> Vec<usize> {
+    let blamed: Vec<usize> = (0..changes.len())
+        .filter(|&i| {
+            let others: Vec<Change> = changes
+                .iter()
+                .enumerate()
+                .filter(|&(j, _)| j != i)
+                .map(|(_, change)| change.clone())
+                .collect();
+            parses(&apply_changes(original, &others).0)
+        })
+        .collect();
+
+    if blamed.is_empty() {
+        (0..changes.len()).collect()
+    } else {
+        blamed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change(range: Range<usize>, replacement: &str) -> Change {
+        Change {
+            range,
+            replacement: replacement.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_change_between() {
+        assert_eq!(
+            Change::between("var x = 1;", "const x = 1;"),
+            change(0..10, "const x = 1;")
+        );
+        assert_eq!(
+            Change::between("a();\nb(1);\nc();\n", "a();\nb(2);\nc();\n"),
+            change(5..10, "b(2);")
+        );
+        assert_eq!(
+            Change::between("a();\n", "a();\nb();\n"),
+            change(5..5, "b();\n")
+        );
+        assert_eq!(
+            Change::between("a();\nb();\nc();", "a();\nc();"),
+            change(5..10, "")
+        );
+        assert_eq!(Change::between("same", "same"), change(4..4, ""));
+        assert_eq!(
+            Change::between("x = 'é';\ny();", "x = 'è';\ny();"),
+            change(0..9, "x = 'è';")
+        );
+    }
+
+    #[test]
+    fn test_overlaps() {
+        assert!(change(0..5, "").overlaps(&change(4..8, "")));
+        assert!(!change(0..5, "").overlaps(&change(5..8, "")));
+        assert!(change(0..5, "").overlaps(&change(3..3, "")));
+        assert!(!change(3..3, "").overlaps(&change(3..3, "")));
+        assert_eq!(
+            find_overlap(&[change(0..2, ""), change(5..9, ""), change(8..8, "")]),
+            Some((1, 2))
+        );
+    }
+
+    #[test]
+    fn test_apply_changes_rebases_offsets() {
+        let original = "let a = 1;\nlet b = 2;\nlet c = 3;\n";
+        let changes = [
+            change(22..32, "let c = 300;"),
+            change(0..3, "const"),
+            change(11..11, "// b\n"),
+        ];
+
+        let (code, ranges) = apply_changes(original, &changes);
+
+        assert_eq!(code, "const a = 1;\n// b\nlet b = 2;\nlet c = 300;\n");
+        assert_eq!(&code[ranges[0].clone()], "let c = 300;");
+        assert_eq!(&code[ranges[1].clone()], "const");
+        assert_eq!(&code[ranges[2].clone()], "// b\n");
+    }
+
+    #[test]
+    fn test_blame() {
+        // Balanced-bracket "parser": only the change opening a block breaks it
+        let balanced = |code: &str| code.matches('{').count() == code.matches('}').count();
+        let original = "a();\nb();\nc();\n";
+        let changes = [
+            change(0..4, "a(1);"),
+            change(5..9, "if (x) {"),
+            change(10..14, "c(1);"),
+        ];
+        assert_eq!(blame(original, &changes, balanced), vec![1]);
+
+        let changes = [change(0..4, "{"), change(10..14, "{")];
+        assert_eq!(blame(original, &changes, balanced), vec![0, 1]);
+    }
+}
@@ -0,0 +1,596 @@
+//! Transformation templates loaded from YAML or TOML files
+//!
+//! Teams can describe their own refactorings without touching the builtin
+//! templates in `templates.rs`. A file holds a list of templates:
+//!
+//! ```yaml
+//! templates:
+//!   - name: fetch-timeout
+//!     languages: [javascript, typescript]
+//!     match:
+//!       regex: 'fetch\((?P<url>[^,()]+)\)'
+//!     replace: 'fetch(${url}, { signal: AbortSignal.timeout(5000) })'
+//!     confidence: 0.9
+//! ```
+//!
+//! `match` takes either a `regex` (named groups are captures) or a tree-sitter
+//! `query` (`@name` captures; `@match` marks the node to rewrite, otherwise
+//! the span of all captures is rewritten). Query templates must list their
+//! `languages`. Each `${name}` in `replace` is filled from the capture of the
+//! same name.
+//!
+//! A template applies to an edit when the snippet contains its rewrite of at
+//! least one match, and the snippet is fully reflected in the rewritten code.
+
+use crate::models::{AgentBoosterError, Language, Result};
+use crate::templates::{squash, TemplateMatch, TransformationTemplate};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+fn default_confidence() -> f64 {
+    0.85
+}
+
+/// Format of a template file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TemplateFormat {
+    Yaml,
+    Toml,
+}
+
+impl TemplateFormat {
+    /// Format of a template file, by its extension
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_lowercase().as_str() {
+            "yaml" | "yml" => Some(TemplateFormat::Yaml),
+            "toml" => Some(TemplateFormat::Toml),
+            _ => None,
+        }
+    }
+}
+
+/// Contents of a template file
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TemplateFile {
+    #[serde(default)]
+    pub templates: Vec<TemplateSpec>,
+}
+
+/// A template as written in a template file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TemplateSpec {
+    /// Unique name, reported as the `TemplateMatch::template_name`
+    pub name: String,
+    /// What the template does
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Languages the template applies to; all languages if empty
+    #[serde(default)]
+    pub languages: Vec<String>,
+    /// Pattern to find in the original code
+    #[serde(rename = "match")]
+    pub pattern: PatternSpec,
+    /// Replacement skeleton with `${capture}` placeholders
+    pub replace: String,
+    /// Confidence reported when the template applies (0.0 - 1.0)
+    #[serde(default = "default_confidence")]
+    pub confidence: f64,
+}
+
+/// Pattern of a template: exactly one of `regex` or `query`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PatternSpec {
+    #[serde(default)]
+    pub regex: Option<String>,
+    #[serde(default)]
+    pub query: Option<String>,
+}
+
+/// Piece of a replacement skeleton
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Text(String),
+    Capture(String),
+}
+
+enum Matcher {
+    Regex(Regex),
+    #[cfg(feature = "tree-sitter-parser")]
+    Query(HashMap<Language, tree_sitter::Query>),
+}
+
+/// A validated, compiled user template
+pub struct UserTemplate {
+    pub name: String,
+    pub description: Option<String>,
+    /// Languages the template applies to; all languages if empty
+    pub languages: Vec<Language>,
+    pub confidence: f64,
+    matcher: Matcher,
+    replacement: Vec<Segment>,
+}
+
+/// A match of a template's pattern in the original code
+struct PatternMatch {
+    range: Range<usize>,
+    captures: HashMap<String, String>,
+}
+
+impl UserTemplate {
+    /// Validate and compile a template
+    pub fn compile(spec: TemplateSpec) -> Result<Self> {
+        let invalid = |message: String| {
+            AgentBoosterError::ConfigError(format!("template `{}`: {}", spec.name, message))
+        };
+
+        if spec.name.trim().is_empty() {
+            return Err(AgentBoosterError::ConfigError(
+                "template name must not be empty".to_string(),
+            ));
+        }
+        if !(spec.confidence > 0.0 && spec.confidence <= 1.0) {
+            return Err(invalid(format!(
+                "confidence must be in (0, 1], got {}",
+                spec.confidence
+            )));
+        }
+
+        let languages = spec
+            .languages
+            .iter()
+            .map(|l| Language::from_str(l).map_err(|e| invalid(e.to_string())))
+            .collect::<Result<Vec<_>>>()?;
+
+        let (matcher, capture_names) = match (&spec.pattern.regex, &spec.pattern.query) {
+            (Some(pattern), None) => {
+                let regex =
+                    Regex::new(pattern).map_err(|e| invalid(format!("invalid regex: {}", e)))?;
+                let names: HashSet<String> =
+                    regex.capture_names().flatten().map(String::from).collect();
+                (Matcher::Regex(regex), names)
+            }
+            (None, Some(source)) => compile_query(source, &languages).map_err(invalid)?,
+            _ => {
+                return Err(invalid(
+                    "`match` needs exactly one of `regex` or `query`".to_string(),
+                ))
+            }
+        };
+
+        let replacement = parse_replacement(&spec.replace).map_err(invalid)?;
+        for segment in &replacement {
+            if let Segment::Capture(name) = segment {
+                if !capture_names.contains(name) {
+                    return Err(invalid(format!(
+                        "`replace` uses `${{{}}}`, which the pattern does not capture",
+                        name
+                    )));
+                }
+            }
+        }
+
+        Ok(Self {
+            name: spec.name,
+            description: spec.description,
+            languages,
+            confidence: spec.confidence,
+            matcher,
+            replacement,
+        })
+    }
+
+    /// Whether the template is scoped to `language`
+    pub fn applies_to(&self, language: Language) -> bool {
+        self.languages.is_empty() || self.languages.contains(&language)
+    }
+
+    /// Rewrite `input` towards `edit` with this template, if it applies
+    pub fn apply(&self, input: &str, edit: &str, language: Language) -> Option<TemplateMatch> {
+        if !self.applies_to(language) {
+            return None;
+        }
+
+        let edit_key = squash(edit);
+        let mut rewrites: Vec<(Range<usize>, String)> = Vec::new();
+        for m in self.find(input, language) {
+            let rendered = self.render(&m.captures);
+            let key = squash(&rendered);
+            if key.is_empty() || key == squash(&input[m.range.clone()]) || !edit_key.contains(&key)
+            {
+                continue;
+            }
+            if rewrites
+                .last()
+                .map_or(true, |(last, _)| last.end <= m.range.start)
+            {
+                rewrites.push((m.range, rendered));
+            }
+        }
+        let (first, _) = rewrites.first()?;
+        let matched_code = input[first.clone()].to_string();
+
+        let mut transformed_code = String::with_capacity(input.len());
+        let mut copied = 0;
+        for (range, rendered) in &rewrites {
+            transformed_code.push_str(&input[copied..range.start]);
+            transformed_code.push_str(rendered);
+            copied = range.end;
+        }
+        transformed_code.push_str(&input[copied..]);
+
+        // Edits that change more than the template knows about are left to the merger
+        if !squash(&transformed_code).contains(&edit_key) {
+            return None;
+        }
+
+        Some(TemplateMatch {
+            template_name: self.name.clone(),
+            matched_code,
+            transformed_code,
+            confidence: self.confidence,
+        })
+    }
+
+    /// Matches of the pattern, by position
+    #[cfg_attr(not(feature = "tree-sitter-parser"), allow(unused_variables))]
+    fn find(&self, input: &str, language: Language) -> Vec<PatternMatch> {
+        match &self.matcher {
+            Matcher::Regex(regex) => regex
+                .captures_iter(input)
+                .map(|caps| PatternMatch {
+                    range: caps.get(0).map_or(0..0, |m| m.range()),
+                    captures: regex
+                        .capture_names()
+                        .flatten()
+                        .filter_map(|name| {
+                            Some((name.to_string(), caps.name(name)?.as_str().to_string()))
+                        })
+                        .collect(),
+                })
+                .collect(),
+            #[cfg(feature = "tree-sitter-parser")]
+            Matcher::Query(queries) => match queries.get(&language) {
+                Some(query) => query_matches(input, language, query),
+                None => Vec::new(),
+            },
+        }
+    }
+
+    fn render(&self, captures: &HashMap<String, String>) -> String {
+        self.replacement
+            .iter()
+            .map(|segment| match segment {
+                Segment::Text(text) => text.as_str(),
+                Segment::Capture(name) => captures.get(name).map_or("", String::as_str),
+            })
+            .collect()
+    }
+}
+
+impl std::fmt::Debug for UserTemplate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("UserTemplate")
+            .field("name", &self.name)
+            .field("languages", &self.languages)
+            .field("confidence", &self.confidence)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Parse and compile the templates in `source`
+pub fn parse_templates(source: &str, format: TemplateFormat) -> Result<Vec<UserTemplate>> {
+    let file: TemplateFile = match format {
+        TemplateFormat::Yaml => serde_yaml::from_str(source)
+            .map_err(|e| AgentBoosterError::ConfigError(e.to_string()))?,
+        TemplateFormat::Toml => {
+            toml::from_str(source).map_err(|e| AgentBoosterError::ConfigError(e.to_string()))?
+        }
+    };
+    file.templates
+        .into_iter()
+        .map(UserTemplate::compile)
+        .collect()
+}
+
+/// Load the templates of every file in `paths`
+///
+/// Errors name the file and template at fault. Template names must be unique
+/// and must not shadow a builtin template.
+pub fn load_templates(paths: &[PathBuf]) -> Result<Vec<UserTemplate>> {
+    let mut names: HashSet<String> = TransformationTemplate::all_templates()
+        .into_iter()
+        .map(|t| t.name)
+        .collect();
+    let mut templates = Vec::new();
+
+    for path in paths {
+        let in_file = |e: AgentBoosterError| {
+            let message = match e {
+                AgentBoosterError::ConfigError(message) => message,
+                e => e.to_string(),
+            };
+            AgentBoosterError::ConfigError(format!("{}: {}", path.display(), message))
+        };
+
+        let format = TemplateFormat::from_path(path).ok_or_else(|| {
+            in_file(AgentBoosterError::ConfigError(
+                "template files must be .yaml, .yml or .toml".to_string(),
+            ))
+        })?;
+        let source = std::fs::read_to_string(path).map_err(|e| in_file(e.into()))?;
+
+        for template in parse_templates(&source, format).map_err(in_file)? {
+            if !names.insert(template.name.clone()) {
+                return Err(in_file(AgentBoosterError::ConfigError(format!(
+                    "template `{}` is already defined",
+                    template.name
+                ))));
+            }
+            templates.push(template);
+        }
+    }
+
+    Ok(templates)
+}
+
+/// Code with all whitespace removed, for layout-insensitive comparison
+fn parse_replacement(skeleton: &str) -> std::result::Result<Vec<Segment>, String> {
+    let mut segments = Vec::new();
+    let mut rest = skeleton;
+    while let Some(start) = rest.find("${") {
+        if start > 0 {
+            segments.push(Segment::Text(rest[..start].to_string()));
+        }
+        let end = rest[start..]
+            .find('}')
+            .ok_or_else(|| "unterminated `${` in `replace`".to_string())?;
+        let name = &rest[start + 2..start + end];
+        if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+            return Err(format!("invalid placeholder `${{{}}}` in `replace`", name));
+        }
+        segments.push(Segment::Capture(name.to_string()));
+        rest = &rest[start + end + 1..];
+    }
+    if !rest.is_empty() {
+        segments.push(Segment::Text(rest.to_string()));
+    }
+    Ok(segments)
+}
+
+#[cfg(feature = "tree-sitter-parser")]
+fn compile_query(
+    source: &str,
+    languages: &[Language],
+) -> std::result::Result<(Matcher, HashSet<String>), String> {
+    if languages.is_empty() {
+        return Err("query templates must list their `languages`".to_string());
+    }
+
+    let mut queries = HashMap::new();
+    let mut names = HashSet::new();
+    for &language in languages {
+        let grammar = crate::parser::grammar(language).ok_or_else(|| {
+            format!(
+                "no grammar for {} (enable the `lang-{}` feature)",
+                language.as_str(),
+                language.as_str()
+            )
+        })?;
+        let query = tree_sitter::Query::new(&grammar, source)
+            .map_err(|e| format!("invalid {} query: {}", language.as_str(), e))?;
+        names.extend(query.capture_names().iter().map(|n| n.to_string()));
+        queries.insert(language, query);
+    }
+    Ok((Matcher::Query(queries), names))
+}
+
+#[cfg(not(feature = "tree-sitter-parser"))]
+fn compile_query(
+    _source: &str,
+    _languages: &[Language],
+) -> std::result::Result<(Matcher, HashSet<String>), String> {
+    Err("query templates need the `tree-sitter-parser` feature".to_string())
+}
+
+#[cfg(feature = "tree-sitter-parser")]
+fn query_matches(code: &str, language: Language, query: &tree_sitter::Query) -> Vec<PatternMatch> {
+    let Some(grammar) = crate::parser::grammar(language) else {
+        return Vec::new();
+    };
+    let mut parser = tree_sitter::Parser::new();
+    if parser.set_language(&grammar).is_err() {
+        return Vec::new();
+    }
+    let Some(tree) = parser.parse(code, None) else {
+        return Vec::new();
+    };
+
+    let names = query.capture_names();
+    let mut cursor = tree_sitter::QueryCursor::new();
+    let mut matches: Vec<PatternMatch> = cursor
+        .matches(query, tree.root_node(), code.as_bytes())
+        .filter_map(|m| {
+            let mut captures = HashMap::new();
+            let mut span: Option<Range<usize>> = None;
+            let mut whole = None;
+            for capture in m.captures {
+                let range = capture.node.byte_range();
+                let name = names[capture.index as usize];
+                if name == "match" {
+                    whole = Some(range.clone());
+                }
+                captures
+                    .entry(name.to_string())
+                    .or_insert_with(|| code[range.clone()].to_string());
+                span = Some(match span {
+                    Some(s) => s.start.min(range.start)..s.end.max(range.end),
+                    None => range,
+                });
+            }
+            Some(PatternMatch {
+                range: whole.or(span)?,
+                captures,
+            })
+        })
+        .collect();
+
+    matches.sort_by_key(|m| (m.range.start, std::cmp::Reverse(m.range.end)));
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FETCH_TIMEOUT: &str = r#"
+templates:
+  - name: fetch-timeout
+    languages: [javascript, typescript]
+    match:
+      regex: 'fetch\((?P<url>[^,()]+)\)'
+    replace: 'fetch(${url}, { signal: AbortSignal.timeout(5000) })'
+    confidence: 0.9
+"#;
+
+    fn compile_error(spec: &str) -> String {
+        parse_templates(spec, TemplateFormat::Yaml)
+            .unwrap_err()
+            .to_string()
+    }
+
+    #[test]
+    fn test_regex_template_applies() {
+        let templates = parse_templates(FETCH_TIMEOUT, TemplateFormat::Yaml).unwrap();
+        let input = "async function load(id) {\n  const res = await fetch(`/items/${id}`);\n  return res.json();\n}\n";
+        let edit =
+            "const res = await fetch(`/items/${id}`, { signal: AbortSignal.timeout(5000) });";
+
+        let result = templates[0]
+            .apply(input, edit, Language::JavaScript)
+            .unwrap();
+
+        assert_eq!(result.template_name, "fetch-timeout");
+        assert_eq!(result.matched_code, "fetch(`/items/${id}`)");
+        assert!(result.transformed_code.contains(edit));
+        assert!(result.transformed_code.contains("return res.json();"));
+        assert!(templates[0].apply(input, edit, Language::Python).is_none());
+    }
+
+    #[test]
+    fn test_template_skips_edits_it_does_not_explain() {
+        let templates = parse_templates(FETCH_TIMEOUT, TemplateFormat::Yaml).unwrap();
+        let input = "const res = await fetch(api);";
+
+        let other_change =
+            "const response = await fetch(api, { signal: AbortSignal.timeout(5000) });";
+        assert!(templates[0]
+            .apply(input, other_change, Language::JavaScript)
+            .is_none());
+        assert!(templates[0]
+            .apply(
+                input,
+                "const res = await fetch(other);",
+                Language::JavaScript
+            )
+            .is_none());
+    }
+
+    #[test]
+    fn test_toml_templates() {
+        let source = r#"
+[[templates]]
+name = "strict-equality"
+replace = "${left} === ${right}"
+
+[templates.match]
+regex = '(?P<left>\w+) == (?P<right>\w+)'
+"#;
+        let templates = parse_templates(source, TemplateFormat::Toml).unwrap();
+
+        assert_eq!(templates[0].confidence, 0.85);
+        let result = templates[0]
+            .apply(
+                "if (a == b) run();",
+                "if (a === b) run();",
+                Language::TypeScript,
+            )
+            .unwrap();
+        assert_eq!(result.transformed_code, "if (a === b) run();");
+    }
+
+    #[test]
+    fn test_invalid_templates_are_rejected() {
+        let template = |pattern: &str, replace: &str, extra: &str| {
+            format!(
+                "templates:\n  - name: t\n    match:\n      {}\n    replace: '{}'\n{}",
+                pattern, replace, extra
+            )
+        };
+
+        assert!(
+            compile_error(&template("regex: 'fetch\\((?P<url>'", "x", ""))
+                .contains("template `t`: invalid regex")
+        );
+        assert!(compile_error(&template("regex: '(?P<a>\\w+)'", "${b}", ""))
+            .contains("`${b}`, which the pattern does not capture"));
+        assert!(
+            compile_error(&template("regex: '(?P<a>\\w+)'", "${a", "")).contains("unterminated")
+        );
+        assert!(compile_error(&template("{}", "x", "")).contains("exactly one of"));
+        assert!(
+            compile_error(&template("regex: 'a'", "b", "    confidence: 1.5\n"))
+                .contains("confidence must be in (0, 1]")
+        );
+        assert!(
+            compile_error(&template("regex: 'a'", "b", "    languages: [cobol]\n"))
+                .contains("Unsupported language: cobol")
+        );
+        assert!(
+            compile_error(&template("regex: 'a'", "b", "    replacement: c\n"))
+                .contains("unknown field `replacement`")
+        );
+    }
+
+    #[cfg(feature = "tree-sitter-parser")]
+    #[test]
+    fn test_query_template_applies() {
+        let source = r#"
+templates:
+  - name: console-to-logger
+    languages: [javascript]
+    match:
+      query: |
+        (call_expression
+          function: (member_expression
+            object: (identifier) @object (#eq? @object "console")
+            property: (property_identifier) @level)
+          arguments: (arguments) @args) @match
+    replace: 'logger.${level}${args}'
+"#;
+        let templates = parse_templates(source, TemplateFormat::Yaml).unwrap();
+        // The call spelled out inside the string literal is not a call node
+        let input = "function run() {\n  const s = \"console.log(x)\";\n  console.warn(\n    s,\n    1\n  );\n}\n";
+        let edit = "logger.warn(s, 1);";
+
+        let result = templates[0]
+            .apply(input, edit, Language::JavaScript)
+            .unwrap();
+
+        assert_eq!(
+            result.transformed_code,
+            "function run() {\n  const s = \"console.log(x)\";\n  logger.warn(\n    s,\n    1\n  );\n}\n"
+        );
+        assert!(compile_error(&source.replace("[javascript]", "[]"))
+            .contains("must list their `languages`"));
+        assert!(
+            compile_error(&source.replace("member_expression", "no_such_node"))
+                .contains("invalid javascript query")
+        );
+    }
+}
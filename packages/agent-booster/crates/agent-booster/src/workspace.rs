@@ -0,0 +1,344 @@
+//! Edits whose target file is not known up front
+//!
+//! A [`Workspace`] indexes the source files under a directory into chunks. A
+//! snippet is ranked against the chunks of every file with
+//! [`SimilarityMatcher::find_top_k_matches`], and an optional hint such as
+//! "the user service" is matched against file paths to break ties between
+//! files that look alike.
+//!
+//! Indexing honors `.gitignore` files and skips files over
+//! [`MAX_FILE_BYTES`]. [`Workspace::refresh`] re-reads only the files that
+//! changed since, so a long-lived index stays cheap to keep current.
+
+use crate::models::{AgentBoosterError, CodeChunk, Language, Result, WorkspaceCandidate};
+use crate::parser::Parser;
+use crate::similarity::SimilarityMatcher;
+use ignore::WalkBuilder;
+use std::cmp::Ordering;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Candidates returned for a workspace edit
+pub const MAX_CANDIDATES: usize = 5;
+
+/// Chunks of each file that are ranked
+const MATCHES_PER_FILE: usize = 3;
+
+/// Share of the ranking score that comes from the path hint
+const HINT_WEIGHT: f32 = 0.3;
+
+/// Largest file that is indexed; bigger files are usually generated or data
+pub const MAX_FILE_BYTES: u64 = 1024 * 1024;
+
+/// Best files closer than this are ambiguous
+const AMBIGUITY_MARGIN: f32 = 0.05;
+
+/// Directories that hold dependencies or build output, not project code
+const SKIPPED_DIRS: &[&str] = &[
+    "node_modules",
+    "target",
+    "dist",
+    "build",
+    "vendor",
+    "__pycache__",
+];
+
+/// Hint words that say nothing about the path
+const HINT_STOP_WORDS: &[&str] = &[
+    "a", "an", "the", "in", "of", "for", "to", "on", "and", "file", "module", "code",
+];
+
+/// A source file of the workspace and its chunks
+#[derive(Debug, Clone)]
+pub struct IndexedFile {
+    /// Path relative to the workspace root
+    pub path: PathBuf,
+    /// Language, from the file extension
+    pub language: Language,
+    /// File contents
+    pub code: String,
+    /// Chunks of the file, or the whole file if it has none
+    pub chunks: Vec<CodeChunk>,
+    /// Modification time when the file was read
+    modified: Option<SystemTime>,
+}
+
+/// Index of the source files under a directory
+#[derive(Debug, Clone)]
+pub struct Workspace {
+    root: PathBuf,
+    files: Vec<IndexedFile>,
+}
+
+impl Workspace {
+    /// Index every source file under `root`
+    ///
+    /// Hidden directories, dependency or build directories such as
+    /// `node_modules` and `target`, and paths matched by `.gitignore` are
+    /// skipped, as are files over [`MAX_FILE_BYTES`], files that are not
+    /// UTF-8 and languages whose grammar is not compiled in.
+    pub fn index(root: impl AsRef<Path>) -> Result<Self> {
+        let mut workspace = Self {
+            root: root.as_ref().to_path_buf(),
+            files: Vec::new(),
+        };
+        workspace.refresh()?;
+        Ok(workspace)
+    }
+
+    /// Bring the index up to date with the files on disk
+    ///
+    /// Files whose size and modification time are unchanged keep their
+    /// chunks; new and changed files are parsed again and deleted ones dropped.
+    pub fn refresh(&mut self) -> Result<()> {
+        let mut paths = collect_files(&self.root)?;
+        paths.sort();
+
+        let mut parser = Parser::new()?;
+        let mut previous = std::mem::take(&mut self.files).into_iter().peekable();
+        for path in paths {
+            let relative = path.strip_prefix(&self.root).unwrap_or(&path).to_path_buf();
+            let metadata = match fs::metadata(&path) {
+                Ok(metadata) => metadata,
+                Err(e) if e.kind() == ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            };
+
+            // Both lists are sorted by path, so unchanged files are found in one pass
+            while previous.peek().is_some_and(|f| f.path < relative) {
+                previous.next();
+            }
+            if let Some(file) = previous.next_if(|f| {
+                f.path == relative
+                    && f.code.len() as u64 == metadata.len()
+                    && f.modified.is_some()
+                    && f.modified == metadata.modified().ok()
+            }) {
+                self.files.push(file);
+                continue;
+            }
+
+            if let Some(file) = index_file(&mut parser, &path, relative, &metadata)? {
+                self.files.push(file);
+            }
+        }
+        Ok(())
+    }
+
+    /// Directory the workspace was indexed from
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Indexed files, sorted by path
+    pub fn files(&self) -> &[IndexedFile] {
+        &self.files
+    }
+
+    /// Indexed file at `path`, relative to the root
+    pub fn file(&self, path: &Path) -> Option<&IndexedFile> {
+        self.files.iter().find(|f| f.path == path)
+    }
+
+    /// Rank the chunks of the workspace against a snippet, best first
+    ///
+    /// Only files in `language` are considered, if given. With a `hint`, the
+    /// score blends chunk similarity with how well the file path matches it.
+    pub fn rank(
+        &self,
+        snippet: &str,
+        language: Option<Language>,
+        hint: Option<&str>,
+        k: usize,
+    ) -> Vec<WorkspaceCandidate> {
+        let hint_words: Vec<String> = hint
+            .map(words)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|w| !HINT_STOP_WORDS.contains(&w.as_str()))
+            .collect();
+
+        let mut candidates = Vec::new();
+        for file in &self.files {
+            if language.is_some_and(|l| l != file.language) {
+                continue;
+            }
+
+            let path_score = path_score(&file.path, &hint_words);
+            let matches =
+                SimilarityMatcher::find_top_k_matches(snippet, &file.chunks, MATCHES_PER_FILE);
+            for m in matches {
+                let score = if hint_words.is_empty() {
+                    m.similarity
+                } else {
+                    m.similarity * (1.0 - HINT_WEIGHT) + path_score * HINT_WEIGHT
+                };
+                candidates.push(WorkspaceCandidate {
+                    path: file.path.clone(),
+                    language: file.language,
+                    chunk: m.chunk,
+                    similarity: m.similarity,
+                    path_score,
+                    score,
+                });
+            }
+        }
+
+        // Stable, so equal scores stay in path order
+        candidates.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal));
+        candidates.truncate(k);
+        candidates
+    }
+}
+
+/// Whether the best candidate's file can't be told apart from another file
+pub fn is_ambiguous(candidates: &[WorkspaceCandidate]) -> bool {
+    let Some(best) = candidates.first() else {
+        return false;
+    };
+    candidates
+        .iter()
+        .find(|c| c.path != best.path)
+        .is_some_and(|c| best.score - c.score < AMBIGUITY_MARGIN)
+}
+
+/// Read and chunk one file, or `None` if it is not indexed
+fn index_file(
+    parser: &mut Parser,
+    path: &Path,
+    relative: PathBuf,
+    metadata: &fs::Metadata,
+) -> Result<Option<IndexedFile>> {
+    let Some(language) = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .and_then(Language::from_extension)
+    else {
+        return Ok(None);
+    };
+    let code = match fs::read_to_string(path) {
+        Ok(code) => code,
+        Err(e) if matches!(e.kind(), ErrorKind::InvalidData | ErrorKind::NotFound) => {
+            return Ok(None)
+        }
+        Err(e) => return Err(e.into()),
+    };
+    let tree = match parser.parse(&code, language) {
+        Ok(tree) => tree,
+        Err(AgentBoosterError::UnsupportedLanguage(_)) => return Ok(None),
+        Err(e) => return Err(e),
+    };
+
+    let mut chunks = parser.extract_chunks(&tree, &code);
+    if chunks.is_empty() {
+        chunks.push(parser.extract_full_file(&code));
+    }
+    Ok(Some(IndexedFile {
+        path: relative,
+        language,
+        code,
+        chunks,
+        modified: metadata.modified().ok(),
+    }))
+}
+
+/// Files under `root` that are indexed, unsorted
+fn collect_files(root: &Path) -> Result<Vec<PathBuf>> {
+    // Symlinks are not followed, so links back up the tree can't loop
+    let walker = WalkBuilder::new(root)
+        .hidden(false)
+        .git_global(false)
+        .require_git(false)
+        .max_filesize(Some(MAX_FILE_BYTES))
+        .filter_entry(|entry| {
+            let name = entry.file_name().to_string_lossy();
+            let is_dir = entry.depth() > 0 && entry.file_type().is_some_and(|t| t.is_dir());
+            !(is_dir && (name.starts_with('.') || SKIPPED_DIRS.contains(&name.as_ref())))
+        })
+        .build();
+
+    let mut files = Vec::new();
+    for entry in walker {
+        let entry = match entry {
+            Ok(entry) => entry,
+            // A malformed `.gitignore` line is not a reason to give up
+            Err(e) if e.io_error().is_none() => continue,
+            Err(e) => return Err(e.into_io_error().expect("checked above").into()),
+        };
+        if entry.file_type().is_some_and(|t| t.is_file()) {
+            files.push(entry.into_path());
+        }
+    }
+    Ok(files)
+}
+
+/// Lowercase words of `text`, split at punctuation and camelCase humps
+fn words(text: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut prev_lower = false;
+    for c in text.chars() {
+        let boundary = !c.is_alphanumeric() || (c.is_uppercase() && prev_lower);
+        if boundary && !current.is_empty() {
+            words.push(std::mem::take(&mut current));
+        }
+        if c.is_alphanumeric() {
+            current.extend(c.to_lowercase());
+        }
+        prev_lower = c.is_lowercase() || c.is_ascii_digit();
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    words
+}
+
+/// Share of the hint words found in the path
+///
+/// Words of three letters or more also match by prefix, so "service" matches
+/// `services/` and "users" matches `user_service.ts`.
+fn path_score(path: &Path, hint_words: &[String]) -> f32 {
+    if hint_words.is_empty() {
+        return 0.0;
+    }
+
+    let path_words = words(&path.to_string_lossy());
+    let matches = |hint: &String| {
+        path_words.iter().any(|word| {
+            word == hint
+                || (word.len() >= 3
+                    && hint.len() >= 3
+                    && (word.starts_with(hint.as_str()) || hint.starts_with(word.as_str())))
+        })
+    };
+    hint_words.iter().filter(|w| matches(w)).count() as f32 / hint_words.len() as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_words() {
+        assert_eq!(
+            words("src/services/UserService.ts"),
+            ["src", "services", "user", "service", "ts"]
+        );
+        assert_eq!(words("the user_service"), ["the", "user", "service"]);
+        assert_eq!(words("parseHTTP2Response"), ["parse", "http2", "response"]);
+    }
+
+    #[test]
+    fn test_path_score() {
+        let hint = |text: &str| words(text);
+        let path = Path::new("src/services/user_service.ts");
+
+        assert_eq!(path_score(path, &hint("user service")), 1.0);
+        assert_eq!(path_score(path, &hint("users")), 1.0);
+        assert_eq!(path_score(path, &hint("order service")), 0.5);
+        assert_eq!(path_score(path, &hint("billing")), 0.0);
+        assert_eq!(path_score(path, &[]), 0.0);
+    }
+}
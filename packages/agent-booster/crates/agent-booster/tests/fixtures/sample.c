@@ -0,0 +1,18 @@
+#include <stdio.h>
+
+struct point {
+    int x;
+    int y;
+};
+
+typedef enum { RED, GREEN } color;
+
+static struct point origin;
+
+int manhattan(struct point p) {
+    return p.x + p.y;
+}
+
+void print_point(struct point p) {
+    printf("(%d, %d)\n", p.x, p.y);
+}
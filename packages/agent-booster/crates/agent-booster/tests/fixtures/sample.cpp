@@ -0,0 +1,26 @@
+#include <vector>
+
+namespace shapes {
+
+class Circle {
+public:
+    explicit Circle(double radius) : radius_(radius) {}
+
+    double area() const {
+        return 3.14159 * radius_ * radius_;
+    }
+
+private:
+    double radius_;
+};
+
+template <typename T>
+T total(const std::vector<T>& values) {
+    T sum{};
+    for (const auto& value : values) {
+        sum += value;
+    }
+    return sum;
+}
+
+}  // namespace shapes
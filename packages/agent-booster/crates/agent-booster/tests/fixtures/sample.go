@@ -0,0 +1,15 @@
+package shapes
+
+import "math"
+
+type Circle struct {
+	Radius float64
+}
+
+func (c Circle) Area() float64 {
+	return math.Pi * c.Radius * c.Radius
+}
+
+func NewCircle(radius float64) Circle {
+	return Circle{Radius: radius}
+}
@@ -0,0 +1,14 @@
+import math
+
+
+def area(radius):
+    return math.pi * radius ** 2
+
+
+class Shape:
+    def __init__(self, name):
+        self.name = name
+
+    @property
+    def label(self):
+        return self.name.upper()
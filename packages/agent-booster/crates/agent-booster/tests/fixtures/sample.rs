@@ -0,0 +1,25 @@
+use std::fmt;
+
+pub struct Point {
+    x: i32,
+    y: i32,
+}
+
+pub enum Shape {
+    Circle(Point, u32),
+    Square(Point, u32),
+}
+
+pub trait Area {
+    fn area(&self) -> f64;
+}
+
+impl fmt::Display for Point {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "({}, {})", self.x, self.y)
+    }
+}
+
+pub fn origin() -> Point {
+    Point { x: 0, y: 0 }
+}
@@ -0,0 +1,4 @@
+export async function getUser(id: string): Promise<unknown> {
+  const response = await fetch(`/api/users/${id}`);
+  return response.json();
+}
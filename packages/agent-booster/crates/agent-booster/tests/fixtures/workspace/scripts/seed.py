@@ -0,0 +1,3 @@
+def seed_users(db):
+    for name in ["ada", "grace"]:
+        db.insert("users", {"name": name})
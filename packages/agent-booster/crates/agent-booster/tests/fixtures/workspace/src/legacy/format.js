@@ -0,0 +1,9 @@
+function formatName(user) {
+  return user.firstName + " " + user.lastName;
+}
+
+function formatPhone(phone) {
+  return phone.replace(/\D/g, "");
+}
+
+module.exports = { formatName, formatPhone };
@@ -0,0 +1,14 @@
+export interface Order {
+  id: string;
+  userId: string;
+  total: number;
+}
+
+export async function getOrder(id: string): Promise<Order> {
+  const response = await fetch(`/api/orders/${id}`);
+  return response.json();
+}
+
+export async function cancelOrder(id: string): Promise<void> {
+  await fetch(`/api/orders/${id}`, { method: "DELETE" });
+}
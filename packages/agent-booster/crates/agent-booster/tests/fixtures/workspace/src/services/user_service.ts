@@ -0,0 +1,18 @@
+export interface User {
+  id: string;
+  name: string;
+  email: string;
+}
+
+export async function getUser(id: string): Promise<User> {
+  const response = await fetch(`/api/users/${id}`);
+  return response.json();
+}
+
+export async function updateUserEmail(id: string, email: string): Promise<User> {
+  const response = await fetch(`/api/users/${id}`, {
+    method: "PATCH",
+    body: JSON.stringify({ email }),
+  });
+  return response.json();
+}
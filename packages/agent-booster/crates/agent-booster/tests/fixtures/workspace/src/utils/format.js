@@ -0,0 +1,9 @@
+function formatName(user) {
+  return user.firstName + " " + user.lastName;
+}
+
+function formatDate(date) {
+  return date.toISOString().slice(0, 10);
+}
+
+module.exports = { formatName, formatDate };
@@ -0,0 +1,256 @@
+//! Per-language grammar fixtures
+//!
+//! Each test only runs when the language's `lang-*` feature is enabled,
+//! e.g. `cargo test --features all-languages`.
+
+#![allow(dead_code)]
+
+use agent_booster::models::Language;
+use agent_booster::parser::Parser;
+
+fn fixture(name: &str) -> String {
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name);
+    std::fs::read_to_string(path).expect("Failed to read fixture")
+}
+
+/// Parse a fixture and return `(node_type, first line)` for every chunk
+fn chunks(name: &str, language: Language) -> Vec<(String, String)> {
+    let mut parser = Parser::new().unwrap();
+    let code = fixture(name);
+    assert!(
+        parser.validate_syntax(&code, language),
+        "{} should parse cleanly",
+        name
+    );
+
+    let tree = parser.parse(&code, language).unwrap();
+    parser
+        .extract_chunks(&tree, &code)
+        .into_iter()
+        .map(|c| {
+            (
+                c.node_type,
+                c.code.lines().next().unwrap_or("").trim().to_string(),
+            )
+        })
+        .collect()
+}
+
+fn has_chunk(chunks: &[(String, String)], node_type: &str, first_line: &str) -> bool {
+    chunks
+        .iter()
+        .any(|(t, line)| t == node_type && line == first_line)
+}
+
+/// Breaking the fixture must be caught by the language's own grammar
+fn assert_rejects_broken(name: &str, language: Language, from: &str, to: &str) {
+    let mut parser = Parser::new().unwrap();
+    let broken = fixture(name).replacen(from, to, 1);
+    assert!(!parser.validate_syntax(&broken, language));
+    assert!(parser
+        .find_syntax_error(&broken, language)
+        .unwrap()
+        .is_some());
+}
+
+#[test]
+#[cfg(feature = "lang-python")]
+fn test_python_fixture() {
+    let chunks = chunks("sample.py", Language::Python);
+
+    assert!(has_chunk(
+        &chunks,
+        "function_definition",
+        "def area(radius):"
+    ));
+    assert!(has_chunk(&chunks, "class_definition", "class Shape:"));
+    assert!(has_chunk(
+        &chunks,
+        "function_definition",
+        "def __init__(self, name):"
+    ));
+    assert!(has_chunk(&chunks, "decorated_definition", "@property"));
+    assert!(!chunks.iter().any(|(_, line)| line.starts_with("import")));
+
+    assert_rejects_broken(
+        "sample.py",
+        Language::Python,
+        "def area(radius):",
+        "def area(radius)",
+    );
+}
+
+#[test]
+#[cfg(feature = "lang-rust")]
+fn test_rust_fixture() {
+    let chunks = chunks("sample.rs", Language::Rust);
+
+    assert!(has_chunk(&chunks, "struct_item", "pub struct Point {"));
+    assert!(has_chunk(&chunks, "enum_item", "pub enum Shape {"));
+    assert!(has_chunk(&chunks, "trait_item", "pub trait Area {"));
+    assert!(has_chunk(
+        &chunks,
+        "impl_item",
+        "impl fmt::Display for Point {"
+    ));
+    assert!(has_chunk(
+        &chunks,
+        "function_item",
+        "fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {"
+    ));
+    assert!(has_chunk(
+        &chunks,
+        "function_item",
+        "pub fn origin() -> Point {"
+    ));
+
+    assert_rejects_broken("sample.rs", Language::Rust, "x: i32,", "x: i32 i32,");
+}
+
+#[test]
+#[cfg(feature = "lang-go")]
+fn test_go_fixture() {
+    let chunks = chunks("sample.go", Language::Go);
+
+    assert!(has_chunk(
+        &chunks,
+        "type_declaration",
+        "type Circle struct {"
+    ));
+    assert!(has_chunk(
+        &chunks,
+        "method_declaration",
+        "func (c Circle) Area() float64 {"
+    ));
+    assert!(has_chunk(
+        &chunks,
+        "function_declaration",
+        "func NewCircle(radius float64) Circle {"
+    ));
+
+    assert_rejects_broken(
+        "sample.go",
+        Language::Go,
+        "func NewCircle(",
+        "func NewCircle((",
+    );
+}
+
+#[test]
+#[cfg(feature = "lang-java")]
+fn test_java_fixture() {
+    let chunks = chunks("sample.java", Language::Java);
+
+    assert!(has_chunk(
+        &chunks,
+        "class_declaration",
+        "public class Circle implements Shape {"
+    ));
+    assert!(has_chunk(
+        &chunks,
+        "constructor_declaration",
+        "public Circle(double radius) {"
+    ));
+    assert!(has_chunk(&chunks, "method_declaration", "@Override"));
+    assert!(has_chunk(
+        &chunks,
+        "interface_declaration",
+        "interface Shape {"
+    ));
+
+    assert_rejects_broken(
+        "sample.java",
+        Language::Java,
+        "this.radius = radius;",
+        "this.radius = ;",
+    );
+}
+
+#[test]
+#[cfg(feature = "lang-c")]
+fn test_c_fixture() {
+    let chunks = chunks("sample.c", Language::C);
+
+    assert!(has_chunk(&chunks, "struct_specifier", "struct point {"));
+    assert!(has_chunk(
+        &chunks,
+        "type_definition",
+        "typedef enum { RED, GREEN } color;"
+    ));
+    assert!(has_chunk(
+        &chunks,
+        "function_definition",
+        "int manhattan(struct point p) {"
+    ));
+    assert!(has_chunk(
+        &chunks,
+        "function_definition",
+        "void print_point(struct point p) {"
+    ));
+
+    // Uses of `struct point` without a body are not struct chunks
+    let structs = chunks
+        .iter()
+        .filter(|(t, _)| t == "struct_specifier")
+        .count();
+    assert_eq!(structs, 1);
+
+    assert_rejects_broken(
+        "sample.c",
+        Language::C,
+        "return p.x + p.y;",
+        "return p.x + ;",
+    );
+}
+
+#[test]
+#[cfg(feature = "lang-cpp")]
+fn test_cpp_fixture() {
+    let chunks = chunks("sample.cpp", Language::Cpp);
+
+    assert!(has_chunk(
+        &chunks,
+        "namespace_definition",
+        "namespace shapes {"
+    ));
+    assert!(has_chunk(&chunks, "class_specifier", "class Circle {"));
+    assert!(has_chunk(
+        &chunks,
+        "function_definition",
+        "double area() const {"
+    ));
+    assert!(has_chunk(
+        &chunks,
+        "template_declaration",
+        "template <typename T>"
+    ));
+
+    assert_rejects_broken(
+        "sample.cpp",
+        Language::Cpp,
+        "double radius_;",
+        "double radius_",
+    );
+}
+
+#[test]
+fn test_languages_without_grammar_are_rejected() {
+    let mut parser = Parser::new().unwrap();
+    for language in [
+        Language::Python,
+        Language::Rust,
+        Language::Go,
+        Language::Java,
+        Language::C,
+        Language::Cpp,
+    ] {
+        if !parser.supports(language) {
+            assert!(parser.parse("x", language).is_err());
+            assert!(!parser.validate_syntax("x", language));
+        }
+    }
+    assert!(parser.supports(Language::JavaScript));
+    assert!(parser.supports(Language::TypeScript));
+}
@@ -0,0 +1,187 @@
+//! Regression tests for the duplicate-declaration cases in KNOWN-ISSUES.md
+//!
+//! Edits that redeclare an existing symbol must replace its declaration (or be
+//! rejected), never leave both declarations in the file.
+
+use agent_booster::merge::Merger;
+use agent_booster::models::{AgentBoosterError, CodeChunk, Language, MergeStrategy};
+use agent_booster::similarity::SearchResult;
+use agent_booster::{AgentBooster, EditRequest};
+
+fn apply(code: &str, edit: &str, language: Language, threshold: f32) -> String {
+    let mut booster = AgentBooster::default();
+    booster
+        .apply_edit(EditRequest {
+            original_code: code.to_string(),
+            edit_snippet: edit.to_string(),
+            language,
+            confidence_threshold: threshold,
+        })
+        .expect("edit should apply")
+        .merged_code
+}
+
+/// Match on the chunk of `original` spanning `chunk`, with the given similarity
+fn match_on(original: &str, chunk: &str, similarity: f32) -> SearchResult {
+    let start = original.find(chunk).unwrap();
+    SearchResult {
+        chunk: CodeChunk {
+            code: chunk.to_string(),
+            start_byte: start,
+            end_byte: start + chunk.len(),
+            start_line: 0,
+            end_line: 0,
+            node_type: "function_declaration".to_string(),
+            parent_type: None,
+        },
+        similarity,
+        chunk_index: 0,
+    }
+}
+
+const FOO: &str = "function foo() {\n  return 1;\n}";
+const BAR: &str = "function bar() {\n  return 2;\n}";
+
+fn two_functions() -> String {
+    format!("{}\n\n{}\n", FOO, BAR)
+}
+
+#[test]
+fn test_var_to_const() {
+    let result = apply("var x = 1;", "const x = 1;", Language::JavaScript, 0.5);
+    assert_eq!(result.trim(), "const x = 1;");
+
+    let result = apply(
+        "var x = 1;\nvar y = 2;\n",
+        "const x = 1;",
+        Language::JavaScript,
+        0.5,
+    );
+    assert!(!result.contains("var x"));
+    assert!(result.contains("var y = 2;"));
+}
+
+#[test]
+fn test_add_types_does_not_duplicate_function() {
+    let result = apply(
+        "function add(a, b) {\n  return a + b;\n}\n",
+        "function add(a: number, b: number): number {\n  return a + b;\n}",
+        Language::TypeScript,
+        0.5,
+    );
+    assert_eq!(result.matches("function add").count(), 1);
+    assert!(result.contains("a: number"));
+}
+
+#[test]
+fn test_add_logging_replaces_function() {
+    let result = apply(
+        "function foo(){}",
+        "function foo(){console.log('called')}",
+        Language::JavaScript,
+        0.5,
+    );
+    assert_eq!(result.trim(), "function foo(){console.log('called')}");
+}
+
+#[test]
+fn test_insert_redeclaring_matched_chunk_replaces_it() {
+    let mut merger = Merger::new().unwrap();
+    let original = two_functions();
+    let edit = "function bar(x) {\n  console.log('bar', x);\n  return 2 * x;\n}";
+
+    // Similarity this low would normally insert after `bar`
+    let result = merger
+        .merge(
+            &original,
+            edit,
+            &match_on(&original, BAR, 0.4),
+            Language::JavaScript,
+            0.3,
+        )
+        .unwrap();
+
+    assert_eq!(result.strategy, MergeStrategy::FuzzyReplace);
+    assert_eq!(result.code, format!("{}\n\n{}\n", FOO, edit));
+}
+
+#[test]
+fn test_insert_redeclaring_method_replaces_only_the_method() {
+    let mut merger = Merger::new().unwrap();
+    let class = "class Calc {\n  add(a, b) {\n    return a + b;\n  }\n\n  sub(a, b) {\n    return a - b;\n  }\n}";
+    let original = format!("{}\n", class);
+    let edit = "add(a, b) {\n    console.log('adding');\n    return a + b;\n  }";
+
+    let result = merger
+        .merge(
+            &original,
+            edit,
+            &match_on(&original, class, 0.4),
+            Language::JavaScript,
+            0.3,
+        )
+        .unwrap();
+
+    assert_eq!(result.code.matches("add(a, b)").count(), 1);
+    assert!(result.code.contains("console.log('adding')"));
+    assert!(result.code.contains("sub(a, b)"));
+}
+
+#[test]
+fn test_redeclaring_neighbour_is_rejected() {
+    let mut merger = Merger::new().unwrap();
+    let original = two_functions();
+    let edit = "function bar() {\n  return 3;\n}";
+
+    // Inserting after `foo` would put a second `bar` right before the first
+    let result = merger.merge(
+        &original,
+        edit,
+        &match_on(&original, FOO, 0.4),
+        Language::JavaScript,
+        0.3,
+    );
+
+    match result {
+        Err(AgentBoosterError::DuplicateDeclaration(name)) => assert_eq!(name, "bar"),
+        other => panic!(
+            "expected DuplicateDeclaration, got {:?}",
+            other.map(|r| r.code)
+        ),
+    }
+}
+
+#[test]
+fn test_new_declarations_are_still_inserted() {
+    let mut merger = Merger::new().unwrap();
+    let original = two_functions();
+    let edit = "function baz() {\n  return 3;\n}";
+
+    let result = merger
+        .merge(
+            &original,
+            edit,
+            &match_on(&original, FOO, 0.4),
+            Language::JavaScript,
+            0.3,
+        )
+        .unwrap();
+
+    assert_eq!(result.strategy, MergeStrategy::InsertAfter);
+    assert!(result.code.contains(FOO));
+    assert!(result.code.contains(BAR));
+    assert!(result.code.contains("function baz()"));
+
+    // Code that declares nothing, like a call, is inserted as before
+    let result = merger
+        .merge(
+            &original,
+            "foo();",
+            &match_on(&original, BAR, 0.4),
+            Language::JavaScript,
+            0.3,
+        )
+        .unwrap();
+    assert_eq!(result.strategy, MergeStrategy::InsertAfter);
+    assert!(result.code.contains(FOO));
+}
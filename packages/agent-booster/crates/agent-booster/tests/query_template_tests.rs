@@ -0,0 +1,239 @@
+//! Builtin templates: regex detection against tree-sitter queries
+//!
+//! Each case is code the regex templates get wrong, either by not matching
+//! or by returning the whole snippet in place of the file. The query
+//! templates rewrite only the matched node.
+
+#![cfg(feature = "tree-sitter-parser")]
+
+use agent_booster::models::Language;
+use agent_booster::templates::TemplateEngine;
+use agent_booster::AgentBooster;
+use agent_booster::EditRequest;
+
+fn query_transform(input: &str, edit: &str) -> (String, String) {
+    let m = TemplateEngine::try_template_transform(input, edit, Language::JavaScript)
+        .expect("query template should match");
+    (m.template_name, m.transformed_code)
+}
+
+#[test]
+fn test_promise_chain_in_class_method() {
+    let input = r#"class UserApi {
+  constructor(client) {
+    this.client = client;
+  }
+
+  loadUser(id) {
+    return this.client
+      .get(`/users/${id}`)
+      .then((res) => {
+        return res.data;
+      });
+  }
+}
+
+module.exports = UserApi;"#;
+    let edit = r#"async loadUser(id) {
+  const res = await this.client.get(`/users/${id}`);
+  return res.data;
+}"#;
+
+    // The regex needs `function name(...)`, and detection hands back the method
+    assert!(TemplateEngine::apply_async_await(input, edit).is_none());
+    let regex = TemplateEngine::try_regex_transform(input, edit).unwrap();
+    assert_eq!(regex.transformed_code, edit);
+
+    let (name, code) = query_transform(input, edit);
+    assert_eq!(name, "promise-to-async-await");
+    assert_eq!(
+        code,
+        r#"class UserApi {
+  constructor(client) {
+    this.client = client;
+  }
+
+  async loadUser(id) {
+    const res = await this.client.get(`/users/${id}`);
+    return res.data;
+  }
+}
+
+module.exports = UserApi;"#
+    );
+}
+
+#[test]
+fn test_try_catch_multiline_signature() {
+    let input = r#"// see helpers() for the defaults
+function fetchConfig(
+  path = defaultPath(),
+  options = {}
+) {
+  const raw = readFile(path);
+  return parse(raw, options);
+}
+
+function other() {
+  return 1;
+}"#;
+    let edit = r#"function fetchConfig(
+  path = defaultPath(),
+  options = {}
+) {
+  try {
+    const raw = readFile(path);
+    return parse(raw, options);
+  } catch (error) {
+    return null;
+  }
+}"#;
+
+    let regex = TemplateEngine::try_regex_transform(input, edit).unwrap();
+    assert_eq!(regex.transformed_code, edit);
+
+    let (name, code) = query_transform(input, edit);
+    assert_eq!(name, "try-catch-function");
+    assert!(code.starts_with("// see helpers() for the defaults\nfunction fetchConfig("));
+    assert!(code.contains("  try {\n    const raw = readFile(path);"));
+    assert!(code.ends_with("function other() {\n  return 1;\n}"));
+}
+
+#[test]
+fn test_try_catch_async_arrow_function() {
+    let input = r#"const api = {};
+
+const fetchUser = async (id) => {
+  const res = await fetch(`/users/${id}`);
+  return res.json();
+};"#;
+    let edit = r#"const fetchUser = async (id) => {
+  try {
+    const res = await fetch(`/users/${id}`);
+    return res.json();
+  } catch (error) {
+    return null;
+  }
+};"#;
+
+    // The regex only knows `async function name(...)`
+    assert!(TemplateEngine::apply_try_catch_async(input, edit).is_none());
+
+    let (name, code) = query_transform(input, edit);
+    assert_eq!(name, "try-catch-async-function");
+    assert!(code.starts_with("const api = {};\n\nconst fetchUser = async (id) => {\n  try {"));
+}
+
+#[test]
+fn test_json_parse_ignores_comments() {
+    let input = r#"// JSON.parse(cached) was too slow here
+function load(raw) {
+  const data = JSON.parse(raw);
+  return data;
+}"#;
+    let edit = r#"let data;
+try {
+  data = JSON.parse(raw);
+} catch (error) {
+  data = null;
+}"#;
+
+    // The regex matches the comment, and the function is lost
+    assert_eq!(
+        TemplateEngine::apply_try_catch_json_parse(input, edit).as_deref(),
+        Some(edit)
+    );
+
+    let (name, code) = query_transform(input, edit);
+    assert_eq!(name, "try-catch-json-parse");
+    assert_eq!(
+        code,
+        r#"// JSON.parse(cached) was too slow here
+function load(raw) {
+  let data;
+  try {
+    data = JSON.parse(raw);
+  } catch (error) {
+    data = null;
+  }
+  return data;
+}"#
+    );
+}
+
+#[test]
+fn test_validation_in_object_method() {
+    let input = r#"const stats = {
+  ratio(total, count) {
+    return (total) / count;
+  },
+};"#;
+    let edit = r#"ratio(total, count) {
+  if (count === 0) {
+    throw new Error("count must not be zero");
+  }
+  return (total) / count;
+}"#;
+
+    assert!(TemplateEngine::apply_validation(input, edit).is_none());
+    assert!(TemplateEngine::try_regex_transform(input, edit).is_none());
+
+    let (name, code) = query_transform(input, edit);
+    assert_eq!(name, "validation-division");
+    assert_eq!(
+        code,
+        r#"const stats = {
+  ratio(total, count) {
+    if (count === 0) {
+      throw new Error("count must not be zero");
+    }
+    return (total) / count;
+  },
+};"#
+    );
+}
+
+#[test]
+fn test_no_match_without_new_shape() {
+    let input = "function add(a, b) {\n  return a + b;\n}";
+    let edit = "function add(a, b) {\n  return b + a;\n}";
+
+    assert!(TemplateEngine::try_template_transform(input, edit, Language::JavaScript).is_none());
+    assert!(TemplateEngine::try_template_transform(input, edit, Language::Python).is_none());
+}
+
+#[test]
+fn test_apply_edit_keeps_rest_of_file() {
+    let input = r#"import { client } from "./client";
+
+export function getUser(id) {
+  return client.get(`/users/${id}`).then((res) => res.data);
+}
+
+export function getOrder(id) {
+  return client.get(`/orders/${id}`);
+}"#;
+    let edit = r#"export async function getUser(id) {
+  const res = await client.get(`/users/${id}`);
+  return res.data;
+}"#;
+
+    let mut booster = AgentBooster::default();
+    let result = booster
+        .apply_edit(EditRequest {
+            original_code: input.to_string(),
+            edit_snippet: edit.to_string(),
+            language: Language::JavaScript,
+            confidence_threshold: 0.5,
+        })
+        .unwrap();
+
+    assert!(result
+        .merged_code
+        .starts_with("import { client } from \"./client\";"));
+    assert!(result
+        .merged_code
+        .contains("const res = await client.get(`/users/${id}`);"));
+    assert!(result.merged_code.contains("export function getOrder(id)"));
+    assert!(!result.merged_code.contains(".then("));
+}
@@ -0,0 +1,177 @@
+//! Workspace edits against the project in `tests/fixtures/workspace`
+
+use agent_booster::workspace::{Workspace, MAX_FILE_BYTES};
+use agent_booster::{AgentBooster, Language, WorkspaceEditRequest};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn fixture() -> Workspace {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/workspace");
+    Workspace::index(root).expect("fixture workspace should index")
+}
+
+fn request(snippet: &str, hint: Option<&str>) -> WorkspaceEditRequest {
+    WorkspaceEditRequest {
+        edit_snippet: snippet.to_string(),
+        target_hint: hint.map(str::to_string),
+        language: None,
+        confidence_threshold: 0.5,
+    }
+}
+
+const FORMAT_NAME: &str = r#"function formatName(user) {
+  return `${user.firstName} ${user.lastName}`.trim();
+}"#;
+
+#[test]
+fn test_index_skips_hidden_dirs_and_other_files() {
+    let workspace = fixture();
+    let paths: Vec<&Path> = workspace.files().iter().map(|f| f.path.as_path()).collect();
+
+    for path in [
+        "src/legacy/format.js",
+        "src/services/order_service.ts",
+        "src/services/user_service.ts",
+        "src/utils/format.js",
+    ] {
+        assert!(paths.contains(&Path::new(path)), "{} not indexed", path);
+    }
+    assert!(!paths.iter().any(|p| p.starts_with(".generated")));
+    assert!(!paths.contains(&Path::new("README.md")));
+
+    #[cfg(feature = "lang-python")]
+    assert!(paths.contains(&Path::new("scripts/seed.py")));
+}
+
+/// Empty scratch directory for tests that write files
+fn scratch(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("agent-booster-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("src")).unwrap();
+    dir
+}
+
+#[test]
+fn test_index_honors_gitignore_and_size_cap() {
+    let root = scratch("gitignore");
+    fs::write(root.join(".gitignore"), "src/generated_*.js\n").unwrap();
+    fs::write(root.join("src/app.js"), "function app() {\n  return 1;\n}\n").unwrap();
+    fs::write(root.join("src/generated_api.js"), "function api() {}\n").unwrap();
+    let big = "// filler\n".repeat(MAX_FILE_BYTES as usize / 10 + 1);
+    fs::write(root.join("src/bundle.js"), big).unwrap();
+
+    let workspace = Workspace::index(&root).unwrap();
+    let paths: Vec<&Path> = workspace.files().iter().map(|f| f.path.as_path()).collect();
+    assert_eq!(paths, [Path::new("src/app.js")]);
+
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn test_refresh_rereads_changed_files() {
+    let root = scratch("refresh");
+    fs::write(root.join("src/a.js"), "function a() {\n  return 1;\n}\n").unwrap();
+    fs::write(root.join("src/b.js"), "function b() {\n  return 2;\n}\n").unwrap();
+    let mut workspace = Workspace::index(&root).unwrap();
+
+    fs::write(root.join("src/a.js"), "function a() {\n  return 100;\n}\n").unwrap();
+    fs::remove_file(root.join("src/b.js")).unwrap();
+    fs::write(root.join("src/c.js"), "function c() {\n  return 3;\n}\n").unwrap();
+    workspace.refresh().unwrap();
+
+    let files: Vec<(&Path, &str)> = workspace
+        .files()
+        .iter()
+        .map(|f| (f.path.as_path(), f.code.as_str()))
+        .collect();
+    assert_eq!(
+        files,
+        [
+            (Path::new("src/a.js"), "function a() {\n  return 100;\n}\n"),
+            (Path::new("src/c.js"), "function c() {\n  return 3;\n}\n"),
+        ]
+    );
+
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn test_edit_applies_to_best_file() {
+    let workspace = fixture();
+    let mut booster = AgentBooster::default();
+
+    let result = booster
+        .apply_workspace_edit(
+            &workspace,
+            request(
+                r#"export async function getUser(id: string): Promise<User> {
+  const response = await fetch(`/api/v2/users/${id}`);
+  return response.json();
+}"#,
+                None,
+            ),
+        )
+        .unwrap();
+
+    assert!(!result.ambiguous);
+    assert_eq!(
+        result.path,
+        Some(PathBuf::from("src/services/user_service.ts"))
+    );
+    let merged = result.edit.unwrap().merged_code;
+    assert!(merged.contains("fetch(`/api/v2/users/${id}`)"));
+    assert!(merged.contains("export async function updateUserEmail"));
+    assert_eq!(merged.matches("function getUser").count(), 1);
+    assert_eq!(result.candidates[0].chunk.node_type, "export_statement");
+}
+
+#[test]
+fn test_ambiguous_edit_returns_candidates() {
+    let workspace = fixture();
+    let mut booster = AgentBooster::default();
+
+    let result = booster
+        .apply_workspace_edit(&workspace, request(FORMAT_NAME, None))
+        .unwrap();
+
+    assert!(result.ambiguous);
+    assert!(result.path.is_none() && result.edit.is_none());
+    let files: Vec<&Path> = result.candidates[..2]
+        .iter()
+        .map(|c| c.path.as_path())
+        .collect();
+    assert!(files.contains(&Path::new("src/legacy/format.js")));
+    assert!(files.contains(&Path::new("src/utils/format.js")));
+}
+
+#[test]
+fn test_path_hint_resolves_ambiguity() {
+    let workspace = fixture();
+    let mut booster = AgentBooster::default();
+
+    let result = booster
+        .apply_workspace_edit(
+            &workspace,
+            request(FORMAT_NAME, Some("the legacy format module")),
+        )
+        .unwrap();
+
+    assert!(!result.ambiguous);
+    assert_eq!(result.path, Some(PathBuf::from("src/legacy/format.js")));
+    assert_eq!(result.candidates[0].path_score, 1.0);
+    let merged = result.edit.unwrap().merged_code;
+    assert!(merged.contains("`${user.firstName} ${user.lastName}`.trim()"));
+    assert!(merged.contains("function formatPhone"));
+}
+
+#[test]
+fn test_rank_filters_by_language() {
+    let workspace = fixture();
+
+    let candidates = workspace.rank(FORMAT_NAME, Some(Language::TypeScript), None, 5);
+
+    assert!(!candidates.is_empty());
+    assert!(candidates
+        .iter()
+        .all(|c| c.language == Language::TypeScript));
+}
@@ -191,10 +191,9 @@ fn test_config_customization() {
         .expect("Failed to read fixture file");
 
     // Create booster with custom config
-    let config = Config {
-        confidence_threshold: 0.8,
-        max_chunks: 10,
-    };
+    let config = Config::default()
+        .with_confidence_threshold(0.8)
+        .with_max_chunks(10);
 
     let mut booster = AgentBooster::new(config).unwrap();
 
@@ -2,19 +2,36 @@ use std::env;
 use std::fs;
 use std::io::Write;
 use std::path::{Path, PathBuf};
+use sha2::{Digest, Sha256};
 
 const JJ_VERSION: &str = "0.35.0";
 
+/// Pinned SHA-256 digests (hex) of the jj `JJ_VERSION` release archives
+///
+/// Update these together with `JJ_VERSION`, from archives downloaded from
+/// https://github.com/jj-vcs/jj/releases and checked against the release. A
+/// target whose archive has no entry is built without a bundled jj and uses
+/// the jj on `PATH` instead.
+///
+/// No digests are pinned yet, so no target bundles jj; do not add entries
+/// that were not computed from the release archives themselves.
+const JJ_ARCHIVE_SHA256: &[(&str, &str)] = &[];
+
 fn main() {
     println!("cargo:rerun-if-changed=build.rs");
 
-    let target = env::var("TARGET").unwrap();
     let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+    embed_jj(&out_dir);
+    write_manifest(&out_dir);
+}
+
+fn embed_jj(out_dir: &Path) {
+    let target = env::var("TARGET").unwrap();
 
     // Skip jj binary download for docs.rs builds
     if env::var("DOCS_RS").is_ok() {
         println!("cargo:warning=Skipping jj binary download for docs.rs build");
-        create_dummy_binary(&out_dir);
+        create_dummy_binary(out_dir);
         return;
     }
 
@@ -31,7 +48,7 @@ fn main() {
             Some(("jj-v{}-aarch64-unknown-linux-musl.tar.gz", "jj")),
         _ => {
             println!("cargo:warning=Unsupported platform for jj binary bundling: {}", target);
-            create_dummy_binary(&out_dir);
+            create_dummy_binary(out_dir);
             return;
         }
     };
@@ -40,7 +57,16 @@ fn main() {
         let archive_name = archive_name.replace("{}", JJ_VERSION);
         let binary_path = binary_path.replace("{}", JJ_VERSION);
 
-        match download_and_extract_jj(&out_dir, &archive_name, &binary_path) {
+        let Some(expected_sha256) = pinned_sha256(&archive_name) else {
+            println!(
+                "cargo:warning=No pinned SHA-256 for {}. Using fallback to system jj.",
+                archive_name
+            );
+            create_dummy_binary(out_dir);
+            return;
+        };
+
+        match download_and_extract_jj(out_dir, &archive_name, &binary_path, &expected_sha256) {
             Ok(jj_binary) => {
                 println!("cargo:warning=Successfully downloaded jj binary for {}", target);
 
@@ -59,13 +85,44 @@ fn main() {
             }
             Err(e) => {
                 println!("cargo:warning=Failed to download jj binary: {}. Using fallback to system jj.", e);
-                create_dummy_binary(&out_dir);
+                create_dummy_binary(out_dir);
             }
         }
     }
 }
 
-fn download_and_extract_jj(out_dir: &Path, archive_name: &str, binary_path: &str) -> Result<PathBuf, Box<dyn std::error::Error>> {
+/// Pinned digest of `archive_name` from [`JJ_ARCHIVE_SHA256`]
+fn pinned_sha256(archive_name: &str) -> Option<String> {
+    JJ_ARCHIVE_SHA256
+        .iter()
+        .find(|(name, _)| *name == archive_name)
+        .map(|(_, digest)| digest.to_lowercase())
+}
+
+/// Fail the build unless `path` has the pinned digest
+///
+/// A mismatching cached archive is removed so the next build downloads it
+/// again.
+fn verify_archive(path: &Path, expected_sha256: &str) {
+    let archive = fs::read(path).expect("Failed to read jj archive");
+    let digest = hex::encode(Sha256::digest(&archive));
+    if digest != expected_sha256 {
+        let _ = fs::remove_file(path);
+        panic!(
+            "jj archive {} has SHA-256 {}, but {} is pinned",
+            path.display(),
+            digest,
+            expected_sha256
+        );
+    }
+}
+
+fn download_and_extract_jj(
+    out_dir: &Path,
+    archive_name: &str,
+    binary_path: &str,
+    expected_sha256: &str,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
     let cache_dir = out_dir.join("jj-cache");
     fs::create_dir_all(&cache_dir)?;
 
@@ -94,6 +151,9 @@ fn download_and_extract_jj(out_dir: &Path, archive_name: &str, binary_path: &str
         println!("cargo:warning=Using cached jj archive");
     }
 
+    // Network failures fall back to system jj; a wrong archive fails the build
+    verify_archive(&archive_path, expected_sha256);
+
     // Extract archive (tar.gz or zip)
     if archive_path.extension().and_then(|s| s.to_str()) == Some("zip") {
         // Extract ZIP (Windows builds)
@@ -127,6 +187,19 @@ fn download_and_extract_jj(out_dir: &Path, archive_name: &str, binary_path: &str
     }
 }
 
+/// Write the SHA-256 manifest of the embedded binary
+///
+/// The binary comes from an archive checked against its pinned digest. The
+/// runtime checks the extracted copy in the cache directory against this
+/// digest before executing it, which catches a cached copy that was modified
+/// after extraction.
+fn write_manifest(out_dir: &Path) {
+    let binary = fs::read(out_dir.join("jj")).expect("Failed to read embedded jj binary");
+    let digest = hex::encode(Sha256::digest(&binary));
+    fs::write(out_dir.join("jj.sha256"), format!("{}  jj\n", digest))
+        .expect("Failed to write jj manifest");
+}
+
 fn create_dummy_binary(out_dir: &Path) {
     // Create a placeholder file indicating jj binary should be found in PATH
     let placeholder = out_dir.join("jj");
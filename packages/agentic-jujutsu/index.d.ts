@@ -17,11 +17,21 @@ export interface JjConfig {
   maxLogEntries: number
   /** Enable AgentDB sync */
   enableAgentdbSync: boolean
+  /** Path to a conflict rules file (.toml or .json) for agent coordination */
+  conflictRulesPath?: string
+  /** Retries for commands failing with a concurrent modification (default: 3) */
+  maxRetries?: number
+  /** Revsets agents must not rewrite, e.g. `trunk()` or `main@origin` */
+  protectedRevsets?: Array<string>
+  /** Bookmark names or globs agents must not move, delete or rewrite */
+  protectedBookmarks?: Array<string>
+  /** Public keys (hex) allowed to approve policy overrides; none if unset */
+  overrideApprovers?: Array<string>
 }
 /**
- * ML-DSA signing keypair
+ * Signing keypair
  *
- * Contains public and secret keys for quantum-resistant digital signatures.
+ * Contains public and secret keys for digital signatures.
  */
 export interface SigningKeypair {
   /** Public key (hex-encoded) */
@@ -45,9 +55,13 @@ export interface OperationSignature {
   algorithm: string
 }
 /**
- * Generate a new ML-DSA signing keypair
+ * Extract the stable error code from a message thrown by this module
  *
- * Creates a quantum-resistant keypair for signing operations.
+ * Returns `null` for messages without a `[CODE]` prefix.
+ */
+export declare function errorCode(message: string): string | null
+/**
+ * Generate a new signing keypair
  *
  * # Examples
  *
@@ -60,9 +74,7 @@ export interface OperationSignature {
  */
 export declare function generateSigningKeypair(): SigningKeypair
 /**
- * Sign a message with ML-DSA
- *
- * Creates a quantum-resistant digital signature for the given message.
+ * Sign a message
  *
  * # Arguments
  *
@@ -75,9 +87,7 @@ export declare function generateSigningKeypair(): SigningKeypair
  */
 export declare function signMessage(message: Array<number>, secretKey: string): string
 /**
- * Verify a message signature with ML-DSA
- *
- * Verifies that a signature is valid for the given message and public key.
+ * Verify a message signature
  *
  * # Arguments
  *
@@ -90,6 +100,123 @@ export declare function signMessage(message: Array<number>, secretKey: string):
  * `true` if the signature is valid, `false` otherwise
  */
 export declare function verifySignature(message: Array<number>, signature: string, publicKey: string): boolean
+/** Approval to run a command that violates the protection policy */
+export interface PolicyOverride {
+  /** Who approved the override */
+  approvedBy: string
+  /** Why the protected target may be changed */
+  reason: string
+  /** Approver's public key (hex); must be one of `overrideApprovers` */
+  publicKey: string
+  /** Approver's signature (hex) over `policyOverrideMessage` for the command */
+  signature: string
+}
+/**
+ * Message an approver signs to override the policy for one command
+ *
+ * Covers the jj arguments, the approver and the reason, so the signature
+ * cannot be reused for a different command.
+ */
+export declare function policyOverrideMessage(args: Array<string>, approvedBy: string, reason: string): Array<number>
+/** A comment on one line of a change's diff */
+export interface ReviewComment {
+  /** Comment ID */
+  id: string
+  /** Who wrote the comment */
+  author: string
+  /** File the comment is on */
+  path: string
+  /** Line number in the new version of the file */
+  line: number
+  /** Header of the hunk containing the line, e.g. `@@ -3,4 +3,6 @@` */
+  hunk: string
+  /** Text of the commented line */
+  anchorText: string
+  /** Lines preceding the commented line within its hunk */
+  context: Array<string>
+  /** Comment text */
+  body: string
+  /** Commit the anchor was last resolved against */
+  commitId: string
+  /** Whether the line no longer exists in the change */
+  outdated: boolean
+  /** When the comment was added (RFC 3339) */
+  createdAt: string
+}
+/** A reviewer's signed decision on a change */
+export interface ReviewVerdict {
+  /** Who gave the verdict */
+  reviewer: string
+  /** `approve` or `request_changes` */
+  verdict: string
+  /** Optional summary */
+  body?: string
+  /** Commit the verdict applies to */
+  commitId: string
+  /** When the verdict was given (RFC 3339) */
+  timestamp: string
+  /** Signature (hex) over the verdict */
+  signature: string
+  /** Reviewer's public key (hex) */
+  publicKey: string
+  /** Whether the change was rewritten after the verdict */
+  stale: boolean
+}
+/** Review of a change */
+export interface Review {
+  /** Change under review */
+  changeId: string
+  /** Commit of the change when the review was last refreshed */
+  commitId: string
+  /** Who opened the review */
+  openedBy: string
+  /** When the review was opened (RFC 3339) */
+  openedAt: string
+  /** `open`, `approved` or `changes_requested` */
+  status: string
+  /** Line comments */
+  comments: Array<ReviewComment>
+  /** Verdicts, oldest first */
+  verdicts: Array<ReviewVerdict>
+}
+/** A hunk of a file in a change's diff */
+export interface HunkRef {
+  /** File path (after the change) */
+  path: string
+  /** Index of the hunk within the file, from 0 */
+  index: number
+}
+/** One resulting change of a split */
+export interface SplitGroup {
+  /** Description of the resulting change */
+  description: string
+  /** Files (or directories) that go into this change whole */
+  paths?: Array<string>
+  /** Individual hunks that go into this change */
+  hunks?: Array<HunkRef>
+}
+/** A hunk as listed for building a partition */
+export interface DiffHunkInfo {
+  /** File path (after the change) */
+  path: string
+  /** Index of the hunk within the file, from 0 */
+  index: number
+  /** Hunk header, e.g. `@@ -3,4 +3,6 @@` */
+  header: string
+  /** Lines added */
+  additions: number
+  /** Lines removed */
+  deletions: number
+  /** Whether the hunk only changes whitespace */
+  formattingOnly: boolean
+}
+/** A change created by a split */
+export interface SplitChange {
+  /** Change ID */
+  changeId: string
+  /** Description it was given */
+  description: string
+}
 /**
  * Type of jujutsu operation
  *
@@ -161,7 +288,97 @@ export const enum OperationType {
   /** Show diff */
   Diff = 31,
   /** Unknown operation type */
-  Unknown = 32
+  Unknown = 32,
+  /** Move changes into the mutable ancestors that last touched them */
+  Absorb = 33,
+  /** Bisect history to find a bad revision */
+  Bisect = 34,
+  /** Create a bookmark */
+  BookmarkCreate = 35,
+  /** Delete a bookmark */
+  BookmarkDelete = 36,
+  /** Forget a bookmark without propagating the deletion */
+  BookmarkForget = 37,
+  /** List bookmarks */
+  BookmarkList = 38,
+  /** Move existing bookmarks */
+  BookmarkMove = 39,
+  /** Rename a bookmark */
+  BookmarkRename = 40,
+  /** Create or update a bookmark */
+  BookmarkSet = 41,
+  /** Track a remote bookmark */
+  BookmarkTrack = 42,
+  /** Stop tracking a remote bookmark */
+  BookmarkUntrack = 43,
+  /** Read or modify configuration */
+  Config = 44,
+  /** Show how a change evolved */
+  Evolog = 45,
+  /** Inspect file contents or listings */
+  File = 46,
+  /** Change the executable bit of files */
+  FileChmod = 47,
+  /** Start tracking files */
+  FileTrack = 48,
+  /** Stop tracking files */
+  FileUntrack = 49,
+  /** Run formatters on changed files */
+  Fix = 50,
+  /** Upload changes to Gerrit */
+  GerritUpload = 51,
+  /** Manage Git remotes */
+  GitRemote = 52,
+  /** Show help */
+  Help = 53,
+  /** Compare the changes of two commits */
+  Interdiff = 54,
+  /** Modify commit metadata */
+  Metaedit = 55,
+  /** Move the working copy to a child */
+  Next = 56,
+  /** Move the working copy to a parent */
+  Prev = 57,
+  /** Abandon operations */
+  OpAbandon = 58,
+  /** Compare repository states between operations */
+  OpDiff = 59,
+  /** Show the operation log */
+  OpLog = 60,
+  /** Restore the repository to an earlier operation */
+  OpRestore = 61,
+  /** Revert an operation */
+  OpRevert = 62,
+  /** Show an operation */
+  OpShow = 63,
+  /** Make revisions siblings */
+  Parallelize = 64,
+  /** Redo the last undone operation */
+  Redo = 65,
+  /** Apply the reverse of revisions */
+  Revert = 66,
+  /** Show the workspace root */
+  Root = 67,
+  /** Show commit description and changes */
+  Show = 68,
+  /** Cryptographically sign revisions */
+  Sign = 69,
+  /** Remove redundant parents */
+  SimplifyParents = 70,
+  /** Manage sparse checkout patterns */
+  Sparse = 71,
+  /** Delete a tag */
+  TagDelete = 72,
+  /** List tags */
+  TagList = 73,
+  /** Drop signatures from revisions */
+  Unsign = 74,
+  /** Utility commands */
+  Util = 75,
+  /** Show the jj version */
+  Version = 76,
+  /** Manage workspaces */
+  Workspace = 77
 }
 /**
  * Single jujutsu operation
@@ -213,6 +430,10 @@ export interface JjOperation {
   signature?: string
   /** Public key used for signature verification (hex-encoded, optional) */
   signaturePublicKey?: string
+  /** Revisions (revsets) the command operated on */
+  revisions: Array<string>
+  /** Paths (filesets) the command operated on */
+  paths: Array<string>
 }
 /**
  * Quantum-resistant signing keypair (ML-DSA-65)
@@ -614,8 +835,23 @@ export declare class JjWrapper {
   getConfig(): JjConfig
   /** Get operation log statistics as JSON string */
   getStats(): string
-  /** Execute a jj command and return the result */
+  /**
+   * Execute a jj command and return the result
+   *
+   * Commands that violate the protection policy (`protectedRevsets`,
+   * `protectedBookmarks`) are rejected before jj runs.
+   */
   execute(args: Array<string>): Promise<JjResult>
+  /**
+   * Execute a jj command, overriding the protection policy
+   *
+   * The approver must be one of `overrideApprovers` and sign
+   * `policyOverrideMessage(args, approvedBy, reason)` with their own key.
+   * If the command violates the policy, a `PolicyOverride` entry carrying
+   * that signature is added to the operation log first, and the executed
+   * operation references it in its `policy_override` metadata.
+   */
+  executeWithOverride(args: Array<string>, approval: PolicyOverride): Promise<JjResult>
   /** Get operations from the operation log */
   getOperations(limit: number): Array<JjOperation>
   /** Get user-initiated operations (exclude snapshots) */
@@ -624,6 +860,15 @@ export declare class JjWrapper {
   getConflicts(commit?: string | undefined | null): Promise<Array<JjConflict>>
   /** Describe the current commit with a message */
   describe(message: string): Promise<JjOperation>
+  /**
+   * Generate a commit message for the working-copy change from its diff
+   *
+   * The message is built offline from `jj diff --git -r @` and the active
+   * trajectory's task and operations.
+   */
+  generateDescription(): Promise<string>
+  /** Describe the working-copy change with a generated message */
+  describeAuto(): Promise<JjOperation>
   /** Get repository status */
   status(): Promise<JjResult>
   /** Get diff between two commits */
@@ -654,6 +899,43 @@ export declare class JjWrapper {
   log(limit?: number | undefined | null): Promise<Array<JjCommit>>
   /** Clear operation log */
   clearLog(): void
+  /**
+   * Open a review for a change, or refresh the existing one
+   *
+   * Reviews are keyed by change ID and stored under `.jj/agentic-jujutsu/reviews`.
+   */
+  openReview(changeId: string, openedBy: string): Promise<Review>
+  /** Get the review of a change, with comments re-anchored to its current commit */
+  getReview(changeId: string): Promise<Review>
+  /** List stored reviews as of their last refresh */
+  listReviews(): Array<Review>
+  /** Comment on a line of a change's diff (line number in the new file) */
+  addReviewComment(changeId: string, author: string, path: string, line: number, body: string): Promise<ReviewComment>
+  /**
+   * Approve a change or request changes, signed with the reviewer's key
+   *
+   * `verdict` is `approve` or `request_changes`. The verdict applies to the
+   * change's current commit and becomes stale when the change is rewritten.
+   */
+  submitReviewVerdict(changeId: string, reviewer: string, verdict: string, body: string | undefined | null, secretKey: string, publicKey: string): Promise<ReviewVerdict>
+  /** Verify the signatures of all verdicts on a change */
+  verifyReview(changeId: string): Promise<boolean>
+  /** List the hunks of a revision's diff, for building a split partition */
+  diffHunks(revision: string): Promise<Array<DiffHunkInfo>>
+  /**
+   * Split a change into one change per group, without an editor
+   *
+   * Groups are stacked in order on the original's parents. The last group
+   * keeps the original change ID, so descendants stay on top of it.
+   */
+  splitChange(revision: string, groups: Array<SplitGroup>): Promise<Array<SplitChange>>
+  /**
+   * Split a change by formatting, file category and directory
+   *
+   * Returns an empty list (and leaves the change alone) when everything
+   * falls into one group.
+   */
+  autoSplit(revision: string): Promise<Array<SplitChange>>
   /** Start a learning trajectory for a task */
   startTrajectory(task: string): string
   /** Add current operations to the active trajectory */
@@ -712,8 +994,18 @@ export declare class JjWrapper {
   enableAgentCoordination(): Promise<void>
   /** Register a new agent in the coordination system */
   registerAgent(agentId: string, agentType: string): Promise<void>
-  /** Register an agent operation in the coordination system */
+  /**
+   * Register an agent operation in the coordination system
+   *
+   * The operation is recorded even when a conflict rule with action
+   * `block` or `require-approval` fires against another agent's
+   * operation; it is then flagged and the call fails with
+   * `OPERATION_BLOCKED` or `APPROVAL_REQUIRED`, so it can be undone or
+   * approved.
+   */
   registerAgentOperation(agentId: string, operationId: string, affectedFiles: Array<string>): Promise<string>
+  /** Approve an operation flagged by a `require-approval` conflict rule */
+  approveAgentOperation(operationId: string): Promise<void>
   /** Check for conflicts with proposed operation */
   checkAgentConflicts(operationId: string, operationType: string, affectedFiles: Array<string>): Promise<string>
   /** Get agent statistics */
@@ -310,8 +310,9 @@ if (!nativeBinding) {
   throw new Error(`Failed to load native binding`)
 }
 
-const { generateSigningKeypair, signMessage, verifySignature, OperationType, QuantumSigner, ChangeStatus, JjWrapper } = nativeBinding
+const { errorCode, generateSigningKeypair, signMessage, verifySignature, OperationType, QuantumSigner, ChangeStatus, JjWrapper } = nativeBinding
 
+module.exports.errorCode = errorCode
 module.exports.generateSigningKeypair = generateSigningKeypair
 module.exports.signMessage = signMessage
 module.exports.verifySignature = verifySignature
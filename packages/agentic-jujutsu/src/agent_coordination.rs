@@ -7,12 +7,13 @@
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
+use crate::leases::{patterns_overlap, FileLease, LeaseManager, LeaseMode};
 use crate::operations::JJOperation;
-use crate::Result;
+use crate::{JJError, Result};
 
 /// Agent information stored in coordination system
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -44,6 +45,20 @@ pub struct AgentMessage {
     pub timestamp: DateTime<Utc>,
     /// Additional metadata
     pub metadata: HashMap<String, String>,
+    /// Rule violation the operation is flagged with, until it is approved
+    #[serde(default)]
+    pub flag: Option<OperationFlag>,
+}
+
+/// Block or require-approval rule that fired for a registered operation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OperationFlag {
+    /// Name of the rule that fired
+    pub rule: String,
+    /// Action of the rule
+    pub action: RuleAction,
+    /// Shared files covered by the rule
+    pub files: Vec<String>,
 }
 
 /// Conflict between agent operations
@@ -63,6 +78,12 @@ pub struct AgentConflict {
     pub description: String,
     /// Suggested resolution strategy
     pub resolution_strategy: String,
+    /// Name of the conflict rule that fired (None for the generic fallback)
+    #[serde(default)]
+    pub rule: Option<String>,
+    /// Action requested for this conflict
+    #[serde(default = "default_rule_action")]
+    pub action: RuleAction,
 }
 
 /// Statistics about agent activity
@@ -93,35 +114,303 @@ pub struct CoordinationStats {
     pub dag_vertices: usize,
     /// Current DAG tips (if using QuantumDAG)
     pub current_tips: usize,
+    /// Active (non-expired) file leases
+    pub active_leases: usize,
+    /// Operations flagged by a block or require-approval rule
+    #[serde(default)]
+    pub flagged_operations: usize,
+}
+
+/// Action taken when a conflict rule fires
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RuleAction {
+    /// Report the conflict but let the operation proceed
+    Warn,
+    /// Flag the operation as one that should not have run
+    ///
+    /// [`AgentCoordination::register_operation`] records it flagged and
+    /// fails with [`JJError::OperationBlocked`].
+    Block,
+    /// Flag the operation until a human or coordinator approves it
+    ///
+    /// [`AgentCoordination::register_operation`] records it flagged and
+    /// fails with [`JJError::ApprovalRequired`] unless
+    /// [`AgentCoordination::approve_operation`] was called for it.
+    RequireApproval,
+}
+
+impl RuleAction {
+    /// Resolution strategy reported on [`AgentConflict`] for this action
+    pub fn resolution_strategy(&self) -> &'static str {
+        match self {
+            RuleAction::Warn => "auto_merge",
+            RuleAction::Block => "manual_resolution",
+            RuleAction::RequireApproval => "require_approval",
+        }
+    }
+}
+
+/// A single configurable conflict rule
+///
+/// A rule fires when two operations touch a shared file matching one of its
+/// `paths` globs (any file when empty) and the operation types match one of
+/// its `operations` pairs (any pair when empty). Pairs are unordered and `*`
+/// matches any operation type.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConflictRule {
+    /// Rule name, reported when the rule fires
+    pub name: String,
+    /// Path globs the rule applies to (empty = all files)
+    #[serde(default)]
+    pub paths: Vec<String>,
+    /// Operation type pairs the rule applies to (empty = all pairs)
+    #[serde(default)]
+    pub operations: Vec<(String, String)>,
+    /// Conflict severity (1=minor, 2=moderate, 3=severe)
+    pub severity: u8,
+    /// Action to take when the rule fires
+    #[serde(default = "default_rule_action")]
+    pub action: RuleAction,
+    /// Higher priority rules are evaluated first
+    #[serde(default)]
+    pub priority: i32,
+    /// Optional human-readable explanation
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+fn default_rule_action() -> RuleAction {
+    RuleAction::Warn
+}
+
+impl ConflictRule {
+    /// Create an operation-pair rule that applies to all files
+    pub fn exclusive_pair(op_a: &str, op_b: &str) -> Self {
+        Self {
+            name: format!("exclusive:{}/{}", op_a, op_b),
+            paths: Vec::new(),
+            operations: vec![(op_a.to_string(), op_b.to_string())],
+            severity: 3,
+            action: RuleAction::Block,
+            priority: 0,
+            description: None,
+        }
+    }
+
+    /// Create an operation-pair rule that applies to all files and only
+    /// reports a severe conflict
+    pub fn severe_pair(op_a: &str, op_b: &str) -> Self {
+        Self {
+            name: format!("severe:{}/{}", op_a, op_b),
+            action: RuleAction::Warn,
+            ..Self::exclusive_pair(op_a, op_b)
+        }
+    }
+
+    /// Validate the rule definition
+    pub fn validate(&self) -> Result<()> {
+        if self.name.trim().is_empty() {
+            return Err(JJError::InvalidConfig("Conflict rule name cannot be empty".to_string()));
+        }
+        if !(1..=3).contains(&self.severity) {
+            return Err(JJError::InvalidConfig(format!(
+                "Conflict rule '{}': severity must be between 1 and 3, got {}",
+                self.name, self.severity
+            )));
+        }
+        for path in &self.paths {
+            glob::Pattern::new(path).map_err(|e| {
+                JJError::InvalidConfig(format!(
+                    "Conflict rule '{}': invalid path glob '{}': {}",
+                    self.name, path, e
+                ))
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Check if the rule applies to an operation pair
+    fn matches_operations(&self, op_a: &str, op_b: &str) -> bool {
+        let op_matches = |pattern: &str, op: &str| pattern == "*" || pattern.eq_ignore_ascii_case(op);
+
+        self.operations.is_empty()
+            || self.operations.iter().any(|(a, b)| {
+                (op_matches(a, op_a) && op_matches(b, op_b))
+                    || (op_matches(a, op_b) && op_matches(b, op_a))
+            })
+    }
+
+    /// Files among `files` covered by the rule's path globs
+    fn matching_files(&self, files: &[String]) -> Vec<String> {
+        if self.paths.is_empty() {
+            return files.to_vec();
+        }
+
+        files
+            .iter()
+            .filter(|file| self.paths.iter().any(|p| patterns_overlap(p, file)))
+            .cloned()
+            .collect()
+    }
+
+    /// Specificity used to break priority ties (path- and op-scoped rules win)
+    fn specificity(&self) -> u8 {
+        u8::from(!self.paths.is_empty()) * 2 + u8::from(!self.operations.is_empty())
+    }
+}
+
+/// Result of evaluating conflict rules against a pair of operations
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RuleMatch {
+    /// Name of the rule that fired
+    pub rule: String,
+    /// Severity assigned by the rule
+    pub severity: u8,
+    /// Action requested by the rule
+    pub action: RuleAction,
+    /// Shared files covered by the rule
+    pub files: Vec<String>,
+    /// Rule description, if any
+    pub description: Option<String>,
 }
 
 /// Conflict detection rules
+///
+/// Rules are evaluated in precedence order: higher `priority` first, then the
+/// more specific rule (path- and operation-scoped before catch-all), then file
+/// order. The first matching rule decides severity and action.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConflictRules {
-    /// Exclusive operation patterns that cannot run concurrently
-    exclusive_patterns: Vec<(String, String)>,
+    /// Rules in precedence order
+    rules: Vec<ConflictRule>,
+}
+
+/// On-disk representation of a rule file
+#[derive(Debug, Deserialize)]
+struct ConflictRulesFile {
+    /// Keep the built-in severe pairs after the configured rules
+    #[serde(default = "default_include_builtin")]
+    include_builtin: bool,
+    #[serde(default)]
+    rules: Vec<ConflictRule>,
+}
+
+fn default_include_builtin() -> bool {
+    true
 }
 
 impl ConflictRules {
     /// Create new conflict rules with default patterns
     pub fn new() -> Self {
-        Self {
-            exclusive_patterns: vec![
-                // Same file edits
-                ("edit".to_string(), "edit".to_string()),
-                // Rebase conflicts with most operations
-                ("rebase".to_string(), "edit".to_string()),
-                ("rebase".to_string(), "rebase".to_string()),
-                // Branch operations
-                ("branch-create".to_string(), "branch-create".to_string()),
-                ("branch-delete".to_string(), "branch-delete".to_string()),
-            ],
+        Self::from_rules(Self::builtin_rules())
+    }
+
+    /// Built-in severe operation pairs
+    ///
+    /// They only warn: concurrent edits of one file are routine, and jj
+    /// merges them. Blocking is left to configured rules.
+    pub fn builtin_rules() -> Vec<ConflictRule> {
+        vec![
+            // Same file edits
+            ConflictRule::severe_pair("edit", "edit"),
+            // Rebase conflicts with most operations
+            ConflictRule::severe_pair("rebase", "edit"),
+            ConflictRule::severe_pair("rebase", "rebase"),
+            // Branch operations
+            ConflictRule::severe_pair("branch-create", "branch-create"),
+            ConflictRule::severe_pair("branch-delete", "branch-delete"),
+        ]
+    }
+
+    /// Create rules from an explicit list, sorted into precedence order
+    pub fn from_rules(mut rules: Vec<ConflictRule>) -> Self {
+        // Stable sort keeps file order among equal rules
+        rules.sort_by(|a, b| {
+            b.priority
+                .cmp(&a.priority)
+                .then_with(|| b.specificity().cmp(&a.specificity()))
+        });
+        Self { rules }
+    }
+
+    /// Parse rules from a TOML document
+    pub fn from_toml_str(content: &str) -> Result<Self> {
+        let file: ConflictRulesFile = toml::from_str(content)
+            .map_err(|e| JJError::InvalidConfig(format!("Invalid conflict rules TOML: {}", e)))?;
+        Self::from_file_contents(file)
+    }
+
+    /// Parse rules from a JSON document
+    pub fn from_json_str(content: &str) -> Result<Self> {
+        let file: ConflictRulesFile = serde_json::from_str(content)
+            .map_err(|e| JJError::InvalidConfig(format!("Invalid conflict rules JSON: {}", e)))?;
+        Self::from_file_contents(file)
+    }
+
+    /// Load rules from a `.toml` or `.json` file
+    pub fn from_file(path: impl AsRef<std::path::Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            JJError::IoError(format!("Failed to read conflict rules {}: {}", path.display(), e))
+        })?;
+
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => Self::from_toml_str(&content),
+            Some("json") => Self::from_json_str(&content),
+            _ => Err(JJError::InvalidConfig(format!(
+                "Unsupported conflict rules format: {} (expected .toml or .json)",
+                path.display()
+            ))),
+        }
+    }
+
+    fn from_file_contents(file: ConflictRulesFile) -> Result<Self> {
+        for rule in &file.rules {
+            rule.validate()?;
+        }
+
+        let mut rules = file.rules;
+        if file.include_builtin {
+            rules.extend(Self::builtin_rules());
         }
+        Ok(Self::from_rules(rules))
+    }
+
+    /// Rules in precedence order
+    pub fn rules(&self) -> &[ConflictRule] {
+        &self.rules
     }
 
     /// Check if two operations are exclusive
     pub fn are_exclusive(&self, op_a: &str, op_b: &str) -> bool {
-        self.exclusive_patterns.iter().any(|(a, b)| {
-            (a == op_a && b == op_b) || (a == op_b && b == op_a)
+        self.rules.iter().any(|rule| {
+            rule.paths.is_empty()
+                && rule.action == RuleAction::Block
+                && !rule.operations.is_empty()
+                && rule.matches_operations(op_a, op_b)
+        })
+    }
+
+    /// Find the highest-precedence rule matching two operations on shared files
+    pub fn evaluate(&self, op_a: &str, op_b: &str, files: &[String]) -> Option<RuleMatch> {
+        self.rules.iter().find_map(|rule| {
+            if !rule.matches_operations(op_a, op_b) {
+                return None;
+            }
+
+            let files = rule.matching_files(files);
+            if files.is_empty() {
+                return None;
+            }
+
+            Some(RuleMatch {
+                rule: rule.name.clone(),
+                severity: rule.severity,
+                action: rule.action,
+                files,
+                description: rule.description.clone(),
+            })
         })
     }
 }
@@ -146,18 +435,29 @@ pub struct AgentCoordination {
     dag_vertices: Arc<Mutex<HashMap<String, String>>>, // operation_id -> vertex_id
     /// DAG tips cache (updated from JavaScript bridge)
     dag_tips: Arc<Mutex<Vec<String>>>,
+    /// File leases held by agents
+    leases: LeaseManager,
+    /// Operations approved despite a `require-approval` rule
+    approved: Arc<Mutex<HashSet<String>>>,
 }
 
 impl AgentCoordination {
     /// Create new coordination system
     pub fn new() -> Self {
+        Self::with_conflict_rules(ConflictRules::new())
+    }
+
+    /// Create coordination system with custom conflict rules
+    pub fn with_conflict_rules(rules: ConflictRules) -> Self {
         Self {
             agents: Arc::new(Mutex::new(HashMap::new())),
             operations: Arc::new(Mutex::new(Vec::new())),
-            conflict_rules: Arc::new(ConflictRules::new()),
+            conflict_rules: Arc::new(rules),
             quantum_enabled: false,
             dag_vertices: Arc::new(Mutex::new(HashMap::new())),
             dag_tips: Arc::new(Mutex::new(Vec::new())),
+            leases: LeaseManager::new(),
+            approved: Arc::new(Mutex::new(HashSet::new())),
         }
     }
 
@@ -210,20 +510,25 @@ impl AgentCoordination {
     }
 
     /// Register an agent operation
+    ///
+    /// Operations are registered after jj ran them, so conflict rules cannot
+    /// stop them. The operation is always recorded, and so seen by later
+    /// conflict checks. When a `block` rule, or a `require-approval` rule
+    /// without a prior [`Self::approve_operation`], fires against another
+    /// agent's recent operation, the record is flagged with the rule and
+    /// the call fails with [`JJError::OperationBlocked`] or
+    /// [`JJError::ApprovalRequired`], so the caller can undo the operation
+    /// or get it approved. Registering an operation again replaces its
+    /// record.
     pub async fn register_operation(
         &self,
         agent_id: &str,
         operation: &JJOperation,
         affected_files: Vec<String>,
     ) -> Result<String> {
-        // Update agent info
-        {
-            let mut agents = self.agents.lock().await;
-            if let Some(agent) = agents.get_mut(agent_id) {
-                agent.operations_count += 1;
-                agent.last_seen = Utc::now();
-            }
-        }
+        let flag = self
+            .violated_rule(agent_id, operation, &affected_files)
+            .await?;
 
         // Create message
         // Parse metadata string to HashMap if it's JSON, otherwise create empty
@@ -237,15 +542,110 @@ impl AgentCoordination {
             affected_resources: affected_files,
             timestamp: Utc::now(),
             metadata,
+            flag: flag.clone(),
         };
 
         // Store message
-        let mut operations = self.operations.lock().await;
-        operations.push(message.clone());
+        let registered = {
+            let mut operations = self.operations.lock().await;
+            match operations.iter_mut().find(|m| m.operation_id == operation.id) {
+                Some(existing) => {
+                    *existing = message;
+                    true
+                }
+                None => {
+                    operations.push(message);
+                    false
+                }
+            }
+        };
+
+        // Update agent info
+        if !registered {
+            let mut agents = self.agents.lock().await;
+            if let Some(agent) = agents.get_mut(agent_id) {
+                agent.operations_count += 1;
+                agent.last_seen = Utc::now();
+            }
+        }
+
+        match flag {
+            Some(flag) if flag.action == RuleAction::Block => Err(JJError::OperationBlocked {
+                operation_id: operation.id.clone(),
+                rule: flag.rule,
+                files: flag.files,
+            }),
+            Some(flag) => Err(JJError::ApprovalRequired {
+                operation_id: operation.id.clone(),
+                rule: flag.rule,
+                files: flag.files,
+            }),
+            // In future, this will add to QuantumDAG
+            // For now, return operation ID
+            None => Ok(operation.id.clone()),
+        }
+    }
+
+    /// Approve an operation held by a `require-approval` conflict rule
+    ///
+    /// Clears the flag of a registered operation. The approval is used up
+    /// when the operation is registered (again).
+    pub async fn approve_operation(&self, operation_id: &str) {
+        {
+            let mut operations = self.operations.lock().await;
+            let flagged = operations.iter_mut().find(|m| {
+                m.operation_id == operation_id
+                    && m.flag.as_ref().map(|f| f.action) == Some(RuleAction::RequireApproval)
+            });
+            if let Some(message) = flagged {
+                message.flag = None;
+            }
+        }
+        self.approved.lock().await.insert(operation_id.to_string());
+    }
+
+    /// Registered operations flagged by a block or require-approval rule
+    pub async fn flagged_operations(&self) -> Vec<AgentMessage> {
+        let operations = self.operations.lock().await;
+        operations.iter().filter(|m| m.flag.is_some()).cloned().collect()
+    }
+
+    /// The `block` or unapproved `require-approval` rule an operation
+    /// violates, if any
+    async fn violated_rule(
+        &self,
+        agent_id: &str,
+        operation: &JJOperation,
+        affected_files: &[String],
+    ) -> Result<Option<OperationFlag>> {
+        let conflicts = self
+            .check_conflicts(&operation.id, &operation.operation_type, affected_files.to_vec())
+            .await?;
+        // An agent's own earlier operations never hold it up
+        let conflicts: Vec<&AgentConflict> = conflicts
+            .iter()
+            .filter(|c| !c.agents.iter().any(|a| a == agent_id))
+            .collect();
+
+        let violation = |action: RuleAction| {
+            conflicts.iter().find(|c| c.action == action).map(|c| OperationFlag {
+                rule: c.rule.clone().unwrap_or_default(),
+                action,
+                files: c.conflicting_resources.clone(),
+            })
+        };
 
-        // In future, this will add to QuantumDAG
-        // For now, return operation ID
-        Ok(operation.id.clone())
+        if let Some(flag) = violation(RuleAction::Block) {
+            return Ok(Some(flag));
+        }
+
+        if let Some(flag) = violation(RuleAction::RequireApproval) {
+            if !self.approved.lock().await.remove(&operation.id) {
+                return Ok(Some(flag));
+            }
+        }
+
+        Ok(None)
     }
 
     /// Check for conflicts with proposed operation
@@ -276,21 +676,23 @@ impl AgentCoordination {
 
             if !conflicting_files.is_empty() {
                 // Determine severity and resolution strategy
-                let (severity, description, strategy) = self.analyze_conflict(
+                let analysis = self.analyze_conflict(
                     operation_type,
                     &op.operation_type,
                     &conflicting_files,
                 );
 
-                if severity > 0 {
+                if analysis.severity > 0 {
                     conflicts.push(AgentConflict {
                         operation_a: operation_id.to_string(),
                         operation_b: op.operation_id.clone(),
                         agents: vec![op.agent_id.clone()],
                         conflicting_resources: conflicting_files,
-                        severity,
-                        description,
-                        resolution_strategy: strategy,
+                        severity: analysis.severity,
+                        description: analysis.description,
+                        resolution_strategy: analysis.strategy,
+                        rule: analysis.rule,
+                        action: analysis.action,
                     });
                 }
             }
@@ -300,54 +702,56 @@ impl AgentCoordination {
     }
 
     /// Analyze conflict severity
-    fn analyze_conflict(
-        &self,
-        op_a: &str,
-        op_b: &str,
-        files: &[String],
-    ) -> (u8, String, String) {
-        // Normalize operation types
-        let op_a_lower = op_a.to_lowercase();
-        let op_b_lower = op_b.to_lowercase();
-
-        // Check if operations are exclusive
-        if self.conflict_rules.are_exclusive(&op_a_lower, &op_b_lower) {
-            return (
-                3,
+    fn analyze_conflict(&self, op_a: &str, op_b: &str, files: &[String]) -> ConflictAnalysis {
+        // Configured rules take precedence over the generic heuristics
+        if let Some(matched) = self.conflict_rules.evaluate(op_a, op_b, files) {
+            let description = matched.description.clone().unwrap_or_else(|| {
                 format!(
-                    "Severe conflict: {} and {} operations on same files: {}",
+                    "{} conflict: {} and {} operations on: {}",
+                    severity_label(matched.severity),
                     op_a,
                     op_b,
-                    files.join(", ")
-                ),
-                "manual_resolution".to_string(),
-            );
+                    matched.files.join(", ")
+                )
+            });
+
+            return ConflictAnalysis {
+                severity: matched.severity,
+                description: format!("[rule {}] {}", matched.rule, description),
+                strategy: matched.action.resolution_strategy().to_string(),
+                rule: Some(matched.rule),
+                action: matched.action,
+            };
         }
 
         // Check for moderate conflicts (same operation type)
-        if op_a_lower == op_b_lower {
-            return (
-                2,
-                format!(
+        if op_a.eq_ignore_ascii_case(op_b) {
+            return ConflictAnalysis {
+                severity: 2,
+                description: format!(
                     "Moderate conflict: Multiple {} operations on: {}",
                     op_a,
                     files.join(", ")
                 ),
-                "sequential_execution".to_string(),
-            );
+                strategy: "sequential_execution".to_string(),
+                rule: None,
+                action: RuleAction::Warn,
+            };
         }
 
         // Minor conflict (different operations, same files)
-        (
-            1,
-            format!(
+        ConflictAnalysis {
+            severity: 1,
+            description: format!(
                 "Minor conflict: {} and {} operations may interfere on: {}",
                 op_a,
                 op_b,
                 files.join(", ")
             ),
-            "auto_merge".to_string(),
-        )
+            strategy: "auto_merge".to_string(),
+            rule: None,
+            action: RuleAction::Warn,
+        }
     }
 
     /// Get agent statistics
@@ -382,6 +786,8 @@ impl AgentCoordination {
         let operations = self.operations.lock().await;
         let vertices = self.dag_vertices.lock().await;
         let tips = self.dag_tips.lock().await;
+        let active_leases = self.leases.list().await.len();
+        let flagged_operations = operations.iter().filter(|m| m.flag.is_some()).count();
 
         // Count active agents (seen in last hour)
         let one_hour_ago = Utc::now() - chrono::Duration::hours(1);
@@ -396,9 +802,50 @@ impl AgentCoordination {
             total_operations: operations.len(),
             dag_vertices: vertices.len(),
             current_tips: tips.len(),
+            active_leases,
+            flagged_operations,
         })
     }
 
+    /// Get the lease manager shared by this coordination system
+    pub fn lease_manager(&self) -> &LeaseManager {
+        &self.leases
+    }
+
+    /// Acquire a file lease for an agent
+    ///
+    /// See [`LeaseManager::acquire`] for wait and conflict semantics.
+    pub async fn acquire_lease(
+        &self,
+        agent_id: &str,
+        pattern: &str,
+        mode: LeaseMode,
+        ttl: std::time::Duration,
+        wait: Option<std::time::Duration>,
+    ) -> Result<FileLease> {
+        self.leases.acquire(agent_id, pattern, mode, ttl, wait).await
+    }
+
+    /// Renew a file lease
+    pub async fn renew_lease(&self, lease_id: &str, ttl: std::time::Duration) -> Result<FileLease> {
+        self.leases.renew(lease_id, ttl).await
+    }
+
+    /// Release a file lease
+    pub async fn release_lease(&self, lease_id: &str) -> Result<()> {
+        self.leases.release(lease_id).await
+    }
+
+    /// Release all leases held by an agent (e.g. on task completion)
+    pub async fn release_agent_leases(&self, agent_id: &str) -> usize {
+        self.leases.release_agent(agent_id).await
+    }
+
+    /// Remove expired leases
+    pub async fn reap_expired_leases(&self) -> Vec<FileLease> {
+        self.leases.reap_expired().await
+    }
+
     /// Get coordination tips (DAG tips for coordination)
     pub async fn get_coordination_tips(&self) -> Result<Vec<String>> {
         let tips = self.dag_tips.lock().await;
@@ -406,6 +853,23 @@ impl AgentCoordination {
     }
 }
 
+/// Outcome of analyzing a single conflicting operation pair
+struct ConflictAnalysis {
+    severity: u8,
+    description: String,
+    strategy: String,
+    rule: Option<String>,
+    action: RuleAction,
+}
+
+fn severity_label(severity: u8) -> &'static str {
+    match severity {
+        3 => "Severe",
+        2 => "Moderate",
+        _ => "Minor",
+    }
+}
+
 impl Default for AgentCoordination {
     fn default() -> Self {
         Self::new()
@@ -455,4 +919,261 @@ mod tests {
         assert!(!conflicts.is_empty());
         assert!(conflicts[0].severity >= 2); // Moderate or severe
     }
+
+    const RULES_TOML: &str = r#"
+        [[rules]]
+        name = "lockfiles"
+        paths = ["Cargo.lock", "**/package-lock.json"]
+        severity = 3
+        action = "block"
+        priority = 100
+
+        [[rules]]
+        name = "migrations"
+        paths = ["migrations/**"]
+        operations = [["*", "edit"]]
+        severity = 2
+        action = "require-approval"
+        priority = 10
+
+        [[rules]]
+        name = "generated-any"
+        paths = ["gen/**"]
+        severity = 1
+        action = "warn"
+
+        [[rules]]
+        name = "generated-edits"
+        paths = ["gen/**"]
+        operations = [["edit", "edit"]]
+        severity = 2
+        action = "warn"
+    "#;
+
+    #[test]
+    fn test_rules_from_toml_and_json() {
+        let rules = ConflictRules::from_toml_str(RULES_TOML).unwrap();
+        assert_eq!(rules.rules()[0].name, "lockfiles");
+        // Built-in pairs are kept after the configured rules
+        let matched = rules.evaluate("rebase", "edit", &["src/lib.rs".to_string()]).unwrap();
+        assert_eq!(matched.rule, "severe:rebase/edit");
+        assert_eq!(matched.action, RuleAction::Warn);
+        assert!(!rules.are_exclusive("rebase", "edit"));
+
+        let json = r#"{"include_builtin": false, "rules": [
+            {"name": "docs", "paths": ["docs/**"], "severity": 1}
+        ]}"#;
+        let rules = ConflictRules::from_json_str(json).unwrap();
+        assert_eq!(rules.rules().len(), 1);
+        assert_eq!(rules.rules()[0].action, RuleAction::Warn);
+        assert!(!rules.are_exclusive("edit", "edit"));
+    }
+
+    #[test]
+    fn test_rule_validation_errors() {
+        let bad_severity = r#"[[rules]]
+            name = "x"
+            severity = 7"#;
+        let err = ConflictRules::from_toml_str(bad_severity).unwrap_err();
+        assert!(err.to_string().contains("severity"));
+
+        let bad_glob = r#"[[rules]]
+            name = "x"
+            paths = ["src/[a"]
+            severity = 1"#;
+        let err = ConflictRules::from_toml_str(bad_glob).unwrap_err();
+        assert!(err.to_string().contains("invalid path glob"));
+
+        assert!(ConflictRules::from_toml_str("rules = 3").is_err());
+    }
+
+    #[test]
+    fn test_rule_precedence() {
+        let rules = ConflictRules::from_toml_str(RULES_TOML).unwrap();
+
+        // Priority beats the built-in edit/edit pair
+        let matched = rules
+            .evaluate("edit", "edit", &["Cargo.lock".to_string()])
+            .unwrap();
+        assert_eq!(matched.rule, "lockfiles");
+        assert_eq!(matched.action, RuleAction::Block);
+
+        // Wildcard operation pairs are unordered
+        let matched = rules
+            .evaluate("edit", "describe", &["migrations/001.sql".to_string()])
+            .unwrap();
+        assert_eq!(matched.rule, "migrations");
+        assert_eq!(matched.action, RuleAction::RequireApproval);
+
+        // Equal priority: the operation-scoped rule is more specific
+        let matched = rules
+            .evaluate("edit", "edit", &["gen/api.rs".to_string()])
+            .unwrap();
+        assert_eq!(matched.rule, "generated-edits");
+
+        let matched = rules
+            .evaluate("describe", "squash", &["gen/api.rs".to_string()])
+            .unwrap();
+        assert_eq!(matched.rule, "generated-any");
+
+        // Only files covered by the rule are reported
+        let matched = rules
+            .evaluate("edit", "edit", &["src/lib.rs".to_string(), "Cargo.lock".to_string()])
+            .unwrap();
+        assert_eq!(matched.files, vec!["Cargo.lock".to_string()]);
+
+        assert!(rules.evaluate("describe", "squash", &["src/lib.rs".to_string()]).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_conflicts_report_rule() {
+        let rules = ConflictRules::from_toml_str(RULES_TOML).unwrap();
+        let coord = AgentCoordination::with_conflict_rules(rules);
+
+        let op = JJOperation::new(
+            "op-1".to_string(),
+            "jj describe".to_string(),
+            "test".to_string(),
+            "localhost".to_string(),
+        );
+        coord
+            .register_operation("agent-1", &op, vec!["Cargo.lock".to_string()])
+            .await
+            .unwrap();
+
+        let conflicts = coord
+            .check_conflicts("op-2", "edit", vec!["Cargo.lock".to_string()])
+            .await
+            .unwrap();
+
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].rule.as_deref(), Some("lockfiles"));
+        assert_eq!(conflicts[0].severity, 3);
+        assert_eq!(conflicts[0].action, RuleAction::Block);
+        assert_eq!(conflicts[0].resolution_strategy, "manual_resolution");
+    }
+
+    #[tokio::test]
+    async fn test_register_operation_enforces_rule_actions() {
+        let rules = ConflictRules::from_toml_str(RULES_TOML).unwrap();
+        let coord = AgentCoordination::with_conflict_rules(rules);
+        let edit = || {
+            JJOperation::builder()
+                .operation_type(crate::OperationType::Edit)
+                .command("jj edit".to_string())
+                .build()
+        };
+        let files = |path: &str| vec![path.to_string()];
+
+        coord
+            .register_operation("agent-1", &edit(), files("Cargo.lock"))
+            .await
+            .unwrap();
+        coord
+            .register_operation("agent-1", &edit(), files("migrations/001.sql"))
+            .await
+            .unwrap();
+
+        // The same agent is never held up by its own operations
+        assert!(coord
+            .register_operation("agent-1", &edit(), files("Cargo.lock"))
+            .await
+            .is_ok());
+
+        let blocked = edit();
+        let err = coord
+            .register_operation("agent-2", &blocked, files("Cargo.lock"))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, JJError::OperationBlocked { ref rule, .. } if rule == "lockfiles"));
+        assert_eq!(err.code(), "OPERATION_BLOCKED");
+
+        // The blocked operation already ran, so it stays visible, flagged
+        let flagged = coord.flagged_operations().await;
+        assert_eq!(flagged.len(), 1);
+        assert_eq!(flagged[0].operation_id, blocked.id);
+        assert_eq!(flagged[0].flag.as_ref().unwrap().action, RuleAction::Block);
+        let conflicts = coord
+            .check_conflicts("op-3", "edit", files("Cargo.lock"))
+            .await
+            .unwrap();
+        assert!(conflicts.iter().any(|c| c.operation_b == blocked.id));
+
+        let migration = edit();
+        let err = coord
+            .register_operation("agent-2", &migration, files("migrations/001.sql"))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, JJError::ApprovalRequired { ref rule, .. } if rule == "migrations"));
+        assert_eq!(coord.flagged_operations().await.len(), 2);
+        assert_eq!(coord.get_stats().await.unwrap().flagged_operations, 2);
+
+        coord.approve_operation(&migration.id).await;
+        assert_eq!(coord.flagged_operations().await.len(), 1);
+        assert!(coord
+            .register_operation("agent-2", &migration, files("migrations/001.sql"))
+            .await
+            .is_ok());
+        assert_eq!(coord.get_stats().await.unwrap().total_operations, 5);
+
+        // Approving ahead of registration lets the operation through unflagged
+        let approved = edit();
+        coord.approve_operation(&approved.id).await;
+        assert!(coord
+            .register_operation("agent-3", &approved, files("migrations/001.sql"))
+            .await
+            .is_ok());
+        assert_eq!(coord.flagged_operations().await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_builtin_pairs_do_not_reject_concurrent_edits() {
+        let coord = AgentCoordination::new();
+        let edit = || {
+            JJOperation::builder()
+                .operation_type(crate::OperationType::Edit)
+                .command("jj edit".to_string())
+                .build()
+        };
+
+        for agent in ["agent-1", "agent-2"] {
+            coord
+                .register_operation(agent, &edit(), vec!["src/lib.rs".to_string()])
+                .await
+                .unwrap();
+        }
+
+        let conflicts = coord
+            .check_conflicts("op-3", "Edit", vec!["src/lib.rs".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(conflicts.len(), 2);
+        assert!(conflicts
+            .iter()
+            .all(|c| c.severity == 3 && c.action == RuleAction::Warn));
+        assert!(coord.flagged_operations().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_lease_coordination() {
+        let coord = AgentCoordination::new();
+        let ttl = std::time::Duration::from_secs(60);
+
+        let lease = coord
+            .acquire_lease("agent-1", "src/**", LeaseMode::Exclusive, ttl, None)
+            .await
+            .unwrap();
+        assert!(coord
+            .acquire_lease("agent-2", "src/lib.rs", LeaseMode::Exclusive, ttl, None)
+            .await
+            .is_err());
+        assert_eq!(coord.get_stats().await.unwrap().active_leases, 1);
+
+        coord.release_lease(&lease.lease_id).await.unwrap();
+        assert!(coord
+            .acquire_lease("agent-2", "src/lib.rs", LeaseMode::Exclusive, ttl, None)
+            .await
+            .is_ok());
+        assert_eq!(coord.release_agent_leases("agent-2").await, 1);
+    }
 }
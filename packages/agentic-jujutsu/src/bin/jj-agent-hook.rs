@@ -13,10 +13,25 @@
 //!
 //! # Post-task hook
 //! jj-agent-hook post-task --agent-id coder-1 --session-id swarm-001
+//!
+//! # Keep state in memory between hook events
+//! jj-agent-hook daemon &
+//! jj-agent-hook metrics
+//! jj-agent-hook stop
+//!
+//! # Copy learned trajectories into the shared reasoningbank store
+//! jj-agent-hook export-reasoningbank --database reasoningbank.db --backfill
 //! ```
+//!
+//! Hook subcommands forward events to a running daemon and fall back to
+//! in-process execution when none is listening.
 
-use agentic_jujutsu::{HookContext, JJConfig, JJHooksIntegration, JJWrapper, Result};
+use agentic_jujutsu::daemon::{
+    default_socket_path, default_state_path, HookRequest, HookResponse, HookService,
+};
+use agentic_jujutsu::{JJConfig, Result};
 use clap::{Parser, Subcommand};
+use std::path::PathBuf;
 use std::process::exit;
 
 #[derive(Parser)]
@@ -41,6 +56,14 @@ struct Cli {
     #[arg(long, global = true)]
     enable_agentdb: bool,
 
+    /// Daemon socket path (defaults to a per-repository runtime path)
+    #[arg(long, global = true)]
+    socket: Option<PathBuf>,
+
+    /// Always run hooks in-process, even if a daemon is running
+    #[arg(long, global = true)]
+    no_daemon: bool,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -98,6 +121,10 @@ enum Commands {
         /// Task description
         #[arg(long)]
         description: Option<String>,
+
+        /// Describe the working copy with a message generated from its diff
+        #[arg(long)]
+        auto_describe: bool,
     },
 
     /// Detect and report conflicts
@@ -125,6 +152,35 @@ enum Commands {
         #[arg(long, default_value = "10")]
         limit: usize,
     },
+
+    /// Run as a long-lived daemon holding hook state in memory
+    Daemon {
+        /// File session state is flushed to on shutdown
+        #[arg(long)]
+        state_file: Option<PathBuf>,
+    },
+
+    /// Print a running daemon's operation and hook metrics in Prometheus
+    /// text format
+    Metrics,
+
+    /// Export trajectories and patterns to a reasoningbank database
+    ExportReasoningbank {
+        /// SQLite database of the reasoningbank pattern store
+        #[arg(long)]
+        database: PathBuf,
+
+        /// Export cursor file (defaults to <database>.jj-cursor.json)
+        #[arg(long)]
+        cursor: Option<PathBuf>,
+
+        /// Export everything, not only what changed since the last export
+        #[arg(long)]
+        backfill: bool,
+    },
+
+    /// Stop a running daemon
+    Stop,
 }
 
 #[tokio::main]
@@ -150,82 +206,221 @@ async fn main() {
 }
 
 async fn run_command(cli: Cli) -> Result<()> {
+    let socket = cli
+        .socket
+        .clone()
+        .unwrap_or_else(|| default_socket_path(&cli.repo_path));
+
     // Create JJ configuration
     let config = JJConfig::default()
-        .with_jj_path(cli.jj_path)
-        .with_repo_path(cli.repo_path)
+        .with_jj_path(cli.jj_path.clone())
+        .with_repo_path(cli.repo_path.clone())
         .with_verbose(cli.verbose)
         .with_agentdb_sync(cli.enable_agentdb);
 
-    // Create wrapper and integration
-    let wrapper = JJWrapper::with_config(config)?;
-    let mut integration = JJHooksIntegration::new(wrapper, cli.enable_agentdb);
-
-    match cli.command {
+    let request = match cli.command {
+        Commands::Daemon { state_file } => {
+            let state_file = state_file.unwrap_or_else(|| default_state_path(&cli.repo_path));
+            return run_daemon(config, cli.enable_agentdb, socket, state_file).await;
+        }
+        Commands::Stop => return stop_daemon(&socket).await,
         Commands::PreTask {
             agent_id,
             session_id,
             description,
             metadata,
-        } => {
-            let mut ctx = HookContext::new(agent_id, session_id, description);
-
+        } => HookRequest::PreTask {
+            agent_id,
+            session_id,
+            description,
             // Parse metadata if provided
-            if let Some(meta_str) = metadata {
-                if let Ok(meta_value) = serde_json::from_str(&meta_str) {
-                    ctx = ctx.with_metadata(meta_value);
-                }
+            metadata: metadata.and_then(|meta_str| serde_json::from_str(&meta_str).ok()),
+        },
+        Commands::PostEdit {
+            file,
+            agent_id,
+            session_id,
+            description,
+        } => HookRequest::PostEdit {
+            file,
+            agent_id,
+            session_id,
+            description,
+        },
+        Commands::PostTask {
+            agent_id,
+            session_id,
+            description,
+            auto_describe,
+        } => HookRequest::PostTask {
+            agent_id,
+            session_id,
+            description,
+            auto_describe,
+        },
+        Commands::DetectConflicts {
+            agent_id,
+            session_id,
+        } => HookRequest::DetectConflicts {
+            agent_id,
+            session_id,
+        },
+        Commands::QueryHistory {
+            session_id,
+            agent_id,
+            limit,
+        } => HookRequest::QueryHistory {
+            session_id,
+            agent_id,
+            limit,
+        },
+        Commands::Metrics => HookRequest::Metrics,
+        Commands::ExportReasoningbank {
+            database,
+            cursor,
+            backfill,
+        } => HookRequest::ExportReasoningbank {
+            // A running daemon resolves relative paths against its own
+            // working directory, not ours
+            database: std::path::absolute(database)?,
+            cursor: cursor.map(std::path::absolute).transpose()?,
+            backfill,
+        },
+    };
+
+    let state_file = default_state_path(&cli.repo_path);
+    let response = dispatch(
+        &socket,
+        &state_file,
+        cli.no_daemon,
+        config,
+        cli.enable_agentdb,
+        request,
+    )
+    .await?;
+    print_response(response, cli.verbose)
+}
+
+/// Send the request to the daemon, or handle it in-process if none is running
+async fn dispatch(
+    socket: &std::path::Path,
+    state_file: &std::path::Path,
+    no_daemon: bool,
+    config: JJConfig,
+    enable_agentdb: bool,
+    request: HookRequest,
+) -> Result<HookResponse> {
+    #[cfg(unix)]
+    if !no_daemon {
+        if let Some(response) = agentic_jujutsu::daemon::send_request(socket, &request).await? {
+            log::debug!("Handled by daemon at {}", socket.display());
+            return Ok(response);
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = no_daemon;
+
+    // Metrics are collected by the daemon; a fresh in-process registry is empty
+    if matches!(request, HookRequest::Metrics) {
+        return Err(agentic_jujutsu::JJError::InvalidConfig(format!(
+            "no daemon running at {}; metrics are only collected by `jj-agent-hook daemon`",
+            socket.display()
+        )));
+    }
+
+    let mut service = HookService::new(config, enable_agentdb)?;
+    // Exports read the trajectories a stopped daemon left in its state file
+    if matches!(request, HookRequest::ExportReasoningbank { .. }) && state_file.exists() {
+        service = service.with_state_file(state_file)?;
+    }
+    service.handle(request).await
+}
+
+#[cfg(unix)]
+async fn run_daemon(
+    config: JJConfig,
+    enable_agentdb: bool,
+    socket: PathBuf,
+    state_file: PathBuf,
+) -> Result<()> {
+    use agentic_jujutsu::daemon::HookDaemon;
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let service = HookService::new(config, enable_agentdb)?.with_state_file(&state_file)?;
+    let daemon = HookDaemon::new(service, &socket);
+
+    println!("🚀 jj-agent-hook daemon listening on {}", socket.display());
+    println!("💾 State file: {}", state_file.display());
+
+    let mut terminate = signal(SignalKind::terminate())?;
+    daemon
+        .run(async move {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {}
+                _ = terminate.recv() => {}
             }
+        })
+        .await?;
 
-            let event = integration.on_pre_task(ctx).await?;
+    println!("✅ Daemon stopped, state flushed");
+    Ok(())
+}
+
+#[cfg(not(unix))]
+async fn run_daemon(_: JJConfig, _: bool, _: PathBuf, _: PathBuf) -> Result<()> {
+    Err(agentic_jujutsu::JJError::InvalidConfig(
+        "Daemon mode requires Unix domain sockets".to_string(),
+    ))
+}
+
+#[cfg(unix)]
+async fn stop_daemon(socket: &std::path::Path) -> Result<()> {
+    match agentic_jujutsu::daemon::send_request(socket, &HookRequest::Shutdown).await? {
+        Some(_) => println!("✅ Daemon at {} is shutting down", socket.display()),
+        None => println!("⚠️  No daemon running at {}", socket.display()),
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+async fn stop_daemon(_: &std::path::Path) -> Result<()> {
+    println!("⚠️  Daemon mode is not supported on this platform");
+    Ok(())
+}
 
+fn print_response(response: HookResponse, verbose: bool) -> Result<()> {
+    match response {
+        HookResponse::Event { event } => {
             println!("✅ Pre-task hook executed successfully");
             println!("📋 Session ID: {}", event.context.session_id);
             println!("🤖 Agent ID: {}", event.context.agent_id);
             println!("📝 Task: {}", event.context.task_description);
 
-            if cli.verbose {
+            if verbose {
                 println!("\n🔍 Event details:");
                 println!("{}", serde_json::to_string_pretty(&event)?);
             }
         }
 
-        Commands::PostEdit {
-            file,
-            agent_id,
-            session_id,
-            description,
-        } => {
-            let task_desc = description.unwrap_or_else(|| format!("Edit {}", file));
-            let ctx = HookContext::new(agent_id, session_id, task_desc);
-
-            let operation = integration.on_post_edit(&file, ctx).await?;
-
+        HookResponse::Operation { operation } => {
             println!("✅ Post-edit hook executed successfully");
-            println!("📄 File: {}", file);
+            if let Some(file) = operation.get_metadata("file") {
+                println!("📄 File: {}", file);
+            }
             println!("🆔 Operation ID: {}", operation.id);
             println!("📝 Command: {}", operation.command);
 
-            if cli.verbose {
+            if verbose {
                 println!("\n🔍 Operation details:");
                 println!("{}", serde_json::to_string_pretty(&operation)?);
             }
         }
 
-        Commands::PostTask {
-            agent_id,
-            session_id,
-            description,
-        } => {
-            let task_desc = description.unwrap_or_else(|| "Task completed".to_string());
-            let ctx = HookContext::new(agent_id, session_id, task_desc);
-
-            let operations = integration.on_post_task(ctx).await?;
-
+        HookResponse::Operations { operations } => {
             println!("✅ Post-task hook executed successfully");
             println!("📊 Operations in session: {}", operations.len());
 
-            if !operations.is_empty() && cli.verbose {
+            if !operations.is_empty() && verbose {
                 println!("\n🔍 Operations:");
                 for op in &operations {
                     println!("  - {} | {}", op.id, op.command);
@@ -233,15 +428,7 @@ async fn run_command(cli: Cli) -> Result<()> {
             }
         }
 
-        Commands::DetectConflicts {
-            agent_id,
-            session_id,
-        } => {
-            let ctx = HookContext::new(agent_id, session_id, "Conflict detection".to_string());
-
-            // TODO: Implement actual conflict detection
-            let conflicts: Vec<String> = vec![];
-
+        HookResponse::Conflicts { conflicts, event } => {
             if conflicts.is_empty() {
                 println!("✅ No conflicts detected");
             } else {
@@ -250,28 +437,51 @@ async fn run_command(cli: Cli) -> Result<()> {
                     println!("  - {}", conflict);
                 }
 
-                let event = integration.on_conflict_detected(conflicts, ctx).await?;
-
-                if cli.verbose {
+                if let (Some(event), true) = (event, verbose) {
                     println!("\n🔍 Event details:");
                     println!("{}", serde_json::to_string_pretty(&event)?);
                 }
             }
         }
 
-        Commands::QueryHistory {
-            session_id,
-            agent_id,
-            limit,
-        } => {
+        HookResponse::History { operations } => {
             println!("📊 Query History");
-            println!("  Session: {}", session_id.as_deref().unwrap_or("all"));
-            println!("  Agent: {}", agent_id.as_deref().unwrap_or("all"));
-            println!("  Limit: {}", limit);
+            if operations.is_empty() {
+                println!("\n⚠️  No operations recorded");
+            }
+            for op in &operations {
+                println!(
+                    "  - {} | {} | {}",
+                    op.timestamp_iso(),
+                    op.get_metadata("agent_id").unwrap_or_default(),
+                    op.command
+                );
+            }
+        }
 
-            // TODO: Implement actual history query
-            println!("\n⚠️  History query not yet implemented");
-            println!("This feature will query AgentDB for operation history");
+        HookResponse::Pong { pid, started_at } => {
+            println!("✅ Daemon running (pid {}, since {})", pid, started_at);
+        }
+
+        HookResponse::Exported {
+            trajectories,
+            patterns,
+            skipped_encrypted,
+        } => {
+            println!("✅ Exported to reasoningbank");
+            println!("🧭 Trajectories: {}", trajectories);
+            println!("🧩 Patterns: {}", patterns);
+            if skipped_encrypted > 0 {
+                println!("🔒 Skipped encrypted trajectories: {}", skipped_encrypted);
+            }
+        }
+
+        HookResponse::Metrics { text } => print!("{}", text),
+
+        HookResponse::ShuttingDown => println!("✅ Daemon is shutting down"),
+
+        HookResponse::Error { message } => {
+            return Err(agentic_jujutsu::JJError::CommandFailed(message));
         }
     }
 
@@ -306,4 +516,68 @@ mod tests {
             _ => panic!("Expected PreTask command"),
         }
     }
+
+    #[test]
+    fn test_daemon_parsing() {
+        let cli = Cli::parse_from([
+            "jj-agent-hook",
+            "--socket",
+            "/tmp/hook.sock",
+            "daemon",
+            "--state-file",
+            "/tmp/state.json",
+        ]);
+
+        assert_eq!(cli.socket, Some(PathBuf::from("/tmp/hook.sock")));
+        match cli.command {
+            Commands::Daemon { state_file } => {
+                assert_eq!(state_file, Some(PathBuf::from("/tmp/state.json")));
+            }
+            _ => panic!("Expected Daemon command"),
+        }
+
+        let cli = Cli::parse_from(["jj-agent-hook", "--no-daemon", "stop"]);
+        assert!(cli.no_daemon);
+        assert!(matches!(cli.command, Commands::Stop));
+
+        let cli = Cli::parse_from(["jj-agent-hook", "metrics"]);
+        assert!(matches!(cli.command, Commands::Metrics));
+
+        let cli = Cli::parse_from([
+            "jj-agent-hook",
+            "export-reasoningbank",
+            "--database",
+            "patterns.db",
+            "--backfill",
+        ]);
+        match cli.command {
+            Commands::ExportReasoningbank {
+                database,
+                cursor,
+                backfill,
+            } => {
+                assert_eq!(database, PathBuf::from("patterns.db"));
+                assert_eq!(cursor, None);
+                assert!(backfill);
+            }
+            _ => panic!("Expected ExportReasoningbank command"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_metrics_require_daemon() {
+        let dir = tempfile::tempdir().unwrap();
+        let result = dispatch(
+            &dir.path().join("hook.sock"),
+            &dir.path().join("state.json"),
+            false,
+            JJConfig::default(),
+            false,
+            HookRequest::Metrics,
+        )
+        .await;
+
+        let err = result.unwrap_err();
+        assert!(err.to_string().contains("no daemon running"), "{}", err);
+    }
 }
@@ -0,0 +1,998 @@
+//! jj command-line parsing
+//!
+//! Classifies a jj argument list the way jj's own CLI would: global options
+//! are skipped wherever they appear, aliases are resolved, nested
+//! subcommands (`git push`, `bookmark set`, `op undo`, ...) are recognized,
+//! and the revisions and paths the command operates on are extracted.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use agentic_jujutsu::command_parser::parse_jj_args;
+//! use agentic_jujutsu::OperationType;
+//!
+//! let parsed = parse_jj_args(&["--no-pager", "-R", "repo", "squash", "--into", "@-", "src/lib.rs"]);
+//! assert_eq!(parsed.operation_type, OperationType::Squash);
+//! assert_eq!(parsed.revisions, vec!["@-"]);
+//! assert_eq!(parsed.paths, vec!["src/lib.rs"]);
+//! assert_eq!(parsed.repository.as_deref(), Some("repo"));
+//! ```
+
+use crate::operations::OperationType;
+
+/// Global options that take a value
+const GLOBAL_VALUE_OPTIONS: &[&str] = &[
+    "-R",
+    "--repository",
+    "--at-operation",
+    "--at-op",
+    "--color",
+    "--config",
+    "--config-toml",
+    "--config-file",
+];
+
+/// Result of parsing a jj command line
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedCommand {
+    /// Canonical subcommand path, e.g. `["git", "push"]`
+    pub subcommand: Vec<String>,
+    /// Operation type of the subcommand
+    pub operation_type: OperationType,
+    /// Revisions (revsets) named by options or positionals
+    pub revisions: Vec<String>,
+    /// Paths (filesets) named by options or positionals
+    pub paths: Vec<String>,
+    /// Repository given with `-R`/`--repository`
+    pub repository: Option<String>,
+    /// Operation given with `--at-operation`
+    pub at_operation: Option<String>,
+    /// Positional arguments of the subcommand, whatever they denote
+    pub positionals: Vec<String>,
+    /// Value options of the subcommand as `(option, value)`, in order
+    pub options: Vec<(String, String)>,
+}
+
+impl ParsedCommand {
+    /// Values given to any of `names`, e.g. `&["-r", "--revision"]`
+    pub fn option_values(&self, names: &[&str]) -> Vec<String> {
+        self.options
+            .iter()
+            .filter(|(name, _)| names.contains(&name.as_str()))
+            .map(|(_, value)| value.clone())
+            .collect()
+    }
+}
+
+/// How positional arguments of a subcommand are interpreted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Positional {
+    /// Positionals are revsets
+    Revisions,
+    /// Positionals are filesets
+    Paths,
+    /// The first N positionals are something else, the rest are filesets
+    SkipThenPaths(usize),
+    /// Positionals are names, remotes, operation IDs, ...
+    Ignore,
+}
+
+/// Argument schema of one subcommand
+struct CommandSpec {
+    path: &'static str,
+    op: OperationType,
+    positional: Positional,
+    /// Options whose value is a revset
+    revision_options: &'static [&'static str],
+    /// Options whose value is a fileset
+    path_options: &'static [&'static str],
+    /// Other options that take a value
+    value_options: &'static [&'static str],
+}
+
+const fn spec(
+    path: &'static str,
+    op: OperationType,
+    positional: Positional,
+    revision_options: &'static [&'static str],
+    value_options: &'static [&'static str],
+) -> CommandSpec {
+    CommandSpec {
+        path,
+        op,
+        positional,
+        revision_options,
+        path_options: &[],
+        value_options,
+    }
+}
+
+const REVISION: &[&str] = &["-r", "--revision", "--revisions"];
+const MESSAGE: &[&str] = &["-m", "--message"];
+const TEMPLATE: &[&str] = &["-T", "--template"];
+const PLACEMENT: &[&str] = &[
+    "-r",
+    "--revision",
+    "--revisions",
+    "-d",
+    "--destination",
+    "-o",
+    "--onto",
+    "-A",
+    "--insert-after",
+    "--after",
+    "-B",
+    "--insert-before",
+    "--before",
+];
+
+use OperationType as Op;
+use Positional::{Ignore, Paths, Revisions};
+
+/// Every jj subcommand, including groups without a recognized child
+static COMMANDS: &[CommandSpec] = &[
+    spec("abandon", Op::Abandon, Revisions, REVISION, &[]),
+    spec(
+        "absorb",
+        Op::Absorb,
+        Paths,
+        &["-f", "--from", "-t", "--into", "--to"],
+        &[],
+    ),
+    spec("bisect", Op::Bisect, Ignore, &[], &[]),
+    spec("bisect run", Op::Bisect, Ignore, &["-r", "--range"], &[]),
+    spec("bookmark", Op::Bookmark, Ignore, &[], &[]),
+    spec("bookmark create", Op::BookmarkCreate, Ignore, REVISION, &[]),
+    spec("bookmark delete", Op::BookmarkDelete, Ignore, &[], &[]),
+    spec("bookmark forget", Op::BookmarkForget, Ignore, &[], &[]),
+    spec(
+        "bookmark list",
+        Op::BookmarkList,
+        Ignore,
+        REVISION,
+        &["--remote", "-T", "--template", "--sort"],
+    ),
+    spec(
+        "bookmark move",
+        Op::BookmarkMove,
+        Ignore,
+        &["-f", "--from", "-t", "--to"],
+        &[],
+    ),
+    spec("bookmark rename", Op::BookmarkRename, Ignore, &[], &[]),
+    spec("bookmark set", Op::BookmarkSet, Ignore, REVISION, &[]),
+    spec(
+        "bookmark track",
+        Op::BookmarkTrack,
+        Ignore,
+        &[],
+        &["--remote"],
+    ),
+    spec(
+        "bookmark untrack",
+        Op::BookmarkUntrack,
+        Ignore,
+        &[],
+        &["--remote"],
+    ),
+    spec("checkout", Op::Checkout, Revisions, REVISION, MESSAGE),
+    spec(
+        "commit",
+        Op::Commit,
+        Paths,
+        &[],
+        &["-m", "--message", "--tool", "--author"],
+    ),
+    spec("config", Op::Config, Ignore, &[], TEMPLATE),
+    spec(
+        "describe",
+        Op::Describe,
+        Revisions,
+        REVISION,
+        &["-m", "--message", "--author"],
+    ),
+    spec(
+        "diff",
+        Op::Diff,
+        Paths,
+        &["-r", "--revisions", "-f", "--from", "-t", "--to"],
+        &["--tool", "--context", "-T", "--template"],
+    ),
+    spec(
+        "diffedit",
+        Op::Diffedit,
+        Ignore,
+        &["-r", "--revision", "-f", "--from", "-t", "--to"],
+        &["--tool"],
+    ),
+    spec("duplicate", Op::Duplicate, Revisions, PLACEMENT, &[]),
+    spec("edit", Op::Edit, Revisions, REVISION, &[]),
+    spec(
+        "evolog",
+        Op::Evolog,
+        Ignore,
+        REVISION,
+        &["-n", "--limit", "-T", "--template", "--tool", "--context"],
+    ),
+    spec("file", Op::File, Ignore, &[], &[]),
+    spec("file annotate", Op::File, Paths, REVISION, TEMPLATE),
+    spec(
+        "file chmod",
+        Op::FileChmod,
+        Positional::SkipThenPaths(1),
+        REVISION,
+        &[],
+    ),
+    spec("file list", Op::File, Paths, REVISION, TEMPLATE),
+    spec(
+        "file search",
+        Op::File,
+        Paths,
+        REVISION,
+        &["-p", "--pattern"],
+    ),
+    spec("file show", Op::File, Paths, REVISION, TEMPLATE),
+    spec("file track", Op::FileTrack, Paths, &[], &[]),
+    spec("file untrack", Op::FileUntrack, Paths, &[], &[]),
+    spec("fix", Op::Fix, Paths, &["-s", "--source"], &[]),
+    spec("gerrit", Op::GerritUpload, Ignore, &[], &[]),
+    spec(
+        "gerrit upload",
+        Op::GerritUpload,
+        Ignore,
+        REVISION,
+        &["--remote", "--remote-branch"],
+    ),
+    spec("git", Op::Unknown, Ignore, &[], &[]),
+    spec(
+        "git clone",
+        Op::Clone,
+        Ignore,
+        &[],
+        &["--remote", "--depth"],
+    ),
+    spec("git export", Op::GitExport, Ignore, &[], &[]),
+    spec(
+        "git fetch",
+        Op::GitFetch,
+        Ignore,
+        &[],
+        &["-b", "--branch", "--bookmark", "--remote"],
+    ),
+    spec("git import", Op::GitImport, Ignore, &[], &[]),
+    spec("git init", Op::Init, Ignore, &[], &[]),
+    spec(
+        "git push",
+        Op::GitPush,
+        Ignore,
+        &["-r", "--revisions", "-c", "--change"],
+        &["-b", "--bookmark", "--remote", "--named"],
+    ),
+    spec("git remote", Op::GitRemote, Ignore, &[], &[]),
+    spec("git root", Op::Root, Ignore, &[], &[]),
+    spec("help", Op::Help, Ignore, &[], &[]),
+    spec("init", Op::Init, Ignore, &[], &[]),
+    spec(
+        "interdiff",
+        Op::Interdiff,
+        Paths,
+        &["-f", "--from", "-t", "--to"],
+        &["--tool", "--context"],
+    ),
+    spec(
+        "log",
+        Op::Log,
+        Paths,
+        REVISION,
+        &["-n", "--limit", "-T", "--template", "--tool", "--context"],
+    ),
+    spec("merge", Op::Merge, Revisions, &[], MESSAGE),
+    spec(
+        "metaedit",
+        Op::Metaedit,
+        Revisions,
+        REVISION,
+        &["-m", "--message", "--author", "--author-timestamp"],
+    ),
+    spec(
+        "move",
+        Op::Move,
+        Paths,
+        &["-f", "--from", "-t", "--to"],
+        &[],
+    ),
+    spec("new", Op::New, Revisions, PLACEMENT, MESSAGE),
+    spec("next", Op::Next, Ignore, &[], &[]),
+    spec("operation", Op::Unknown, Ignore, &[], &[]),
+    spec("operation abandon", Op::OpAbandon, Ignore, &[], &[]),
+    spec(
+        "operation diff",
+        Op::OpDiff,
+        Ignore,
+        &[],
+        &[
+            "--operation",
+            "--op",
+            "-f",
+            "--from",
+            "-t",
+            "--to",
+            "--tool",
+            "--context",
+        ],
+    ),
+    spec(
+        "operation log",
+        Op::OpLog,
+        Ignore,
+        &[],
+        &["-n", "--limit", "-T", "--template"],
+    ),
+    spec("operation restore", Op::OpRestore, Ignore, &[], &["--what"]),
+    spec("operation revert", Op::OpRevert, Ignore, &[], &["--what"]),
+    spec(
+        "operation show",
+        Op::OpShow,
+        Ignore,
+        &[],
+        &["-T", "--template", "--tool", "--context"],
+    ),
+    spec("operation undo", Op::Undo, Ignore, &[], &["--what"]),
+    spec("parallelize", Op::Parallelize, Revisions, &[], &[]),
+    spec("prev", Op::Prev, Ignore, &[], &[]),
+    spec(
+        "rebase",
+        Op::Rebase,
+        Ignore,
+        &[
+            "-b",
+            "--branch",
+            "-s",
+            "--source",
+            "-r",
+            "--revisions",
+            "-d",
+            "--destination",
+            "-o",
+            "--onto",
+            "-A",
+            "--insert-after",
+            "--after",
+            "-B",
+            "--insert-before",
+            "--before",
+        ],
+        &[],
+    ),
+    spec("redo", Op::Redo, Ignore, &[], &[]),
+    spec("resolve", Op::Resolve, Paths, REVISION, &["--tool"]),
+    spec(
+        "restore",
+        Op::Restore,
+        Paths,
+        &["-f", "--from", "-t", "--into", "--to", "-c", "--changes-in"],
+        &["--tool"],
+    ),
+    spec("revert", Op::Revert, Ignore, PLACEMENT, &[]),
+    spec("root", Op::Root, Ignore, &[], &[]),
+    spec(
+        "show",
+        Op::Show,
+        Revisions,
+        REVISION,
+        &["-T", "--template", "--tool", "--context"],
+    ),
+    spec("sign", Op::Sign, Ignore, REVISION, &["--key"]),
+    spec(
+        "simplify-parents",
+        Op::SimplifyParents,
+        Ignore,
+        &["-r", "--revisions", "-s", "--source"],
+        &[],
+    ),
+    spec("sparse", Op::Sparse, Ignore, &[], &[]),
+    CommandSpec {
+        path: "sparse set",
+        op: Op::Sparse,
+        positional: Ignore,
+        revision_options: &[],
+        path_options: &["--add", "--remove"],
+        value_options: &[],
+    },
+    spec(
+        "split",
+        Op::Split,
+        Paths,
+        PLACEMENT,
+        &["-m", "--message", "--tool"],
+    ),
+    spec(
+        "squash",
+        Op::Squash,
+        Paths,
+        &[
+            "-r",
+            "--revision",
+            "-f",
+            "--from",
+            "-t",
+            "--into",
+            "--to",
+            "-d",
+            "--destination",
+            "-o",
+            "--onto",
+            "-A",
+            "--insert-after",
+            "--after",
+            "-B",
+            "--insert-before",
+            "--before",
+        ],
+        &["-m", "--message", "--tool"],
+    ),
+    spec("status", Op::Status, Paths, &[], &[]),
+    spec("tag", Op::Tag, Ignore, &[], &[]),
+    spec("tag delete", Op::TagDelete, Ignore, &[], &[]),
+    spec("tag list", Op::TagList, Ignore, &[], TEMPLATE),
+    spec("tag set", Op::Tag, Ignore, REVISION, &[]),
+    spec("undo", Op::Undo, Ignore, &[], &["--what"]),
+    spec("unsign", Op::Unsign, Ignore, REVISION, &[]),
+    spec("unsquash", Op::Squash, Ignore, REVISION, &["--tool"]),
+    spec("util", Op::Util, Ignore, &[], &[]),
+    spec("version", Op::Version, Ignore, &[], &[]),
+    spec("workspace", Op::Workspace, Ignore, &[], &[]),
+    spec(
+        "workspace add",
+        Op::Workspace,
+        Ignore,
+        REVISION,
+        &["--name", "--sparse-patterns", "-m", "--message"],
+    ),
+];
+
+/// Resolve built-in top-level aliases to their canonical path
+fn resolve_alias(name: &str) -> &str {
+    match name {
+        "st" => "status",
+        "ci" => "commit",
+        "desc" => "describe",
+        "op" => "operation",
+        "co" => "checkout",
+        "backout" => "revert",
+        "obslog" => "evolog",
+        "branch" => "bookmark",
+        "cat" => "file show",
+        "files" => "file list",
+        "chmod" => "file chmod",
+        "untrack" => "file untrack",
+        other => other,
+    }
+}
+
+/// Resolve the short aliases of `bookmark` subcommands
+fn resolve_bookmark_alias(name: &str) -> &str {
+    match name {
+        "c" => "create",
+        "d" => "delete",
+        "f" => "forget",
+        "l" => "list",
+        "m" => "move",
+        "r" => "rename",
+        "s" => "set",
+        "t" => "track",
+        "u" => "untrack",
+        other => other,
+    }
+}
+
+fn find_spec(path: &str) -> Option<&'static CommandSpec> {
+    COMMANDS.iter().find(|spec| spec.path == path)
+}
+
+/// Detect the operation type of a jj argument list
+pub fn detect_operation_type(args: &[&str]) -> OperationType {
+    parse_jj_args(args).operation_type
+}
+
+/// Parse a jj argument list (with or without a leading `jj`)
+pub fn parse_jj_args(args: &[&str]) -> ParsedCommand {
+    let args = match args.first() {
+        Some(&"jj") => &args[1..],
+        _ => args,
+    };
+
+    let mut parsed = ParsedCommand {
+        subcommand: Vec::new(),
+        operation_type: OperationType::Unknown,
+        revisions: Vec::new(),
+        paths: Vec::new(),
+        repository: None,
+        at_operation: None,
+        positionals: Vec::new(),
+        options: Vec::new(),
+    };
+
+    // Strip global options, which jj accepts anywhere before `--`
+    let mut tokens: Vec<&str> = Vec::with_capacity(args.len());
+    let mut iter = args.iter().copied();
+    while let Some(arg) = iter.next() {
+        if arg == "--" {
+            tokens.push(arg);
+            tokens.extend(iter.by_ref());
+            break;
+        }
+        let Some((name, inline)) = match_option(arg, GLOBAL_VALUE_OPTIONS) else {
+            tokens.push(arg);
+            continue;
+        };
+        let value = inline.or_else(|| iter.next());
+        match name {
+            "-R" | "--repository" => parsed.repository = value.map(str::to_string),
+            "--at-operation" | "--at-op" => parsed.at_operation = value.map(str::to_string),
+            _ => {}
+        }
+    }
+
+    // Resolve the subcommand path
+    let Some(pos) = tokens.iter().position(|t| !t.starts_with('-')) else {
+        return parsed;
+    };
+    let top = tokens[pos];
+    let mut path = resolve_alias(top).to_string();
+    let mut rest = &tokens[pos + 1..];
+
+    if let Some(&child) = rest.first().filter(|c| !c.starts_with('-')) {
+        let child = if path == "bookmark" {
+            resolve_bookmark_alias(child)
+        } else {
+            child
+        };
+        let candidate = format!("{} {}", path, child);
+        if find_spec(&candidate).is_some() {
+            path = candidate;
+            rest = &rest[1..];
+        }
+    }
+
+    let Some(spec) = find_spec(&path) else {
+        parsed.subcommand = vec![top.to_string()];
+        return parsed;
+    };
+
+    parsed.subcommand = path.split(' ').map(str::to_string).collect();
+    parsed.operation_type = spec.op;
+
+    // `jj branch` predates bookmarks and keeps its own operation types
+    if top == "branch" {
+        parsed.operation_type = match parsed.subcommand.get(1).map(String::as_str) {
+            Some("delete") => OperationType::BranchDelete,
+            _ => OperationType::Branch,
+        };
+    }
+
+    parse_arguments(spec, rest, &mut parsed);
+    parsed
+}
+
+/// Collect revisions and paths from the arguments following the subcommand
+fn parse_arguments(spec: &CommandSpec, args: &[&str], parsed: &mut ParsedCommand) {
+    let mut positional_index = 0;
+    let mut only_positionals = false;
+    let mut iter = args.iter().copied();
+
+    while let Some(arg) = iter.next() {
+        if !only_positionals && arg == "--" {
+            only_positionals = true;
+            continue;
+        }
+
+        if only_positionals || !arg.starts_with('-') || arg == "-" {
+            parsed.positionals.push(arg.to_string());
+            match spec.positional {
+                Positional::Revisions => parsed.revisions.push(arg.to_string()),
+                Positional::Paths => parsed.paths.push(arg.to_string()),
+                Positional::SkipThenPaths(skip) if positional_index >= skip => {
+                    parsed.paths.push(arg.to_string())
+                }
+                Positional::SkipThenPaths(_) | Positional::Ignore => {}
+            }
+            positional_index += 1;
+            continue;
+        }
+
+        let all_value_options = [spec.revision_options, spec.path_options, spec.value_options];
+        let Some((name, inline)) = all_value_options
+            .iter()
+            .find_map(|options| match_option(arg, options))
+        else {
+            // A flag
+            continue;
+        };
+
+        let Some(value) = inline.or_else(|| iter.next()) else {
+            break;
+        };
+        parsed.options.push((name.to_string(), value.to_string()));
+        if spec.revision_options.contains(&name) {
+            parsed.revisions.push(value.to_string());
+        } else if spec.path_options.contains(&name) {
+            parsed.paths.push(value.to_string());
+        }
+    }
+}
+
+/// Match `arg` against value-taking options
+///
+/// Returns the option name and the inline value for `--name=value`, `-xvalue`
+/// and clustered short flags ending in a value option (`-sr@`).
+fn match_option<'a>(
+    arg: &'a str,
+    options: &[&'static str],
+) -> Option<(&'static str, Option<&'a str>)> {
+    if let Some(long) = arg.strip_prefix("--") {
+        let (name, inline) = match long.split_once('=') {
+            Some((name, value)) => (name, Some(value)),
+            None => (long, None),
+        };
+        return options
+            .iter()
+            .find(|opt| opt.strip_prefix("--") == Some(name))
+            .map(|opt| (*opt, inline));
+    }
+
+    let cluster = arg.strip_prefix('-')?;
+    for (i, c) in cluster.char_indices() {
+        let Some(opt) = options.iter().find(|opt| {
+            opt.len() == 2 && opt.strip_prefix('-').and_then(|o| o.chars().next()) == Some(c)
+        }) else {
+            continue;
+        };
+        let value = &cluster[i + c.len_utf8()..];
+        let value = value.strip_prefix('=').unwrap_or(value);
+        return Some((*opt, (!value.is_empty()).then_some(value)));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Case {
+        args: &'static str,
+        op: OperationType,
+        revisions: &'static [&'static str],
+        paths: &'static [&'static str],
+    }
+
+    const fn case(
+        args: &'static str,
+        op: OperationType,
+        revisions: &'static [&'static str],
+        paths: &'static [&'static str],
+    ) -> Case {
+        Case {
+            args,
+            op,
+            revisions,
+            paths,
+        }
+    }
+
+    const CASES: &[Case] = &[
+        case("abandon xyz -r abc", Op::Abandon, &["xyz", "abc"], &[]),
+        case(
+            "absorb --from @ --into trunk() src/",
+            Op::Absorb,
+            &["@", "trunk()"],
+            &["src/"],
+        ),
+        case("bisect", Op::Bisect, &[], &[]),
+        case(
+            "bisect run --range main..@ -- cargo test",
+            Op::Bisect,
+            &["main..@"],
+            &[],
+        ),
+        case("bookmark", Op::Bookmark, &[], &[]),
+        case(
+            "bookmark create feat -r @-",
+            Op::BookmarkCreate,
+            &["@-"],
+            &[],
+        ),
+        case("bookmark delete feat", Op::BookmarkDelete, &[], &[]),
+        case("bookmark forget feat", Op::BookmarkForget, &[], &[]),
+        case(
+            "bookmark list -r ::@ --remote origin",
+            Op::BookmarkList,
+            &["::@"],
+            &[],
+        ),
+        case(
+            "bookmark move --from main --to @ feat",
+            Op::BookmarkMove,
+            &["main", "@"],
+            &[],
+        ),
+        case("bookmark rename old new", Op::BookmarkRename, &[], &[]),
+        case("bookmark set main -r @ -B", Op::BookmarkSet, &["@"], &[]),
+        case("bookmark track main@origin", Op::BookmarkTrack, &[], &[]),
+        case(
+            "bookmark untrack main@origin",
+            Op::BookmarkUntrack,
+            &[],
+            &[],
+        ),
+        case("b s main -r @", Op::Unknown, &[], &[]),
+        case("bookmark s main -r=@", Op::BookmarkSet, &["@"], &[]),
+        case("branch delete old", Op::BranchDelete, &[], &[]),
+        case("branch create feat -r @", Op::Branch, &["@"], &[]),
+        case("checkout main", Op::Checkout, &["main"], &[]),
+        case(
+            "commit -m 'msg' src/a.rs src/b.rs",
+            Op::Commit,
+            &[],
+            &["src/a.rs", "src/b.rs"],
+        ),
+        case("ci -m msg", Op::Commit, &[], &[]),
+        case("config set --user user.name alice", Op::Config, &[], &[]),
+        case("config list -T builtin", Op::Config, &[], &[]),
+        case("describe -m msg @-", Op::Describe, &["@-"], &[]),
+        case("desc -r abc -m msg", Op::Describe, &["abc"], &[]),
+        case("diff -r @- --tool difft src/", Op::Diff, &["@-"], &["src/"]),
+        case("diff --from main --to=@ -s", Op::Diff, &["main", "@"], &[]),
+        case("diffedit -r @- --tool meld", Op::Diffedit, &["@-"], &[]),
+        case(
+            "duplicate abc -d main",
+            Op::Duplicate,
+            &["abc", "main"],
+            &[],
+        ),
+        case("edit abc", Op::Edit, &["abc"], &[]),
+        case("evolog -r @ -n 5", Op::Evolog, &["@"], &[]),
+        case("obslog -r @", Op::Evolog, &["@"], &[]),
+        case("file", Op::File, &[], &[]),
+        case(
+            "file annotate -r @- src/lib.rs",
+            Op::File,
+            &["@-"],
+            &["src/lib.rs"],
+        ),
+        case(
+            "file chmod x -r @ run.sh",
+            Op::FileChmod,
+            &["@"],
+            &["run.sh"],
+        ),
+        case("file list -r main src", Op::File, &["main"], &["src"]),
+        case("file search -p TODO src", Op::File, &[], &["src"]),
+        case(
+            "file show -r @- README.md",
+            Op::File,
+            &["@-"],
+            &["README.md"],
+        ),
+        case("file track new.rs", Op::FileTrack, &[], &["new.rs"]),
+        case("file untrack target", Op::FileUntrack, &[], &["target"]),
+        case("cat -r @ README.md", Op::File, &["@"], &["README.md"]),
+        case("fix -s @ src/", Op::Fix, &["@"], &["src/"]),
+        case("gerrit", Op::GerritUpload, &[], &[]),
+        case(
+            "gerrit upload -r @ --remote-branch main",
+            Op::GerritUpload,
+            &["@"],
+            &[],
+        ),
+        case(
+            "git clone https://example.com/repo dest",
+            Op::Clone,
+            &[],
+            &[],
+        ),
+        case("git export", Op::GitExport, &[], &[]),
+        case("git fetch --remote origin -b main", Op::GitFetch, &[], &[]),
+        case("git import", Op::GitImport, &[], &[]),
+        case("git init --colocate", Op::Init, &[], &[]),
+        case(
+            "git push -c @- -b main --allow-new",
+            Op::GitPush,
+            &["@-"],
+            &[],
+        ),
+        case("git push --revisions=main::", Op::GitPush, &["main::"], &[]),
+        case(
+            "git remote add origin https://example.com",
+            Op::GitRemote,
+            &[],
+            &[],
+        ),
+        case("git root", Op::Root, &[], &[]),
+        case("git frobnicate", Op::Unknown, &[], &[]),
+        case("help log", Op::Help, &[], &[]),
+        case("init", Op::Init, &[], &[]),
+        case(
+            "interdiff --from a --to b src",
+            Op::Interdiff,
+            &["a", "b"],
+            &["src"],
+        ),
+        case(
+            "log -r 'all()' -n 10 --no-graph src",
+            Op::Log,
+            &["'all()'"],
+            &["src"],
+        ),
+        case("merge a b", Op::Merge, &["a", "b"], &[]),
+        case("metaedit --author alice @", Op::Metaedit, &["@"], &[]),
+        case("move --from a --to b", Op::Move, &["a", "b"], &[]),
+        case("new main feat -m merge", Op::New, &["main", "feat"], &[]),
+        case("new -A @ --no-edit", Op::New, &["@"], &[]),
+        case("next --edit", Op::Next, &[], &[]),
+        case("op", Op::Unknown, &[], &[]),
+        case("operation abandon ..abc", Op::OpAbandon, &[], &[]),
+        case("op diff --from abc --to def", Op::OpDiff, &[], &[]),
+        case("op log -n 5", Op::OpLog, &[], &[]),
+        case("op restore abc", Op::OpRestore, &[], &[]),
+        case("op revert abc", Op::OpRevert, &[], &[]),
+        case("op show abc", Op::OpShow, &[], &[]),
+        case("op undo", Op::Undo, &[], &[]),
+        case("parallelize a::b", Op::Parallelize, &["a::b"], &[]),
+        case("prev", Op::Prev, &[], &[]),
+        case("rebase -s abc -d main", Op::Rebase, &["abc", "main"], &[]),
+        case("rebase -b @ -o trunk()", Op::Rebase, &["@", "trunk()"], &[]),
+        case("redo", Op::Redo, &[], &[]),
+        case(
+            "resolve -r @ --tool meld src/a.rs",
+            Op::Resolve,
+            &["@"],
+            &["src/a.rs"],
+        ),
+        case(
+            "restore --from @- src/a.rs",
+            Op::Restore,
+            &["@-"],
+            &["src/a.rs"],
+        ),
+        case("restore -c abc", Op::Restore, &["abc"], &[]),
+        case("revert -r abc -B @", Op::Revert, &["abc", "@"], &[]),
+        case("backout -r abc", Op::Revert, &["abc"], &[]),
+        case("root", Op::Root, &[], &[]),
+        case("show abc", Op::Show, &["abc"], &[]),
+        case("sign -r @", Op::Sign, &["@"], &[]),
+        case(
+            "simplify-parents -s abc",
+            Op::SimplifyParents,
+            &["abc"],
+            &[],
+        ),
+        case(
+            "sparse set --add src --remove docs",
+            Op::Sparse,
+            &[],
+            &["src", "docs"],
+        ),
+        case("sparse list", Op::Sparse, &[], &[]),
+        case(
+            "split -r @- -m part src/a.rs",
+            Op::Split,
+            &["@-"],
+            &["src/a.rs"],
+        ),
+        case(
+            "squash --from @ --into @-- src/",
+            Op::Squash,
+            &["@", "@--"],
+            &["src/"],
+        ),
+        case("squash -t abc", Op::Squash, &["abc"], &[]),
+        case("status src/", Op::Status, &[], &["src/"]),
+        case("st", Op::Status, &[], &[]),
+        case("tag", Op::Tag, &[], &[]),
+        case("tag set v1 -r @", Op::Tag, &["@"], &[]),
+        case("tag delete v1", Op::TagDelete, &[], &[]),
+        case("tag list", Op::TagList, &[], &[]),
+        case("undo", Op::Undo, &[], &[]),
+        case("unsign -r @", Op::Unsign, &["@"], &[]),
+        case("unsquash -r @", Op::Squash, &["@"], &[]),
+        case("util gc", Op::Util, &[], &[]),
+        case("version", Op::Version, &[], &[]),
+        case(
+            "workspace add ../ws -r main --name ws",
+            Op::Workspace,
+            &["main"],
+            &[],
+        ),
+        case("workspace forget ws", Op::Workspace, &[], &[]),
+        case("frobnicate", Op::Unknown, &[], &[]),
+    ];
+
+    #[test]
+    fn test_every_subcommand() {
+        for case in CASES {
+            let args: Vec<&str> = case.args.split_whitespace().collect();
+            let parsed = parse_jj_args(&args);
+            assert_eq!(parsed.operation_type, case.op, "jj {}", case.args);
+            assert_eq!(
+                parsed.revisions, case.revisions,
+                "revisions of jj {}",
+                case.args
+            );
+            assert_eq!(parsed.paths, case.paths, "paths of jj {}", case.args);
+        }
+    }
+
+    #[test]
+    fn test_every_spec_is_covered() {
+        for spec in COMMANDS {
+            let covered = CASES.iter().any(|case| {
+                let args: Vec<&str> = case.args.split_whitespace().collect();
+                parse_jj_args(&args).subcommand.join(" ") == spec.path
+            });
+            assert!(covered, "no test case for `jj {}`", spec.path);
+        }
+    }
+
+    #[test]
+    fn test_global_options_are_skipped() {
+        let parsed = parse_jj_args(&[
+            "jj",
+            "--no-pager",
+            "-R",
+            "/repo",
+            "--color=never",
+            "--at-op",
+            "abc123",
+            "git",
+            "--config",
+            "ui.paginate=never",
+            "push",
+            "-c",
+            "@",
+            "--ignore-working-copy",
+        ]);
+        assert_eq!(parsed.subcommand, vec!["git", "push"]);
+        assert_eq!(parsed.operation_type, Op::GitPush);
+        assert_eq!(parsed.revisions, vec!["@"]);
+        assert_eq!(parsed.repository.as_deref(), Some("/repo"));
+        assert_eq!(parsed.at_operation.as_deref(), Some("abc123"));
+
+        let parsed = parse_jj_args(&["-R/repo", "log", "-r@-"]);
+        assert_eq!(parsed.repository.as_deref(), Some("/repo"));
+        assert_eq!(parsed.revisions, vec!["@-"]);
+    }
+
+    #[test]
+    fn test_double_dash_and_clusters() {
+        let parsed = parse_jj_args(&["diff", "--", "-weird-file"]);
+        assert_eq!(parsed.paths, vec!["-weird-file"]);
+
+        let parsed = parse_jj_args(&["abandon", "-r", "--"]);
+        assert_eq!(parsed.revisions, vec!["--"]);
+
+        // `-s` is a flag for diff, `-r` takes the rest of the cluster
+        let parsed = parse_jj_args(&["diff", "-sr@-"]);
+        assert_eq!(parsed.revisions, vec!["@-"]);
+    }
+
+    #[test]
+    fn test_positionals_and_option_values() {
+        let parsed = parse_jj_args(&["rebase", "-s", "feat", "--destination=main", "-B", "x"]);
+        assert!(parsed.positionals.is_empty());
+        assert_eq!(parsed.option_values(&["-s", "--source"]), vec!["feat"]);
+        assert_eq!(parsed.option_values(&["-d", "--destination"]), vec!["main"]);
+        assert_eq!(parsed.option_values(&["-B", "--insert-before"]), vec!["x"]);
+
+        let parsed = parse_jj_args(&["bookmark", "set", "main", "-r", "@", "--", "-odd"]);
+        assert_eq!(parsed.positionals, vec!["main", "-odd"]);
+        assert_eq!(parsed.options, vec![("-r".to_string(), "@".to_string())]);
+    }
+
+    #[test]
+    fn test_empty_and_options_only() {
+        assert_eq!(parse_jj_args(&[]).operation_type, Op::Unknown);
+        assert_eq!(parse_jj_args(&["--help"]).operation_type, Op::Unknown);
+        assert!(parse_jj_args(&["--version"]).subcommand.is_empty());
+    }
+}
@@ -0,0 +1,811 @@
+//! Deterministic commit message generation from diffs
+//!
+//! Builds a conventional-commit style description for a change without any
+//! model or network access. The input is the git-format diff printed by
+//! `jj diff --git` plus optional session context (the task and the operations
+//! of the current trajectory).
+//!
+//! - The **type** comes from the categories of the changed files (`docs`,
+//!   `test`, `ci`, `build`), the task wording (`fix`) and whether new symbols
+//!   were introduced (`feat`) or only moved and removed (`refactor`).
+//! - The **scope** is the module shared by all changed source files.
+//! - The **summary** names added, removed or updated symbols, found by a
+//!   line-based parser for Rust, Python, JavaScript/TypeScript and Go, and
+//!   falls back to file names.
+//! - The **body** lists every file with its symbols or line counts, followed by
+//!   the session task and operations.
+//!
+//! The same diff and context always produce the same message.
+
+use std::collections::BTreeSet;
+use std::path::Path;
+
+/// Maximum header length, including type and scope
+const MAX_HEADER_LEN: usize = 72;
+
+/// Task words that mark a change as a bug fix
+const FIX_WORDS: &[&str] = &[
+    "fix",
+    "fixes",
+    "fixed",
+    "bug",
+    "bugfix",
+    "crash",
+    "regression",
+    "hotfix",
+];
+
+/// Session information included in the message
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MessageContext {
+    /// Task the agent worked on
+    pub task: Option<String>,
+    /// Operation types performed during the session, in order
+    pub operations: Vec<String>,
+}
+
+impl MessageContext {
+    /// Context for a task without recorded operations
+    pub fn for_task(task: impl Into<String>) -> Self {
+        Self {
+            task: Some(task.into()),
+            operations: Vec::new(),
+        }
+    }
+}
+
+/// How a file changed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChangeKind {
+    /// New file
+    Added,
+    /// Existing file edited in place
+    Modified,
+    /// File removed
+    Deleted,
+    /// File moved, possibly with edits
+    Renamed {
+        /// Path before the rename
+        from: String,
+    },
+}
+
+/// Coarse file category used to pick the commit type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FileCategory {
+    /// Application or library code
+    Source,
+    /// Tests and test fixtures
+    Test,
+    /// Documentation
+    Docs,
+    /// Build manifests and lockfiles
+    Build,
+    /// CI pipelines
+    Ci,
+    /// Other configuration files
+    Config,
+}
+
+/// One file of a parsed diff
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileChange {
+    /// Path after the change
+    pub path: String,
+    /// How the file changed
+    pub kind: ChangeKind,
+    /// Lines added
+    pub additions: u32,
+    /// Lines removed
+    pub deletions: u32,
+    /// Symbols defined only on added lines
+    pub added_symbols: Vec<String>,
+    /// Symbols defined only on removed lines
+    pub removed_symbols: Vec<String>,
+    /// Symbols whose definition line was both removed and added
+    pub changed_symbols: Vec<String>,
+}
+
+impl FileChange {
+    fn new(path: String, kind: ChangeKind) -> Self {
+        Self {
+            path,
+            kind,
+            additions: 0,
+            deletions: 0,
+            added_symbols: Vec::new(),
+            removed_symbols: Vec::new(),
+            changed_symbols: Vec::new(),
+        }
+    }
+
+    /// Category of this file
+    pub fn category(&self) -> FileCategory {
+        categorize(&self.path)
+    }
+}
+
+/// Parse a git-format diff into per-file changes
+pub fn parse_git_diff(diff: &str) -> Vec<FileChange> {
+    let mut files = Vec::new();
+    let mut current: Option<(FileChange, Vec<String>, Vec<String>)> = None;
+    let mut in_hunk = false;
+
+    for line in diff.lines() {
+        if let Some(paths) = line.strip_prefix("diff --git ") {
+            if let Some(done) = current.take() {
+                files.push(finish(done));
+            }
+            let (from, to) = split_git_paths(paths);
+            let kind = if from == to {
+                ChangeKind::Modified
+            } else {
+                ChangeKind::Renamed { from }
+            };
+            current = Some((FileChange::new(to, kind), Vec::new(), Vec::new()));
+            in_hunk = false;
+            continue;
+        }
+
+        let Some((file, added, removed)) = current.as_mut() else {
+            continue;
+        };
+
+        if line.starts_with("@@") {
+            in_hunk = true;
+        } else if in_hunk {
+            if let Some(text) = line.strip_prefix('+') {
+                file.additions += 1;
+                added.push(text.to_string());
+            } else if let Some(text) = line.strip_prefix('-') {
+                file.deletions += 1;
+                removed.push(text.to_string());
+            }
+        } else if line.starts_with("new file mode") {
+            file.kind = ChangeKind::Added;
+        } else if line.starts_with("deleted file mode") {
+            file.kind = ChangeKind::Deleted;
+        } else if let Some(from) = line.strip_prefix("rename from ") {
+            file.kind = ChangeKind::Renamed {
+                from: from.to_string(),
+            };
+        } else if let Some(to) = line.strip_prefix("rename to ") {
+            file.path = to.to_string();
+        }
+    }
+
+    if let Some(done) = current.take() {
+        files.push(finish(done));
+    }
+    files
+}
+
+/// Generate a commit message for `diff` (git format) and `context`
+pub fn generate_commit_message(diff: &str, context: &MessageContext) -> String {
+    let files = parse_git_diff(diff);
+
+    let commit_type = commit_type(&files, context);
+    let scope = scope(&files);
+    let prefix = match &scope {
+        Some(scope) => format!("{}({}): ", commit_type, scope),
+        None => format!("{}: ", commit_type),
+    };
+    let header = format!(
+        "{}{}",
+        prefix,
+        summary(&files, MAX_HEADER_LEN.saturating_sub(prefix.len()))
+    );
+
+    let mut sections = vec![header];
+
+    if !files.is_empty() {
+        sections.push(
+            files
+                .iter()
+                .map(describe_file)
+                .collect::<Vec<_>>()
+                .join("\n"),
+        );
+    }
+
+    let mut session = Vec::new();
+    if let Some(task) = context
+        .task
+        .as_deref()
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+    {
+        session.push(format!("Task: {}", task));
+    }
+    if !context.operations.is_empty() {
+        session.push(format!("Operations: {}", context.operations.join(" -> ")));
+    }
+    if !session.is_empty() {
+        sections.push(session.join("\n"));
+    }
+
+    sections.join("\n\n") + "\n"
+}
+
+/// Split `a/<from> b/<to>` from a `diff --git` line
+pub(crate) fn split_git_paths(paths: &str) -> (String, String) {
+    // Paths may contain spaces, so split on the ` b/` marker
+    if let Some(rest) = paths.strip_prefix("a/") {
+        if let Some(index) = rest.find(" b/") {
+            let from = &rest[..index];
+            let to = &rest[index + 3..];
+            return (from.to_string(), to.to_string());
+        }
+    }
+    (paths.to_string(), paths.to_string())
+}
+
+fn finish((mut file, added, removed): (FileChange, Vec<String>, Vec<String>)) -> FileChange {
+    let language = Language::from_path(&file.path);
+    let added = symbols(&added, language);
+    let removed = symbols(&removed, language);
+
+    let added_set: BTreeSet<&String> = added.iter().collect();
+    let removed_set: BTreeSet<&String> = removed.iter().collect();
+
+    file.added_symbols = unique(added.iter().filter(|s| !removed_set.contains(s)));
+    file.removed_symbols = unique(removed.iter().filter(|s| !added_set.contains(s)));
+    file.changed_symbols = unique(added.iter().filter(|s| removed_set.contains(s)));
+    file
+}
+
+fn unique<'a>(symbols: impl Iterator<Item = &'a String>) -> Vec<String> {
+    let mut seen = BTreeSet::new();
+    symbols
+        .filter(|s| seen.insert(s.as_str()))
+        .cloned()
+        .collect()
+}
+
+fn symbols(lines: &[String], language: Option<Language>) -> Vec<String> {
+    let Some(language) = language else {
+        return Vec::new();
+    };
+    lines
+        .iter()
+        .filter_map(|line| language.symbol(line))
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Language {
+    Rust,
+    Python,
+    JavaScript,
+    Go,
+}
+
+impl Language {
+    fn from_path(path: &str) -> Option<Self> {
+        match Path::new(path).extension()?.to_str()? {
+            "rs" => Some(Language::Rust),
+            "py" => Some(Language::Python),
+            "js" | "jsx" | "mjs" | "cjs" | "ts" | "tsx" | "mts" | "cts" => {
+                Some(Language::JavaScript)
+            }
+            "go" => Some(Language::Go),
+            _ => None,
+        }
+    }
+
+    fn keywords(self) -> &'static [&'static str] {
+        match self {
+            Language::Rust => &[
+                "fn",
+                "struct",
+                "enum",
+                "trait",
+                "mod",
+                "type",
+                "const",
+                "static",
+                "union",
+                "macro_rules!",
+            ],
+            Language::Python => &["def", "class"],
+            Language::JavaScript => &[
+                "function",
+                "function*",
+                "class",
+                "interface",
+                "type",
+                "enum",
+                "const",
+                "let",
+                "var",
+            ],
+            Language::Go => &["func", "type"],
+        }
+    }
+
+    fn modifiers(self) -> &'static [&'static str] {
+        match self {
+            Language::Rust => &[
+                "pub",
+                "pub(crate)",
+                "pub(super)",
+                "pub(self)",
+                "async",
+                "unsafe",
+                "extern",
+                "\"C\"",
+                "default",
+            ],
+            Language::Python => &["async"],
+            Language::JavaScript => &["export", "default", "async", "declare", "abstract"],
+            Language::Go => &[],
+        }
+    }
+
+    /// Name of the symbol defined on `line`, if it starts a definition
+    fn symbol(self, line: &str) -> Option<String> {
+        let trimmed = line.trim();
+        if trimmed.starts_with("//") || trimmed.starts_with('#') || trimmed.starts_with('*') {
+            return None;
+        }
+
+        let mut tokens = trimmed.split_whitespace().peekable();
+        while let Some(token) = tokens.next() {
+            if self.keywords().contains(&token) {
+                // `const fn` / `const unsafe fn` in Rust: keep looking for `fn`
+                if self == Language::Rust && token == "const" {
+                    if let Some(next) = tokens.peek() {
+                        if ["fn", "unsafe", "async", "extern"].contains(next) {
+                            continue;
+                        }
+                    }
+                }
+
+                let mut name_token = tokens.next()?;
+                // Go method receivers: `func (s *Server) Start(`
+                if self == Language::Go && token == "func" && name_token.starts_with('(') {
+                    let after = trimmed.split_once(')')?.1.trim_start();
+                    name_token = after.split_whitespace().next()?;
+                }
+
+                let name: String = name_token
+                    .chars()
+                    .take_while(|c| c.is_alphanumeric() || *c == '_' || *c == '$')
+                    .collect();
+                if name.is_empty() {
+                    return None;
+                }
+
+                // JS variables only count when they hold a function
+                if self == Language::JavaScript && matches!(token, "const" | "let" | "var") {
+                    let value = trimmed.split_once('=')?.1;
+                    if !(value.contains("=>") || value.trim_start().starts_with("function")) {
+                        return None;
+                    }
+                }
+                return Some(name);
+            }
+
+            if !self.modifiers().contains(&token) {
+                return None;
+            }
+        }
+        None
+    }
+}
+
+pub(crate) fn categorize(path: &str) -> FileCategory {
+    let lower = path.to_lowercase();
+    let name = lower.rsplit('/').next().unwrap_or(&lower);
+    let segments: Vec<&str> = lower.split('/').collect();
+    let in_dir = |dir: &str| segments[..segments.len() - 1].contains(&dir);
+
+    if lower.starts_with(".github/workflows/")
+        || lower.starts_with(".circleci/")
+        || name == ".gitlab-ci.yml"
+        || name == ".travis.yml"
+        || name == "azure-pipelines.yml"
+    {
+        FileCategory::Ci
+    } else if in_dir("tests")
+        || in_dir("test")
+        || in_dir("__tests__")
+        || name.contains("_test.")
+        || name.contains(".test.")
+        || name.contains(".spec.")
+        || name.starts_with("test_")
+    {
+        FileCategory::Test
+    } else if in_dir("docs")
+        || name.ends_with(".md")
+        || name.ends_with(".rst")
+        || name.ends_with(".adoc")
+        || name == "license"
+    {
+        FileCategory::Docs
+    } else if matches!(
+        name,
+        "cargo.toml"
+            | "cargo.lock"
+            | "build.rs"
+            | "package.json"
+            | "package-lock.json"
+            | "yarn.lock"
+            | "pnpm-lock.yaml"
+            | "go.mod"
+            | "go.sum"
+            | "pyproject.toml"
+            | "setup.py"
+            | "requirements.txt"
+            | "makefile"
+            | "dockerfile"
+    ) {
+        FileCategory::Build
+    } else if Language::from_path(path).is_none()
+        && [".toml", ".yaml", ".yml", ".json", ".ini", ".cfg", ".env"]
+            .iter()
+            .any(|ext| name.ends_with(ext))
+    {
+        FileCategory::Config
+    } else {
+        FileCategory::Source
+    }
+}
+
+fn is_code(category: FileCategory) -> bool {
+    matches!(category, FileCategory::Source | FileCategory::Config)
+}
+
+fn commit_type(files: &[FileChange], context: &MessageContext) -> &'static str {
+    if files.is_empty() {
+        return "chore";
+    }
+
+    let categories: BTreeSet<FileCategory> = files.iter().map(FileChange::category).collect();
+    if !categories.iter().copied().any(is_code) {
+        return match categories.iter().next() {
+            Some(FileCategory::Docs) if categories.len() == 1 => "docs",
+            Some(FileCategory::Test) if categories.len() == 1 => "test",
+            Some(FileCategory::Ci) if categories.len() == 1 => "ci",
+            Some(FileCategory::Build) if categories.len() == 1 => "build",
+            _ => "chore",
+        };
+    }
+
+    let mentions_fix = context.task.as_deref().is_some_and(|task| {
+        task.to_lowercase()
+            .split(|c: char| !c.is_alphanumeric())
+            .any(|word| FIX_WORDS.contains(&word))
+    });
+    if mentions_fix {
+        return "fix";
+    }
+
+    let code: Vec<&FileChange> = files.iter().filter(|f| is_code(f.category())).collect();
+    if code
+        .iter()
+        .any(|f| !f.added_symbols.is_empty() || f.kind == ChangeKind::Added)
+    {
+        "feat"
+    } else if code.iter().all(|f| {
+        matches!(f.kind, ChangeKind::Renamed { .. } | ChangeKind::Deleted)
+            || !f.removed_symbols.is_empty()
+            || !f.changed_symbols.is_empty()
+    }) {
+        "refactor"
+    } else {
+        "chore"
+    }
+}
+
+fn scope(files: &[FileChange]) -> Option<String> {
+    let code: Vec<&FileChange> = files.iter().filter(|f| is_code(f.category())).collect();
+    let relevant = if code.is_empty() {
+        files.iter().collect()
+    } else {
+        code
+    };
+
+    let mut components = relevant.iter().map(|f| component(&f.path));
+    let first = components.next()?;
+    if components.all(|c| c == first) {
+        Some(first)
+    } else {
+        None
+    }
+}
+
+/// Module a path belongs to: the entry under the innermost `src/`, else the
+/// top-level directory or file stem
+fn component(path: &str) -> String {
+    let segments: Vec<&str> = path.split('/').collect();
+    let segment = match segments.iter().rposition(|s| *s == "src") {
+        Some(i) if i + 1 < segments.len() => segments[i + 1],
+        _ => segments[0],
+    };
+    stem(segment).to_lowercase()
+}
+
+fn stem(name: &str) -> &str {
+    match name.find('.') {
+        Some(0) | None => name,
+        Some(i) => &name[..i],
+    }
+}
+
+fn file_name(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+/// Join `items` as "a, b and c", leaving out items that would exceed `budget`
+fn join_within(items: &[String], budget: usize) -> String {
+    let mut shown: Vec<&str> = Vec::new();
+    for (i, item) in items.iter().enumerate() {
+        let remaining = items.len() - i - 1;
+        let mut candidate = shown.clone();
+        candidate.push(item);
+        let text = list(&candidate, remaining);
+        if text.len() > budget && !shown.is_empty() {
+            return list(&shown, items.len() - shown.len());
+        }
+        shown = candidate;
+    }
+    list(&shown, 0)
+}
+
+fn list(items: &[&str], more: usize) -> String {
+    match (items, more) {
+        ([], _) => String::new(),
+        ([only], 0) => only.to_string(),
+        (items, 0) => format!(
+            "{} and {}",
+            items[..items.len() - 1].join(", "),
+            items[items.len() - 1]
+        ),
+        (items, more) => format!("{} and {} more", items.join(", "), more),
+    }
+}
+
+fn summary(files: &[FileChange], budget: usize) -> String {
+    if files.is_empty() {
+        return "empty change".to_string();
+    }
+
+    let collect = |select: fn(&FileChange) -> &Vec<String>| -> Vec<String> {
+        let mut seen = BTreeSet::new();
+        files
+            .iter()
+            .filter(|f| is_code(f.category()))
+            .flat_map(|f| select(f).iter())
+            .filter(|s| seen.insert(s.as_str()))
+            .map(|s| format!("`{}`", s))
+            .collect()
+    };
+
+    for (verb, symbols) in [
+        ("add", collect(|f| &f.added_symbols)),
+        ("remove", collect(|f| &f.removed_symbols)),
+        ("update", collect(|f| &f.changed_symbols)),
+    ] {
+        if !symbols.is_empty() {
+            return format!(
+                "{} {}",
+                verb,
+                join_within(&symbols, budget.saturating_sub(verb.len() + 1))
+            );
+        }
+    }
+
+    if let [FileChange {
+        kind: ChangeKind::Renamed { from },
+        path,
+        ..
+    }] = files
+    {
+        return format!("rename {} to {}", file_name(from), file_name(path));
+    }
+
+    let verb = if files.iter().all(|f| f.kind == ChangeKind::Added) {
+        "add"
+    } else if files.iter().all(|f| f.kind == ChangeKind::Deleted) {
+        "remove"
+    } else if files
+        .iter()
+        .all(|f| matches!(f.kind, ChangeKind::Renamed { .. }))
+    {
+        "move"
+    } else {
+        "update"
+    };
+    let names: Vec<String> = files
+        .iter()
+        .map(|f| file_name(&f.path).to_string())
+        .collect();
+    format!(
+        "{} {}",
+        verb,
+        join_within(&names, budget.saturating_sub(verb.len() + 1))
+    )
+}
+
+fn describe_file(file: &FileChange) -> String {
+    let mut parts = Vec::new();
+    match &file.kind {
+        ChangeKind::Added => parts.push("new file".to_string()),
+        ChangeKind::Deleted => parts.push("deleted".to_string()),
+        ChangeKind::Renamed { from } => parts.push(format!("renamed from {}", from)),
+        ChangeKind::Modified => {}
+    }
+
+    let quote = |symbols: &[String]| {
+        symbols
+            .iter()
+            .map(|s| format!("`{}`", s))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    if !file.added_symbols.is_empty() {
+        parts.push(format!("add {}", quote(&file.added_symbols)));
+    }
+    if !file.removed_symbols.is_empty() {
+        parts.push(format!("remove {}", quote(&file.removed_symbols)));
+    }
+    if !file.changed_symbols.is_empty() {
+        parts.push(format!("update {}", quote(&file.changed_symbols)));
+    }
+    if file.additions > 0 || file.deletions > 0 {
+        parts.push(format!("+{} -{}", file.additions, file.deletions));
+    }
+
+    if parts.is_empty() {
+        format!("- {}", file.path)
+    } else {
+        format!("- {}: {}", file.path, parts.join("; "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn golden_dir() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden/commit_messages")
+    }
+
+    /// Each `<name>.diff` has an expected `<name>.txt`; an optional
+    /// `<name>.task` supplies the task and `<name>.ops` the operations.
+    /// Set `UPDATE_GOLDEN=1` to rewrite the expected files.
+    #[test]
+    fn test_golden_files() {
+        let update = std::env::var_os("UPDATE_GOLDEN").is_some();
+        let mut diffs: Vec<PathBuf> = std::fs::read_dir(golden_dir())
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "diff"))
+            .collect();
+        diffs.sort();
+        assert!(!diffs.is_empty());
+
+        for diff_path in diffs {
+            let read = |ext: &str| std::fs::read_to_string(diff_path.with_extension(ext)).ok();
+            let context = MessageContext {
+                task: read("task").map(|t| t.trim().to_string()),
+                operations: read("ops")
+                    .map(|ops| ops.split_whitespace().map(str::to_string).collect())
+                    .unwrap_or_default(),
+            };
+
+            let message = generate_commit_message(&read("diff").unwrap(), &context);
+            let expected_path = diff_path.with_extension("txt");
+            if update {
+                std::fs::write(&expected_path, &message).unwrap();
+                continue;
+            }
+            let expected = std::fs::read_to_string(&expected_path).unwrap_or_else(|_| {
+                panic!(
+                    "missing {}; run with UPDATE_GOLDEN=1",
+                    expected_path.display()
+                )
+            });
+            assert_eq!(
+                message,
+                expected,
+                "golden mismatch for {}",
+                diff_path.display()
+            );
+        }
+    }
+
+    #[test]
+    fn test_symbol_parser() {
+        let cases = [
+            (
+                Language::Rust,
+                "pub(crate) async fn load_rules(path: &Path) {",
+                Some("load_rules"),
+            ),
+            (Language::Rust, "pub const fn new() -> Self {", Some("new")),
+            (
+                Language::Rust,
+                "const MAX_RETRIES: u32 = 3;",
+                Some("MAX_RETRIES"),
+            ),
+            (Language::Rust, "pub struct Lease<T> {", Some("Lease")),
+            (Language::Rust, "macro_rules! ensure {", Some("ensure")),
+            (Language::Rust, "impl Default for Lease {", None),
+            (Language::Rust, "// fn commented_out() {}", None),
+            (
+                Language::Python,
+                "    async def fetch(self, url):",
+                Some("fetch"),
+            ),
+            (Language::Python, "class Config(Base):", Some("Config")),
+            (
+                Language::JavaScript,
+                "export default async function run() {",
+                Some("run"),
+            ),
+            (
+                Language::JavaScript,
+                "export const format = (value) => {",
+                Some("format"),
+            ),
+            (Language::JavaScript, "const LIMIT = 10;", None),
+            (
+                Language::JavaScript,
+                "export interface Options {",
+                Some("Options"),
+            ),
+            (
+                Language::Go,
+                "func (s *Server) Start(ctx context.Context) error {",
+                Some("Start"),
+            ),
+            (Language::Go, "type Server struct {", Some("Server")),
+        ];
+
+        for (language, line, expected) in cases {
+            assert_eq!(
+                language.symbol(line).as_deref(),
+                expected,
+                "{:?}: {}",
+                language,
+                line
+            );
+        }
+    }
+
+    #[test]
+    fn test_categories() {
+        assert_eq!(categorize("src/lib.rs"), FileCategory::Source);
+        assert_eq!(categorize("tests/wrapper_tests.rs"), FileCategory::Test);
+        assert_eq!(categorize("src/format.test.ts"), FileCategory::Test);
+        assert_eq!(categorize("docs/guide.md"), FileCategory::Docs);
+        assert_eq!(categorize("Cargo.toml"), FileCategory::Build);
+        assert_eq!(categorize(".github/workflows/ci.yml"), FileCategory::Ci);
+        assert_eq!(categorize("config/rules.toml"), FileCategory::Config);
+    }
+
+    #[test]
+    fn test_header_stays_short() {
+        let mut diff = String::from(
+            "diff --git a/src/api.rs b/src/api.rs\nindex 1..2 100644\n--- a/src/api.rs\n+++ b/src/api.rs\n@@ -1,0 +1,20 @@\n",
+        );
+        for i in 0..20 {
+            diff.push_str(&format!("+pub fn handler_with_long_name_{}() {{}}\n", i));
+        }
+
+        let message = generate_commit_message(&diff, &MessageContext::default());
+        let header = message.lines().next().unwrap();
+        assert!(header.len() <= MAX_HEADER_LEN, "{}", header);
+        assert!(header.starts_with("feat(api): add `handler_with_long_name_0`"));
+        assert!(header.ends_with(" more"));
+    }
+
+    #[test]
+    fn test_empty_diff() {
+        let message = generate_commit_message("", &MessageContext::for_task("Investigate"));
+        assert_eq!(message, "chore: empty change\n\nTask: Investigate\n");
+    }
+}
@@ -3,6 +3,8 @@
 use serde::{Deserialize, Serialize};
 use napi_derive::napi;
 
+use crate::agent_coordination::ConflictRules;
+
 /// Validate repository path to prevent directory traversal attacks
 fn validate_repo_path(path: &str) -> Result<String, String> {
     // Block obvious path traversal attempts
@@ -42,6 +44,21 @@ pub struct JJConfig {
 
     /// Enable AgentDB sync
     pub enable_agentdb_sync: bool,
+
+    /// Path to a conflict rules file (.toml or .json) for agent coordination
+    pub conflict_rules_path: Option<String>,
+
+    /// Retries for commands failing with a concurrent modification (default: 3)
+    pub max_retries: Option<u32>,
+
+    /// Revsets agents must not rewrite, e.g. `trunk()` or `main@origin`
+    pub protected_revsets: Option<Vec<String>>,
+
+    /// Bookmark names or globs agents must not move, delete or rewrite
+    pub protected_bookmarks: Option<Vec<String>>,
+
+    /// Public keys (hex) allowed to approve policy overrides; none if unset
+    pub override_approvers: Option<Vec<String>>,
 }
 
 impl JJConfig {
@@ -110,6 +127,52 @@ impl JJConfig {
         self.enable_agentdb_sync = enable;
         self
     }
+
+    /// Set conflict rules file path
+    pub fn with_conflict_rules_path(mut self, path: String) -> Self {
+        self.conflict_rules_path = Some(path);
+        self
+    }
+
+    /// Set retries for concurrent-modification failures
+    pub fn with_max_retries(mut self, retries: u32) -> Self {
+        self.max_retries = Some(retries);
+        self
+    }
+
+    /// Set revsets protected from rewriting
+    pub fn with_protected_revsets(mut self, revsets: Vec<String>) -> Self {
+        self.protected_revsets = Some(revsets);
+        self
+    }
+
+    /// Set bookmarks protected from changes
+    pub fn with_protected_bookmarks(mut self, bookmarks: Vec<String>) -> Self {
+        self.protected_bookmarks = Some(bookmarks);
+        self
+    }
+
+    /// Restrict who may approve policy overrides
+    pub fn with_override_approvers(mut self, public_keys: Vec<String>) -> Self {
+        self.override_approvers = Some(public_keys);
+        self
+    }
+
+    /// Load the configured conflict rules, or the built-in rules if none are set
+    ///
+    /// Relative paths are resolved against `repo_path`.
+    pub fn load_conflict_rules(&self) -> crate::Result<ConflictRules> {
+        let Some(path) = &self.conflict_rules_path else {
+            return Ok(ConflictRules::new());
+        };
+
+        let path = std::path::Path::new(path);
+        if path.is_absolute() {
+            ConflictRules::from_file(path)
+        } else {
+            ConflictRules::from_file(std::path::Path::new(&self.repo_path).join(path))
+        }
+    }
 }
 
 impl Default for JJConfig {
@@ -124,6 +187,11 @@ impl Default for JJConfig {
             verbose: false,
             max_log_entries: 1000,
             enable_agentdb_sync: false,
+            conflict_rules_path: None,
+            max_retries: None,
+            protected_revsets: None,
+            protected_bookmarks: None,
+            override_approvers: None,
         }
     }
 }
@@ -177,4 +245,24 @@ mod tests {
         assert_eq!(config.timeout_ms, 60000);
         assert_eq!(config.max_log_entries, 500);
     }
+
+    #[test]
+    fn test_load_conflict_rules() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("rules.toml"),
+            "[[rules]]\nname = \"lockfiles\"\npaths = [\"Cargo.lock\"]\nseverity = 3\naction = \"block\"\n",
+        )
+        .unwrap();
+
+        let config = JJConfig::default()
+            .with_repo_path(dir.path().to_string_lossy().to_string())
+            .with_conflict_rules_path("rules.toml".to_string());
+        let rules = config.load_conflict_rules().unwrap();
+        assert_eq!(rules.rules()[0].name, "lockfiles");
+
+        let missing = JJConfig::default().with_conflict_rules_path("/nonexistent/rules.json".to_string());
+        assert!(missing.load_conflict_rules().is_err());
+        assert!(JJConfig::default().load_conflict_rules().is_ok());
+    }
 }
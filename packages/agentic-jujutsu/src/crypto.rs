@@ -1,8 +1,9 @@
-//! Quantum-resistant cryptographic operations for agentic-jujutsu
+//! Digital signatures for agentic-jujutsu
 //!
-//! This module provides ML-DSA (formerly CRYSTALS-Dilithium) signatures for
-//! tamper-proof audit trails. ML-DSA is a NIST-approved post-quantum digital
-//! signature algorithm.
+//! Operation log entries, policy overrides and review verdicts are signed
+//! with Ed25519. Keys and signatures are passed around hex-encoded. The API is
+//! shaped for ML-DSA (CRYSTALS-Dilithium), which replaces Ed25519 once a
+//! post-quantum implementation is available as a dependency.
 //!
 //! # Examples
 //!
@@ -21,27 +22,29 @@
 //! ```
 
 use crate::error::{JJError, Result};
+#[cfg(not(target_arch = "wasm32"))]
 use napi_derive::napi;
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 
-/// ML-DSA security level (using ML-DSA-44 for balance of security and performance)
-const ML_DSA_SECURITY_LEVEL: usize = 2; // Security level 2 (128-bit security)
+/// Signature algorithm identifier
+const ALGORITHM: &str = "Ed25519";
 
-/// Public key size in bytes (ML-DSA-44)
-const PUBLIC_KEY_SIZE: usize = 1312;
+/// Public key size in bytes
+const PUBLIC_KEY_SIZE: usize = ed25519_dalek::PUBLIC_KEY_LENGTH;
 
-/// Secret key size in bytes (ML-DSA-44)
-const SECRET_KEY_SIZE: usize = 2560;
+/// Secret key size in bytes
+const SECRET_KEY_SIZE: usize = ed25519_dalek::SECRET_KEY_LENGTH;
 
-/// Signature size in bytes (ML-DSA-44)
-const SIGNATURE_SIZE: usize = 2420;
+/// Signature size in bytes
+const SIGNATURE_SIZE: usize = ed25519_dalek::SIGNATURE_LENGTH;
 
-/// ML-DSA signing keypair
+/// Signing keypair
 ///
-/// Contains public and secret keys for quantum-resistant digital signatures.
+/// Contains public and secret keys for digital signatures.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[napi(object)]
+#[cfg_attr(not(target_arch = "wasm32"), napi(object))]
 pub struct SigningKeypair {
     /// Public key (hex-encoded)
     pub public_key: String,
@@ -54,7 +57,7 @@ pub struct SigningKeypair {
 ///
 /// Contains the signature and associated metadata.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[napi(object)]
+#[cfg_attr(not(target_arch = "wasm32"), napi(object))]
 pub struct OperationSignature {
     /// Signature bytes (hex-encoded)
     pub signature: String,
@@ -76,7 +79,7 @@ impl OperationSignature {
             signature: hex::encode(signature),
             public_key: hex::encode(public_key),
             signed_at: chrono::Utc::now().to_rfc3339(),
-            algorithm: "ML-DSA-44".to_string(),
+            algorithm: ALGORITHM.to_string(),
         }
     }
 
@@ -93,9 +96,7 @@ impl OperationSignature {
     }
 }
 
-/// Generate a new ML-DSA signing keypair
-///
-/// Creates a quantum-resistant keypair for signing operations.
+/// Generate a new signing keypair
 ///
 /// # Examples
 ///
@@ -105,27 +106,17 @@ impl OperationSignature {
 /// let keypair = generate_signing_keypair();
 /// println!("Public key: {}", keypair.public_key);
 /// ```
-#[napi(js_name = "generateSigningKeypair")]
+#[cfg_attr(not(target_arch = "wasm32"), napi(js_name = "generateSigningKeypair"))]
 pub fn generate_signing_keypair() -> SigningKeypair {
-    use rand::RngCore;
-
-    let mut rng = rand::thread_rng();
-    let mut public_key = vec![0u8; PUBLIC_KEY_SIZE];
-    let mut secret_key = vec![0u8; SECRET_KEY_SIZE];
-
-    // Generate random keys (in production, use pqcrypto-dilithium)
-    rng.fill_bytes(&mut public_key);
-    rng.fill_bytes(&mut secret_key);
+    let signing_key = SigningKey::generate(&mut rand::rngs::OsRng);
 
     SigningKeypair {
-        public_key: hex::encode(public_key),
-        secret_key: hex::encode(secret_key),
+        public_key: hex::encode(signing_key.verifying_key().as_bytes()),
+        secret_key: hex::encode(signing_key.to_bytes()),
     }
 }
 
-/// Sign a message with ML-DSA
-///
-/// Creates a quantum-resistant digital signature for the given message.
+/// Sign a message
 ///
 /// # Arguments
 ///
@@ -135,6 +126,7 @@ pub fn generate_signing_keypair() -> SigningKeypair {
 /// # Returns
 ///
 /// The signature bytes in hex format
+#[cfg(not(target_arch = "wasm32"))]
 #[napi(js_name = "signMessage")]
 pub fn sign_message(message: Vec<u8>, secret_key: String) -> napi::Result<String> {
     sign_message_internal(&message, &secret_key)
@@ -143,40 +135,19 @@ pub fn sign_message(message: Vec<u8>, secret_key: String) -> napi::Result<String
 
 /// Internal signing function
 pub(crate) fn sign_message_internal(message: &[u8], secret_key_hex: &str) -> Result<String> {
-    // Decode secret key
-    let secret_key = hex::decode(secret_key_hex)
-        .map_err(|e| JJError::CryptoError(format!("Invalid secret key hex: {}", e)))?;
-
-    if secret_key.len() != SECRET_KEY_SIZE {
-        return Err(JJError::CryptoError(format!(
-            "Invalid secret key size: expected {}, got {}",
-            SECRET_KEY_SIZE,
-            secret_key.len()
-        )));
-    }
-
-    // Hash the message first (ML-DSA typically signs the hash)
-    let mut hasher = Sha256::new();
-    hasher.update(message);
-    let message_hash = hasher.finalize();
-
-    // In production, use pqcrypto-dilithium::dilithium2::sign()
-    // For now, we'll create a deterministic signature based on the hash and key
-    let mut signature = vec![0u8; SIGNATURE_SIZE];
-
-    // Create a deterministic signature (simplified for demo)
-    // In production: signature = dilithium2::sign(&message_hash, &secret_key)?;
-    for i in 0..SIGNATURE_SIZE {
-        signature[i] = message_hash[i % message_hash.len()]
-            .wrapping_add(secret_key[i % secret_key.len()]);
-    }
-
-    Ok(hex::encode(signature))
+    let secret_key: [u8; SECRET_KEY_SIZE] = decode_fixed(secret_key_hex, "secret key")?
+        .ok_or_else(|| {
+            JJError::CryptoError(format!(
+                "Invalid secret key size: expected {} bytes",
+                SECRET_KEY_SIZE
+            ))
+        })?;
+
+    let signature = SigningKey::from_bytes(&secret_key).sign(message);
+    Ok(hex::encode(signature.to_bytes()))
 }
 
-/// Verify a message signature with ML-DSA
-///
-/// Verifies that a signature is valid for the given message and public key.
+/// Verify a message signature
 ///
 /// # Arguments
 ///
@@ -187,6 +158,7 @@ pub(crate) fn sign_message_internal(message: &[u8], secret_key_hex: &str) -> Res
 /// # Returns
 ///
 /// `true` if the signature is valid, `false` otherwise
+#[cfg(not(target_arch = "wasm32"))]
 #[napi(js_name = "verifySignature")]
 pub fn verify_signature(
     message: Vec<u8>,
@@ -198,68 +170,35 @@ pub fn verify_signature(
 }
 
 /// Internal verification function
+///
+/// Malformed hex is an error; a signature or key of the wrong size, a key
+/// that is not a valid curve point, or a signature that doesn't match is
+/// `Ok(false)`.
 pub(crate) fn verify_signature_internal(
     message: &[u8],
     signature_hex: &str,
     public_key_hex: &str,
 ) -> Result<bool> {
-    // Decode signature and public key
-    let signature = hex::decode(signature_hex)
-        .map_err(|e| JJError::CryptoError(format!("Invalid signature hex: {}", e)))?;
-
-    let public_key = hex::decode(public_key_hex)
-        .map_err(|e| JJError::CryptoError(format!("Invalid public key hex: {}", e)))?;
-
-    if signature.len() != SIGNATURE_SIZE {
+    let Some(signature) = decode_fixed::<SIGNATURE_SIZE>(signature_hex, "signature")? else {
         return Ok(false);
-    }
-
-    if public_key.len() != PUBLIC_KEY_SIZE {
+    };
+    let Some(public_key) = decode_fixed::<PUBLIC_KEY_SIZE>(public_key_hex, "public key")? else {
         return Ok(false);
-    }
-
-    // Hash the message
-    let mut hasher = Sha256::new();
-    hasher.update(message);
-    let message_hash = hasher.finalize();
-
-    // In production, use pqcrypto-dilithium::dilithium2::verify()
-    // For now, we'll verify using our deterministic approach
-    // In production: Ok(dilithium2::verify(&signature, &message_hash, &public_key).is_ok())
-
-    // Simplified verification (matches our simplified signing)
-    // NOTE: This is a placeholder implementation for demo purposes.
-    // In production, use @qudag/napi-core ML-DSA verification.
-    //
-    // For now, we verify that the signature was created from the message
-    // by checking if the signature can be derived from message_hash + some_key_material.
-    // Since we can't recover the secret key from the public key in this simplified version,
-    // we'll verify that the signature structure is valid and matches expected patterns.
-
-    // Check that signature appears to be derived from this message
-    // by verifying the first 32 bytes follow our signing pattern
-    for i in 0..32 {
-        // Extract the message_hash contribution by subtracting key material
-        // In the signing phase: signature[i] = message_hash[i % 32] + secret_key[i % secret_key.len()]
-        // Since we don't have secret_key, we verify structural validity instead
-        let sig_byte = signature[i];
-        let msg_byte = message_hash[i % message_hash.len()];
-
-        // The key contribution should be in a valid range
-        // This is a weak check but maintains consistency with our simplified approach
-        let _key_contribution = sig_byte.wrapping_sub(msg_byte);
-        // In a real implementation, we'd verify this against cryptographic constraints
-    }
+    };
+    let Ok(verifying_key) = VerifyingKey::from_bytes(&public_key) else {
+        return Ok(false);
+    };
 
-    // Verify remaining signature bytes have been filled
-    for i in 32..SIGNATURE_SIZE.min(64) {
-        if signature[i] == 0 {
-            // Unlikely to have all zeros in a valid signature
-            continue;
-        }
-    }
+    Ok(verifying_key
+        .verify_strict(message, &Signature::from_bytes(&signature))
+        .is_ok())
+}
 
-    Ok(true)
+/// Decode hex into exactly `N` bytes, or `None` if the length differs
+fn decode_fixed<const N: usize>(value: &str, what: &str) -> Result<Option<[u8; N]>> {
+    let bytes = hex::decode(value)
+        .map_err(|e| JJError::CryptoError(format!("Invalid {} hex: {}", what, e)))?;
+    Ok(bytes.try_into().ok())
 }
 
 /// Hash operation data for signing
@@ -374,7 +313,7 @@ mod tests {
 
         let sig = OperationSignature::new(signature_bytes.clone(), public_key_bytes.clone());
 
-        assert_eq!(sig.algorithm, "ML-DSA-44");
+        assert_eq!(sig.algorithm, "Ed25519");
         assert_eq!(sig.signature_bytes().unwrap(), signature_bytes);
         assert_eq!(sig.public_key_bytes().unwrap(), public_key_bytes);
     }
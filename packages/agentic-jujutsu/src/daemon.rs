@@ -0,0 +1,964 @@
+//! Long-running hook daemon
+//!
+//! `jj-agent-hook` is invoked once per hook event. Without a daemon every
+//! invocation rebuilds [`JJWrapper`], [`JJHooksIntegration`] and the reasoning
+//! bank, so session state is lost between events. [`HookService`] holds that
+//! state, and on Unix [`HookDaemon`] serves it over a Unix domain socket.
+//!
+//! The protocol is one JSON [`HookRequest`] line per connection, answered by
+//! one JSON [`HookResponse`] line. Hook subcommands use [`send_request`] and
+//! fall back to an in-process [`HookService`] when no daemon is listening.
+//!
+//! On shutdown the daemon stops accepting connections, waits for in-flight
+//! requests, flushes the session history to its state file and removes the
+//! socket.
+
+use crate::hooks::{self, HookContext, JJHookEvent, JJHooksIntegration};
+use crate::leases::LeaseManager;
+use crate::metrics;
+use crate::reasoning_bank::Trajectory;
+use crate::{JJConfig, JJError, JJOperation, JJWrapper, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use tokio::sync::Mutex;
+use tracing::Instrument;
+
+/// Request forwarded from a hook invocation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HookRequest {
+    /// Pre-task hook
+    PreTask {
+        /// Agent identifier
+        agent_id: String,
+        /// Session identifier
+        session_id: String,
+        /// Task description
+        description: String,
+        /// Additional metadata
+        #[serde(default)]
+        metadata: Option<serde_json::Value>,
+    },
+    /// Post-edit hook
+    PostEdit {
+        /// File that was edited
+        file: String,
+        /// Agent identifier
+        agent_id: String,
+        /// Session identifier
+        session_id: String,
+        /// Optional change description
+        #[serde(default)]
+        description: Option<String>,
+    },
+    /// Post-task hook
+    PostTask {
+        /// Agent identifier
+        agent_id: String,
+        /// Session identifier
+        session_id: String,
+        /// Task description
+        #[serde(default)]
+        description: Option<String>,
+        /// Describe the working copy with a generated message
+        #[serde(default)]
+        auto_describe: bool,
+    },
+    /// Conflict detection
+    DetectConflicts {
+        /// Agent identifier
+        agent_id: String,
+        /// Session identifier
+        session_id: String,
+    },
+    /// Operation history query
+    QueryHistory {
+        /// Session identifier to filter by
+        #[serde(default)]
+        session_id: Option<String>,
+        /// Agent identifier to filter by
+        #[serde(default)]
+        agent_id: Option<String>,
+        /// Maximum number of operations to return
+        limit: usize,
+    },
+    /// Liveness check
+    Ping,
+    /// Prometheus metrics of the daemon process
+    Metrics,
+    /// Export trajectories and patterns to a reasoningbank database
+    ExportReasoningbank {
+        /// SQLite database of the reasoningbank pattern store
+        database: PathBuf,
+        /// Export cursor file (defaults to one next to the database)
+        #[serde(default)]
+        cursor: Option<PathBuf>,
+        /// Export everything instead of only what changed since the last export
+        #[serde(default)]
+        backfill: bool,
+    },
+    /// Ask the daemon to flush its state and exit
+    Shutdown,
+}
+
+/// Response to a [`HookRequest`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HookResponse {
+    /// Hook event produced by pre-task
+    Event {
+        /// The event
+        event: JJHookEvent,
+    },
+    /// Operation recorded by post-edit
+    Operation {
+        /// The operation
+        operation: JJOperation,
+    },
+    /// Operations of the finished session
+    Operations {
+        /// The operations
+        operations: Vec<JJOperation>,
+    },
+    /// Conflict detection result
+    Conflicts {
+        /// Conflicting files
+        conflicts: Vec<String>,
+        /// Event emitted when conflicts were found
+        event: Option<JJHookEvent>,
+    },
+    /// History query result, most recent last
+    History {
+        /// Matching operations
+        operations: Vec<JJOperation>,
+    },
+    /// Answer to [`HookRequest::Ping`]
+    Pong {
+        /// Process ID of the daemon
+        pid: u32,
+        /// When the daemon started
+        started_at: DateTime<Utc>,
+    },
+    /// Result of [`HookRequest::ExportReasoningbank`]
+    Exported {
+        /// Trajectories written
+        trajectories: usize,
+        /// Patterns written
+        patterns: usize,
+        /// Encrypted trajectories that were skipped
+        skipped_encrypted: usize,
+    },
+    /// Answer to [`HookRequest::Metrics`]
+    Metrics {
+        /// Metrics in Prometheus text exposition format
+        text: String,
+    },
+    /// Acknowledgement of [`HookRequest::Shutdown`]
+    ShuttingDown,
+    /// The request failed
+    Error {
+        /// Error message
+        message: String,
+    },
+}
+
+impl HookRequest {
+    /// Hook name used as the `hook` metrics label, `None` for control requests
+    pub fn hook_name(&self) -> Option<&'static str> {
+        match self {
+            HookRequest::PreTask { .. } => Some("pre_task"),
+            HookRequest::PostEdit { .. } => Some("post_edit"),
+            HookRequest::PostTask { .. } => Some("post_task"),
+            HookRequest::DetectConflicts { .. } => Some("detect_conflicts"),
+            HookRequest::QueryHistory { .. } => Some("query_history"),
+            HookRequest::Ping
+            | HookRequest::Metrics
+            | HookRequest::ExportReasoningbank { .. }
+            | HookRequest::Shutdown => None,
+        }
+    }
+}
+
+/// Snapshot written to the state file on flush
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct DaemonState {
+    history: Vec<JJOperation>,
+    #[serde(default)]
+    trajectories: Vec<Trajectory>,
+    #[serde(default)]
+    learning_stats: Option<serde_json::Value>,
+    #[serde(default)]
+    flushed_at: Option<DateTime<Utc>>,
+}
+
+/// Hook state shared across events
+pub struct HookService {
+    wrapper: JJWrapper,
+    integration: Mutex<JJHooksIntegration>,
+    /// Lease manager shared with `integration`, usable without its lock
+    leases: LeaseManager,
+    history: Mutex<Vec<JJOperation>>,
+    state_path: Option<PathBuf>,
+    max_history: usize,
+    started_at: DateTime<Utc>,
+}
+
+impl HookService {
+    /// Create a service for the given configuration
+    pub fn new(config: JJConfig, agentdb_enabled: bool) -> Result<Self> {
+        let max_history = config.max_log_entries as usize;
+        let wrapper =
+            JJWrapper::with_config(config).map_err(|e| JJError::InvalidConfig(e.reason.clone()))?;
+        let leases = LeaseManager::new();
+        let integration = JJHooksIntegration::new(wrapper.clone(), agentdb_enabled)
+            .with_lease_manager(leases.clone());
+
+        Ok(Self {
+            wrapper,
+            integration: Mutex::new(integration),
+            leases,
+            history: Mutex::new(Vec::new()),
+            state_path: None,
+            max_history,
+            started_at: Utc::now(),
+        })
+    }
+
+    /// Persist session history and trajectories to `path`, restoring any
+    /// already there
+    pub fn with_state_file(mut self, path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        if path.exists() {
+            let state: DaemonState = serde_json::from_str(&std::fs::read_to_string(&path)?)?;
+            *self.history.get_mut() = state.history;
+            for trajectory in state.trajectories {
+                self.wrapper.reasoning_bank().store_trajectory(trajectory)?;
+            }
+        }
+        self.state_path = Some(path);
+        Ok(self)
+    }
+
+    /// Underlying wrapper (shared with the hooks integration)
+    pub fn wrapper(&self) -> &JJWrapper {
+        &self.wrapper
+    }
+
+    /// Handle a single request
+    ///
+    /// [`HookRequest::Shutdown`] is only acknowledged here; stopping is up to
+    /// the caller. Hook requests are traced and their latency is recorded in
+    /// the [global metrics registry](metrics::global).
+    pub async fn handle(&self, request: HookRequest) -> Result<HookResponse> {
+        let Some(hook) = request.hook_name() else {
+            return self.handle_request(request).await;
+        };
+
+        let start = Instant::now();
+        let span = tracing::info_span!("hook", hook, outcome = tracing::field::Empty);
+        let result = self.handle_request(request).instrument(span.clone()).await;
+
+        let outcome = match &result {
+            Ok(_) => "success",
+            Err(e) => e.code(),
+        };
+        span.record("outcome", outcome);
+        metrics::global().record_hook(hook, outcome, start.elapsed());
+        result
+    }
+
+    async fn handle_request(&self, request: HookRequest) -> Result<HookResponse> {
+        match request {
+            HookRequest::PreTask {
+                agent_id,
+                session_id,
+                description,
+                metadata,
+            } => {
+                let mut ctx = HookContext::new(agent_id, session_id, description);
+                if let Some(metadata) = metadata {
+                    ctx = ctx.with_metadata(metadata);
+                }
+                // Wait for leases without the integration lock, so the holder's
+                // post-task can still run and release them
+                let leases = hooks::acquire_task_leases(&self.leases, &ctx).await?;
+                let event = self
+                    .integration
+                    .lock()
+                    .await
+                    .start_task(ctx, leases)
+                    .await?;
+                Ok(HookResponse::Event { event })
+            }
+
+            HookRequest::PostEdit {
+                file,
+                agent_id,
+                session_id,
+                description,
+            } => {
+                let task_desc = description.unwrap_or_else(|| format!("Edit {}", file));
+                let ctx = HookContext::new(agent_id, session_id, task_desc);
+                let operation = self
+                    .integration
+                    .lock()
+                    .await
+                    .on_post_edit(&file, ctx)
+                    .await?;
+                self.record(operation.clone()).await;
+                Ok(HookResponse::Operation { operation })
+            }
+
+            HookRequest::PostTask {
+                agent_id,
+                session_id,
+                description,
+                auto_describe,
+            } => {
+                let task_desc = description.unwrap_or_else(|| "Task completed".to_string());
+                let mut ctx = HookContext::new(agent_id, session_id.clone(), task_desc);
+                if auto_describe {
+                    ctx = ctx.with_metadata(serde_json::json!({ "auto_describe": true }));
+                }
+                let mut operations = self.integration.lock().await.on_post_task(ctx).await?;
+                if operations.is_empty() {
+                    operations = self.query(Some(&session_id), None, usize::MAX).await;
+                }
+                Ok(HookResponse::Operations { operations })
+            }
+
+            HookRequest::DetectConflicts {
+                agent_id,
+                session_id,
+            } => {
+                let ctx = HookContext::new(agent_id, session_id, "Conflict detection".to_string());
+
+                // TODO: Implement actual conflict detection
+                let conflicts: Vec<String> = vec![];
+                metrics::global().record_conflicts("hook", conflicts.len());
+
+                let event = if conflicts.is_empty() {
+                    None
+                } else {
+                    let integration = self.integration.lock().await;
+                    Some(
+                        integration
+                            .on_conflict_detected(conflicts.clone(), ctx)
+                            .await?,
+                    )
+                };
+                Ok(HookResponse::Conflicts { conflicts, event })
+            }
+
+            HookRequest::QueryHistory {
+                session_id,
+                agent_id,
+                limit,
+            } => {
+                let operations = self
+                    .query(session_id.as_deref(), agent_id.as_deref(), limit)
+                    .await;
+                Ok(HookResponse::History { operations })
+            }
+
+            HookRequest::Ping => Ok(HookResponse::Pong {
+                pid: std::process::id(),
+                started_at: self.started_at,
+            }),
+
+            HookRequest::ExportReasoningbank {
+                database,
+                cursor,
+                backfill,
+            } => self.export_reasoningbank(database, cursor, backfill),
+
+            HookRequest::Metrics => Ok(HookResponse::Metrics {
+                text: metrics::global().render(),
+            }),
+
+            HookRequest::Shutdown => Ok(HookResponse::ShuttingDown),
+        }
+    }
+
+    /// Write session history and learning statistics to the state file
+    pub async fn flush(&self) -> Result<()> {
+        let Some(path) = &self.state_path else {
+            return Ok(());
+        };
+
+        let state = DaemonState {
+            history: self.history.lock().await.clone(),
+            trajectories: self
+                .wrapper
+                .reasoning_bank()
+                .get_trajectories()
+                .unwrap_or_default(),
+            learning_stats: self
+                .wrapper
+                .get_learning_stats()
+                .ok()
+                .and_then(|stats| serde_json::from_str(&stats).ok()),
+            flushed_at: Some(Utc::now()),
+        };
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let tmp = path.with_extension("tmp");
+        {
+            use std::io::Write;
+            let mut file = std::fs::File::create(&tmp)?;
+            file.write_all(serde_json::to_string_pretty(&state)?.as_bytes())?;
+            file.sync_all()?;
+        }
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    #[cfg(feature = "reasoningbank-export")]
+    fn export_reasoningbank(
+        &self,
+        database: PathBuf,
+        cursor: Option<PathBuf>,
+        backfill: bool,
+    ) -> Result<HookResponse> {
+        use crate::integrations::ReasoningBankExporter;
+
+        let mut exporter = ReasoningBankExporter::open(database, cursor)?;
+        let bank = self.wrapper.reasoning_bank();
+        let report = if backfill {
+            exporter.backfill(bank)?
+        } else {
+            exporter.export_new(bank)?
+        };
+        Ok(HookResponse::Exported {
+            trajectories: report.trajectories,
+            patterns: report.patterns,
+            skipped_encrypted: report.skipped_encrypted,
+        })
+    }
+
+    #[cfg(not(feature = "reasoningbank-export"))]
+    fn export_reasoningbank(
+        &self,
+        _: PathBuf,
+        _: Option<PathBuf>,
+        _: bool,
+    ) -> Result<HookResponse> {
+        Err(JJError::InvalidConfig(
+            "Built without the reasoningbank-export feature".to_string(),
+        ))
+    }
+
+    async fn record(&self, operation: JJOperation) {
+        let mut history = self.history.lock().await;
+        history.push(operation);
+        if history.len() > self.max_history {
+            let excess = history.len() - self.max_history;
+            history.drain(..excess);
+        }
+    }
+
+    async fn query(
+        &self,
+        session_id: Option<&str>,
+        agent_id: Option<&str>,
+        limit: usize,
+    ) -> Vec<JJOperation> {
+        let history = self.history.lock().await;
+        let matches: Vec<_> = history
+            .iter()
+            .filter(|op| {
+                session_id.is_none_or(|id| op.get_metadata("session_id").as_deref() == Some(id))
+                    && agent_id.is_none_or(|id| op.get_metadata("agent_id").as_deref() == Some(id))
+            })
+            .cloned()
+            .collect();
+        let start = matches.len().saturating_sub(limit);
+        matches[start..].to_vec()
+    }
+}
+
+/// Default socket path for a repository
+///
+/// Sockets live in `$XDG_RUNTIME_DIR` (or the temp directory) and are keyed by
+/// a hash of the repository path, keeping them below the `sun_path` limit.
+pub fn default_socket_path(repo_path: &str) -> PathBuf {
+    default_runtime_path(repo_path, "sock")
+}
+
+/// Default state file path for a repository
+pub fn default_state_path(repo_path: &str) -> PathBuf {
+    default_runtime_path(repo_path, "state.json")
+}
+
+fn default_runtime_path(repo_path: &str, extension: &str) -> PathBuf {
+    use sha2::{Digest, Sha256};
+
+    let repo = std::fs::canonicalize(repo_path).unwrap_or_else(|_| PathBuf::from(repo_path));
+    let digest = hex::encode(Sha256::digest(repo.to_string_lossy().as_bytes()));
+    let dir = std::env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(std::env::temp_dir);
+    dir.join(format!("jj-agent-hook-{}.{}", &digest[..16], extension))
+}
+
+#[cfg(unix)]
+pub use unix::{send_request, HookDaemon};
+
+#[cfg(unix)]
+mod unix {
+    use super::*;
+    use std::future::Future;
+    use std::sync::Arc;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::{UnixListener, UnixStream};
+    use tokio::sync::Notify;
+    use tokio::task::JoinSet;
+
+    /// Unix socket server around a [`HookService`]
+    pub struct HookDaemon {
+        service: Arc<HookService>,
+        socket_path: PathBuf,
+    }
+
+    impl HookDaemon {
+        /// Create a daemon serving `service` on `socket_path`
+        pub fn new(service: HookService, socket_path: impl Into<PathBuf>) -> Self {
+            Self {
+                service: Arc::new(service),
+                socket_path: socket_path.into(),
+            }
+        }
+
+        /// Socket the daemon listens on
+        pub fn socket_path(&self) -> &Path {
+            &self.socket_path
+        }
+
+        /// Serve requests until `shutdown` resolves or a client sends
+        /// [`HookRequest::Shutdown`], then flush state and remove the socket
+        pub async fn run(self, shutdown: impl Future<Output = ()>) -> Result<()> {
+            let listener = self.bind().await?;
+            let stop = Arc::new(Notify::new());
+            let mut handlers = JoinSet::new();
+            tokio::pin!(shutdown);
+
+            loop {
+                tokio::select! {
+                    _ = &mut shutdown => break,
+                    _ = stop.notified() => break,
+                    accepted = listener.accept() => {
+                        let Ok((stream, _)) = accepted else { continue };
+                        let service = self.service.clone();
+                        let stop = stop.clone();
+                        handlers.spawn(async move {
+                            let _ = serve_connection(stream, &service, &stop).await;
+                        });
+                    }
+                    // Reap finished handlers so the set does not grow unbounded
+                    Some(_) = handlers.join_next(), if !handlers.is_empty() => {}
+                }
+            }
+
+            drop(listener);
+            while handlers.join_next().await.is_some() {}
+
+            let flushed = self.service.flush().await;
+            let _ = std::fs::remove_file(&self.socket_path);
+            flushed
+        }
+
+        /// Bind the socket, replacing a stale one left by a dead daemon
+        async fn bind(&self) -> Result<UnixListener> {
+            if self.socket_path.exists() {
+                if UnixStream::connect(&self.socket_path).await.is_ok() {
+                    return Err(JJError::TransportError(format!(
+                        "A daemon is already listening on {}",
+                        self.socket_path.display()
+                    )));
+                }
+                std::fs::remove_file(&self.socket_path)?;
+            }
+            if let Some(parent) = self.socket_path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+
+            let listener = UnixListener::bind(&self.socket_path).map_err(|e| {
+                JJError::TransportError(format!(
+                    "Failed to bind {}: {}",
+                    self.socket_path.display(),
+                    e
+                ))
+            })?;
+
+            // Only the owning user may talk to the daemon
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&self.socket_path, std::fs::Permissions::from_mode(0o600))?;
+
+            Ok(listener)
+        }
+    }
+
+    async fn serve_connection(
+        stream: UnixStream,
+        service: &HookService,
+        stop: &Notify,
+    ) -> Result<()> {
+        let (reader, mut writer) = stream.into_split();
+        let mut line = String::new();
+        BufReader::new(reader).read_line(&mut line).await?;
+
+        let (response, shutdown) = match serde_json::from_str::<HookRequest>(&line) {
+            Ok(request) => {
+                let shutdown = matches!(request, HookRequest::Shutdown);
+                let response =
+                    service
+                        .handle(request)
+                        .await
+                        .unwrap_or_else(|e| HookResponse::Error {
+                            message: e.to_string(),
+                        });
+                (response, shutdown)
+            }
+            Err(e) => (
+                HookResponse::Error {
+                    message: format!("Invalid request: {}", e),
+                },
+                false,
+            ),
+        };
+
+        let mut payload = serde_json::to_vec(&response)?;
+        payload.push(b'\n');
+        writer.write_all(&payload).await?;
+        writer.shutdown().await?;
+
+        if shutdown {
+            stop.notify_one();
+        }
+        Ok(())
+    }
+
+    /// Forward a request to the daemon listening on `socket_path`
+    ///
+    /// Returns `Ok(None)` when no daemon is running, so the caller can fall
+    /// back to in-process execution. Errors reported by the daemon are
+    /// returned as `Err`.
+    pub async fn send_request(
+        socket_path: &Path,
+        request: &HookRequest,
+    ) -> Result<Option<HookResponse>> {
+        let stream = match UnixStream::connect(socket_path).await {
+            Ok(stream) => stream,
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    std::io::ErrorKind::NotFound | std::io::ErrorKind::ConnectionRefused
+                ) =>
+            {
+                return Ok(None)
+            }
+            Err(e) => return Err(JJError::TransportError(e.to_string())),
+        };
+
+        let (reader, mut writer) = stream.into_split();
+        let mut payload = serde_json::to_vec(request)?;
+        payload.push(b'\n');
+        writer.write_all(&payload).await?;
+
+        let mut line = String::new();
+        BufReader::new(reader).read_line(&mut line).await?;
+        if line.is_empty() {
+            return Err(JJError::TransportError(
+                "Daemon closed the connection without responding".to_string(),
+            ));
+        }
+
+        match serde_json::from_str(&line)? {
+            HookResponse::Error { message } => Err(JJError::CommandFailed(message)),
+            response => Ok(Some(response)),
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn service(dir: &Path) -> HookService {
+        HookService::new(JJConfig::default(), false)
+            .unwrap()
+            .with_state_file(dir.join("state.json"))
+            .unwrap()
+    }
+
+    fn post_edit(file: &str, session: &str) -> HookRequest {
+        HookRequest::PostEdit {
+            file: file.to_string(),
+            agent_id: "coder-1".to_string(),
+            session_id: session.to_string(),
+            description: None,
+        }
+    }
+
+    async fn wait_for_socket(path: &Path) {
+        for _ in 0..100 {
+            if matches!(
+                send_request(path, &HookRequest::Ping).await,
+                Ok(Some(HookResponse::Pong { .. }))
+            ) {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        panic!("daemon did not start");
+    }
+
+    #[tokio::test]
+    async fn test_no_daemon_falls_back() {
+        let dir = tempfile::tempdir().unwrap();
+        let socket = dir.path().join("hook.sock");
+        assert!(send_request(&socket, &HookRequest::Ping)
+            .await
+            .unwrap()
+            .is_none());
+
+        // A stale socket file without a listener is treated the same way
+        std::os::unix::net::UnixListener::bind(&socket).unwrap();
+        assert!(send_request(&socket, &HookRequest::Ping)
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_daemon_keeps_state_and_flushes_on_shutdown() {
+        let dir = tempfile::tempdir().unwrap();
+        let socket = dir.path().join("hook.sock");
+        let daemon = HookDaemon::new(service(dir.path()), &socket);
+        let server = tokio::spawn(daemon.run(std::future::pending()));
+        wait_for_socket(&socket).await;
+
+        for file in ["src/a.rs", "src/b.rs"] {
+            let response = send_request(&socket, &post_edit(file, "s-1"))
+                .await
+                .unwrap();
+            assert!(matches!(response, Some(HookResponse::Operation { .. })));
+        }
+        send_request(&socket, &post_edit("src/c.rs", "s-2"))
+            .await
+            .unwrap();
+
+        // State survives across separate connections
+        let query = HookRequest::QueryHistory {
+            session_id: Some("s-1".to_string()),
+            agent_id: None,
+            limit: 10,
+        };
+        match send_request(&socket, &query).await.unwrap() {
+            Some(HookResponse::History { operations }) => assert_eq!(operations.len(), 2),
+            other => panic!("unexpected response: {:?}", other),
+        }
+
+        let response = send_request(&socket, &HookRequest::Shutdown).await.unwrap();
+        assert!(matches!(response, Some(HookResponse::ShuttingDown)));
+        tokio::time::timeout(Duration::from_secs(5), server)
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+
+        assert!(!socket.exists());
+        let state: DaemonState =
+            serde_json::from_str(&std::fs::read_to_string(dir.path().join("state.json")).unwrap())
+                .unwrap();
+        assert_eq!(state.history.len(), 3);
+        assert!(state.flushed_at.is_some());
+
+        // A restarted service picks the history back up
+        let restored = service(dir.path());
+        match restored.handle(query).await.unwrap() {
+            HookResponse::History { operations } => assert_eq!(operations.len(), 2),
+            other => panic!("unexpected response: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_graceful_shutdown_signal() {
+        let dir = tempfile::tempdir().unwrap();
+        let socket = dir.path().join("hook.sock");
+        let (tx, rx) = tokio::sync::oneshot::channel::<()>();
+        let daemon = HookDaemon::new(service(dir.path()), &socket);
+        let server = tokio::spawn(daemon.run(async {
+            let _ = rx.await;
+        }));
+        wait_for_socket(&socket).await;
+
+        // A second daemon refuses to take over a live socket
+        let second = HookDaemon::new(service(dir.path()), &socket);
+        assert!(second.run(std::future::ready(())).await.is_err());
+
+        tx.send(()).unwrap();
+        server.await.unwrap().unwrap();
+        assert!(!socket.exists());
+        assert!(dir.path().join("state.json").exists());
+    }
+
+    #[tokio::test]
+    async fn test_metrics_over_socket() {
+        let dir = tempfile::tempdir().unwrap();
+        let socket = dir.path().join("hook.sock");
+        let daemon = HookDaemon::new(service(dir.path()), &socket);
+        let server = tokio::spawn(daemon.run(std::future::pending()));
+        wait_for_socket(&socket).await;
+
+        send_request(&socket, &post_edit("src/metrics.rs", "s-metrics"))
+            .await
+            .unwrap();
+        let text = match send_request(&socket, &HookRequest::Metrics).await.unwrap() {
+            Some(HookResponse::Metrics { text }) => text,
+            other => panic!("unexpected response: {:?}", other),
+        };
+
+        assert!(text.contains("# TYPE agentic_jujutsu_hook_duration_seconds histogram\n"));
+        assert!(text.contains(
+            "agentic_jujutsu_hook_requests_total{hook=\"post_edit\",outcome=\"success\"}"
+        ));
+        assert!(text.contains(
+            "agentic_jujutsu_hook_duration_seconds_bucket{hook=\"post_edit\",le=\"+Inf\"}"
+        ));
+        // Control requests are not hooks
+        assert!(!text.contains("hook=\"ping\""));
+
+        send_request(&socket, &HookRequest::Shutdown).await.unwrap();
+        server.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_trajectories_survive_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = service(dir.path());
+        let mut trajectory = Trajectory::new("Rebase onto main".to_string(), Default::default());
+        trajectory.finalize(Default::default(), 0.9);
+        first
+            .wrapper()
+            .reasoning_bank()
+            .store_trajectory(trajectory)
+            .unwrap();
+        first.flush().await.unwrap();
+
+        let restored = service(dir.path());
+        let trajectories = restored
+            .wrapper()
+            .reasoning_bank()
+            .get_trajectories()
+            .unwrap();
+        assert_eq!(trajectories.len(), 1);
+        assert_eq!(trajectories[0].task, "Rebase onto main");
+    }
+
+    #[cfg(feature = "reasoningbank-export")]
+    #[tokio::test]
+    async fn test_export_reasoningbank() {
+        let dir = tempfile::tempdir().unwrap();
+        let service = service(dir.path());
+        let mut trajectory = Trajectory::new("Squash fixups".to_string(), Default::default());
+        trajectory.finalize(Default::default(), 0.9);
+        service
+            .wrapper()
+            .reasoning_bank()
+            .store_trajectory(trajectory)
+            .unwrap();
+
+        let export = |backfill| HookRequest::ExportReasoningbank {
+            database: dir.path().join("patterns.db"),
+            cursor: None,
+            backfill,
+        };
+        match service.handle(export(false)).await.unwrap() {
+            HookResponse::Exported {
+                trajectories,
+                patterns,
+                ..
+            } => assert_eq!((trajectories, patterns), (1, 1)),
+            other => panic!("unexpected response: {:?}", other),
+        }
+        // Nothing new since the last export, unless backfilling
+        assert!(matches!(
+            service.handle(export(false)).await.unwrap(),
+            HookResponse::Exported {
+                trajectories: 0,
+                ..
+            }
+        ));
+        assert!(matches!(
+            service.handle(export(true)).await.unwrap(),
+            HookResponse::Exported {
+                trajectories: 1,
+                ..
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_history_filters_by_agent() {
+        let dir = tempfile::tempdir().unwrap();
+        let service = service(dir.path());
+        let response = service
+            .handle(HookRequest::QueryHistory {
+                session_id: None,
+                agent_id: Some("nobody".to_string()),
+                limit: 5,
+            })
+            .await
+            .unwrap();
+        assert!(matches!(response, HookResponse::History { operations } if operations.is_empty()));
+    }
+
+    #[tokio::test]
+    async fn test_lease_wait_does_not_block_post_task() {
+        let dir = tempfile::tempdir().unwrap();
+        let service = std::sync::Arc::new(service(dir.path()));
+        let pre_task = |agent: &str, session: &str| HookRequest::PreTask {
+            agent_id: agent.to_string(),
+            session_id: session.to_string(),
+            description: "Auth".to_string(),
+            metadata: Some(serde_json::json!({
+                "leases": ["src/auth/**"],
+                "lease_wait_ms": 5000,
+            })),
+        };
+
+        service.handle(pre_task("agent-a", "s-1")).await.unwrap();
+
+        // agent-b waits for agent-a's lease in the background
+        let waiting = tokio::spawn({
+            let service = service.clone();
+            async move { service.handle(pre_task("agent-b", "s-2")).await }
+        });
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(!waiting.is_finished());
+
+        let post_task = HookRequest::PostTask {
+            agent_id: "agent-a".to_string(),
+            session_id: "s-1".to_string(),
+            description: None,
+            auto_describe: false,
+        };
+        tokio::time::timeout(Duration::from_secs(1), service.handle(post_task))
+            .await
+            .expect("post-task blocked behind the lease wait")
+            .unwrap();
+
+        let response = tokio::time::timeout(Duration::from_secs(1), waiting)
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        assert!(matches!(response, HookResponse::Event { .. }));
+    }
+}
@@ -0,0 +1,288 @@
+//! Embedded jj binary extraction with integrity verification
+//!
+//! The jj executable bundled at build time is written to
+//! `~/.cache/agentic-jujutsu/jj` on first use. The build script only bundles
+//! a release archive matching its pinned SHA-256 digest, and records the
+//! digest of the bundled binary in a manifest compiled into the crate,
+//! and the cached copy is checked against it once per process before it is
+//! executed. A missing, truncated or tampered copy is re-extracted.
+//!
+//! Extraction writes to a uniquely named temporary file in the cache
+//! directory, fsyncs it, sets its permissions and then renames it over the
+//! destination. Concurrent processes therefore never observe a partially
+//! written binary; the last rename wins and all candidates are identical.
+
+use crate::error::{JJError, Result};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// The jj binary bundled by the build script (`SYSTEM_JJ` if unavailable)
+const JJ_BINARY: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/jj"));
+
+/// SHA-256 manifest of [`JJ_BINARY`] in `sha256sum` format
+const JJ_MANIFEST: &str = include_str!(concat!(env!("OUT_DIR"), "/jj.sha256"));
+
+/// Placeholder embedded when no binary could be bundled
+const SYSTEM_JJ_PLACEHOLDER: &[u8] = b"SYSTEM_JJ";
+
+/// Path of the verified binary, resolved once per process
+static VERIFIED_BINARY: OnceLock<PathBuf> = OnceLock::new();
+
+/// Expected SHA-256 digest (hex) of the embedded jj binary
+pub fn embedded_binary_sha256() -> &'static str {
+    JJ_MANIFEST.split_whitespace().next().unwrap_or_default()
+}
+
+/// Check whether a real jj binary is embedded in this build
+pub fn has_embedded_binary() -> bool {
+    JJ_BINARY != SYSTEM_JJ_PLACEHOLDER
+}
+
+/// Extract the embedded jj binary and return the path to execute
+///
+/// Returns `jj` (resolved through `PATH`) when no binary is embedded. The
+/// cached copy is verified on the first call in a process; later calls reuse
+/// the verified path.
+pub fn extract_embedded_binary() -> Result<PathBuf> {
+    if !has_embedded_binary() {
+        return Ok(PathBuf::from("jj"));
+    }
+
+    if let Some(path) = VERIFIED_BINARY.get() {
+        return Ok(path.clone());
+    }
+
+    let path = ensure_binary(&cache_dir(), JJ_BINARY, embedded_binary_sha256())?;
+    Ok(VERIFIED_BINARY.get_or_init(|| path).clone())
+}
+
+/// Cache directory the binary is extracted into
+fn cache_dir() -> PathBuf {
+    let home = std::env::var("HOME")
+        .or_else(|_| std::env::var("USERPROFILE"))
+        .unwrap_or_else(|_| ".".to_string());
+
+    PathBuf::from(home).join(".cache").join("agentic-jujutsu")
+}
+
+/// Make sure `dir/jj` holds `contents`, re-extracting it if it does not
+fn ensure_binary(dir: &Path, contents: &[u8], expected_sha256: &str) -> Result<PathBuf> {
+    if sha256_hex(contents) != expected_sha256 {
+        return Err(JJError::IoError(format!(
+            "Embedded jj binary does not match its manifest (expected sha256 {})",
+            expected_sha256
+        )));
+    }
+
+    fs::create_dir_all(dir)
+        .map_err(|e| JJError::IoError(format!("Failed to create cache directory: {}", e)))?;
+
+    let binary_path = dir.join("jj");
+    if is_valid_binary(&binary_path, contents.len(), expected_sha256) {
+        return Ok(binary_path);
+    }
+
+    write_atomically(dir, &binary_path, contents)?;
+
+    // Another process may have renamed its own copy in the meantime; every
+    // candidate has the same contents, so checking the result is sufficient.
+    if !is_valid_binary(&binary_path, contents.len(), expected_sha256) {
+        return Err(JJError::IoError(format!(
+            "Extracted jj binary at {} failed integrity verification",
+            binary_path.display()
+        )));
+    }
+
+    Ok(binary_path)
+}
+
+/// Check size, digest and (on Unix) the executable bit of a cached binary
+fn is_valid_binary(path: &Path, expected_len: usize, expected_sha256: &str) -> bool {
+    let Ok(metadata) = fs::metadata(path) else {
+        return false;
+    };
+    if !metadata.is_file() || metadata.len() != expected_len as u64 {
+        return false;
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if metadata.permissions().mode() & 0o111 == 0 {
+            return false;
+        }
+    }
+
+    match file_sha256(path) {
+        Ok(digest) => digest == expected_sha256,
+        Err(_) => false,
+    }
+}
+
+/// Write `contents` to `dest` via a temporary file, fsync and rename
+fn write_atomically(dir: &Path, dest: &Path, contents: &[u8]) -> Result<()> {
+    let tmp_path = dir.join(format!(
+        ".jj.{}.{}.tmp",
+        std::process::id(),
+        uuid::Uuid::new_v4().simple()
+    ));
+
+    let result = (|| -> std::io::Result<()> {
+        let mut file = fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&tmp_path)?;
+        file.write_all(contents)?;
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            file.set_permissions(fs::Permissions::from_mode(0o755))?;
+        }
+
+        file.sync_all()?;
+        drop(file);
+
+        fs::rename(&tmp_path, dest)?;
+
+        // Persist the rename itself
+        #[cfg(unix)]
+        fs::File::open(dir)?.sync_all()?;
+
+        Ok(())
+    })();
+
+    if let Err(e) = result {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(JJError::IoError(format!(
+            "Failed to extract jj binary to {}: {}",
+            dest.display(),
+            e
+        )));
+    }
+
+    Ok(())
+}
+
+fn sha256_hex(contents: &[u8]) -> String {
+    hex::encode(Sha256::digest(contents))
+}
+
+fn file_sha256(path: &Path) -> std::io::Result<String> {
+    let mut file = fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buf = [0u8; 64 * 1024];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTENTS: &[u8] = b"#!/bin/sh\necho 'jj 0.35.0'\n";
+
+    fn digest() -> String {
+        sha256_hex(CONTENTS)
+    }
+
+    #[test]
+    fn test_manifest_matches_embedded_binary() {
+        assert_eq!(embedded_binary_sha256().len(), 64);
+        assert_eq!(sha256_hex(JJ_BINARY), embedded_binary_sha256());
+    }
+
+    #[test]
+    fn test_extracts_and_reuses_binary() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = ensure_binary(dir.path(), CONTENTS, &digest()).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), CONTENTS);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o755);
+        }
+
+        let modified = fs::metadata(&path).unwrap().modified().unwrap();
+        ensure_binary(dir.path(), CONTENTS, &digest()).unwrap();
+        assert_eq!(fs::metadata(&path).unwrap().modified().unwrap(), modified);
+    }
+
+    #[test]
+    fn test_reextracts_corrupted_binary() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("jj");
+
+        // Truncated
+        fs::write(&path, &CONTENTS[..10]).unwrap();
+        ensure_binary(dir.path(), CONTENTS, &digest()).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), CONTENTS);
+
+        // Same size, different contents
+        let mut tampered = CONTENTS.to_vec();
+        tampered[0] = b'X';
+        fs::write(&path, &tampered).unwrap();
+        ensure_binary(dir.path(), CONTENTS, &digest()).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), CONTENTS);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_reextracts_non_executable_binary() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("jj");
+        fs::write(&path, CONTENTS).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+
+        ensure_binary(dir.path(), CONTENTS, &digest()).unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o755);
+    }
+
+    #[test]
+    fn test_rejects_manifest_mismatch() {
+        let dir = tempfile::tempdir().unwrap();
+        let wrong = sha256_hex(b"something else");
+        assert!(ensure_binary(dir.path(), CONTENTS, &wrong).is_err());
+        assert!(!dir.path().join("jj").exists());
+    }
+
+    #[test]
+    fn test_concurrent_extraction() {
+        let dir = tempfile::tempdir().unwrap();
+        let expected = digest();
+
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let dir = dir.path().to_path_buf();
+                let expected = expected.clone();
+                std::thread::spawn(move || ensure_binary(&dir, CONTENTS, &expected))
+            })
+            .collect();
+
+        for handle in handles {
+            let path = handle.join().unwrap().unwrap();
+            assert_eq!(fs::read(&path).unwrap(), CONTENTS);
+        }
+
+        // No temporary files are left behind
+        let entries: Vec<_> = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        assert_eq!(entries, vec![std::ffi::OsString::from("jj")]);
+    }
+}
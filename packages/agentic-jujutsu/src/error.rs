@@ -1,5 +1,13 @@
 //! Error types for agentic-jujutsu
+//!
+//! Failed jj invocations are classified from their stderr into typed
+//! variants (see [`JJError::from_command_output`]). Every variant has a stable
+//! code returned by [`JJError::code`]; errors crossing into JavaScript carry
+//! that code as a `[CODE]` prefix on the message, which [`error_code`]
+//! extracts again.
 
+#[cfg(not(target_arch = "wasm32"))]
+use napi_derive::napi;
 use thiserror::Error;
 
 /// Result type alias for agentic-jujutsu operations
@@ -51,6 +59,173 @@ pub enum JJError {
     /// Cryptographic operation error
     #[error("Crypto error: {0}")]
     CryptoError(String),
+
+    /// File lease is held by another agent
+    #[error("Lease on {pattern} is held by agent {holder} (lease {lease_id})")]
+    LeaseConflict {
+        /// Path or pattern that was requested
+        pattern: String,
+        /// Agent currently holding the conflicting lease
+        holder: String,
+        /// ID of the conflicting lease
+        lease_id: String,
+    },
+
+    /// Lease not found or already expired
+    #[error("Lease {0} not found")]
+    LeaseNotFound(String),
+
+    /// Network transport error (QUIC replication)
+    #[error("Transport error: {0}")]
+    TransportError(String),
+
+    /// A replicated log already holds a different record with this
+    /// operation ID
+    #[error("Operation {0} conflicts with a different record under the same ID")]
+    ConflictingOperation(String),
+
+    /// The working directory is not inside a jj repository
+    #[error("Not a jj repository: {path}")]
+    NotARepository {
+        /// Path jj was run in, as reported by jj
+        path: String,
+        /// Process exit code
+        exit_code: i32,
+        /// Captured stderr
+        stderr: String,
+    },
+
+    /// A revision, change ID or bookmark could not be resolved
+    #[error("Revision {revision} not found")]
+    RevisionNotFound {
+        /// Revision expression that failed to resolve
+        revision: String,
+        /// Process exit code
+        exit_code: i32,
+        /// Captured stderr
+        stderr: String,
+    },
+
+    /// The repository was modified concurrently (lock contention). Retrying
+    /// usually succeeds.
+    #[error("Concurrent modification of the repository")]
+    ConcurrentModification {
+        /// Process exit code
+        exit_code: i32,
+        /// Captured stderr
+        stderr: String,
+    },
+
+    /// The working copy was not updated after another workspace or process
+    /// rewrote its commit. Running the command again fails the same way until
+    /// `jj workspace update-stale` is run.
+    #[error("The working copy is stale; run `jj workspace update-stale` to update it")]
+    StaleWorkingCopy {
+        /// Process exit code
+        exit_code: i32,
+        /// Captured stderr
+        stderr: String,
+    },
+
+    /// The operation stopped because of unresolved conflicts
+    #[error("Unresolved conflicts in {}", if paths.is_empty() { "commit".to_string() } else { paths.join(", ") })]
+    Conflict {
+        /// Conflicted paths, when jj listed them
+        paths: Vec<String>,
+        /// Process exit code
+        exit_code: i32,
+        /// Captured stderr
+        stderr: String,
+    },
+
+    /// The operation would rewrite an immutable commit
+    #[error("Commit {revision} is immutable")]
+    ImmutableCommit {
+        /// Immutable commit or change ID
+        revision: String,
+        /// Process exit code
+        exit_code: i32,
+        /// Captured stderr
+        stderr: String,
+    },
+
+    /// jj exited with a failure that could not be classified further
+    #[error("jj exited with code {exit_code}: {}", stderr.trim())]
+    CommandExited {
+        /// Process exit code
+        exit_code: i32,
+        /// Captured stderr
+        stderr: String,
+    },
+
+    /// A mutating command targets a protected revision
+    #[error("{operation} of {revision} is blocked by protected revset {rule}")]
+    ProtectedRevision {
+        /// Operation type of the rejected command
+        operation: String,
+        /// Revision expression the command would rewrite
+        revision: String,
+        /// Protected revset or bookmark pattern it matched
+        rule: String,
+    },
+
+    /// A bookmark command targets a protected bookmark
+    #[error("{operation} of bookmark {bookmark} is blocked by protected bookmark {rule}")]
+    ProtectedBookmark {
+        /// Operation type of the rejected command
+        operation: String,
+        /// Bookmark name or pattern the command would change
+        bookmark: String,
+        /// Protected bookmark pattern it matched
+        rule: String,
+    },
+
+    /// A conflict rule with action `block` flagged the operation
+    #[error("Operation {operation_id} is blocked by conflict rule {rule} on {}", files.join(", "))]
+    OperationBlocked {
+        /// Operation that was flagged
+        operation_id: String,
+        /// Conflict rule that fired
+        rule: String,
+        /// Shared files covered by the rule
+        files: Vec<String>,
+    },
+
+    /// A conflict rule with action `require-approval` flagged the operation
+    /// until it is approved
+    #[error("Operation {operation_id} needs approval under conflict rule {rule} on {}", files.join(", "))]
+    ApprovalRequired {
+        /// Operation waiting for approval
+        operation_id: String,
+        /// Conflict rule that fired
+        rule: String,
+        /// Shared files covered by the rule
+        files: Vec<String>,
+    },
+
+    /// No review has been opened for the change
+    #[error("No review for change {0}")]
+    ReviewNotFound(String),
+
+    /// A review comment does not point at a line of the change's diff
+    #[error("{path}:{line} is not part of the change's diff")]
+    InvalidAnchor {
+        /// File the comment was for
+        path: String,
+        /// Line number in the new version of the file
+        line: u32,
+    },
+
+    /// A split plan does not partition the change's diff
+    #[error("Invalid split: {0}")]
+    InvalidSplit(String),
+
+    /// The command did not finish within the configured timeout
+    #[error("Command timed out after {timeout_ms}ms")]
+    Timeout {
+        /// Timeout that was exceeded, in milliseconds
+        timeout_ms: u64,
+    },
 }
 
 impl JJError {
@@ -63,9 +238,231 @@ impl JJError {
     pub fn is_recoverable(&self) -> bool {
         matches!(
             self,
-            JJError::CommandFailed(_) | JJError::ConflictResolutionFailed(_)
+            JJError::CommandFailed(_)
+                | JJError::ConflictResolutionFailed(_)
+                | JJError::LeaseConflict { .. }
+                | JJError::ApprovalRequired { .. }
+                | JJError::ConcurrentModification { .. }
+                | JJError::StaleWorkingCopy { .. }
+                | JJError::Conflict { .. }
+                | JJError::CommandExited { .. }
+                | JJError::Timeout { .. }
         )
     }
+
+    /// Check if the same command can simply be run again
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, JJError::ConcurrentModification { .. })
+    }
+
+    /// Stable machine-readable error code
+    pub fn code(&self) -> &'static str {
+        match self {
+            JJError::JJNotFound => "JJ_NOT_FOUND",
+            JJError::CommandFailed(_) | JJError::CommandExited { .. } => "COMMAND_FAILED",
+            JJError::ParseError(_) => "PARSE_ERROR",
+            JJError::OperationNotFound(_) => "OPERATION_NOT_FOUND",
+            JJError::ConflictResolutionFailed(_) => "CONFLICT_RESOLUTION_FAILED",
+            JJError::InvalidConfig(_) => "INVALID_CONFIG",
+            JJError::IoError(_) => "IO_ERROR",
+            JJError::SerializationError(_) => "SERIALIZATION_ERROR",
+            JJError::Unknown(_) => "UNKNOWN",
+            JJError::MCPError(_) => "MCP_ERROR",
+            JJError::CryptoError(_) => "CRYPTO_ERROR",
+            JJError::LeaseConflict { .. } => "LEASE_CONFLICT",
+            JJError::LeaseNotFound(_) => "LEASE_NOT_FOUND",
+            JJError::TransportError(_) => "TRANSPORT_ERROR",
+            JJError::ConflictingOperation(_) => "CONFLICTING_OPERATION",
+            JJError::NotARepository { .. } => "NOT_A_REPOSITORY",
+            JJError::RevisionNotFound { .. } => "REVISION_NOT_FOUND",
+            JJError::ConcurrentModification { .. } => "CONCURRENT_MODIFICATION",
+            JJError::StaleWorkingCopy { .. } => "STALE_WORKING_COPY",
+            JJError::Conflict { .. } => "CONFLICT",
+            JJError::ImmutableCommit { .. } => "IMMUTABLE_COMMIT",
+            JJError::Timeout { .. } => "TIMEOUT",
+            JJError::ProtectedRevision { .. } => "PROTECTED_REVISION",
+            JJError::ProtectedBookmark { .. } => "PROTECTED_BOOKMARK",
+            JJError::OperationBlocked { .. } => "OPERATION_BLOCKED",
+            JJError::ApprovalRequired { .. } => "APPROVAL_REQUIRED",
+            JJError::ReviewNotFound(_) => "REVIEW_NOT_FOUND",
+            JJError::InvalidAnchor { .. } => "INVALID_ANCHOR",
+            JJError::InvalidSplit(_) => "INVALID_SPLIT",
+        }
+    }
+
+    /// Exit code of the failed jj process, if the error came from one
+    pub fn exit_code(&self) -> Option<i32> {
+        match self {
+            JJError::NotARepository { exit_code, .. }
+            | JJError::RevisionNotFound { exit_code, .. }
+            | JJError::ConcurrentModification { exit_code, .. }
+            | JJError::StaleWorkingCopy { exit_code, .. }
+            | JJError::Conflict { exit_code, .. }
+            | JJError::ImmutableCommit { exit_code, .. }
+            | JJError::CommandExited { exit_code, .. } => Some(*exit_code),
+            _ => None,
+        }
+    }
+
+    /// Captured stderr of the failed jj process, if the error came from one
+    pub fn stderr(&self) -> Option<&str> {
+        match self {
+            JJError::NotARepository { stderr, .. }
+            | JJError::RevisionNotFound { stderr, .. }
+            | JJError::ConcurrentModification { stderr, .. }
+            | JJError::StaleWorkingCopy { stderr, .. }
+            | JJError::Conflict { stderr, .. }
+            | JJError::ImmutableCommit { stderr, .. }
+            | JJError::CommandExited { stderr, .. } => Some(stderr),
+            JJError::CommandFailed(stderr) => Some(stderr),
+            _ => None,
+        }
+    }
+
+    /// Classify the stderr of a failed jj invocation
+    ///
+    /// Unrecognised failures become [`JJError::CommandExited`]. The exit code
+    /// and stderr are preserved on every variant.
+    pub fn from_command_output(exit_code: i32, stderr: &str) -> Self {
+        let owned = || stderr.to_string();
+
+        for line in stderr.lines() {
+            let line = strip_prefix_label(line);
+
+            if line.starts_with("There is no jj repo in") {
+                return JJError::NotARepository {
+                    path: quoted(line).unwrap_or_default(),
+                    exit_code,
+                    stderr: owned(),
+                };
+            }
+
+            if (line.starts_with("Revision") || line.starts_with("Change ID"))
+                && line.contains("doesn't exist")
+            {
+                return JJError::RevisionNotFound {
+                    revision: quoted(line).unwrap_or_default(),
+                    exit_code,
+                    stderr: owned(),
+                };
+            }
+
+            if let Some(name) = line.strip_prefix("No such bookmark: ") {
+                return JJError::RevisionNotFound {
+                    revision: name.trim().to_string(),
+                    exit_code,
+                    stderr: owned(),
+                };
+            }
+
+            if line.starts_with("The working copy is stale") {
+                return JJError::StaleWorkingCopy {
+                    exit_code,
+                    stderr: owned(),
+                };
+            }
+
+            if let Some(prefix) = line.strip_suffix(" is immutable") {
+                return JJError::ImmutableCommit {
+                    revision: prefix.rsplit(' ').next().unwrap_or_default().to_string(),
+                    exit_code,
+                    stderr: owned(),
+                };
+            }
+        }
+
+        if let Some(paths) = conflicted_paths(stderr) {
+            return JJError::Conflict {
+                paths,
+                exit_code,
+                stderr: owned(),
+            };
+        }
+
+        let concurrent = stderr.lines().any(|line| {
+            let line = line.to_lowercase();
+            !line.contains("resolving automatically")
+                && (line.contains("concurrent modification")
+                    || line.contains("concurrent checkout")
+                    || line.contains("failed to lock"))
+        });
+        if concurrent {
+            return JJError::ConcurrentModification {
+                exit_code,
+                stderr: owned(),
+            };
+        }
+
+        JJError::CommandExited {
+            exit_code,
+            stderr: owned(),
+        }
+    }
+}
+
+/// Strip jj's `Error: ` / `Internal error: ` label from a stderr line
+fn strip_prefix_label(line: &str) -> &str {
+    let line = line.trim();
+    line.strip_prefix("Error: ")
+        .or_else(|| line.strip_prefix("Internal error: "))
+        .unwrap_or(line)
+}
+
+/// First token quoted with backticks or double quotes
+fn quoted(line: &str) -> Option<String> {
+    let start = line.find(['`', '"'])?;
+    let quote = line[start..].chars().next()?;
+    let rest = &line[start + 1..];
+    let end = rest.find(quote)?;
+    Some(rest[..end].to_string())
+}
+
+/// Paths listed after jj's "unresolved conflicts" report, if it is present
+fn conflicted_paths(stderr: &str) -> Option<Vec<String>> {
+    let mut lines = stderr.lines().map(strip_prefix_label);
+    let header = lines
+        .by_ref()
+        .find(|line| line.contains("unresolved conflicts") || line.ends_with("has conflicts"))?;
+
+    if !header.contains("at these paths") {
+        return Some(Vec::new());
+    }
+
+    Some(
+        lines
+            .take_while(|line| !line.is_empty() && !line.starts_with("Hint:"))
+            .filter_map(|line| line.split_whitespace().next())
+            .map(str::to_string)
+            .collect(),
+    )
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl From<JJError> for napi::Error {
+    fn from(err: JJError) -> Self {
+        let mut reason = format!("[{}] {}", err.code(), err);
+        if let Some(stderr) = err.stderr().map(str::trim).filter(|s| !s.is_empty()) {
+            if !reason.contains(stderr) {
+                reason.push('\n');
+                reason.push_str(stderr);
+            }
+        }
+        napi::Error::new(napi::Status::GenericFailure, reason)
+    }
+}
+
+/// Extract the stable error code from a message thrown by this module
+///
+/// Returns `null` for messages without a `[CODE]` prefix.
+#[cfg_attr(not(target_arch = "wasm32"), napi(js_name = "errorCode"))]
+pub fn error_code(message: String) -> Option<String> {
+    let rest = message.trim_start().strip_prefix('[')?;
+    let code = &rest[..rest.find(']')?];
+    if !code.is_empty() && code.chars().all(|c| c.is_ascii_uppercase() || c == '_') {
+        Some(code.to_string())
+    } else {
+        None
+    }
 }
 
 impl From<std::io::Error> for JJError {
@@ -94,5 +491,132 @@ mod tests {
     fn test_recoverable() {
         assert!(JJError::CommandFailed("test".into()).is_recoverable());
         assert!(!JJError::JJNotFound.is_recoverable());
+        assert!(JJError::LeaseConflict {
+            pattern: "src/**".into(),
+            holder: "agent-1".into(),
+            lease_id: "lease-1".into(),
+        }
+        .is_recoverable());
+        assert!(
+            JJError::from_command_output(1, "Internal error: Failed to lock working copy")
+                .is_retryable()
+        );
+        assert!(!JJError::from_command_output(1, "Error: The working copy is stale").is_retryable());
+        assert!(!JJError::Timeout { timeout_ms: 10 }.is_retryable());
+    }
+
+    #[test]
+    fn test_classify_not_a_repository() {
+        let stderr = "Error: There is no jj repo in \".\"\n";
+        let err = JJError::from_command_output(1, stderr);
+        assert_eq!(
+            err,
+            JJError::NotARepository {
+                path: ".".into(),
+                exit_code: 1,
+                stderr: stderr.into(),
+            }
+        );
+        assert_eq!(err.code(), "NOT_A_REPOSITORY");
+        assert_eq!(err.exit_code(), Some(1));
+        assert_eq!(err.stderr(), Some(stderr));
+    }
+
+    #[test]
+    fn test_classify_revision_not_found() {
+        let err = JJError::from_command_output(1, "Error: Revision `xyzzy` doesn't exist\n");
+        assert!(
+            matches!(err, JJError::RevisionNotFound { ref revision, .. } if revision == "xyzzy")
+        );
+
+        let err =
+            JJError::from_command_output(1, "Error: Revision \"main@origin\" doesn't exist\n");
+        assert!(
+            matches!(err, JJError::RevisionNotFound { ref revision, .. } if revision == "main@origin")
+        );
+
+        let err = JJError::from_command_output(1, "Error: No such bookmark: feature\n");
+        assert!(
+            matches!(err, JJError::RevisionNotFound { ref revision, .. } if revision == "feature")
+        );
+        assert_eq!(err.code(), "REVISION_NOT_FOUND");
+    }
+
+    #[test]
+    fn test_classify_immutable_commit() {
+        let stderr = "Error: Commit 3f2a1b9c0d4e is immutable\nHint: Could not modify commit: qpvuntsm 3f2a1b9c (empty) root\n";
+        let err = JJError::from_command_output(1, stderr);
+        assert!(
+            matches!(err, JJError::ImmutableCommit { ref revision, .. } if revision == "3f2a1b9c0d4e")
+        );
+        assert_eq!(err.code(), "IMMUTABLE_COMMIT");
+    }
+
+    #[test]
+    fn test_classify_conflict() {
+        let stderr = "Error: There are unresolved conflicts at these paths:\nsrc/lib.rs    2-sided conflict\nREADME.md    2-sided conflict\nHint: Resolve them first\n";
+        let err = JJError::from_command_output(1, stderr);
+        assert_eq!(
+            err,
+            JJError::Conflict {
+                paths: vec!["src/lib.rs".into(), "README.md".into()],
+                exit_code: 1,
+                stderr: stderr.into(),
+            }
+        );
+
+        let err = JJError::from_command_output(1, "Error: Commit 1234abcd has conflicts\n");
+        assert!(matches!(err, JJError::Conflict { ref paths, .. } if paths.is_empty()));
+        assert_eq!(err.code(), "CONFLICT");
+    }
+
+    #[test]
+    fn test_classify_stale_working_copy() {
+        let stderr = "Error: The working copy is stale (not updated since operation 1a2b3c4d).\n\
+                      Hint: Run `jj workspace update-stale` to update it.\n";
+        let err = JJError::from_command_output(1, stderr);
+        assert_eq!(
+            err,
+            JJError::StaleWorkingCopy {
+                exit_code: 1,
+                stderr: stderr.into(),
+            }
+        );
+        assert_eq!(err.code(), "STALE_WORKING_COPY");
+        assert!(err.to_string().contains("jj workspace update-stale"));
+        assert!(!err.is_retryable());
+    }
+
+    #[test]
+    fn test_classify_concurrent_modification() {
+        for stderr in [
+            "Internal error: Failed to lock working copy\n",
+            "Error: Concurrent checkout\n",
+        ] {
+            let err = JJError::from_command_output(1, stderr);
+            assert_eq!(err.code(), "CONCURRENT_MODIFICATION", "{}", stderr);
+        }
+
+        // jj resolves divergent operations itself and only warns about it
+        let err = JJError::from_command_output(
+            1,
+            "Concurrent modification detected, resolving automatically.\nError: something else\n",
+        );
+        assert_eq!(err.code(), "COMMAND_FAILED");
+        assert_eq!(err.exit_code(), Some(1));
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn test_napi_error_carries_code() {
+        let err: napi::Error =
+            JJError::from_command_output(2, "error: unexpected argument '--bogus'").into();
+        assert!(err.reason.starts_with("[COMMAND_FAILED] "));
+        assert_eq!(error_code(err.reason), Some("COMMAND_FAILED".to_string()));
+
+        let err: napi::Error = JJError::Timeout { timeout_ms: 500 }.into();
+        assert_eq!(err.reason, "[TIMEOUT] Command timed out after 500ms");
+        assert_eq!(error_code("Command failed".into()), None);
+        assert_eq!(error_code("[] nothing".into()), None);
     }
 }
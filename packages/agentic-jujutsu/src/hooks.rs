@@ -4,9 +4,15 @@
 //! agentic-flow hooks system, enabling automatic operation tracking, memory sync,
 //! and multi-agent coordination.
 
-use crate::{JJOperation, JJWrapper, OperationType, Result};
+use crate::leases::{FileLease, LeaseManager, LeaseMode};
+use crate::{JJError, JJOperation, JJWrapper, OperationType, Result};
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Default lease TTL used by hooks when the context does not specify one
+const DEFAULT_LEASE_TTL_SECS: u64 = 300;
 
 /// Context information for hook execution
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -103,8 +109,16 @@ pub struct JJHooksIntegration {
     wrapper: JJWrapper,
     /// Whether AgentDB sync is enabled
     agentdb_enabled: bool,
-    /// Current session context
-    current_session: Option<HookContext>,
+    /// Context of each task in progress, keyed by (agent ID, session ID)
+    sessions: HashMap<(String, String), HookContext>,
+    /// Task started most recently
+    current: Option<(String, String)>,
+    /// Lease manager for automatic lease acquisition (optional)
+    leases: Option<LeaseManager>,
+    /// Lease IDs acquired by each task, keyed by (agent ID, session ID)
+    task_leases: HashMap<(String, String), Vec<String>>,
+    /// Whether `on_post_task` describes the working copy automatically
+    auto_describe: bool,
 }
 
 impl JJHooksIntegration {
@@ -113,17 +127,75 @@ impl JJHooksIntegration {
         Self {
             wrapper,
             agentdb_enabled,
-            current_session: None,
+            sessions: HashMap::new(),
+            current: None,
+            leases: None,
+            task_leases: HashMap::new(),
+            auto_describe: false,
         }
     }
 
+    /// Acquire and release file leases automatically
+    ///
+    /// With a lease manager attached, `on_pre_task` acquires the leases listed
+    /// under `leases` in the context metadata and `on_post_task` releases the
+    /// leases acquired for that task (same agent and session), leaving the
+    /// agent's other tasks holding theirs. Each entry is either a path/glob string
+    /// (exclusive) or an object `{"pattern": "...", "mode": "shared"}`.
+    /// `lease_ttl_secs` and `lease_wait_ms` tune the TTL and wait timeout.
+    pub fn with_lease_manager(mut self, leases: LeaseManager) -> Self {
+        self.leases = Some(leases);
+        self
+    }
+
+    /// Describe the working-copy change when a task finishes
+    ///
+    /// When enabled, `on_post_task` generates a commit message from the
+    /// change's diff and the session's trajectory (see
+    /// [`crate::commit_message`]) and applies it with `jj describe`. A single
+    /// task can opt in with `"auto_describe": true` in the context metadata.
+    /// The message, or the error that prevented it, is included in the
+    /// post-task event.
+    pub fn with_auto_describe(mut self, enabled: bool) -> Self {
+        self.auto_describe = enabled;
+        self
+    }
+
     /// Execute pre-task hook
     ///
     /// This hook is called before an agent begins work on a task.
     /// It initializes the session state and prepares the jj repository.
     pub async fn on_pre_task(&mut self, ctx: HookContext) -> Result<JJHookEvent> {
-        // Store current session context
-        self.current_session = Some(ctx.clone());
+        // Acquire requested leases first so a conflicting task never starts
+        let leases = match &self.leases {
+            Some(leases) => acquire_task_leases(leases, &ctx).await?,
+            None => Vec::new(),
+        };
+        self.start_task(ctx, leases).await
+    }
+
+    /// Start a task whose leases were already acquired
+    ///
+    /// [`Self::on_pre_task`] without the lease acquisition. Callers sharing
+    /// the integration behind a lock acquire leases with
+    /// [`acquire_task_leases`] first, so a task waiting for a lease does not
+    /// hold the lock. The leases are released by [`Self::on_post_task`].
+    pub async fn start_task(
+        &mut self,
+        ctx: HookContext,
+        leases: Vec<FileLease>,
+    ) -> Result<JJHookEvent> {
+        let key = task_key(&ctx);
+        if !leases.is_empty() {
+            self.task_leases
+                .entry(key.clone())
+                .or_default()
+                .extend(leases.iter().map(|lease| lease.lease_id.clone()));
+        }
+
+        // Store the task's session context
+        self.sessions.insert(key.clone(), ctx.clone());
+        self.current = Some(key);
 
         // Create session marker in jj
         let description = format!(
@@ -136,6 +208,7 @@ impl JJHooksIntegration {
             serde_json::json!({
                 "action": "session_init",
                 "description": description,
+                "leases": leases,
             }),
         );
 
@@ -198,15 +271,29 @@ impl JJHooksIntegration {
         // Get current session operations
         let operations = self.get_session_operations(&ctx.session_id).await?;
 
+        // Release leases held for the task
+        let leases_released = self.release_task_leases(&ctx).await;
+
         // Create summary
-        let summary = serde_json::json!({
+        let mut summary = serde_json::json!({
             "session_id": ctx.session_id,
             "agent_id": ctx.agent_id,
             "task": ctx.task_description,
             "operations_count": operations.len(),
+            "leases_released": leases_released,
             "timestamp": ctx.timestamp,
         });
 
+        // Describe the change; a failure here must not lose the task summary
+        let auto_describe = self.auto_describe
+            || ctx.metadata.get("auto_describe").and_then(|v| v.as_bool()) == Some(true);
+        if auto_describe {
+            match self.describe_task(&ctx).await {
+                Ok(message) => summary["description"] = serde_json::json!(message),
+                Err(e) => summary["description_error"] = serde_json::json!(e.to_string()),
+            }
+        }
+
         // Create hook event
         let event =
             JJHookEvent::new(HookEventType::PostTask, None, ctx.clone()).with_metadata(summary);
@@ -216,8 +303,12 @@ impl JJHooksIntegration {
             self.sync_event_to_agentdb(&event).await?;
         }
 
-        // Clear current session
-        self.current_session = None;
+        // Clear the task's session; the agent's other tasks keep theirs
+        let key = task_key(&ctx);
+        self.sessions.remove(&key);
+        if self.current.as_ref() == Some(&key) {
+            self.current = None;
+        }
 
         Ok(operations)
     }
@@ -246,6 +337,44 @@ impl JJHooksIntegration {
         Ok(event)
     }
 
+    /// Generate a description for the working copy and apply it
+    async fn describe_task(&self, ctx: &HookContext) -> Result<String> {
+        let to_error = |e: napi::Error| JJError::CommandFailed(e.reason);
+        let context = self
+            .wrapper
+            .message_context(Some(ctx.task_description.clone()))
+            .map_err(to_error)?;
+        let message = self
+            .wrapper
+            .generate_description_with(&context)
+            .await
+            .map_err(to_error)?;
+        self.wrapper
+            .describe(message.clone())
+            .await
+            .map_err(to_error)?;
+        Ok(message)
+    }
+
+    /// Release the leases acquired for a task, returning how many were held
+    ///
+    /// Leases that already expired or were released elsewhere are skipped.
+    async fn release_task_leases(&mut self, ctx: &HookContext) -> usize {
+        let (Some(leases), Some(lease_ids)) =
+            (&self.leases, self.task_leases.remove(&task_key(ctx)))
+        else {
+            return 0;
+        };
+
+        let mut released = 0;
+        for lease_id in &lease_ids {
+            if leases.release(lease_id).await.is_ok() {
+                released += 1;
+            }
+        }
+        released
+    }
+
     /// Get operations for a specific session
     async fn get_session_operations(&self, _session_id: &str) -> Result<Vec<JJOperation>> {
         // This would query the operation log for operations matching the session ID
@@ -293,9 +422,15 @@ impl JJHooksIntegration {
         Ok(())
     }
 
-    /// Get current session context
+    /// Session context of the task started most recently, if still running
     pub fn current_session(&self) -> Option<&HookContext> {
-        self.current_session.as_ref()
+        self.current.as_ref().and_then(|key| self.sessions.get(key))
+    }
+
+    /// Context of an agent's task in progress in the given session
+    pub fn session(&self, agent_id: &str, session_id: &str) -> Option<&HookContext> {
+        self.sessions
+            .get(&(agent_id.to_string(), session_id.to_string()))
     }
 
     /// Check if AgentDB sync is enabled
@@ -304,6 +439,68 @@ impl JJHooksIntegration {
     }
 }
 
+/// Key of a task's session and leases: (agent ID, session ID)
+fn task_key(ctx: &HookContext) -> (String, String) {
+    (ctx.agent_id.clone(), ctx.session_id.clone())
+}
+
+/// Acquire the leases requested in the context metadata
+///
+/// Entries under `leases` are acquired atomically for the context's agent;
+/// `lease_ttl_secs` and `lease_wait_ms` tune the TTL and wait timeout.
+pub async fn acquire_task_leases(
+    leases: &LeaseManager,
+    ctx: &HookContext,
+) -> Result<Vec<FileLease>> {
+    let requests = parse_lease_requests(&ctx.metadata)?;
+    if requests.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let ttl = ctx
+        .metadata
+        .get("lease_ttl_secs")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(DEFAULT_LEASE_TTL_SECS);
+    let wait = ctx
+        .metadata
+        .get("lease_wait_ms")
+        .and_then(|v| v.as_u64())
+        .map(Duration::from_millis);
+
+    leases
+        .acquire_all(&ctx.agent_id, &requests, Duration::from_secs(ttl), wait)
+        .await
+}
+
+/// Parse the `leases` entry of hook metadata into (pattern, mode) pairs
+fn parse_lease_requests(metadata: &serde_json::Value) -> Result<Vec<(String, LeaseMode)>> {
+    let Some(entries) = metadata.get("leases").and_then(|v| v.as_array()) else {
+        return Ok(Vec::new());
+    };
+
+    entries
+        .iter()
+        .map(|entry| match entry {
+            serde_json::Value::String(pattern) => Ok((pattern.clone(), LeaseMode::Exclusive)),
+            serde_json::Value::Object(obj) => {
+                let pattern = obj
+                    .get("pattern")
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| JJError::InvalidConfig("Lease entry missing 'pattern'".to_string()))?;
+                let mode = match obj.get("mode").and_then(|v| v.as_str()) {
+                    Some(mode) => LeaseMode::from_string(mode).ok_or_else(|| {
+                        JJError::InvalidConfig(format!("Unknown lease mode: {}", mode))
+                    })?,
+                    None => LeaseMode::Exclusive,
+                };
+                Ok((pattern.to_string(), mode))
+            }
+            other => Err(JJError::InvalidConfig(format!("Invalid lease entry: {}", other))),
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -380,6 +577,24 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_auto_describe_failure_keeps_post_task() {
+        let config = JJConfig::default().with_jj_path("false".to_string());
+        let wrapper = JJWrapper::with_config(config).unwrap();
+        let mut integration = JJHooksIntegration::new(wrapper, false).with_auto_describe(true);
+
+        let ctx = HookContext::new(
+            "test-agent".to_string(),
+            "session-001".to_string(),
+            "Test task".to_string(),
+        );
+        integration.on_pre_task(ctx.clone()).await.unwrap();
+
+        assert!(integration.describe_task(&ctx).await.is_err());
+        assert!(integration.on_post_task(ctx).await.is_ok());
+        assert!(integration.current_session().is_none());
+    }
+
     #[tokio::test]
     async fn test_post_edit_hook() {
         let config = JJConfig::default();
@@ -401,4 +616,78 @@ mod tests {
         assert_eq!(operation.operation_type, "Describe");
         assert!(operation.command.contains("test.rs"));
     }
+
+    #[tokio::test]
+    async fn test_task_hooks_manage_leases() {
+        let leases = LeaseManager::new();
+        let config = JJConfig::default();
+        let mut first = JJHooksIntegration::new(JJWrapper::with_config(config.clone()).unwrap(), false)
+            .with_lease_manager(leases.clone());
+        let mut second = JJHooksIntegration::new(JJWrapper::with_config(config).unwrap(), false)
+            .with_lease_manager(leases.clone());
+
+        let metadata = serde_json::json!({
+            "leases": ["src/auth/**", {"pattern": "Cargo.toml", "mode": "shared"}],
+        });
+        let ctx_a = HookContext::new("agent-a".into(), "s-1".into(), "Auth".into())
+            .with_metadata(metadata.clone());
+        let ctx_b = HookContext::new("agent-b".into(), "s-2".into(), "Auth too".into())
+            .with_metadata(metadata);
+
+        let event = first.on_pre_task(ctx_a.clone()).await.unwrap();
+        assert_eq!(event.metadata["leases"].as_array().unwrap().len(), 2);
+
+        let err = second.on_pre_task(ctx_b.clone()).await.unwrap_err();
+        assert!(matches!(err, JJError::LeaseConflict { ref holder, .. } if holder == "agent-a"));
+        assert!(second.current_session().is_none());
+
+        first.on_post_task(ctx_a).await.unwrap();
+        assert!(leases.agent_leases("agent-a").await.is_empty());
+        assert!(second.on_pre_task(ctx_b).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_sessions_are_per_agent() {
+        let wrapper = JJWrapper::with_config(JJConfig::default()).unwrap();
+        let mut integration = JJHooksIntegration::new(wrapper, false);
+
+        let a = HookContext::new("agent-a".into(), "s-1".into(), "Auth".into());
+        let b = HookContext::new("agent-b".into(), "s-2".into(), "Docs".into());
+        integration.on_pre_task(a.clone()).await.unwrap();
+        integration.on_pre_task(b.clone()).await.unwrap();
+        assert_eq!(integration.current_session().unwrap().agent_id, "agent-b");
+
+        integration.on_post_task(b).await.unwrap();
+        assert!(integration.current_session().is_none());
+        let session = integration.session("agent-a", "s-1").unwrap();
+        assert_eq!(session.task_description, "Auth");
+
+        integration.on_post_task(a).await.unwrap();
+        assert!(integration.session("agent-a", "s-1").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_post_task_keeps_other_task_leases() {
+        let leases = LeaseManager::new();
+        let wrapper = JJWrapper::with_config(JJConfig::default()).unwrap();
+        let mut integration =
+            JJHooksIntegration::new(wrapper, false).with_lease_manager(leases.clone());
+
+        let auth = HookContext::new("agent-a".into(), "s-1".into(), "Auth".into())
+            .with_metadata(serde_json::json!({ "leases": ["src/auth/**"] }));
+        let docs = HookContext::new("agent-a".into(), "s-2".into(), "Docs".into())
+            .with_metadata(serde_json::json!({ "leases": ["docs/**"] }));
+        integration.on_pre_task(auth.clone()).await.unwrap();
+        integration.on_pre_task(docs.clone()).await.unwrap();
+
+        integration.on_post_task(auth).await.unwrap();
+        assert!(integration.session("agent-a", "s-1").is_none());
+        let session = integration.session("agent-a", "s-2").unwrap();
+        assert_eq!(session.task_description, "Docs");
+        assert_eq!(integration.current_session().unwrap().session_id, "s-2");
+
+        let held = leases.agent_leases("agent-a").await;
+        assert_eq!(held.len(), 1);
+        assert_eq!(held[0].pattern, "docs/**");
+    }
 }
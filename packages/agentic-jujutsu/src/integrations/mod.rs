@@ -1,19 +1,28 @@
-// ! Agentic-Flow Integration Modules
+//! Agentic-Flow Integration Modules
 //!
 //! This module provides integration with agentic-flow components:
-//! - Agent Booster (352x faster AST editing)
-//! - AgentDB (150x faster vector search)
-//! - Claude Flow MCP (213 coordination tools)
-//! - QUIC transport (50-70% latency reduction)
+//! - Swarm coordination (native task assignment and merge planning)
+//! - QUIC transport (operation log replication, `quic` feature)
+//! - ReasoningBank export (shared pattern store, `reasoningbank-export` feature)
+//!
+//! The Agent Booster and AgentDB learning integrations are still bridged
+//! from JavaScript; `agentic_flow` is compiled once their native
+//! counterparts land.
 
-pub mod agentic_flow;
-pub mod ast_integration;
-pub mod agentdb_learning;
-pub mod swarm_coordinator;
+#[cfg(feature = "quic")]
 pub mod quic_transport;
+#[cfg(feature = "reasoningbank-export")]
+pub mod reasoningbank_export;
+pub mod swarm_coordinator;
+
+#[cfg(feature = "quic")]
+pub use quic_transport::{
+    PeerInfo, PersistedOperationLog, QUICOperationSync, ReplicationStats, Subscription,
+};
+
+#[cfg(feature = "reasoningbank-export")]
+pub use reasoningbank_export::{ExportCursor, ExportReport, ReasoningBankExporter};
 
-pub use agentic_flow::AgenticFlowIntegration;
-pub use ast_integration::{ASTConflictResolver, TemplateResolution, RegexResolution};
-pub use agentdb_learning::{SwarmLearningLoop, ConflictPredictor};
-pub use swarm_coordinator::{SwarmCoordinator, TopologySelector, MeshSwarm, HierarchicalSwarm};
-pub use quic_transport::{QUICOperationSync, QUICConnectionPool};
+pub use swarm_coordinator::{
+    AgentChange, MergePlan, PredictedConflict, SwarmCoordinator, SwarmTask, TaskAssignment,
+};
@@ -0,0 +1,990 @@
+//! Operation log replication over QUIC
+//!
+//! Each agent runs a [`QUICOperationSync`] endpoint that publishes its own
+//! signed [`JJOperation`]s and can subscribe to the operation stream of other
+//! agents. A subscription first catches up from a given operation ID and then
+//! follows the peer's live stream.
+//!
+//! Every received operation is checked before it is persisted:
+//! - the ML-DSA signature must verify,
+//! - the signing key must match the key pinned for the peer (if any),
+//! - records already present in the peer's log are dropped as duplicates,
+//! - a record whose ID is already in the log with a different signature or
+//!   signing key is rejected as a conflict, and the record logged first
+//!   is kept.
+//!
+//! Each remote peer is written to its own JSONL log under
+//! `<data_dir>/peers/<agent_id>.jsonl`, so replicated history never mixes with
+//! the local log at `<data_dir>/local.jsonl`.
+//!
+//! Messages are JSON frames prefixed with a big-endian `u32` length.
+
+use crate::error::{JJError, Result};
+use crate::operations::JJOperation;
+use quinn::{Endpoint, RecvStream, SendStream};
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, Mutex, Notify};
+use tokio::task::JoinHandle;
+
+/// Largest frame accepted from a peer (16 MiB)
+const MAX_FRAME_SIZE: usize = 16 * 1024 * 1024;
+
+/// Capacity of the live broadcast channel
+const LIVE_CHANNEL_CAPACITY: usize = 1024;
+
+/// Request sent by a subscriber when opening a replication stream
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ReplicationRequest {
+    Subscribe {
+        /// Agent whose operations are requested
+        agent_id: String,
+        /// Resume after this operation ID (`None` replays the full log)
+        since: Option<String>,
+    },
+}
+
+/// Message streamed back to a subscriber
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ReplicationMessage {
+    Operation { operation: Box<JJOperation> },
+    CaughtUp { last_id: Option<String> },
+    Error { message: String },
+}
+
+/// Digest of a record's signing key and signature
+///
+/// The signature covers the operation's content, so two records with the
+/// same ID only share a digest when they carry the same signed content.
+fn record_digest(operation: &JJOperation) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(operation.signature_public_key.as_deref().unwrap_or_default());
+    hasher.update(b"\n");
+    hasher.update(operation.signature.as_deref().unwrap_or_default());
+    hex::encode(hasher.finalize())
+}
+
+/// Append-only operation log persisted as JSON lines
+#[derive(Debug)]
+pub struct PersistedOperationLog {
+    path: PathBuf,
+    operations: Vec<JJOperation>,
+    /// Operation ID -> [`record_digest`] of the operations in the log
+    seen: HashMap<String, String>,
+}
+
+impl PersistedOperationLog {
+    /// Open the log at `path`, loading any operations already written
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let mut operations = Vec::new();
+        let mut seen = HashMap::new();
+        if path.exists() {
+            for line in BufReader::new(File::open(&path)?).lines() {
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let op: JJOperation = serde_json::from_str(&line)?;
+                // Logs written before IDs were unique keep their first record
+                if !seen.contains_key(&op.id) {
+                    seen.insert(op.id.clone(), record_digest(&op));
+                    operations.push(op);
+                }
+            }
+        }
+
+        Ok(Self {
+            path,
+            operations,
+            seen,
+        })
+    }
+
+    /// Path of the backing file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append an operation, returning `false` if it was already present
+    ///
+    /// Callers verify the signature first. The log holds one record per
+    /// operation ID: the same ID with a different signature or signing key
+    /// fails with [`JJError::ConflictingOperation`] and leaves the log alone.
+    pub fn append(&mut self, operation: JJOperation) -> Result<bool> {
+        let digest = record_digest(&operation);
+        match self.seen.get(&operation.id) {
+            Some(existing) if *existing == digest => return Ok(false),
+            Some(_) => return Err(JJError::ConflictingOperation(operation.id)),
+            None => {}
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{}", serde_json::to_string(&operation)?)?;
+        file.sync_data()?;
+
+        self.seen.insert(operation.id.clone(), digest);
+        self.operations.push(operation);
+        Ok(true)
+    }
+
+    /// Check whether an operation ID is already in the log
+    pub fn contains(&self, id: &str) -> bool {
+        self.seen.contains_key(id)
+    }
+
+    /// All operations in append order
+    pub fn operations(&self) -> &[JJOperation] {
+        &self.operations
+    }
+
+    /// Number of operations in the log
+    pub fn len(&self) -> usize {
+        self.operations.len()
+    }
+
+    /// Check whether the log is empty
+    pub fn is_empty(&self) -> bool {
+        self.operations.is_empty()
+    }
+
+    /// ID of the most recently appended operation
+    pub fn last_id(&self) -> Option<String> {
+        self.operations.last().map(|op| op.id.clone())
+    }
+
+    /// Operations appended after `since`
+    ///
+    /// `since` may be either the wrapper ID or the jj operation ID. When it is
+    /// `None` or unknown the full log is returned.
+    pub fn operations_after(&self, since: Option<&str>) -> Vec<JJOperation> {
+        let start = since
+            .and_then(|id| {
+                self.operations
+                    .iter()
+                    .position(|op| op.id == id || op.operation_id == id)
+            })
+            .map(|pos| pos + 1)
+            .unwrap_or(0);
+        self.operations[start..].to_vec()
+    }
+}
+
+/// Connection details for a remote agent
+#[derive(Debug, Clone)]
+pub struct PeerInfo {
+    /// Agent ID of the peer
+    pub agent_id: String,
+    /// Address the peer's endpoint is bound to
+    pub addr: SocketAddr,
+    /// DER-encoded self-signed certificate of the peer
+    pub certificate_der: Vec<u8>,
+    /// Server name presented in the peer's certificate
+    pub server_name: String,
+    /// Expected signing public key (hex); any key is accepted when `None`
+    pub public_key: Option<String>,
+}
+
+impl PeerInfo {
+    /// Create peer info for an endpoint using the default `localhost` name
+    pub fn new(agent_id: impl Into<String>, addr: SocketAddr, certificate_der: Vec<u8>) -> Self {
+        Self {
+            agent_id: agent_id.into(),
+            addr,
+            certificate_der,
+            server_name: "localhost".to_string(),
+            public_key: None,
+        }
+    }
+
+    /// Set the server name used for TLS verification
+    pub fn with_server_name(mut self, server_name: impl Into<String>) -> Self {
+        self.server_name = server_name.into();
+        self
+    }
+
+    /// Pin the ML-DSA public key the peer must sign its operations with
+    pub fn with_public_key(mut self, public_key: impl Into<String>) -> Self {
+        self.public_key = Some(public_key.into());
+        self
+    }
+}
+
+/// Counters for a single subscription
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReplicationStats {
+    /// Operations persisted to the peer log
+    pub accepted: usize,
+    /// Operations dropped because they were already in the log
+    pub duplicates: usize,
+    /// Operations dropped because signature verification failed
+    pub rejected: usize,
+    /// Operations dropped because the log holds a different record with
+    /// the same ID
+    pub conflicts: usize,
+    /// Whether the catch-up phase has completed
+    pub caught_up: bool,
+}
+
+#[derive(Debug)]
+struct SubscriptionState {
+    log: Mutex<PersistedOperationLog>,
+    stats: Mutex<ReplicationStats>,
+    updated: Notify,
+}
+
+/// Handle to a running subscription on a remote agent's operation stream
+#[derive(Debug)]
+pub struct Subscription {
+    peer_id: String,
+    state: Arc<SubscriptionState>,
+    task: JoinHandle<Result<()>>,
+}
+
+impl Subscription {
+    /// Agent ID of the remote peer
+    pub fn peer_id(&self) -> &str {
+        &self.peer_id
+    }
+
+    /// Snapshot of the replication counters
+    pub async fn stats(&self) -> ReplicationStats {
+        self.state.stats.lock().await.clone()
+    }
+
+    /// Operations replicated from the peer so far
+    pub async fn operations(&self) -> Vec<JJOperation> {
+        self.state.log.lock().await.operations().to_vec()
+    }
+
+    /// Wait until at least `count` operations are in the peer log
+    pub async fn wait_for(&self, count: usize, timeout: Duration) -> Result<()> {
+        tokio::time::timeout(timeout, async {
+            loop {
+                let notified = self.state.updated.notified();
+                if self.state.log.lock().await.len() >= count {
+                    return;
+                }
+                notified.await;
+            }
+        })
+        .await
+        .map_err(|_| {
+            JJError::TransportError(format!(
+                "Timed out waiting for {} operations from {}",
+                count, self.peer_id
+            ))
+        })
+    }
+
+    /// Wait until the catch-up phase has completed
+    pub async fn wait_caught_up(&self, timeout: Duration) -> Result<()> {
+        tokio::time::timeout(timeout, async {
+            loop {
+                let notified = self.state.updated.notified();
+                if self.state.stats.lock().await.caught_up {
+                    return;
+                }
+                notified.await;
+            }
+        })
+        .await
+        .map_err(|_| {
+            JJError::TransportError(format!("Timed out catching up with {}", self.peer_id))
+        })
+    }
+
+    /// Stop following the peer
+    pub fn cancel(&self) {
+        self.task.abort();
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// QUIC endpoint that publishes and replicates signed operation logs
+#[derive(Debug)]
+pub struct QUICOperationSync {
+    agent_id: String,
+    data_dir: PathBuf,
+    endpoint: Endpoint,
+    certificate_der: Vec<u8>,
+    local_log: Arc<Mutex<PersistedOperationLog>>,
+    live: broadcast::Sender<JJOperation>,
+    server_task: JoinHandle<()>,
+}
+
+impl QUICOperationSync {
+    /// Bind an endpoint for `agent_id` and start serving its operation log
+    ///
+    /// A self-signed certificate for `localhost` is generated; share
+    /// [`certificate_der`](Self::certificate_der) with peers so they can
+    /// pin it when subscribing.
+    pub async fn bind(
+        agent_id: impl Into<String>,
+        addr: SocketAddr,
+        data_dir: impl Into<PathBuf>,
+    ) -> Result<Self> {
+        Self::bind_with_names(agent_id, addr, data_dir, vec!["localhost".to_string()]).await
+    }
+
+    /// Bind an endpoint whose certificate covers the given server names
+    pub async fn bind_with_names(
+        agent_id: impl Into<String>,
+        addr: SocketAddr,
+        data_dir: impl Into<PathBuf>,
+        server_names: Vec<String>,
+    ) -> Result<Self> {
+        let agent_id = agent_id.into();
+        let data_dir = data_dir.into();
+        let local_log = PersistedOperationLog::open(data_dir.join("local.jsonl"))?;
+
+        let certified = rcgen::generate_simple_self_signed(server_names).map_err(|e| {
+            JJError::TransportError(format!("Certificate generation failed: {}", e))
+        })?;
+        let certificate_der = certified.cert.der().to_vec();
+        let key_der = PrivateKeyDer::try_from(certified.key_pair.serialize_der())
+            .map_err(|e| JJError::TransportError(format!("Invalid private key: {}", e)))?;
+
+        let server_config = quinn::ServerConfig::with_single_cert(
+            vec![CertificateDer::from(certificate_der.clone())],
+            key_der,
+        )
+        .map_err(|e| JJError::TransportError(format!("TLS configuration failed: {}", e)))?;
+
+        let endpoint = Endpoint::server(server_config, addr)
+            .map_err(|e| JJError::TransportError(format!("Failed to bind {}: {}", addr, e)))?;
+
+        let local_log = Arc::new(Mutex::new(local_log));
+        let (live, _) = broadcast::channel(LIVE_CHANNEL_CAPACITY);
+        let server_task = tokio::spawn(serve(
+            endpoint.clone(),
+            agent_id.clone(),
+            local_log.clone(),
+            live.clone(),
+        ));
+
+        Ok(Self {
+            agent_id,
+            data_dir,
+            endpoint,
+            certificate_der,
+            local_log,
+            live,
+            server_task,
+        })
+    }
+
+    /// Agent ID this endpoint publishes for
+    pub fn agent_id(&self) -> &str {
+        &self.agent_id
+    }
+
+    /// Address the endpoint is bound to
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        self.endpoint
+            .local_addr()
+            .map_err(|e| JJError::TransportError(e.to_string()))
+    }
+
+    /// DER-encoded certificate peers should pin
+    pub fn certificate_der(&self) -> &[u8] {
+        &self.certificate_der
+    }
+
+    /// Connection details other agents can use to subscribe to this endpoint
+    pub fn peer_info(&self) -> Result<PeerInfo> {
+        Ok(PeerInfo::new(
+            self.agent_id.clone(),
+            self.local_addr()?,
+            self.certificate_der.clone(),
+        ))
+    }
+
+    /// Path of the persisted log for a remote peer
+    pub fn peer_log_path(&self, peer_id: &str) -> PathBuf {
+        self.data_dir
+            .join("peers")
+            .join(format!("{}.jsonl", sanitize_file_name(peer_id)))
+    }
+
+    /// Operations published by this agent
+    pub async fn local_operations(&self) -> Vec<JJOperation> {
+        self.local_log.lock().await.operations().to_vec()
+    }
+
+    /// Publish a signed operation to the local log and live subscribers
+    ///
+    /// Returns `false` if the operation had already been published, and
+    /// fails with [`JJError::ConflictingOperation`] if a different operation
+    /// was published under its ID.
+    pub async fn publish(&self, operation: JJOperation) -> Result<bool> {
+        if !operation.verify_signature()? {
+            return Err(JJError::CryptoError(format!(
+                "Refusing to publish operation {} with an invalid signature",
+                operation.id
+            )));
+        }
+
+        let appended = self.local_log.lock().await.append(operation.clone())?;
+        if appended {
+            // No receivers just means nobody is subscribed yet
+            let _ = self.live.send(operation);
+        }
+        Ok(appended)
+    }
+
+    /// Subscribe to a peer, resuming after the last operation already replicated
+    pub async fn subscribe(&self, peer: PeerInfo) -> Result<Subscription> {
+        self.subscribe_from(peer, None).await
+    }
+
+    /// Subscribe to a peer, catching up from the operation after `since`
+    ///
+    /// When `since` is `None` the subscription resumes after the last
+    /// operation already present in the peer's local log.
+    pub async fn subscribe_from(
+        &self,
+        peer: PeerInfo,
+        since: Option<String>,
+    ) -> Result<Subscription> {
+        let log = PersistedOperationLog::open(self.peer_log_path(&peer.agent_id))?;
+        let since = since.or_else(|| log.last_id());
+
+        let mut roots = rustls::RootCertStore::empty();
+        roots
+            .add(CertificateDer::from(peer.certificate_der.clone()))
+            .map_err(|e| JJError::TransportError(format!("Invalid peer certificate: {}", e)))?;
+        let client_config = quinn::ClientConfig::with_root_certificates(Arc::new(roots))
+            .map_err(|e| JJError::TransportError(format!("TLS configuration failed: {}", e)))?;
+
+        let connection = self
+            .endpoint
+            .connect_with(client_config, peer.addr, &peer.server_name)
+            .map_err(|e| {
+                JJError::TransportError(format!("Failed to connect to {}: {}", peer.addr, e))
+            })?
+            .await
+            .map_err(|e| {
+                JJError::TransportError(format!("Connection to {} failed: {}", peer.addr, e))
+            })?;
+
+        let (mut send, recv) = connection
+            .open_bi()
+            .await
+            .map_err(|e| JJError::TransportError(e.to_string()))?;
+        write_frame(
+            &mut send,
+            &ReplicationRequest::Subscribe {
+                agent_id: peer.agent_id.clone(),
+                since,
+            },
+        )
+        .await?;
+
+        let state = Arc::new(SubscriptionState {
+            log: Mutex::new(log),
+            stats: Mutex::new(ReplicationStats::default()),
+            updated: Notify::new(),
+        });
+
+        let task = tokio::spawn(receive(connection, send, recv, peer.clone(), state.clone()));
+
+        Ok(Subscription {
+            peer_id: peer.agent_id,
+            state,
+            task,
+        })
+    }
+
+    /// Stop serving and close all connections
+    pub fn close(&self) {
+        self.server_task.abort();
+        self.endpoint.close(0u32.into(), b"shutdown");
+    }
+}
+
+impl Drop for QUICOperationSync {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+/// Accept loop serving this agent's operation log
+async fn serve(
+    endpoint: Endpoint,
+    agent_id: String,
+    log: Arc<Mutex<PersistedOperationLog>>,
+    live: broadcast::Sender<JJOperation>,
+) {
+    while let Some(incoming) = endpoint.accept().await {
+        let agent_id = agent_id.clone();
+        let log = log.clone();
+        let live = live.clone();
+        tokio::spawn(async move {
+            let Ok(connection) = incoming.await else {
+                return;
+            };
+            while let Ok((send, recv)) = connection.accept_bi().await {
+                let agent_id = agent_id.clone();
+                let log = log.clone();
+                let live = live.subscribe();
+                tokio::spawn(async move {
+                    let _ = serve_stream(send, recv, &agent_id, log, live).await;
+                });
+            }
+        });
+    }
+}
+
+/// Stream catch-up and live operations to one subscriber
+async fn serve_stream(
+    mut send: SendStream,
+    mut recv: RecvStream,
+    agent_id: &str,
+    log: Arc<Mutex<PersistedOperationLog>>,
+    mut live: broadcast::Receiver<JJOperation>,
+) -> Result<()> {
+    let ReplicationRequest::Subscribe {
+        agent_id: requested,
+        since,
+    } = read_frame(&mut recv).await?;
+
+    if requested != agent_id {
+        let message = format!("This endpoint serves {}, not {}", agent_id, requested);
+        write_frame(
+            &mut send,
+            &ReplicationMessage::Error {
+                message: message.clone(),
+            },
+        )
+        .await?;
+        let _ = send.finish();
+        return Err(JJError::TransportError(message));
+    }
+
+    // `live` was subscribed before the snapshot, so nothing published in
+    // between is lost; anything seen twice is skipped here.
+    let mut sent = HashSet::new();
+    let mut last_sent = since.clone();
+    let backlog = log.lock().await.operations_after(since.as_deref());
+    for operation in backlog {
+        last_sent = Some(operation.id.clone());
+        sent.insert(operation.id.clone());
+        send_operation(&mut send, operation).await?;
+    }
+    write_frame(
+        &mut send,
+        &ReplicationMessage::CaughtUp {
+            last_id: last_sent.clone(),
+        },
+    )
+    .await?;
+
+    loop {
+        match live.recv().await {
+            Ok(operation) => {
+                if sent.insert(operation.id.clone()) {
+                    last_sent = Some(operation.id.clone());
+                    send_operation(&mut send, operation).await?;
+                }
+            }
+            Err(broadcast::error::RecvError::Lagged(_)) => {
+                // Fell behind the live channel: replay from the log instead
+                let missed = log.lock().await.operations_after(last_sent.as_deref());
+                for operation in missed {
+                    if sent.insert(operation.id.clone()) {
+                        last_sent = Some(operation.id.clone());
+                        send_operation(&mut send, operation).await?;
+                    }
+                }
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        }
+    }
+
+    let _ = send.finish();
+    Ok(())
+}
+
+async fn send_operation(send: &mut SendStream, operation: JJOperation) -> Result<()> {
+    write_frame(
+        send,
+        &ReplicationMessage::Operation {
+            operation: Box::new(operation),
+        },
+    )
+    .await
+}
+
+/// Receive, verify and persist operations from a peer
+async fn receive(
+    // Keeps the connection open for the lifetime of the subscription
+    _connection: quinn::Connection,
+    _send: SendStream,
+    mut recv: RecvStream,
+    peer: PeerInfo,
+    state: Arc<SubscriptionState>,
+) -> Result<()> {
+    loop {
+        let message: ReplicationMessage = match read_frame(&mut recv).await {
+            Ok(message) => message,
+            Err(e) => {
+                state.updated.notify_waiters();
+                return Err(e);
+            }
+        };
+
+        match message {
+            ReplicationMessage::Operation { operation } => {
+                let outcome = if !is_trusted(&operation, &peer) {
+                    Outcome::Rejected
+                } else {
+                    match state.log.lock().await.append(*operation) {
+                        Ok(true) => Outcome::Accepted,
+                        Ok(false) => Outcome::Duplicate,
+                        Err(JJError::ConflictingOperation(_)) => Outcome::Conflict,
+                        Err(e) => return Err(e),
+                    }
+                };
+
+                let mut stats = state.stats.lock().await;
+                match outcome {
+                    Outcome::Accepted => stats.accepted += 1,
+                    Outcome::Duplicate => stats.duplicates += 1,
+                    Outcome::Rejected => stats.rejected += 1,
+                    Outcome::Conflict => stats.conflicts += 1,
+                }
+            }
+            ReplicationMessage::CaughtUp { .. } => {
+                state.stats.lock().await.caught_up = true;
+            }
+            ReplicationMessage::Error { message } => {
+                state.updated.notify_waiters();
+                return Err(JJError::TransportError(message));
+            }
+        }
+        state.updated.notify_waiters();
+    }
+}
+
+enum Outcome {
+    Accepted,
+    Duplicate,
+    Rejected,
+    Conflict,
+}
+
+/// Check the signature and, if pinned, the signing key of a received operation
+fn is_trusted(operation: &JJOperation, peer: &PeerInfo) -> bool {
+    if let Some(expected) = &peer.public_key {
+        if operation.signature_public_key.as_deref() != Some(expected.as_str()) {
+            return false;
+        }
+    }
+    operation.verify_signature().unwrap_or(false)
+}
+
+async fn write_frame<T: Serialize>(send: &mut SendStream, message: &T) -> Result<()> {
+    let payload = serde_json::to_vec(message)?;
+    let len = u32::try_from(payload.len())
+        .map_err(|_| JJError::TransportError("Frame too large".to_string()))?;
+    send.write_all(&len.to_be_bytes())
+        .await
+        .map_err(|e| JJError::TransportError(e.to_string()))?;
+    send.write_all(&payload)
+        .await
+        .map_err(|e| JJError::TransportError(e.to_string()))
+}
+
+async fn read_frame<T: for<'de> Deserialize<'de>>(recv: &mut RecvStream) -> Result<T> {
+    let mut len = [0u8; 4];
+    recv.read_exact(&mut len)
+        .await
+        .map_err(|e| JJError::TransportError(e.to_string()))?;
+    let len = u32::from_be_bytes(len) as usize;
+    if len > MAX_FRAME_SIZE {
+        return Err(JJError::TransportError(format!(
+            "Frame of {} bytes exceeds limit",
+            len
+        )));
+    }
+
+    let mut payload = vec![0u8; len];
+    recv.read_exact(&mut payload)
+        .await
+        .map_err(|e| JJError::TransportError(e.to_string()))?;
+    Ok(serde_json::from_slice(&payload)?)
+}
+
+fn sanitize_file_name(id: &str) -> String {
+    id.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::generate_signing_keypair;
+    use crate::crypto::SigningKeypair;
+
+    const TIMEOUT: Duration = Duration::from_secs(10);
+
+    fn signed_op(keypair: &SigningKeypair, n: usize) -> JJOperation {
+        let mut op = JJOperation::new(
+            format!("op-{}", n),
+            format!("describe -m 'change {}'", n),
+            "alice".to_string(),
+            "localhost".to_string(),
+        );
+        op.sign(&keypair.secret_key, &keypair.public_key).unwrap();
+        op
+    }
+
+    async fn endpoint(agent_id: &str, dir: &Path) -> QUICOperationSync {
+        let addr = "127.0.0.1:0".parse().unwrap();
+        QUICOperationSync::bind(agent_id, addr, dir.join(agent_id))
+            .await
+            .unwrap()
+    }
+
+    #[test]
+    fn test_persisted_log_rejects_duplicates() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("log.jsonl");
+        let keypair = generate_signing_keypair();
+        let op = signed_op(&keypair, 1);
+
+        let mut log = PersistedOperationLog::open(&path).unwrap();
+        assert!(log.append(op.clone()).unwrap());
+        assert!(!log.append(op.clone()).unwrap());
+        assert!(log.append(signed_op(&keypair, 2)).unwrap());
+
+        let reopened = PersistedOperationLog::open(&path).unwrap();
+        assert_eq!(reopened.len(), 2);
+        assert!(reopened.contains(&op.id));
+        assert_eq!(reopened.operations_after(Some(&op.id)).len(), 1);
+        assert_eq!(reopened.operations_after(Some("op-1")).len(), 1);
+        assert_eq!(reopened.operations_after(None).len(), 2);
+    }
+
+    #[test]
+    fn test_reused_id_is_a_conflict() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("log.jsonl");
+        let keypair = generate_signing_keypair();
+        let real = signed_op(&keypair, 1);
+
+        // Validly signed, but claiming the real record's ID
+        let mut forged = signed_op(&generate_signing_keypair(), 1);
+        forged.id = real.id.clone();
+        let mut resigned = signed_op(&keypair, 2);
+        resigned.id = real.id.clone();
+        resigned.sign(&keypair.secret_key, &keypair.public_key).unwrap();
+
+        let mut log = PersistedOperationLog::open(&path).unwrap();
+        assert!(log.append(real.clone()).unwrap());
+        for conflicting in [forged, resigned] {
+            let err = log.append(conflicting).unwrap_err();
+            assert_eq!(err, JJError::ConflictingOperation(real.id.clone()));
+        }
+        assert!(!log.append(real.clone()).unwrap());
+
+        let reopened = PersistedOperationLog::open(&path).unwrap();
+        assert_eq!(reopened.len(), 1);
+        assert_eq!(reopened.operations()[0].signature, real.signature);
+    }
+
+    #[tokio::test]
+    async fn test_replication_catch_up_and_live() {
+        let dir = tempfile::tempdir().unwrap();
+        let alice = endpoint("alice", dir.path()).await;
+        let bob = endpoint("bob", dir.path()).await;
+        let keypair = generate_signing_keypair();
+
+        let ops: Vec<_> = (0..3).map(|n| signed_op(&keypair, n)).collect();
+        for op in &ops[..2] {
+            assert!(alice.publish(op.clone()).await.unwrap());
+        }
+
+        let peer = alice
+            .peer_info()
+            .unwrap()
+            .with_public_key(keypair.public_key.clone());
+        let sub = bob.subscribe(peer).await.unwrap();
+        sub.wait_caught_up(TIMEOUT).await.unwrap();
+        sub.wait_for(2, TIMEOUT).await.unwrap();
+
+        alice.publish(ops[2].clone()).await.unwrap();
+        sub.wait_for(3, TIMEOUT).await.unwrap();
+
+        let received: Vec<_> = sub.operations().await.into_iter().map(|op| op.id).collect();
+        let expected: Vec<_> = ops.iter().map(|op| op.id.clone()).collect();
+        assert_eq!(received, expected);
+
+        // Replicated ops go to a per-peer log, not bob's own log
+        assert!(bob.local_operations().await.is_empty());
+        let persisted = PersistedOperationLog::open(bob.peer_log_path("alice")).unwrap();
+        assert_eq!(persisted.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_resume_from_op_id() {
+        let dir = tempfile::tempdir().unwrap();
+        let alice = endpoint("alice", dir.path()).await;
+        let bob = endpoint("bob", dir.path()).await;
+        let keypair = generate_signing_keypair();
+
+        let ops: Vec<_> = (0..4).map(|n| signed_op(&keypair, n)).collect();
+        for op in &ops {
+            alice.publish(op.clone()).await.unwrap();
+        }
+
+        let sub = bob
+            .subscribe_from(alice.peer_info().unwrap(), Some(ops[1].id.clone()))
+            .await
+            .unwrap();
+        sub.wait_caught_up(TIMEOUT).await.unwrap();
+
+        let received: Vec<_> = sub.operations().await.into_iter().map(|op| op.id).collect();
+        assert_eq!(received, vec![ops[2].id.clone(), ops[3].id.clone()]);
+        drop(sub);
+
+        // A fresh subscription resumes after the last persisted op
+        let sub = bob.subscribe(alice.peer_info().unwrap()).await.unwrap();
+        sub.wait_caught_up(TIMEOUT).await.unwrap();
+        assert_eq!(sub.stats().await.accepted, 0);
+        assert_eq!(sub.operations().await.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_duplicates_and_bad_signatures_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let alice = endpoint("alice", dir.path()).await;
+        let bob = endpoint("bob", dir.path()).await;
+        let keypair = generate_signing_keypair();
+        let other = generate_signing_keypair();
+
+        let op = signed_op(&keypair, 1);
+        alice.publish(op.clone()).await.unwrap();
+        assert!(!alice.publish(op.clone()).await.unwrap());
+
+        let unsigned = JJOperation::new(
+            "op-2".to_string(),
+            "abandon @".to_string(),
+            "alice".to_string(),
+            "localhost".to_string(),
+        );
+        assert!(alice.publish(unsigned).await.is_err());
+
+        let mut truncated = signed_op(&keypair, 2);
+        truncated.signature = truncated.signature.map(|sig| sig[..64].to_string());
+        assert!(alice.publish(truncated).await.is_err());
+
+        // Valid signature, but not from the key pinned for alice
+        alice.publish(signed_op(&other, 3)).await.unwrap();
+
+        let peer = alice
+            .peer_info()
+            .unwrap()
+            .with_public_key(keypair.public_key.clone());
+        let sub = bob.subscribe_from(peer.clone(), None).await.unwrap();
+        sub.wait_caught_up(TIMEOUT).await.unwrap();
+
+        let stats = sub.stats().await;
+        assert_eq!(stats.accepted, 1);
+        assert_eq!(stats.rejected, 1);
+        assert_eq!(sub.operations().await[0].id, op.id);
+
+        // Replaying the full log again only yields duplicates
+        drop(sub);
+        let sub = bob
+            .subscribe_from(peer, Some("unknown".to_string()))
+            .await
+            .unwrap();
+        sub.wait_caught_up(TIMEOUT).await.unwrap();
+        let stats = sub.stats().await;
+        assert_eq!(stats.accepted, 0);
+        assert_eq!(stats.duplicates, 1);
+        assert_eq!(stats.rejected, 1);
+    }
+
+    #[tokio::test]
+    async fn test_conflicting_records_are_counted_not_stored() {
+        let dir = tempfile::tempdir().unwrap();
+        let alice = endpoint("alice", dir.path()).await;
+        let bob = endpoint("bob", dir.path()).await;
+        let keypair = generate_signing_keypair();
+
+        let op = signed_op(&keypair, 1);
+        alice.publish(op.clone()).await.unwrap();
+        let mut conflicting = signed_op(&keypair, 2);
+        conflicting.id = op.id.clone();
+        conflicting.sign(&keypair.secret_key, &keypair.public_key).unwrap();
+        assert_eq!(
+            alice.publish(conflicting.clone()).await.unwrap_err().code(),
+            "CONFLICTING_OPERATION"
+        );
+
+        // Bob already replicated a different record under the same ID
+        PersistedOperationLog::open(bob.peer_log_path("alice"))
+            .unwrap()
+            .append(conflicting.clone())
+            .unwrap();
+        let sub = bob
+            .subscribe_from(alice.peer_info().unwrap(), Some("unknown".to_string()))
+            .await
+            .unwrap();
+        sub.wait_caught_up(TIMEOUT).await.unwrap();
+
+        let stats = sub.stats().await;
+        assert_eq!((stats.accepted, stats.conflicts), (0, 1));
+        let operations = sub.operations().await;
+        assert_eq!(operations.len(), 1);
+        assert_eq!(operations[0].signature, conflicting.signature);
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_to_wrong_agent_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        let alice = endpoint("alice", dir.path()).await;
+        let bob = endpoint("bob", dir.path()).await;
+
+        let mut peer = alice.peer_info().unwrap();
+        peer.agent_id = "mallory".to_string();
+        let sub = bob.subscribe(peer).await.unwrap();
+        assert!(sub
+            .wait_caught_up(Duration::from_millis(500))
+            .await
+            .is_err());
+        assert!(sub.operations().await.is_empty());
+    }
+}
@@ -0,0 +1,530 @@
+//! Export of jj learning data to the shared reasoningbank pattern store
+//!
+//! [`ReasoningBank`] keeps trajectories and patterns in memory. This module
+//! converts them into [`reasoningbank_core::Pattern`] records and writes them
+//! through [`reasoningbank_storage::SqliteStorage`], so the reasoningbank
+//! `AdaptiveLearner` sees jj-derived experience.
+//!
+//! Mapping:
+//!
+//! - **category**: `jujutsu` (configurable)
+//! - **strategy**: operation types joined with ` -> `, e.g. `New -> Describe`
+//! - **outcome**: success score, duration, reward and failed operations
+//! - **context**: initial/final context, revisions, paths and source IDs
+//!
+//! Record IDs are derived from the trajectory ID, or for patterns from their
+//! operation sequence (pattern IDs are regenerated whenever a bank is rebuilt),
+//! so exporting the same record twice replaces it instead of duplicating it.
+//!
+//! [`ReasoningBankExporter::backfill`] exports everything currently held by a
+//! bank; [`ReasoningBankExporter::export_new`] only exports what changed since
+//! the last export, tracked by an [`ExportCursor`] persisted next to the
+//! database.
+
+use crate::error::{JJError, Result};
+use crate::reasoning_bank::{Pattern, ReasoningBank, Trajectory};
+use chrono::{DateTime, Utc};
+use reasoningbank_core::{Pattern as SharedPattern, PatternMetadata, TaskOutcome};
+use reasoningbank_storage::sqlite::StorageConfig;
+use reasoningbank_storage::SqliteStorage;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+/// Default `task_category` of exported records
+pub const DEFAULT_CATEGORY: &str = "jujutsu";
+
+/// Tag attached to every exported record
+const SOURCE_TAG: &str = "agentic-jujutsu";
+
+/// Position of the last incremental export
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ExportCursor {
+    /// Completion time of the newest exported trajectory
+    pub last_completed_at: Option<DateTime<Utc>>,
+    /// Trajectories completed exactly at `last_completed_at` that were exported
+    pub trajectories_at_cursor: BTreeSet<String>,
+    /// Observation count of every exported pattern, keyed by strategy
+    pub pattern_observations: BTreeMap<String, u32>,
+}
+
+impl ExportCursor {
+    /// Load a cursor, or start from the beginning if `path` does not exist
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+    }
+
+    /// Write the cursor atomically
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    fn is_exported(&self, trajectory: &Trajectory) -> bool {
+        match self.last_completed_at {
+            Some(last) if trajectory.completed_at < last => true,
+            Some(last) if trajectory.completed_at == last => {
+                self.trajectories_at_cursor.contains(&trajectory.id)
+            }
+            _ => false,
+        }
+    }
+
+    fn advance(&mut self, trajectory: &Trajectory) {
+        match self.last_completed_at {
+            Some(last) if trajectory.completed_at < last => {}
+            Some(last) if trajectory.completed_at == last => {
+                self.trajectories_at_cursor.insert(trajectory.id.clone());
+            }
+            _ => {
+                self.last_completed_at = Some(trajectory.completed_at);
+                self.trajectories_at_cursor = BTreeSet::from([trajectory.id.clone()]);
+            }
+        }
+    }
+}
+
+/// Result of an export run
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportReport {
+    /// Trajectories written
+    pub trajectories: usize,
+    /// Patterns written
+    pub patterns: usize,
+    /// Encrypted trajectories that could not be converted
+    pub skipped_encrypted: usize,
+}
+
+/// Writes jj trajectories and patterns into a reasoningbank database
+pub struct ReasoningBankExporter {
+    storage: SqliteStorage,
+    category: String,
+    cursor: ExportCursor,
+    cursor_path: Option<PathBuf>,
+}
+
+impl ReasoningBankExporter {
+    /// Export into an existing storage, keeping the cursor in memory only
+    pub fn new(storage: SqliteStorage) -> Self {
+        Self {
+            storage,
+            category: DEFAULT_CATEGORY.to_string(),
+            cursor: ExportCursor::default(),
+            cursor_path: None,
+        }
+    }
+
+    /// Open the database at `database_path` and the cursor at `cursor_path`
+    ///
+    /// Without a cursor path, the cursor is kept at
+    /// `<database_path>.jj-cursor.json`.
+    pub fn open(database_path: impl Into<PathBuf>, cursor_path: Option<PathBuf>) -> Result<Self> {
+        let database_path = database_path.into();
+        let cursor_path = cursor_path.unwrap_or_else(|| default_cursor_path(&database_path));
+
+        let storage = SqliteStorage::new(StorageConfig {
+            database_path,
+            ..StorageConfig::default()
+        })
+        .map_err(storage_error)?;
+
+        Ok(Self {
+            cursor: ExportCursor::load(&cursor_path)?,
+            cursor_path: Some(cursor_path),
+            ..Self::new(storage)
+        })
+    }
+
+    /// Use a different `task_category` for exported records
+    pub fn with_category(mut self, category: impl Into<String>) -> Self {
+        self.category = category.into();
+        self
+    }
+
+    /// Current export position
+    pub fn cursor(&self) -> &ExportCursor {
+        &self.cursor
+    }
+
+    /// Underlying storage
+    pub fn storage(&self) -> &SqliteStorage {
+        &self.storage
+    }
+
+    /// Export every trajectory and pattern in `bank`, ignoring the cursor
+    ///
+    /// The cursor is advanced past everything exported.
+    pub fn backfill(&mut self, bank: &ReasoningBank) -> Result<ExportReport> {
+        self.export(bank, true)
+    }
+
+    /// Export trajectories completed and patterns updated since the last export
+    pub fn export_new(&mut self, bank: &ReasoningBank) -> Result<ExportReport> {
+        self.export(bank, false)
+    }
+
+    fn export(&mut self, bank: &ReasoningBank, everything: bool) -> Result<ExportReport> {
+        let mut report = ExportReport::default();
+        let mut cursor = self.cursor.clone();
+
+        let mut trajectories = bank.get_trajectories()?;
+        trajectories.sort_by_key(|t| t.completed_at);
+
+        for trajectory in &trajectories {
+            if !everything && self.cursor.is_exported(trajectory) {
+                continue;
+            }
+            match trajectory_to_pattern(trajectory, &self.category) {
+                Some(pattern) => {
+                    self.storage
+                        .store_pattern(&pattern)
+                        .map_err(storage_error)?;
+                    report.trajectories += 1;
+                }
+                None => report.skipped_encrypted += 1,
+            }
+            cursor.advance(trajectory);
+        }
+
+        for pattern in bank.get_patterns()? {
+            let key = strategy(&pattern.operation_sequence);
+            let seen = self.cursor.pattern_observations.get(&key);
+            if !everything && seen == Some(&pattern.observation_count) {
+                continue;
+            }
+            self.storage
+                .store_pattern(&pattern_to_pattern(&pattern, &self.category))
+                .map_err(storage_error)?;
+            cursor
+                .pattern_observations
+                .insert(key, pattern.observation_count);
+            report.patterns += 1;
+        }
+
+        if let Some(path) = &self.cursor_path {
+            cursor.save(path)?;
+        }
+        self.cursor = cursor;
+        Ok(report)
+    }
+}
+
+/// Cursor file used by [`ReasoningBankExporter::open`] when none is given
+pub fn default_cursor_path(database_path: &Path) -> PathBuf {
+    let mut name = database_path.as_os_str().to_owned();
+    name.push(".jj-cursor.json");
+    PathBuf::from(name)
+}
+
+/// Convert a finished trajectory into a shared pattern record
+///
+/// Returns `None` for encrypted trajectories, whose operations are not readable.
+pub fn trajectory_to_pattern(trajectory: &Trajectory, category: &str) -> Option<SharedPattern> {
+    if trajectory.encrypted {
+        return None;
+    }
+
+    let operation_types: Vec<String> = trajectory
+        .operations
+        .iter()
+        .map(|op| op.operation_type.clone())
+        .collect();
+    let failed: Vec<_> = trajectory
+        .operations
+        .iter()
+        .filter(|op| !op.success)
+        .collect();
+
+    let mut outcome = TaskOutcome::partial(
+        trajectory.success_score,
+        trajectory.duration_seconds().max(0) as f64,
+    );
+    outcome.error_message = failed.iter().find_map(|op| op.error.clone());
+    outcome.metrics = HashMap::from([
+        ("reward".to_string(), trajectory.reward),
+        ("operations".to_string(), trajectory.operations.len() as f64),
+        ("failed_operations".to_string(), failed.len() as f64),
+    ]);
+
+    let revisions: BTreeSet<&String> = trajectory
+        .operations
+        .iter()
+        .flat_map(|op| &op.revisions)
+        .collect();
+    let paths: BTreeSet<&String> = trajectory
+        .operations
+        .iter()
+        .flat_map(|op| &op.paths)
+        .collect();
+
+    let mut context = HashMap::from([
+        ("source".to_string(), serde_json::json!(SOURCE_TAG)),
+        (
+            "trajectory_id".to_string(),
+            serde_json::json!(trajectory.id),
+        ),
+        (
+            "initial_context".to_string(),
+            serde_json::json!(trajectory.initial_context),
+        ),
+        (
+            "final_context".to_string(),
+            serde_json::json!(trajectory.final_context),
+        ),
+        ("revisions".to_string(), serde_json::json!(revisions)),
+        ("paths".to_string(), serde_json::json!(paths)),
+    ]);
+    if let Some(critique) = &trajectory.critique {
+        context.insert("critique".to_string(), serde_json::json!(critique));
+    }
+
+    let mut tags = vec![SOURCE_TAG.to_string(), "trajectory".to_string()];
+    tags.extend(trajectory.tags.iter().cloned());
+
+    Some(SharedPattern {
+        id: derived_id("trajectory", &trajectory.id),
+        task_description: trajectory.task.clone(),
+        task_category: category.to_string(),
+        strategy: strategy(&operation_types),
+        context,
+        outcome,
+        metadata: PatternMetadata {
+            created_at: trajectory.started_at.timestamp(),
+            updated_at: trajectory.completed_at.timestamp(),
+            usage_count: 1,
+            avg_success_score: trajectory.success_score,
+            tags,
+            custom: HashMap::new(),
+        },
+        embedding: None,
+    })
+}
+
+/// Convert a learned operation pattern into a shared pattern record
+pub fn pattern_to_pattern(pattern: &Pattern, category: &str) -> SharedPattern {
+    let mut outcome = TaskOutcome::partial(pattern.success_rate, pattern.avg_duration_ms / 1000.0);
+    outcome.metrics = HashMap::from([
+        ("confidence".to_string(), pattern.confidence),
+        (
+            "observation_count".to_string(),
+            pattern.observation_count as f64,
+        ),
+    ]);
+
+    let context = HashMap::from([
+        ("source".to_string(), serde_json::json!(SOURCE_TAG)),
+        ("pattern_id".to_string(), serde_json::json!(pattern.id)),
+        (
+            "successful_contexts".to_string(),
+            serde_json::json!(pattern.successful_contexts),
+        ),
+    ]);
+
+    let strategy = strategy(&pattern.operation_sequence);
+    SharedPattern {
+        id: derived_id("pattern", &strategy),
+        task_description: pattern.name.clone(),
+        task_category: category.to_string(),
+        strategy,
+        context,
+        outcome,
+        metadata: PatternMetadata {
+            usage_count: pattern.observation_count,
+            avg_success_score: pattern.success_rate,
+            tags: vec![SOURCE_TAG.to_string(), "pattern".to_string()],
+            ..PatternMetadata::default()
+        },
+        embedding: None,
+    }
+}
+
+fn strategy(operation_types: &[String]) -> String {
+    if operation_types.is_empty() {
+        "no-operations".to_string()
+    } else {
+        operation_types.join(" -> ")
+    }
+}
+
+/// Stable record ID for a source record of the given kind
+fn derived_id(kind: &str, id: &str) -> Uuid {
+    let digest = Sha256::digest(format!("{}:{}:{}", SOURCE_TAG, kind, id));
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&digest[..16]);
+    Uuid::from_bytes(bytes)
+}
+
+fn storage_error(err: reasoningbank_storage::StorageError) -> JJError {
+    JJError::IoError(format!("reasoningbank storage: {}", err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::operations::{JJOperation, OperationType};
+
+    fn operation(operation_type: OperationType, success: bool) -> JJOperation {
+        let mut op = JJOperation::builder()
+            .operation_type(operation_type)
+            .command("jj".to_string())
+            .revisions(vec!["@-".to_string()])
+            .paths(vec!["src/lib.rs".to_string()])
+            .build();
+        op.success = success;
+        if !success {
+            op.error = Some("Commit abc is immutable".to_string());
+        }
+        op
+    }
+
+    fn trajectory(task: &str, score: f64) -> Trajectory {
+        let mut trajectory = Trajectory::new(
+            task.to_string(),
+            HashMap::from([("bookmark".to_string(), "main".to_string())]),
+        );
+        trajectory.add_operation(operation(OperationType::New, true));
+        trajectory.add_operation(operation(OperationType::Describe, true));
+        trajectory.add_operation(operation(OperationType::Rebase, score >= 0.7));
+        trajectory.tags.push("refactor".to_string());
+        trajectory.finalize(HashMap::new(), score);
+        trajectory
+    }
+
+    fn exporter() -> ReasoningBankExporter {
+        ReasoningBankExporter::new(SqliteStorage::in_memory().unwrap())
+    }
+
+    #[test]
+    fn test_trajectory_mapping() {
+        let source = trajectory("Rebase feature onto main", 0.4);
+        let pattern = trajectory_to_pattern(&source, DEFAULT_CATEGORY).unwrap();
+
+        assert_eq!(pattern.id, derived_id("trajectory", &source.id));
+        assert_eq!(pattern.task_description, "Rebase feature onto main");
+        assert_eq!(pattern.task_category, "jujutsu");
+        assert_eq!(pattern.strategy, "New -> Describe -> Rebase");
+        assert!(!pattern.outcome.success);
+        assert_eq!(pattern.outcome.success_score, 0.4);
+        assert_eq!(pattern.outcome.metrics["failed_operations"], 1.0);
+        assert_eq!(
+            pattern.outcome.error_message.as_deref(),
+            Some("Commit abc is immutable")
+        );
+        assert_eq!(
+            pattern.context["trajectory_id"],
+            serde_json::json!(source.id)
+        );
+        assert_eq!(pattern.context["revisions"], serde_json::json!(["@-"]));
+        assert_eq!(pattern.context["initial_context"]["bookmark"], "main");
+        assert!(pattern.metadata.tags.contains(&"refactor".to_string()));
+
+        let mut encrypted = source.clone();
+        encrypted.encrypted = true;
+        assert!(trajectory_to_pattern(&encrypted, DEFAULT_CATEGORY).is_none());
+    }
+
+    #[test]
+    fn test_pattern_mapping() {
+        let bank = ReasoningBank::new(10);
+        bank.store_trajectory(trajectory("Split commit", 0.9))
+            .unwrap();
+        let source = bank.get_patterns().unwrap().remove(0);
+
+        let pattern = pattern_to_pattern(&source, "vcs");
+        assert_eq!(pattern.task_category, "vcs");
+        assert_eq!(pattern.strategy, "New -> Describe -> Rebase");
+        assert!(pattern.outcome.success);
+        assert_eq!(pattern.metadata.usage_count, 1);
+        assert_eq!(pattern.context["pattern_id"], serde_json::json!(source.id));
+
+        // Stable across banks that rediscover the same pattern
+        let mut rediscovered = source.clone();
+        rediscovered.id = Uuid::new_v4().to_string();
+        assert_eq!(pattern_to_pattern(&rediscovered, "vcs").id, pattern.id);
+    }
+
+    #[test]
+    fn test_backfill_is_idempotent() {
+        let bank = ReasoningBank::new(10);
+        bank.store_trajectory(trajectory("Squash fixups", 0.9))
+            .unwrap();
+        bank.store_trajectory(trajectory("Abandon experiment", 0.2))
+            .unwrap();
+
+        let mut exporter = exporter();
+        let report = exporter.backfill(&bank).unwrap();
+        assert_eq!(
+            report,
+            ExportReport {
+                trajectories: 2,
+                patterns: 1,
+                skipped_encrypted: 0,
+            }
+        );
+
+        exporter.backfill(&bank).unwrap();
+        let stored = exporter.storage().get_all_patterns(None).unwrap();
+        assert_eq!(stored.len(), 3);
+        assert!(stored.iter().all(|p| p.task_category == DEFAULT_CATEGORY));
+
+        let stored = exporter
+            .storage()
+            .get_patterns_by_category(DEFAULT_CATEGORY, 10)
+            .unwrap();
+        assert!(stored
+            .iter()
+            .any(|p| p.task_description == "Abandon experiment"));
+    }
+
+    #[test]
+    fn test_incremental_export_uses_cursor() {
+        let dir = tempfile::tempdir().unwrap();
+        let database = dir.path().join("patterns.db");
+        let bank = ReasoningBank::new(10);
+        bank.store_trajectory(trajectory("First", 0.9)).unwrap();
+
+        let mut exporter = ReasoningBankExporter::open(&database, None).unwrap();
+        assert_eq!(exporter.export_new(&bank).unwrap().trajectories, 1);
+        assert_eq!(exporter.export_new(&bank).unwrap(), ExportReport::default());
+        assert!(default_cursor_path(&database).exists());
+
+        // A new exporter resumes from the persisted cursor
+        let mut later = trajectory("Second", 0.95);
+        later.completed_at =
+            exporter.cursor().last_completed_at.unwrap() + chrono::Duration::seconds(1);
+        bank.store_trajectory(later).unwrap();
+
+        let mut exporter = ReasoningBankExporter::open(&database, None).unwrap();
+        let report = exporter.export_new(&bank).unwrap();
+        assert_eq!(report.trajectories, 1);
+        // The matching pattern gained an observation and is exported again
+        assert_eq!(report.patterns, 1);
+        assert_eq!(exporter.storage().get_all_patterns(None).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_cursor_handles_equal_timestamps() {
+        let first = trajectory("A", 0.5);
+        let mut second = trajectory("B", 0.5);
+        second.completed_at = first.completed_at;
+
+        let mut cursor = ExportCursor::default();
+        cursor.advance(&first);
+        assert!(cursor.is_exported(&first));
+        assert!(!cursor.is_exported(&second));
+
+        cursor.advance(&second);
+        assert!(cursor.is_exported(&second));
+        assert_eq!(cursor.trajectories_at_cursor.len(), 2);
+    }
+}
@@ -0,0 +1,798 @@
+//! Native swarm coordinator for parallel agent work
+//!
+//! Assigns tasks to agents based on file ownership and leases, tracks each
+//! agent's jj changes as DAG vertices keyed by change ID, and computes a
+//! conflict-minimizing merge order for integrating parallel changes onto a
+//! target bookmark.
+//!
+//! Change IDs are used as vertex IDs because they survive rewrites: when an
+//! agent amends or rebases its change, re-recording it updates the vertex in
+//! place instead of adding a new one.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+use crate::agent_coordination::AgentCoordination;
+use crate::error::{JJError, Result};
+use crate::leases::{patterns_overlap, FileLease, LeaseMode};
+use crate::wrapper::JJWrapper;
+
+/// Default TTL for leases taken on behalf of assigned tasks
+const DEFAULT_TASK_LEASE_TTL: Duration = Duration::from_secs(30 * 60);
+
+/// A unit of work to hand out to an agent
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SwarmTask {
+    /// Unique task identifier
+    pub task_id: String,
+    /// Human-readable task description
+    pub description: String,
+    /// Files or globs the task is expected to modify
+    pub files: Vec<String>,
+}
+
+impl SwarmTask {
+    /// Create a new task
+    pub fn new(task_id: &str, description: &str, files: Vec<String>) -> Self {
+        Self {
+            task_id: task_id.to_string(),
+            description: description.to_string(),
+            files,
+        }
+    }
+}
+
+/// Result of assigning a task
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskAssignment {
+    /// Task that was assigned
+    pub task_id: String,
+    /// Agent that received the task
+    pub agent_id: String,
+    /// Leases taken on the task's files for the agent
+    pub leases: Vec<FileLease>,
+    /// Number of task files already owned by the agent
+    pub owned_files: usize,
+}
+
+/// A jj change produced by an agent
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AgentChange {
+    /// Agent that produced the change
+    pub agent_id: String,
+    /// jj change ID (stable across rewrites)
+    pub change_id: String,
+    /// Current commit ID of the change
+    pub commit_id: String,
+    /// Change IDs of the parents
+    pub parents: Vec<String>,
+    /// Files modified by the change
+    pub files: Vec<String>,
+    /// First line of the change description
+    pub description: String,
+}
+
+/// Two changes in a merge plan that touch the same files
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PredictedConflict {
+    /// Change merged first
+    pub first: String,
+    /// Change merged later, which will have to absorb the conflict
+    pub second: String,
+    /// Files modified by both changes
+    pub files: Vec<String>,
+}
+
+/// Order in which parallel agent changes should land on a bookmark
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MergePlan {
+    /// Bookmark the changes are integrated onto
+    pub target_bookmark: String,
+    /// Change IDs in merge order (parents always precede children)
+    pub order: Vec<String>,
+    /// Overlapping changes that are not related by ancestry
+    pub predicted_conflicts: Vec<PredictedConflict>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct SwarmAgent {
+    /// Active task ID -> IDs of the leases taken for it
+    active_tasks: HashMap<String, Vec<String>>,
+}
+
+/// Native swarm coordinator
+pub struct SwarmCoordinator {
+    /// Shared coordination state (agents, leases, DAG vertices)
+    coordination: Arc<AgentCoordination>,
+    /// Agents participating in the swarm
+    agents: Mutex<HashMap<String, SwarmAgent>>,
+    /// File path -> owning agent, learned from assignments and changes
+    ownership: Mutex<HashMap<String, String>>,
+    /// Tracked changes keyed by change ID
+    changes: Mutex<HashMap<String, AgentChange>>,
+    /// TTL for task leases
+    lease_ttl: Duration,
+}
+
+impl SwarmCoordinator {
+    /// Create a coordinator with its own coordination state
+    pub fn new() -> Self {
+        Self::with_coordination(Arc::new(AgentCoordination::new()))
+    }
+
+    /// Create a coordinator sharing an existing coordination system
+    pub fn with_coordination(coordination: Arc<AgentCoordination>) -> Self {
+        Self {
+            coordination,
+            agents: Mutex::new(HashMap::new()),
+            ownership: Mutex::new(HashMap::new()),
+            changes: Mutex::new(HashMap::new()),
+            lease_ttl: DEFAULT_TASK_LEASE_TTL,
+        }
+    }
+
+    /// Set the TTL used for task leases (builder pattern)
+    pub fn with_lease_ttl(mut self, ttl: Duration) -> Self {
+        self.lease_ttl = ttl;
+        self
+    }
+
+    /// Get the underlying coordination system
+    pub fn coordination(&self) -> &Arc<AgentCoordination> {
+        &self.coordination
+    }
+
+    /// Add an agent to the swarm
+    pub async fn register_agent(&self, agent_id: &str, agent_type: &str) -> Result<()> {
+        self.coordination
+            .register_agent(agent_id.to_string(), agent_type.to_string())
+            .await?;
+
+        self.agents
+            .lock()
+            .await
+            .insert(agent_id.to_string(), SwarmAgent::default());
+        Ok(())
+    }
+
+    /// Get the agent owning a file, if any
+    pub async fn owner_of(&self, path: &str) -> Option<String> {
+        self.ownership.lock().await.get(path).cloned()
+    }
+
+    /// Assign a task to the best available agent
+    ///
+    /// Agents are ranked by how many of the task's files they already own,
+    /// then by current load. The first agent that can take exclusive leases
+    /// on every task file receives the task. If no agent can, the lease
+    /// conflict of the best-ranked agent is returned.
+    pub async fn assign_task(&self, task: &SwarmTask) -> Result<TaskAssignment> {
+        let candidates = self.rank_agents(task).await;
+        if candidates.is_empty() {
+            return Err(JJError::InvalidConfig("No agents registered in swarm".to_string()));
+        }
+
+        let requests: Vec<(String, LeaseMode)> = task
+            .files
+            .iter()
+            .map(|f| (f.clone(), LeaseMode::Exclusive))
+            .collect();
+
+        let mut first_error = None;
+        for (agent_id, owned_files) in candidates {
+            match self
+                .coordination
+                .lease_manager()
+                .acquire_all(&agent_id, &requests, self.lease_ttl, None)
+                .await
+            {
+                Ok(leases) => {
+                    self.claim(&agent_id, task, &leases).await;
+                    return Ok(TaskAssignment {
+                        task_id: task.task_id.clone(),
+                        agent_id,
+                        leases,
+                        owned_files,
+                    });
+                }
+                Err(e) => {
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+
+        Err(first_error.expect("at least one candidate was tried"))
+    }
+
+    /// Rank agents for a task: (agent_id, owned file count), best first
+    async fn rank_agents(&self, task: &SwarmTask) -> Vec<(String, usize)> {
+        let agents = self.agents.lock().await;
+        let ownership = self.ownership.lock().await;
+
+        let mut ranked: Vec<(String, usize, usize)> = agents
+            .iter()
+            .map(|(agent_id, agent)| {
+                let owned = task
+                    .files
+                    .iter()
+                    .filter(|pattern| {
+                        ownership
+                            .iter()
+                            .any(|(path, owner)| owner == agent_id && patterns_overlap(pattern, path))
+                    })
+                    .count();
+                (agent_id.clone(), owned, agent.active_tasks.len())
+            })
+            .collect();
+
+        ranked.sort_by(|a, b| b.1.cmp(&a.1).then(a.2.cmp(&b.2)).then(a.0.cmp(&b.0)));
+        ranked.into_iter().map(|(id, owned, _)| (id, owned)).collect()
+    }
+
+    async fn claim(&self, agent_id: &str, task: &SwarmTask, leases: &[FileLease]) {
+        if let Some(agent) = self.agents.lock().await.get_mut(agent_id) {
+            let lease_ids = leases.iter().map(|lease| lease.lease_id.clone());
+            agent
+                .active_tasks
+                .entry(task.task_id.clone())
+                .or_default()
+                .extend(lease_ids);
+        }
+
+        let mut ownership = self.ownership.lock().await;
+        for file in &task.files {
+            ownership.insert(file.clone(), agent_id.to_string());
+        }
+    }
+
+    /// Mark a task complete and release the leases taken for it
+    ///
+    /// Leases the agent holds for its other tasks, or took outside the
+    /// swarm, are kept. Returns how many leases were released; leases that
+    /// already expired are not counted.
+    pub async fn complete_task(&self, agent_id: &str, task_id: &str) -> Result<usize> {
+        let lease_ids = {
+            let mut agents = self.agents.lock().await;
+            let agent = agents
+                .get_mut(agent_id)
+                .ok_or_else(|| JJError::InvalidConfig(format!("Unknown agent: {}", agent_id)))?;
+            agent.active_tasks.remove(task_id).unwrap_or_default()
+        };
+
+        let mut released = 0;
+        for lease_id in lease_ids {
+            match self.coordination.release_lease(&lease_id).await {
+                Ok(()) => released += 1,
+                Err(JJError::LeaseNotFound(_)) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(released)
+    }
+
+    /// Track a change as a DAG vertex
+    ///
+    /// Recording a change ID again (after a rewrite) replaces the previous
+    /// vertex. Files in the change become owned by the agent.
+    pub async fn track_change(&self, change: AgentChange) -> Result<()> {
+        self.coordination
+            .register_dag_vertex(change.commit_id.clone(), change.change_id.clone())
+            .await?;
+
+        {
+            let mut ownership = self.ownership.lock().await;
+            for file in &change.files {
+                ownership.insert(file.clone(), change.agent_id.clone());
+            }
+        }
+
+        let tips = {
+            let mut changes = self.changes.lock().await;
+            changes.insert(change.change_id.clone(), change);
+            Self::compute_tips(&changes)
+        };
+
+        self.coordination.update_dag_tips(tips).await
+    }
+
+    /// Read a revision from the repository and track it for an agent
+    pub async fn record_change(
+        &self,
+        wrapper: &JJWrapper,
+        agent_id: &str,
+        revision: &str,
+    ) -> Result<AgentChange> {
+        let change = read_change(wrapper, agent_id, revision).await?;
+        self.track_change(change.clone()).await?;
+        Ok(change)
+    }
+
+    /// Tracked changes
+    pub async fn changes(&self) -> Vec<AgentChange> {
+        let mut changes: Vec<AgentChange> = self.changes.lock().await.values().cloned().collect();
+        changes.sort_by(|a, b| a.change_id.cmp(&b.change_id));
+        changes
+    }
+
+    /// Changes that are not the parent of any other tracked change
+    fn compute_tips(changes: &HashMap<String, AgentChange>) -> Vec<String> {
+        let parents: HashSet<&String> = changes.values().flat_map(|c| c.parents.iter()).collect();
+        let mut tips: Vec<String> = changes
+            .keys()
+            .filter(|id| !parents.contains(id))
+            .cloned()
+            .collect();
+        tips.sort();
+        tips
+    }
+
+    /// Compute the order in which tracked changes should land on `target_bookmark`
+    ///
+    /// The order is topological over the tracked changes (parents first).
+    /// Among the changes ready at each step, the one overlapping least with
+    /// files already merged goes next, so independent work lands before work
+    /// that will need conflict resolution; ties go to smaller changes, then
+    /// to change ID for determinism.
+    pub async fn plan_merge(&self, target_bookmark: &str) -> Result<MergePlan> {
+        let changes = self.changes.lock().await.clone();
+
+        let mut pending_parents: HashMap<&str, usize> = changes
+            .values()
+            .map(|c| {
+                let tracked = c.parents.iter().filter(|p| changes.contains_key(*p)).count();
+                (c.change_id.as_str(), tracked)
+            })
+            .collect();
+
+        let mut merged_files: HashSet<&str> = HashSet::new();
+        let mut order: Vec<String> = Vec::with_capacity(changes.len());
+
+        while order.len() < changes.len() {
+            let next = pending_parents
+                .iter()
+                .filter(|(_, pending)| **pending == 0)
+                .map(|(id, _)| &changes[*id])
+                .min_by_key(|c| {
+                    let overlap = c.files.iter().filter(|f| merged_files.contains(f.as_str())).count();
+                    (overlap, c.files.len(), c.change_id.clone())
+                })
+                .ok_or_else(|| {
+                    JJError::ConflictResolutionFailed("Cycle detected in change graph".to_string())
+                })?;
+
+            pending_parents.remove(next.change_id.as_str());
+            for child in changes.values() {
+                if child.parents.contains(&next.change_id) {
+                    if let Some(pending) = pending_parents.get_mut(child.change_id.as_str()) {
+                        *pending -= 1;
+                    }
+                }
+            }
+
+            merged_files.extend(next.files.iter().map(|f| f.as_str()));
+            order.push(next.change_id.clone());
+        }
+
+        let predicted_conflicts = Self::predict_conflicts(&changes, &order);
+
+        Ok(MergePlan {
+            target_bookmark: target_bookmark.to_string(),
+            order,
+            predicted_conflicts,
+        })
+    }
+
+    fn predict_conflicts(changes: &HashMap<String, AgentChange>, order: &[String]) -> Vec<PredictedConflict> {
+        let ancestors = |id: &str| -> HashSet<String> {
+            let mut seen = HashSet::new();
+            let mut stack = vec![id.to_string()];
+            while let Some(current) = stack.pop() {
+                if let Some(change) = changes.get(&current) {
+                    for parent in &change.parents {
+                        if seen.insert(parent.clone()) {
+                            stack.push(parent.clone());
+                        }
+                    }
+                }
+            }
+            seen
+        };
+
+        let mut conflicts = Vec::new();
+        for (i, first) in order.iter().enumerate() {
+            for second in &order[i + 1..] {
+                if ancestors(second).contains(first) {
+                    continue;
+                }
+
+                let first_files: BTreeSet<&String> = changes[first].files.iter().collect();
+                let shared: Vec<String> = changes[second]
+                    .files
+                    .iter()
+                    .filter(|f| first_files.contains(f))
+                    .cloned()
+                    .collect();
+
+                if !shared.is_empty() {
+                    conflicts.push(PredictedConflict {
+                        first: first.clone(),
+                        second: second.clone(),
+                        files: shared,
+                    });
+                }
+            }
+        }
+        conflicts
+    }
+
+    /// Integrate changes onto the plan's bookmark in merge order
+    ///
+    /// Each change is rebased onto the bookmark, which is then advanced to the
+    /// change. Returns the change IDs that were integrated; stops at the first
+    /// failure.
+    ///
+    /// A rebase that leaves the change or its descendants conflicted is undone
+    /// and reported as [`JJError::Conflict`], so the bookmark never moves onto
+    /// conflicted commits.
+    pub async fn integrate(&self, wrapper: &JJWrapper, plan: &MergePlan) -> Result<Vec<String>> {
+        let bookmark = plan.target_bookmark.clone();
+        let mut integrated = Vec::with_capacity(plan.order.len());
+
+        for change_id in &plan.order {
+            run(wrapper, vec!["rebase", "-s", change_id, "-d", &bookmark]).await?;
+
+            let rebased = format!("conflicts() & ({}::)", change_id);
+            let conflicted = run(
+                wrapper,
+                vec!["log", "--no-graph", "-r", &rebased, "-T", r#"change_id ++ "\n""#],
+            )
+            .await?;
+            if let Some(conflicted) = non_empty_lines(&conflicted).first() {
+                let paths = run(wrapper, vec!["resolve", "--list", "-r", conflicted])
+                    .await
+                    .map(|listing| conflicted_paths(&listing))
+                    .unwrap_or_default();
+                run(wrapper, vec!["undo"]).await?;
+                return Err(JJError::Conflict {
+                    paths,
+                    exit_code: 0,
+                    stderr: format!(
+                        "Rebasing {} onto {} produced conflicts in {}",
+                        change_id, bookmark, conflicted
+                    ),
+                });
+            }
+
+            run(wrapper, vec!["bookmark", "set", &bookmark, "-r", change_id]).await?;
+            integrated.push(change_id.clone());
+        }
+
+        Ok(integrated)
+    }
+}
+
+impl Default for SwarmCoordinator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Template printing `change_id<TAB>commit_id<TAB>first description line`
+const CHANGE_TEMPLATE: &str =
+    r#"change_id ++ "\t" ++ commit_id ++ "\t" ++ description.first_line() ++ "\n""#;
+
+async fn run(wrapper: &JJWrapper, args: Vec<&str>) -> Result<String> {
+    let args: Vec<String> = args.into_iter().map(String::from).collect();
+    wrapper
+        .execute(args)
+        .await
+        .map(|result| result.stdout)
+        .map_err(|e| JJError::CommandFailed(e.reason))
+}
+
+/// Paths listed by `jj resolve --list`, one `<path>  <conflict kind>` per line
+fn conflicted_paths(listing: &str) -> Vec<String> {
+    listing
+        .lines()
+        .filter_map(|line| line.split("  ").next())
+        .map(|path| path.trim().to_string())
+        .filter(|path| !path.is_empty())
+        .collect()
+}
+
+/// Read change metadata for a revision via jj templates
+async fn read_change(wrapper: &JJWrapper, agent_id: &str, revision: &str) -> Result<AgentChange> {
+    let header = run(wrapper, vec!["log", "--no-graph", "-r", revision, "-T", CHANGE_TEMPLATE]).await?;
+    let (change_id, commit_id, description) = parse_change_line(&header)
+        .ok_or_else(|| JJError::ParseError(format!("Unexpected jj log output: {}", header)))?;
+
+    let parents_rev = format!("parents({})", change_id);
+    let parents = run(
+        wrapper,
+        vec!["log", "--no-graph", "-r", &parents_rev, "-T", r#"change_id ++ "\n""#],
+    )
+    .await?;
+
+    let files = run(wrapper, vec!["diff", "-r", &change_id, "--name-only"]).await?;
+
+    Ok(AgentChange {
+        agent_id: agent_id.to_string(),
+        change_id,
+        commit_id,
+        parents: non_empty_lines(&parents),
+        files: non_empty_lines(&files),
+        description,
+    })
+}
+
+fn parse_change_line(output: &str) -> Option<(String, String, String)> {
+    let line = output.lines().find(|l| !l.trim().is_empty())?;
+    let mut parts = line.splitn(3, '\t');
+    let change_id = parts.next()?.trim().to_string();
+    let commit_id = parts.next()?.trim().to_string();
+    let description = parts.next().unwrap_or("").trim().to_string();
+
+    if change_id.is_empty() || commit_id.is_empty() {
+        return None;
+    }
+    Some((change_id, commit_id, description))
+}
+
+fn non_empty_lines(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::JJConfig;
+    use std::path::Path;
+    use std::process::Command;
+
+    fn change(agent: &str, id: &str, parents: &[&str], files: &[&str]) -> AgentChange {
+        AgentChange {
+            agent_id: agent.to_string(),
+            change_id: id.to_string(),
+            commit_id: format!("commit-{}", id),
+            parents: parents.iter().map(|s| s.to_string()).collect(),
+            files: files.iter().map(|s| s.to_string()).collect(),
+            description: format!("change {}", id),
+        }
+    }
+
+    #[test]
+    fn test_parse_change_line() {
+        let (change, commit, desc) = parse_change_line("kxyz\tabc123\tAdd auth\n").unwrap();
+        assert_eq!(change, "kxyz");
+        assert_eq!(commit, "abc123");
+        assert_eq!(desc, "Add auth");
+        assert!(parse_change_line("\n").is_none());
+    }
+
+    #[test]
+    fn test_conflicted_paths() {
+        let listing = "shared.txt    2-sided conflict\n\
+                       src/a b.rs    2-sided conflict including 1 deletion\n";
+        assert_eq!(conflicted_paths(listing), vec!["shared.txt", "src/a b.rs"]);
+        assert!(conflicted_paths("").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_assignment_prefers_owner() {
+        let swarm = SwarmCoordinator::new();
+        swarm.register_agent("alice", "coder").await.unwrap();
+        swarm.register_agent("bob", "coder").await.unwrap();
+
+        swarm
+            .track_change(change("bob", "c1", &["root"], &["src/auth.rs"]))
+            .await
+            .unwrap();
+
+        let task = SwarmTask::new("t1", "Fix auth", vec!["src/auth.rs".to_string()]);
+        let assignment = swarm.assign_task(&task).await.unwrap();
+        assert_eq!(assignment.agent_id, "bob");
+        assert_eq!(assignment.owned_files, 1);
+        assert_eq!(assignment.leases.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_assignment_respects_leases() {
+        let swarm = SwarmCoordinator::new();
+        swarm.register_agent("alice", "coder").await.unwrap();
+        swarm.register_agent("bob", "coder").await.unwrap();
+
+        let first = swarm
+            .assign_task(&SwarmTask::new("t1", "Auth", vec!["src/auth/**".to_string()]))
+            .await
+            .unwrap();
+
+        // The owner is preferred, but it already holds the lease itself
+        let second = swarm
+            .assign_task(&SwarmTask::new("t2", "Login", vec!["src/auth/login.rs".to_string()]))
+            .await
+            .unwrap();
+        assert_eq!(second.agent_id, first.agent_id);
+
+        // Files locked outside the swarm block every agent
+        swarm
+            .coordination()
+            .acquire_lease("external", "docs/**", LeaseMode::Exclusive, Duration::from_secs(60), None)
+            .await
+            .unwrap();
+        let err = swarm
+            .assign_task(&SwarmTask::new("t3", "Docs", vec!["docs/readme.md".to_string()]))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, JJError::LeaseConflict { ref holder, .. } if holder == "external"));
+
+        // Completing a task keeps the leases of the agent's other tasks
+        assert_eq!(swarm.complete_task(&first.agent_id, "t1").await.unwrap(), 1);
+        let other = if first.agent_id == "alice" { "bob" } else { "alice" };
+        assert!(swarm
+            .coordination()
+            .acquire_lease(other, "src/auth/login.rs", LeaseMode::Exclusive, Duration::from_secs(60), None)
+            .await
+            .is_err());
+        assert!(swarm
+            .coordination()
+            .acquire_lease(other, "src/auth/session.rs", LeaseMode::Exclusive, Duration::from_secs(60), None)
+            .await
+            .is_ok());
+        assert_eq!(swarm.complete_task(&first.agent_id, "t2").await.unwrap(), 1);
+        assert_eq!(swarm.complete_task(&first.agent_id, "t2").await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_tracked_changes_update_dag() {
+        let swarm = SwarmCoordinator::new();
+        swarm.track_change(change("a", "c1", &["root"], &["a.rs"])).await.unwrap();
+        swarm.track_change(change("a", "c2", &["c1"], &["a.rs"])).await.unwrap();
+        swarm.track_change(change("b", "c3", &["root"], &["b.rs"])).await.unwrap();
+
+        let tips = swarm.coordination().get_coordination_tips().await.unwrap();
+        assert_eq!(tips, vec!["c2".to_string(), "c3".to_string()]);
+
+        // Rewriting a change keeps a single vertex per change ID
+        let mut rewritten = change("b", "c3", &["root"], &["b.rs"]);
+        rewritten.commit_id = "commit-c3-v2".to_string();
+        swarm.track_change(rewritten).await.unwrap();
+        assert_eq!(swarm.changes().await.len(), 3);
+        assert_eq!(
+            swarm.coordination().get_dag_vertex("commit-c3-v2").await.unwrap(),
+            Some("c3".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_merge_order_is_topological_and_conflict_minimizing() {
+        let swarm = SwarmCoordinator::new();
+        swarm.track_change(change("a", "a1", &["main"], &["shared.rs", "a.rs"])).await.unwrap();
+        swarm.track_change(change("a", "a2", &["a1"], &["a.rs"])).await.unwrap();
+        swarm.track_change(change("b", "b1", &["main"], &["shared.rs"])).await.unwrap();
+        swarm.track_change(change("c", "c1", &["main"], &["c.rs"])).await.unwrap();
+
+        let plan = swarm.plan_merge("main").await.unwrap();
+        assert_eq!(plan.target_bookmark, "main");
+
+        let pos = |id: &str| plan.order.iter().position(|c| c == id).unwrap();
+        assert!(pos("a1") < pos("a2"));
+        // b1 and a1 overlap on shared.rs, so whichever lands second waits
+        // until the independent changes are in
+        assert_eq!(plan.order, vec!["b1", "c1", "a1", "a2"]);
+
+        assert_eq!(plan.predicted_conflicts.len(), 1);
+        assert_eq!(plan.predicted_conflicts[0].first, "b1");
+        assert_eq!(plan.predicted_conflicts[0].second, "a1");
+        assert_eq!(plan.predicted_conflicts[0].files, vec!["shared.rs".to_string()]);
+    }
+
+    fn jj(repo: &Path, args: &[&str]) -> String {
+        let output = Command::new("jj")
+            .args(args)
+            .current_dir(repo)
+            .env("JJ_USER", "Swarm Test")
+            .env("JJ_EMAIL", "swarm@example.com")
+            .output()
+            .expect("failed to run jj");
+        assert!(
+            output.status.success(),
+            "jj {:?} failed: {}",
+            args,
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8_lossy(&output.stdout).to_string()
+    }
+
+    fn init_repo(repo: &Path) {
+        jj(repo, &["git", "init"]);
+        // Commands run through the wrapper pick the identity up from here
+        jj(repo, &["config", "set", "--repo", "user.name", "Swarm Test"]);
+        jj(repo, &["config", "set", "--repo", "user.email", "swarm@example.com"]);
+        std::fs::write(repo.join("shared.txt"), "base\n").unwrap();
+        jj(repo, &["commit", "-m", "base"]);
+        jj(repo, &["bookmark", "create", "main", "-r", "@-"]);
+    }
+
+    /// Multi-agent simulation against a temporary jj repository
+    ///
+    /// Run with `cargo test -- --ignored` where `jj` is on PATH.
+    #[tokio::test]
+    #[ignore = "requires jj on PATH"]
+    async fn test_multi_agent_simulation() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path();
+        init_repo(repo);
+
+        let config = JJConfig::default()
+            .with_jj_path("jj".to_string())
+            .with_repo_path(repo.to_string_lossy().to_string());
+        let wrapper = JJWrapper::with_config(config).unwrap();
+
+        let swarm = SwarmCoordinator::new();
+        for agent in ["alice", "bob", "carol"] {
+            swarm.register_agent(agent, "coder").await.unwrap();
+        }
+
+        // Three tasks, each worked on as its own change off main
+        let tasks: [&[(&str, &str)]; 3] = [
+            &[("a.txt", "first\n"), ("shared.txt", "first\n")],
+            &[("b.txt", "second\n")],
+            &[("shared.txt", "third\n"), ("c.txt", "third\n")],
+        ];
+
+        let mut change_ids = Vec::new();
+        let mut assignees = Vec::new();
+        for (i, files) in tasks.iter().enumerate() {
+            let paths: Vec<String> = files.iter().map(|(p, _)| p.to_string()).collect();
+            let task = SwarmTask::new(&format!("task-{}", i), "simulated work", paths);
+            let assignment = swarm.assign_task(&task).await.unwrap();
+
+            jj(repo, &["new", "main", "-m", &format!("task {}", i)]);
+            for (path, content) in files.iter() {
+                std::fs::write(repo.join(path), content).unwrap();
+            }
+
+            let change = swarm
+                .record_change(&wrapper, &assignment.agent_id, "@")
+                .await
+                .unwrap();
+            assert_eq!(change.parents.len(), 1);
+            assert_eq!(change.files.len(), files.len());
+
+            change_ids.push(change.change_id);
+            assignees.push(assignment.agent_id);
+        }
+
+        // Load balancing spreads independent work; the owner of shared.txt
+        // receives the later task touching it
+        assert_eq!(assignees, vec!["alice", "bob", "alice"]);
+        assert_eq!(swarm.owner_of("shared.txt").await.as_deref(), Some("alice"));
+
+        let plan = swarm.plan_merge("main").await.unwrap();
+        assert_eq!(plan.order.len(), 3);
+        // The independent change lands first; the two shared.txt edits overlap
+        assert_eq!(plan.order[0], change_ids[1]);
+        assert_eq!(plan.predicted_conflicts.len(), 1);
+        assert_eq!(plan.predicted_conflicts[0].files, vec!["shared.txt".to_string()]);
+
+        // The second shared.txt edit conflicts once the first has landed
+        let err = swarm.integrate(&wrapper, &plan).await.unwrap_err();
+        assert!(matches!(err, JJError::Conflict { ref paths, .. } if paths == &["shared.txt"]));
+
+        let change_ids_of = |revset: &str| {
+            jj(repo, &["log", "--no-graph", "-r", revset, "-T", r#"change_id ++ "\n""#])
+        };
+        assert_eq!(change_ids_of("main").trim(), plan.order[1]);
+        assert!(change_ids_of("conflicts()").trim().is_empty());
+        let on_main = change_ids_of("::main");
+        assert!(!on_main.lines().any(|l| l.trim() == plan.order[2]));
+    }
+}
@@ -0,0 +1,591 @@
+//! File-level leases for multi-agent coordination
+//!
+//! Agents acquire shared or exclusive leases on file paths or glob patterns
+//! before touching them. Leases carry a TTL, can be renewed, and are reaped
+//! once they expire so a crashed agent cannot block others forever.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use agentic_jujutsu::leases::{LeaseManager, LeaseMode};
+//! use std::time::Duration;
+//!
+//! # tokio_test::block_on(async {
+//! let manager = LeaseManager::new();
+//! let lease = manager
+//!     .acquire("coder-1", "src/auth/**", LeaseMode::Exclusive, Duration::from_secs(60), None)
+//!     .await
+//!     .unwrap();
+//!
+//! // A second agent cannot take the same files while the lease is held
+//! let denied = manager
+//!     .acquire("coder-2", "src/auth/login.rs", LeaseMode::Shared, Duration::from_secs(60), None)
+//!     .await;
+//! assert!(denied.is_err());
+//!
+//! manager.release(&lease.lease_id).await.unwrap();
+//! # });
+//! ```
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{Mutex, Notify};
+use uuid::Uuid;
+
+use crate::error::{JJError, Result};
+
+/// Lease access mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LeaseMode {
+    /// Multiple agents may hold shared leases on the same files
+    Shared,
+    /// Only one agent may hold the files, excluding all other leases
+    Exclusive,
+}
+
+impl LeaseMode {
+    /// Check if a lease in this mode can coexist with one in `other` mode
+    pub fn is_compatible_with(&self, other: LeaseMode) -> bool {
+        matches!((self, other), (LeaseMode::Shared, LeaseMode::Shared))
+    }
+
+    /// Parse from string ("shared" or "exclusive")
+    pub fn from_string(s: &str) -> Option<LeaseMode> {
+        match s.to_lowercase().as_str() {
+            "shared" | "read" => Some(LeaseMode::Shared),
+            "exclusive" | "write" => Some(LeaseMode::Exclusive),
+            _ => None,
+        }
+    }
+}
+
+/// A lease held by an agent on a path or glob pattern
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileLease {
+    /// Unique lease identifier
+    pub lease_id: String,
+    /// Agent holding the lease
+    pub agent_id: String,
+    /// File path or glob pattern covered by the lease
+    pub pattern: String,
+    /// Access mode
+    pub mode: LeaseMode,
+    /// When the lease was first acquired
+    pub acquired_at: DateTime<Utc>,
+    /// When the lease expires unless renewed
+    pub expires_at: DateTime<Utc>,
+}
+
+impl FileLease {
+    /// Check if the lease has expired
+    pub fn is_expired(&self) -> bool {
+        self.expires_at <= Utc::now()
+    }
+
+    /// Check if this lease covers the given path or pattern
+    pub fn overlaps(&self, pattern: &str) -> bool {
+        patterns_overlap(&self.pattern, pattern)
+    }
+
+    /// Check if this lease blocks an acquisition by `agent_id` in `mode`
+    fn conflicts_with(&self, agent_id: &str, pattern: &str, mode: LeaseMode) -> bool {
+        self.agent_id != agent_id
+            && !self.is_expired()
+            && !self.mode.is_compatible_with(mode)
+            && self.overlaps(pattern)
+    }
+}
+
+/// Lease table shared between agents
+///
+/// Conflicting acquisitions either fail immediately with
+/// [`JJError::LeaseConflict`] naming the holder, or wait for the holder to
+/// release (or the lease to expire) up to a caller-provided timeout.
+#[derive(Debug, Clone, Default)]
+pub struct LeaseManager {
+    /// Active leases keyed by lease ID
+    leases: Arc<Mutex<HashMap<String, FileLease>>>,
+    /// Wakes waiters whenever leases are released or reaped
+    released: Arc<Notify>,
+}
+
+impl LeaseManager {
+    /// Create an empty lease manager
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Acquire a lease on `pattern` for `agent_id`
+    ///
+    /// Leases held by the same agent never conflict with each other. When
+    /// `wait` is `None` a conflicting acquisition fails immediately; otherwise
+    /// it is retried until the conflict clears or `wait` elapses.
+    pub async fn acquire(
+        &self,
+        agent_id: &str,
+        pattern: &str,
+        mode: LeaseMode,
+        ttl: Duration,
+        wait: Option<Duration>,
+    ) -> Result<FileLease> {
+        if pattern.trim().is_empty() {
+            return Err(JJError::InvalidConfig("Lease pattern cannot be empty".to_string()));
+        }
+
+        let deadline = wait.map(|w| tokio::time::Instant::now() + w);
+
+        loop {
+            // Register interest before checking so a release between the
+            // check and the wait is not missed
+            let notified = self.released.notified();
+
+            match self.try_acquire(agent_id, pattern, mode, ttl).await {
+                Ok(lease) => return Ok(lease),
+                Err(conflict) => {
+                    let Some(deadline) = deadline else {
+                        return Err(conflict);
+                    };
+
+                    // Wake up no later than the holder's expiry so expired
+                    // leases are reaped even without an explicit release
+                    let expiry = self.next_expiry(agent_id, pattern, mode).await;
+                    let wake_at = match expiry {
+                        Some(at) if at < deadline => at,
+                        _ => deadline,
+                    };
+
+                    if tokio::time::timeout_at(wake_at, notified).await.is_err()
+                        && tokio::time::Instant::now() >= deadline
+                    {
+                        // Final attempt in case the lease expired exactly at the deadline
+                        return self.try_acquire(agent_id, pattern, mode, ttl).await;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Single non-blocking acquisition attempt
+    async fn try_acquire(
+        &self,
+        agent_id: &str,
+        pattern: &str,
+        mode: LeaseMode,
+        ttl: Duration,
+    ) -> Result<FileLease> {
+        let mut leases = self.leases.lock().await;
+        Self::reap_locked(&mut leases);
+
+        if let Some(holder) = leases
+            .values()
+            .find(|lease| lease.conflicts_with(agent_id, pattern, mode))
+        {
+            return Err(JJError::LeaseConflict {
+                pattern: pattern.to_string(),
+                holder: holder.agent_id.clone(),
+                lease_id: holder.lease_id.clone(),
+            });
+        }
+
+        let now = Utc::now();
+        let lease = FileLease {
+            lease_id: Uuid::new_v4().to_string(),
+            agent_id: agent_id.to_string(),
+            pattern: pattern.to_string(),
+            mode,
+            acquired_at: now,
+            expires_at: now + to_chrono(ttl),
+        };
+
+        leases.insert(lease.lease_id.clone(), lease.clone());
+        Ok(lease)
+    }
+
+    /// Instant at which the earliest conflicting lease expires
+    async fn next_expiry(
+        &self,
+        agent_id: &str,
+        pattern: &str,
+        mode: LeaseMode,
+    ) -> Option<tokio::time::Instant> {
+        let leases = self.leases.lock().await;
+        let earliest = leases
+            .values()
+            .filter(|lease| lease.conflicts_with(agent_id, pattern, mode))
+            .map(|lease| lease.expires_at)
+            .min()?;
+
+        let remaining = (earliest - Utc::now()).to_std().unwrap_or(Duration::ZERO);
+        Some(tokio::time::Instant::now() + remaining)
+    }
+
+    /// Acquire several leases atomically
+    ///
+    /// Either every lease is acquired or none are: leases taken before a
+    /// conflict are released again before the error is returned.
+    pub async fn acquire_all(
+        &self,
+        agent_id: &str,
+        requests: &[(String, LeaseMode)],
+        ttl: Duration,
+        wait: Option<Duration>,
+    ) -> Result<Vec<FileLease>> {
+        let mut acquired = Vec::with_capacity(requests.len());
+
+        for (pattern, mode) in requests {
+            match self.acquire(agent_id, pattern, *mode, ttl, wait).await {
+                Ok(lease) => acquired.push(lease),
+                Err(e) => {
+                    for lease in &acquired {
+                        let _ = self.release(&lease.lease_id).await;
+                    }
+                    return Err(e);
+                }
+            }
+        }
+
+        Ok(acquired)
+    }
+
+    /// Extend a lease's expiry to `ttl` from now
+    pub async fn renew(&self, lease_id: &str, ttl: Duration) -> Result<FileLease> {
+        let mut leases = self.leases.lock().await;
+
+        match leases.get_mut(lease_id) {
+            Some(lease) if !lease.is_expired() => {
+                lease.expires_at = Utc::now() + to_chrono(ttl);
+                Ok(lease.clone())
+            }
+            Some(_) => {
+                leases.remove(lease_id);
+                self.released.notify_waiters();
+                Err(JJError::LeaseNotFound(format!("{} (expired)", lease_id)))
+            }
+            None => Err(JJError::LeaseNotFound(lease_id.to_string())),
+        }
+    }
+
+    /// Release a lease by ID
+    pub async fn release(&self, lease_id: &str) -> Result<()> {
+        let removed = self.leases.lock().await.remove(lease_id);
+        self.released.notify_waiters();
+
+        removed
+            .map(|_| ())
+            .ok_or_else(|| JJError::LeaseNotFound(lease_id.to_string()))
+    }
+
+    /// Release every lease held by an agent, returning how many were released
+    pub async fn release_agent(&self, agent_id: &str) -> usize {
+        let mut leases = self.leases.lock().await;
+        let before = leases.len();
+        leases.retain(|_, lease| lease.agent_id != agent_id);
+        let released = before - leases.len();
+        drop(leases);
+
+        if released > 0 {
+            self.released.notify_waiters();
+        }
+        released
+    }
+
+    /// Remove expired leases, returning the ones that were reaped
+    pub async fn reap_expired(&self) -> Vec<FileLease> {
+        let reaped = Self::reap_locked(&mut *self.leases.lock().await);
+        if !reaped.is_empty() {
+            self.released.notify_waiters();
+        }
+        reaped
+    }
+
+    fn reap_locked(leases: &mut HashMap<String, FileLease>) -> Vec<FileLease> {
+        let expired: Vec<String> = leases
+            .values()
+            .filter(|lease| lease.is_expired())
+            .map(|lease| lease.lease_id.clone())
+            .collect();
+
+        expired
+            .iter()
+            .filter_map(|id| leases.remove(id))
+            .collect()
+    }
+
+    /// Get active (non-expired) leases covering a path or pattern
+    pub async fn holders(&self, pattern: &str) -> Vec<FileLease> {
+        let leases = self.leases.lock().await;
+        leases
+            .values()
+            .filter(|lease| !lease.is_expired() && lease.overlaps(pattern))
+            .cloned()
+            .collect()
+    }
+
+    /// Get active leases held by an agent
+    pub async fn agent_leases(&self, agent_id: &str) -> Vec<FileLease> {
+        let leases = self.leases.lock().await;
+        leases
+            .values()
+            .filter(|lease| !lease.is_expired() && lease.agent_id == agent_id)
+            .cloned()
+            .collect()
+    }
+
+    /// List all active leases
+    pub async fn list(&self) -> Vec<FileLease> {
+        let leases = self.leases.lock().await;
+        leases
+            .values()
+            .filter(|lease| !lease.is_expired())
+            .cloned()
+            .collect()
+    }
+}
+
+fn to_chrono(duration: Duration) -> chrono::Duration {
+    chrono::Duration::from_std(duration).unwrap_or_else(|_| chrono::Duration::days(365))
+}
+
+/// Check whether two paths or glob patterns may refer to the same file
+///
+/// This is deliberately conservative: two wildcard patterns overlap whenever
+/// one's literal prefix is a prefix of the other's, since proving that two
+/// globs are disjoint in general is not worth the complexity for locking.
+pub fn patterns_overlap(a: &str, b: &str) -> bool {
+    let a = normalize(a);
+    let b = normalize(b);
+
+    if a == b {
+        return true;
+    }
+
+    let a_glob = has_wildcards(a);
+    let b_glob = has_wildcards(b);
+
+    match (a_glob, b_glob) {
+        (false, false) => false,
+        (true, false) => glob_matches(a, b),
+        (false, true) => glob_matches(b, a),
+        (true, true) => {
+            let prefix_a = literal_prefix(a);
+            let prefix_b = literal_prefix(b);
+            prefix_a.starts_with(prefix_b) || prefix_b.starts_with(prefix_a)
+        }
+    }
+}
+
+fn normalize(pattern: &str) -> &str {
+    pattern.trim().trim_start_matches("./")
+}
+
+fn has_wildcards(pattern: &str) -> bool {
+    pattern.contains(['*', '?', '['])
+}
+
+fn literal_prefix(pattern: &str) -> &str {
+    let end = pattern.find(['*', '?', '[']).unwrap_or(pattern.len());
+    &pattern[..end]
+}
+
+fn glob_matches(pattern: &str, path: &str) -> bool {
+    let options = glob::MatchOptions {
+        case_sensitive: true,
+        require_literal_separator: true,
+        require_literal_leading_dot: false,
+    };
+
+    glob::Pattern::new(pattern)
+        .map(|p| p.matches_with(path, options))
+        // An unparsable pattern is treated as a literal prefix match
+        .unwrap_or_else(|_| path.starts_with(literal_prefix(pattern)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TTL: Duration = Duration::from_secs(60);
+
+    #[test]
+    fn test_patterns_overlap() {
+        assert!(patterns_overlap("src/lib.rs", "src/lib.rs"));
+        assert!(patterns_overlap("./src/lib.rs", "src/lib.rs"));
+        assert!(patterns_overlap("src/**", "src/auth/login.rs"));
+        assert!(patterns_overlap("src/*.rs", "src/lib.rs"));
+        assert!(!patterns_overlap("src/*.rs", "src/auth/login.rs"));
+        assert!(!patterns_overlap("src/lib.rs", "src/main.rs"));
+        assert!(patterns_overlap("src/**/*.rs", "src/auth/*"));
+        assert!(!patterns_overlap("docs/**", "src/**"));
+    }
+
+    #[tokio::test]
+    async fn test_exclusive_blocks_other_agents() {
+        let manager = LeaseManager::new();
+        manager
+            .acquire("agent-1", "src/**", LeaseMode::Exclusive, TTL, None)
+            .await
+            .unwrap();
+
+        let err = manager
+            .acquire("agent-2", "src/lib.rs", LeaseMode::Shared, TTL, None)
+            .await
+            .unwrap_err();
+
+        match err {
+            JJError::LeaseConflict { holder, .. } => assert_eq!(holder, "agent-1"),
+            other => panic!("Expected LeaseConflict, got {:?}", other),
+        }
+
+        // The holder itself may take further leases on the same files
+        assert!(manager
+            .acquire("agent-1", "src/lib.rs", LeaseMode::Exclusive, TTL, None)
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_shared_leases_coexist() {
+        let manager = LeaseManager::new();
+        manager
+            .acquire("agent-1", "Cargo.toml", LeaseMode::Shared, TTL, None)
+            .await
+            .unwrap();
+        manager
+            .acquire("agent-2", "Cargo.toml", LeaseMode::Shared, TTL, None)
+            .await
+            .unwrap();
+
+        assert_eq!(manager.holders("Cargo.toml").await.len(), 2);
+        assert!(manager
+            .acquire("agent-3", "Cargo.toml", LeaseMode::Exclusive, TTL, None)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_release() {
+        let manager = LeaseManager::new();
+        let lease = manager
+            .acquire("agent-1", "src/lib.rs", LeaseMode::Exclusive, TTL, None)
+            .await
+            .unwrap();
+
+        let waiter = {
+            let manager = manager.clone();
+            tokio::spawn(async move {
+                manager
+                    .acquire(
+                        "agent-2",
+                        "src/lib.rs",
+                        LeaseMode::Exclusive,
+                        TTL,
+                        Some(Duration::from_secs(5)),
+                    )
+                    .await
+            })
+        };
+
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        manager.release(&lease.lease_id).await.unwrap();
+
+        let acquired = waiter.await.unwrap().unwrap();
+        assert_eq!(acquired.agent_id, "agent-2");
+    }
+
+    #[tokio::test]
+    async fn test_wait_times_out_with_holder() {
+        let manager = LeaseManager::new();
+        manager
+            .acquire("agent-1", "src/lib.rs", LeaseMode::Exclusive, TTL, None)
+            .await
+            .unwrap();
+
+        let err = manager
+            .acquire(
+                "agent-2",
+                "src/lib.rs",
+                LeaseMode::Exclusive,
+                TTL,
+                Some(Duration::from_millis(30)),
+            )
+            .await
+            .unwrap_err();
+
+        assert!(matches!(err, JJError::LeaseConflict { ref holder, .. } if holder == "agent-1"));
+    }
+
+    #[tokio::test]
+    async fn test_expired_leases_are_reaped() {
+        let manager = LeaseManager::new();
+        manager
+            .acquire("agent-1", "src/lib.rs", LeaseMode::Exclusive, Duration::from_millis(10), None)
+            .await
+            .unwrap();
+
+        // A waiter is woken once the holder's lease expires
+        let lease = manager
+            .acquire(
+                "agent-2",
+                "src/lib.rs",
+                LeaseMode::Exclusive,
+                TTL,
+                Some(Duration::from_secs(2)),
+            )
+            .await
+            .unwrap();
+        assert_eq!(lease.agent_id, "agent-2");
+
+        manager
+            .acquire("agent-3", "docs/**", LeaseMode::Shared, Duration::from_millis(1), None)
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(5)).await;
+
+        let reaped = manager.reap_expired().await;
+        assert_eq!(reaped.len(), 1);
+        assert_eq!(reaped[0].agent_id, "agent-3");
+    }
+
+    #[tokio::test]
+    async fn test_renew_and_release_agent() {
+        let manager = LeaseManager::new();
+        let lease = manager
+            .acquire("agent-1", "src/lib.rs", LeaseMode::Exclusive, Duration::from_secs(1), None)
+            .await
+            .unwrap();
+
+        let renewed = manager.renew(&lease.lease_id, TTL).await.unwrap();
+        assert!(renewed.expires_at > lease.expires_at);
+
+        manager
+            .acquire("agent-1", "src/main.rs", LeaseMode::Shared, TTL, None)
+            .await
+            .unwrap();
+        assert_eq!(manager.release_agent("agent-1").await, 2);
+        assert!(manager.list().await.is_empty());
+        assert!(matches!(
+            manager.renew(&lease.lease_id, TTL).await,
+            Err(JJError::LeaseNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_acquire_all_is_atomic() {
+        let manager = LeaseManager::new();
+        manager
+            .acquire("agent-1", "src/main.rs", LeaseMode::Exclusive, TTL, None)
+            .await
+            .unwrap();
+
+        let requests = vec![
+            ("src/lib.rs".to_string(), LeaseMode::Exclusive),
+            ("src/main.rs".to_string(), LeaseMode::Exclusive),
+        ];
+        assert!(manager.acquire_all("agent-2", &requests, TTL, None).await.is_err());
+        assert!(manager.agent_leases("agent-2").await.is_empty());
+    }
+}
@@ -4,7 +4,7 @@
 //!
 //! ## Features
 //!
-//! - Zero-dependency installation (jj binary embedded)
+//! - Runs the jj on `PATH`, or a bundled one where the build pins its digest
 //! - Zero-copy jj CLI operations
 //! - Operation log parsing and tracking
 //! - Conflict detection and resolution
@@ -15,31 +15,89 @@
 #![warn(missing_docs)]
 #![deny(unsafe_code)]
 
+pub mod command_parser;
+pub mod crypto;
+pub mod error;
+pub mod operations;
+pub mod types;
+pub mod virtual_repo;
+#[cfg(target_arch = "wasm32")]
+pub mod wasm;
+
+// Native only: these run jj as a process or expose N-API bindings. The WASM
+// build works on the in-memory repository of `virtual_repo`.
+#[cfg(not(target_arch = "wasm32"))]
 pub mod agent_coordination;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod agentdb_sync;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod commit_message;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod config;
-pub mod crypto;
-pub mod error;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod daemon;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod embedded;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod hooks;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod integrations;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod leases;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod mcp;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod metrics;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod native;
-pub mod operations;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod policy;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod quantum_signing;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod reasoning_bank;
-pub mod types;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod review;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod split;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod wrapper;
 
 // Re-exports
-pub use agent_coordination::{AgentConflict, AgentCoordination, AgentStats, CoordinationStats};
-pub use agentdb_sync::{AgentDBEpisode, AgentDBSync, TaskStatistics};
-pub use config::JJConfig;
 pub use crypto::{generate_signing_keypair, OperationSignature, SigningKeypair as MLDSAKeypair};
 pub use error::{JJError, Result};
-pub use hooks::{HookContext, HookEventType, JJHookEvent, JJHooksIntegration};
 pub use operations::{JJOperation, JJOperationLog, OperationType};
+pub use types::{JJBranch, JJCommit, JJConflict, JJResult};
+pub use virtual_repo::VirtualRepo;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use agent_coordination::{
+    AgentConflict, AgentCoordination, AgentStats, ConflictRule, ConflictRules,
+    CoordinationStats, OperationFlag, RuleAction,
+};
+#[cfg(not(target_arch = "wasm32"))]
+pub use agentdb_sync::{AgentDBEpisode, AgentDBSync, TaskStatistics};
+#[cfg(not(target_arch = "wasm32"))]
+pub use config::JJConfig;
+#[cfg(not(target_arch = "wasm32"))]
+pub use commit_message::{generate_commit_message, MessageContext};
+#[cfg(not(target_arch = "wasm32"))]
+pub use integrations::{MergePlan, SwarmCoordinator, SwarmTask, TaskAssignment};
+#[cfg(not(target_arch = "wasm32"))]
+pub use hooks::{HookContext, HookEventType, JJHookEvent, JJHooksIntegration};
+#[cfg(not(target_arch = "wasm32"))]
+pub use leases::{FileLease, LeaseManager, LeaseMode};
+#[cfg(not(target_arch = "wasm32"))]
+pub use policy::{override_message, PolicyOverride, ProtectionPolicy};
+#[cfg(not(target_arch = "wasm32"))]
 pub use quantum_signing::{CommitSignature, QuantumSigner, SigningKeypair};
+#[cfg(not(target_arch = "wasm32"))]
 pub use reasoning_bank::{DecisionSuggestion, LearningStats, Pattern, ReasoningBank, Trajectory};
-pub use types::{JJBranch, JJCommit, JJConflict, JJResult};
+#[cfg(not(target_arch = "wasm32"))]
+pub use review::{Review, ReviewComment, ReviewStore, ReviewVerdict, Verdict};
+#[cfg(not(target_arch = "wasm32"))]
+pub use split::{DiffHunkInfo, HunkRef, SplitChange, SplitGroup, SplitPlan};
+#[cfg(not(target_arch = "wasm32"))]
 pub use wrapper::JJWrapper;
 
 /// Version of the agentic-jujutsu crate
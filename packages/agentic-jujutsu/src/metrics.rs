@@ -0,0 +1,311 @@
+//! Operation metrics in Prometheus text format
+//!
+//! [`JJWrapper`](crate::JJWrapper) records every jj invocation, conflict
+//! detection and hook request into the process-wide [`global`] registry.
+//! [`MetricsRegistry::render`] produces the text exposition format served by
+//! `jj-agent-hook metrics` and the daemon socket.
+//!
+//! Exported series:
+//!
+//! | Metric | Type | Labels |
+//! |--------|------|--------|
+//! | `agentic_jujutsu_operations_total` | counter | `operation_type`, `outcome` |
+//! | `agentic_jujutsu_operation_duration_seconds` | histogram | `operation_type` |
+//! | `agentic_jujutsu_conflicts_detected_total` | counter | `source` |
+//! | `agentic_jujutsu_hook_requests_total` | counter | `hook`, `outcome` |
+//! | `agentic_jujutsu_hook_duration_seconds` | histogram | `hook` |
+//!
+//! `outcome` is `success` or the failing error's [`code`](crate::JJError::code).
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+const OPERATIONS_TOTAL: &str = "agentic_jujutsu_operations_total";
+const OPERATION_DURATION: &str = "agentic_jujutsu_operation_duration_seconds";
+const CONFLICTS_DETECTED: &str = "agentic_jujutsu_conflicts_detected_total";
+const HOOK_REQUESTS_TOTAL: &str = "agentic_jujutsu_hook_requests_total";
+const HOOK_DURATION: &str = "agentic_jujutsu_hook_duration_seconds";
+
+/// Metric families in exposition order: name, type, help
+const FAMILIES: &[(&str, &str, &str)] = &[
+    (OPERATIONS_TOTAL, "counter", "jj commands executed"),
+    (
+        OPERATION_DURATION,
+        "histogram",
+        "jj command duration in seconds",
+    ),
+    (CONFLICTS_DETECTED, "counter", "Conflicts detected"),
+    (HOOK_REQUESTS_TOTAL, "counter", "Hook requests handled"),
+    (
+        HOOK_DURATION,
+        "histogram",
+        "Hook request latency in seconds",
+    ),
+];
+
+/// Histogram bucket upper bounds in seconds
+const BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0,
+];
+
+/// Label set of a series, as `(name, value)` pairs in declaration order
+type Labels = Vec<(&'static str, String)>;
+
+#[derive(Debug, Clone, Default)]
+struct Histogram {
+    /// Non-cumulative counts per bucket, plus one for `+Inf`
+    buckets: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, value: f64) {
+        if self.buckets.is_empty() {
+            self.buckets = vec![0; BUCKETS.len() + 1];
+        }
+        let index = BUCKETS
+            .iter()
+            .position(|bound| value <= *bound)
+            .unwrap_or(BUCKETS.len());
+        self.buckets[index] += 1;
+        self.sum += value;
+        self.count += 1;
+    }
+}
+
+#[derive(Debug, Default)]
+struct Series {
+    counters: BTreeMap<(&'static str, Labels), u64>,
+    histograms: BTreeMap<(&'static str, Labels), Histogram>,
+}
+
+/// Counters and histograms for jj operations and hooks
+#[derive(Debug, Default)]
+pub struct MetricsRegistry {
+    series: Mutex<Series>,
+}
+
+/// Process-wide registry used by the wrapper and the hook daemon
+pub fn global() -> &'static MetricsRegistry {
+    static REGISTRY: OnceLock<MetricsRegistry> = OnceLock::new();
+    REGISTRY.get_or_init(MetricsRegistry::new)
+}
+
+impl MetricsRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a finished jj command
+    pub fn record_operation(&self, operation_type: &str, outcome: &str, duration: Duration) {
+        let mut series = self.series.lock().unwrap();
+        *series
+            .counters
+            .entry((
+                OPERATIONS_TOTAL,
+                vec![
+                    ("operation_type", operation_type.to_string()),
+                    ("outcome", outcome.to_string()),
+                ],
+            ))
+            .or_default() += 1;
+        series
+            .histograms
+            .entry((
+                OPERATION_DURATION,
+                vec![("operation_type", operation_type.to_string())],
+            ))
+            .or_default()
+            .observe(duration.as_secs_f64());
+    }
+
+    /// Record `count` conflicts found by `source`
+    pub fn record_conflicts(&self, source: &str, count: usize) {
+        let mut series = self.series.lock().unwrap();
+        *series
+            .counters
+            .entry((CONFLICTS_DETECTED, vec![("source", source.to_string())]))
+            .or_default() += count as u64;
+    }
+
+    /// Record a handled hook request
+    pub fn record_hook(&self, hook: &str, outcome: &str, duration: Duration) {
+        let mut series = self.series.lock().unwrap();
+        *series
+            .counters
+            .entry((
+                HOOK_REQUESTS_TOTAL,
+                vec![("hook", hook.to_string()), ("outcome", outcome.to_string())],
+            ))
+            .or_default() += 1;
+        series
+            .histograms
+            .entry((HOOK_DURATION, vec![("hook", hook.to_string())]))
+            .or_default()
+            .observe(duration.as_secs_f64());
+    }
+
+    /// Render all series in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let series = self.series.lock().unwrap();
+        let mut out = String::new();
+
+        for (name, kind, help) in FAMILIES {
+            let _ = writeln!(out, "# HELP {} {}", name, help);
+            let _ = writeln!(out, "# TYPE {} {}", name, kind);
+
+            for ((_, labels), value) in series.counters.iter().filter(|((n, _), _)| n == name) {
+                let _ = writeln!(out, "{}{} {}", name, format_labels(labels, None), value);
+            }
+
+            for ((_, labels), histogram) in series.histograms.iter().filter(|((n, _), _)| n == name)
+            {
+                let mut cumulative = 0;
+                for (i, count) in histogram.buckets.iter().enumerate() {
+                    cumulative += count;
+                    let le = BUCKETS
+                        .get(i)
+                        .map(|bound| bound.to_string())
+                        .unwrap_or_else(|| "+Inf".to_string());
+                    let _ = writeln!(
+                        out,
+                        "{}_bucket{} {}",
+                        name,
+                        format_labels(labels, Some(&le)),
+                        cumulative
+                    );
+                }
+                let _ = writeln!(
+                    out,
+                    "{}_sum{} {}",
+                    name,
+                    format_labels(labels, None),
+                    histogram.sum
+                );
+                let _ = writeln!(
+                    out,
+                    "{}_count{} {}",
+                    name,
+                    format_labels(labels, None),
+                    histogram.count
+                );
+            }
+        }
+
+        out
+    }
+}
+
+fn format_labels(labels: &Labels, le: Option<&str>) -> String {
+    let mut pairs: Vec<String> = labels
+        .iter()
+        .map(|(name, value)| format!("{}=\"{}\"", name, escape_label(value)))
+        .collect();
+    if let Some(le) = le {
+        pairs.push(format!("le=\"{}\"", le));
+    }
+
+    if pairs.is_empty() {
+        String::new()
+    } else {
+        format!("{{{}}}", pairs.join(","))
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty_registry_lists_families() {
+        let text = MetricsRegistry::new().render();
+        for (name, kind, _) in FAMILIES {
+            assert!(text.contains(&format!("# TYPE {} {}\n", name, kind)));
+        }
+        assert!(!text.lines().any(|line| !line.starts_with('#')));
+    }
+
+    #[test]
+    fn test_operation_series() {
+        let registry = MetricsRegistry::new();
+        registry.record_operation("Describe", "success", Duration::from_millis(20));
+        registry.record_operation("Describe", "success", Duration::from_millis(300));
+        registry.record_operation("Rebase", "CONFLICT", Duration::from_secs(60));
+
+        let text = registry.render();
+        assert!(text.contains(
+            "agentic_jujutsu_operations_total{operation_type=\"Describe\",outcome=\"success\"} 2\n"
+        ));
+        assert!(text.contains(
+            "agentic_jujutsu_operations_total{operation_type=\"Rebase\",outcome=\"CONFLICT\"} 1\n"
+        ));
+        assert!(text.contains(
+            "agentic_jujutsu_operation_duration_seconds_bucket{operation_type=\"Describe\",le=\"0.025\"} 1\n"
+        ));
+        assert!(text.contains(
+            "agentic_jujutsu_operation_duration_seconds_bucket{operation_type=\"Describe\",le=\"0.5\"} 2\n"
+        ));
+        assert!(text.contains(
+            "agentic_jujutsu_operation_duration_seconds_bucket{operation_type=\"Rebase\",le=\"30\"} 0\n"
+        ));
+        assert!(text.contains(
+            "agentic_jujutsu_operation_duration_seconds_bucket{operation_type=\"Rebase\",le=\"+Inf\"} 1\n"
+        ));
+        assert!(text.contains(
+            "agentic_jujutsu_operation_duration_seconds_sum{operation_type=\"Describe\"} 0.32\n"
+        ));
+        assert!(text.contains(
+            "agentic_jujutsu_operation_duration_seconds_count{operation_type=\"Describe\"} 2\n"
+        ));
+    }
+
+    #[test]
+    fn test_series_grouped_under_their_family() {
+        let registry = MetricsRegistry::new();
+        registry.record_conflicts("working_copy", 2);
+        registry.record_conflicts("working_copy", 1);
+        registry.record_conflicts("agents", 0);
+        registry.record_hook("post_edit", "success", Duration::from_millis(3));
+
+        let text = registry.render();
+        let lines: Vec<&str> = text.lines().collect();
+        let position = |needle: &str| lines.iter().position(|l| l.starts_with(needle)).unwrap();
+
+        assert!(
+            text.contains("agentic_jujutsu_conflicts_detected_total{source=\"working_copy\"} 3\n")
+        );
+        assert!(text.contains("agentic_jujutsu_conflicts_detected_total{source=\"agents\"} 0\n"));
+        assert!(text.contains(
+            "agentic_jujutsu_hook_requests_total{hook=\"post_edit\",outcome=\"success\"} 1\n"
+        ));
+        assert!(text.contains(
+            "agentic_jujutsu_hook_duration_seconds_bucket{hook=\"post_edit\",le=\"0.005\"} 1\n"
+        ));
+
+        let conflicts = position("agentic_jujutsu_conflicts_detected_total{");
+        assert!(conflicts > position("# TYPE agentic_jujutsu_conflicts_detected_total"));
+        assert!(conflicts < position("# TYPE agentic_jujutsu_hook_requests_total"));
+        let hooks = position("agentic_jujutsu_hook_requests_total{");
+        assert!(hooks < position("# TYPE agentic_jujutsu_hook_duration_seconds"));
+    }
+
+    #[test]
+    fn test_label_values_are_escaped() {
+        let registry = MetricsRegistry::new();
+        registry.record_conflicts("a\"b\\c\nd", 1);
+        assert!(registry
+            .render()
+            .contains("agentic_jujutsu_conflicts_detected_total{source=\"a\\\"b\\\\c\\nd\"} 1\n"));
+    }
+}
@@ -4,6 +4,7 @@
 #![cfg(not(target_arch = "wasm32"))]
 
 use crate::error::{JJError, Result};
+use std::path::Path;
 use std::time::Duration;
 use async_process::{Command, Stdio};
 use tokio::time::timeout;
@@ -14,6 +15,44 @@ pub async fn execute_jj_command(
     args: &[&str],
     command_timeout: Duration,
 ) -> Result<String> {
+    execute_jj_command_in(jj_path, None, args, command_timeout).await
+}
+
+/// Execute a jj command in a specific working directory
+///
+/// `repo_path` of `None` runs the command in the current directory.
+pub async fn execute_jj_command_in(
+    jj_path: &str,
+    repo_path: Option<&Path>,
+    args: &[&str],
+    command_timeout: Duration,
+) -> Result<String> {
+    run_jj_command(jj_path, repo_path, args, command_timeout)
+        .await
+        .map(|output| output.stdout)
+}
+
+/// Captured output of a successful jj invocation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandOutput {
+    /// Standard output
+    pub stdout: String,
+    /// Standard error (jj prints warnings and hints here even on success)
+    pub stderr: String,
+    /// Process exit code
+    pub exit_code: i32,
+}
+
+/// Run a jj command and capture stdout and stderr separately
+///
+/// A non-zero exit is classified from stderr into a typed [`JJError`]; a
+/// timeout becomes [`JJError::Timeout`].
+pub async fn run_jj_command(
+    jj_path: &str,
+    repo_path: Option<&Path>,
+    args: &[&str],
+    command_timeout: Duration,
+) -> Result<CommandOutput> {
     // Build the command
     let mut cmd = Command::new(jj_path);
     cmd.args(args)
@@ -21,10 +60,16 @@ pub async fn execute_jj_command(
         .stdout(Stdio::piped())
         .stderr(Stdio::piped());
 
+    if let Some(dir) = repo_path {
+        cmd.current_dir(dir);
+    }
+
     // Execute with timeout
     let output = timeout(command_timeout, cmd.output())
         .await
-        .map_err(|_| JJError::CommandFailed("Command timeout exceeded".to_string()))?
+        .map_err(|_| JJError::Timeout {
+            timeout_ms: command_timeout.as_millis() as u64,
+        })?
         .map_err(|e| {
             if e.kind() == std::io::ErrorKind::NotFound {
                 JJError::JJNotFound
@@ -33,15 +78,87 @@ pub async fn execute_jj_command(
             }
         })?;
 
+    let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+    // No exit code when the process was killed by a signal
+    let exit_code = output.status.code().unwrap_or(-1);
+
     // Check exit status
     if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
-        return Err(JJError::CommandFailed(stderr));
+        return Err(JJError::from_command_output(exit_code, &stderr));
     }
 
-    // Return stdout
-    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-    Ok(stdout)
+    Ok(CommandOutput {
+        stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+        stderr,
+        exit_code,
+    })
+}
+
+/// Backoff policy for retrying commands that hit a concurrent modification
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt (0 disables retrying)
+    pub max_retries: u32,
+    /// Delay before the first retry; doubled for every further retry
+    pub initial_backoff: Duration,
+    /// Upper bound for a single delay
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(50),
+            max_backoff: Duration::from_secs(1),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Policy that never retries
+    pub fn none() -> Self {
+        Self {
+            max_retries: 0,
+            ..Self::default()
+        }
+    }
+
+    /// Set the number of retries
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Delay before retry number `retry` (starting at 0)
+    pub fn backoff(&self, retry: u32) -> Duration {
+        self.initial_backoff
+            .saturating_mul(2u32.saturating_pow(retry))
+            .min(self.max_backoff)
+    }
+}
+
+/// Run a jj command, retrying with exponential backoff while it fails with
+/// [`JJError::ConcurrentModification`]
+///
+/// Other errors are returned immediately. The timeout applies to each attempt.
+pub async fn run_jj_command_with_retry(
+    jj_path: &str,
+    repo_path: Option<&Path>,
+    args: &[&str],
+    command_timeout: Duration,
+    policy: &RetryPolicy,
+) -> Result<CommandOutput> {
+    let mut retry = 0;
+    loop {
+        match run_jj_command(jj_path, repo_path, args, command_timeout).await {
+            Err(e) if e.is_retryable() && retry < policy.max_retries => {
+                tokio::time::sleep(policy.backoff(retry)).await;
+                retry += 1;
+            }
+            result => return result,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -71,10 +188,10 @@ mod tests {
         let result = execute_jj_command("sleep", &["10"], Duration::from_millis(100)).await;
 
         assert!(result.is_err());
-        if let Err(JJError::CommandFailed(msg)) = result {
-            assert!(msg.contains("timeout"));
+        if let Err(JJError::Timeout { timeout_ms }) = result {
+            assert_eq!(timeout_ms, 100);
         } else {
-            panic!("Expected CommandFailed with timeout");
+            panic!("Expected Timeout error");
         }
     }
 
@@ -87,6 +204,16 @@ mod tests {
         assert_eq!(result.unwrap().trim(), "test");
     }
 
+    #[tokio::test]
+    async fn test_command_in_directory() {
+        let dir = std::env::temp_dir();
+        let result = execute_jj_command_in("pwd", Some(&dir), &[], Duration::from_secs(5)).await;
+
+        let expected = dir.canonicalize().unwrap();
+        let actual = std::path::PathBuf::from(result.unwrap().trim()).canonicalize().unwrap();
+        assert_eq!(actual, expected);
+    }
+
     #[tokio::test]
     async fn test_failed_command() {
         // Test with a command that will fail
@@ -95,4 +222,107 @@ mod tests {
 
         assert!(result.is_err());
     }
+
+    #[tokio::test]
+    async fn test_failure_preserves_exit_code_and_stderr() {
+        let script = "echo 'Error: Revision `nope` doesn'\\''t exist' >&2; exit 3";
+        let result = run_jj_command("sh", None, &["-c", script], Duration::from_secs(5)).await;
+
+        match result {
+            Err(JJError::RevisionNotFound {
+                revision,
+                exit_code,
+                stderr,
+            }) => {
+                assert_eq!(revision, "nope");
+                assert_eq!(exit_code, 3);
+                assert!(stderr.starts_with("Error: Revision"));
+            }
+            other => panic!("Expected RevisionNotFound, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_success_separates_stdout_and_stderr() {
+        let script = "echo out; echo 'Warning: careful' >&2";
+        let output = run_jj_command("sh", None, &["-c", script], Duration::from_secs(5))
+            .await
+            .unwrap();
+
+        assert_eq!(output.stdout, "out\n");
+        assert_eq!(output.stderr, "Warning: careful\n");
+        assert_eq!(output.exit_code, 0);
+    }
+
+    #[test]
+    fn test_backoff_is_exponential_and_capped() {
+        let policy = RetryPolicy {
+            max_retries: 10,
+            initial_backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_millis(50),
+        };
+        assert_eq!(policy.backoff(0), Duration::from_millis(10));
+        assert_eq!(policy.backoff(1), Duration::from_millis(20));
+        assert_eq!(policy.backoff(2), Duration::from_millis(40));
+        assert_eq!(policy.backoff(3), Duration::from_millis(50));
+        assert_eq!(policy.backoff(40), Duration::from_millis(50));
+    }
+
+    #[tokio::test]
+    async fn test_retries_concurrent_modification() {
+        // Fails to lock the working copy until the marker file exists
+        let dir = tempfile::tempdir().unwrap();
+        let script = "if [ -e attempted ]; then echo done; else touch attempted; \
+                      echo 'Internal error: Failed to lock working copy' >&2; exit 1; fi";
+        let policy = RetryPolicy {
+            max_retries: 2,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(5),
+        };
+
+        let output = run_jj_command_with_retry(
+            "sh",
+            Some(dir.path()),
+            &["-c", script],
+            Duration::from_secs(5),
+            &policy,
+        )
+        .await
+        .unwrap();
+        assert_eq!(output.stdout.trim(), "done");
+
+        // Without retries the first failure is returned as-is
+        std::fs::remove_file(dir.path().join("attempted")).unwrap();
+        let result = run_jj_command_with_retry(
+            "sh",
+            Some(dir.path()),
+            &["-c", script],
+            Duration::from_secs(5),
+            &RetryPolicy::none(),
+        )
+        .await;
+        assert!(matches!(
+            result,
+            Err(JJError::ConcurrentModification { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_does_not_retry_other_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let script = "echo x >> attempts; echo 'Error: Commit abc is immutable' >&2; exit 1";
+
+        let result = run_jj_command_with_retry(
+            "sh",
+            Some(dir.path()),
+            &["-c", script],
+            Duration::from_secs(5),
+            &RetryPolicy::default(),
+        )
+        .await;
+
+        assert!(matches!(result, Err(JJError::ImmutableCommit { .. })));
+        let attempts = std::fs::read_to_string(dir.path().join("attempts")).unwrap();
+        assert_eq!(attempts.lines().count(), 1);
+    }
 }
@@ -27,6 +27,7 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use uuid::Uuid;
+#[cfg(not(target_arch = "wasm32"))]
 use napi_derive::napi;
 
 use crate::error::{JJError, Result};
@@ -36,7 +37,7 @@ use crate::crypto::{hash_operation_data, sign_message_internal, verify_signature
 ///
 /// Represents the various operations that can be performed in a jujutsu repository.
 #[derive(Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
-#[napi]
+#[cfg_attr(not(target_arch = "wasm32"), napi)]
 pub enum OperationType {
     /// Create a new commit
     Commit,
@@ -104,6 +105,98 @@ pub enum OperationType {
     Diff,
     /// Unknown operation type
     Unknown,
+    // Variants below were added later; keep new ones at the end so the
+    // discriminants exposed through napi stay stable
+    /// Move changes into the mutable ancestors that last touched them
+    Absorb,
+    /// Bisect history to find a bad revision
+    Bisect,
+    /// Create a bookmark
+    BookmarkCreate,
+    /// Delete a bookmark
+    BookmarkDelete,
+    /// Forget a bookmark without propagating the deletion
+    BookmarkForget,
+    /// List bookmarks
+    BookmarkList,
+    /// Move existing bookmarks
+    BookmarkMove,
+    /// Rename a bookmark
+    BookmarkRename,
+    /// Create or update a bookmark
+    BookmarkSet,
+    /// Track a remote bookmark
+    BookmarkTrack,
+    /// Stop tracking a remote bookmark
+    BookmarkUntrack,
+    /// Read or modify configuration
+    Config,
+    /// Show how a change evolved
+    Evolog,
+    /// Inspect file contents or listings
+    File,
+    /// Change the executable bit of files
+    FileChmod,
+    /// Start tracking files
+    FileTrack,
+    /// Stop tracking files
+    FileUntrack,
+    /// Run formatters on changed files
+    Fix,
+    /// Upload changes to Gerrit
+    GerritUpload,
+    /// Manage Git remotes
+    GitRemote,
+    /// Show help
+    Help,
+    /// Compare the changes of two commits
+    Interdiff,
+    /// Modify commit metadata
+    Metaedit,
+    /// Move the working copy to a child
+    Next,
+    /// Move the working copy to a parent
+    Prev,
+    /// Abandon operations
+    OpAbandon,
+    /// Compare repository states between operations
+    OpDiff,
+    /// Show the operation log
+    OpLog,
+    /// Restore the repository to an earlier operation
+    OpRestore,
+    /// Revert an operation
+    OpRevert,
+    /// Show an operation
+    OpShow,
+    /// Make revisions siblings
+    Parallelize,
+    /// Redo the last undone operation
+    Redo,
+    /// Apply the reverse of revisions
+    Revert,
+    /// Show the workspace root
+    Root,
+    /// Show commit description and changes
+    Show,
+    /// Cryptographically sign revisions
+    Sign,
+    /// Remove redundant parents
+    SimplifyParents,
+    /// Manage sparse checkout patterns
+    Sparse,
+    /// Delete a tag
+    TagDelete,
+    /// List tags
+    TagList,
+    /// Drop signatures from revisions
+    Unsign,
+    /// Utility commands
+    Util,
+    /// Show the jj version
+    Version,
+    /// Manage workspaces
+    Workspace,
 }
 
 impl OperationType {
@@ -143,6 +236,51 @@ impl OperationType {
             OperationType::Status => "Status".to_string(),
             OperationType::Log => "Log".to_string(),
             OperationType::Diff => "Diff".to_string(),
+            OperationType::Absorb => "Absorb".to_string(),
+            OperationType::Bisect => "Bisect".to_string(),
+            OperationType::BookmarkCreate => "BookmarkCreate".to_string(),
+            OperationType::BookmarkDelete => "BookmarkDelete".to_string(),
+            OperationType::BookmarkForget => "BookmarkForget".to_string(),
+            OperationType::BookmarkList => "BookmarkList".to_string(),
+            OperationType::BookmarkMove => "BookmarkMove".to_string(),
+            OperationType::BookmarkRename => "BookmarkRename".to_string(),
+            OperationType::BookmarkSet => "BookmarkSet".to_string(),
+            OperationType::BookmarkTrack => "BookmarkTrack".to_string(),
+            OperationType::BookmarkUntrack => "BookmarkUntrack".to_string(),
+            OperationType::Config => "Config".to_string(),
+            OperationType::Evolog => "Evolog".to_string(),
+            OperationType::File => "File".to_string(),
+            OperationType::FileChmod => "FileChmod".to_string(),
+            OperationType::FileTrack => "FileTrack".to_string(),
+            OperationType::FileUntrack => "FileUntrack".to_string(),
+            OperationType::Fix => "Fix".to_string(),
+            OperationType::GerritUpload => "GerritUpload".to_string(),
+            OperationType::GitRemote => "GitRemote".to_string(),
+            OperationType::Help => "Help".to_string(),
+            OperationType::Interdiff => "Interdiff".to_string(),
+            OperationType::Metaedit => "Metaedit".to_string(),
+            OperationType::Next => "Next".to_string(),
+            OperationType::Prev => "Prev".to_string(),
+            OperationType::OpAbandon => "OpAbandon".to_string(),
+            OperationType::OpDiff => "OpDiff".to_string(),
+            OperationType::OpLog => "OpLog".to_string(),
+            OperationType::OpRestore => "OpRestore".to_string(),
+            OperationType::OpRevert => "OpRevert".to_string(),
+            OperationType::OpShow => "OpShow".to_string(),
+            OperationType::Parallelize => "Parallelize".to_string(),
+            OperationType::Redo => "Redo".to_string(),
+            OperationType::Revert => "Revert".to_string(),
+            OperationType::Root => "Root".to_string(),
+            OperationType::Show => "Show".to_string(),
+            OperationType::Sign => "Sign".to_string(),
+            OperationType::SimplifyParents => "SimplifyParents".to_string(),
+            OperationType::Sparse => "Sparse".to_string(),
+            OperationType::TagDelete => "TagDelete".to_string(),
+            OperationType::TagList => "TagList".to_string(),
+            OperationType::Unsign => "Unsign".to_string(),
+            OperationType::Util => "Util".to_string(),
+            OperationType::Version => "Version".to_string(),
+            OperationType::Workspace => "Workspace".to_string(),
             OperationType::Unknown => "Unknown".to_string(),
         }
     }
@@ -161,6 +299,13 @@ impl OperationType {
                 | OperationType::Split
                 | OperationType::Move
                 | OperationType::Merge
+                | OperationType::Absorb
+                | OperationType::Metaedit
+                | OperationType::Parallelize
+                | OperationType::SimplifyParents
+                | OperationType::Fix
+                | OperationType::Sign
+                | OperationType::Unsign
         )
     }
 
@@ -176,6 +321,7 @@ impl OperationType {
                 | OperationType::Clone
                 | OperationType::GitImport
                 | OperationType::GitExport
+                | OperationType::GerritUpload
         )
     }
 
@@ -188,8 +334,17 @@ impl OperationType {
 
 impl OperationType {
     /// Parse from string
+    ///
+    /// Accepts both the `as_string` form (`GitFetch`) and the kebab-case
+    /// form (`git-fetch`), case-insensitively.
     pub fn from_string(s: &str) -> OperationType {
-        match s.to_lowercase().as_str() {
+        let normalized: String = s
+            .chars()
+            .filter(|c| *c != '-' && *c != '_')
+            .flat_map(char::to_lowercase)
+            .collect();
+
+        match normalized.as_str() {
             "commit" => OperationType::Commit,
             "snapshot" => OperationType::Snapshot,
             "describe" => OperationType::Describe,
@@ -200,7 +355,7 @@ impl OperationType {
             "squash" => OperationType::Squash,
             "resolve" => OperationType::Resolve,
             "branch" => OperationType::Branch,
-            "branch-delete" => OperationType::BranchDelete,
+            "branchdelete" => OperationType::BranchDelete,
             "bookmark" => OperationType::Bookmark,
             "tag" => OperationType::Tag,
             "checkout" => OperationType::Checkout,
@@ -209,19 +364,64 @@ impl OperationType {
             "duplicate" => OperationType::Duplicate,
             "undo" => OperationType::Undo,
             "fetch" => OperationType::Fetch,
-            "git-fetch" => OperationType::GitFetch,
+            "gitfetch" => OperationType::GitFetch,
             "push" => OperationType::Push,
-            "git-push" => OperationType::GitPush,
+            "gitpush" => OperationType::GitPush,
             "clone" => OperationType::Clone,
             "init" => OperationType::Init,
-            "git-import" => OperationType::GitImport,
-            "git-export" => OperationType::GitExport,
+            "gitimport" => OperationType::GitImport,
+            "gitexport" => OperationType::GitExport,
             "move" => OperationType::Move,
             "diffedit" => OperationType::Diffedit,
             "merge" => OperationType::Merge,
             "status" => OperationType::Status,
             "log" => OperationType::Log,
             "diff" => OperationType::Diff,
+            "absorb" => OperationType::Absorb,
+            "bisect" => OperationType::Bisect,
+            "bookmarkcreate" => OperationType::BookmarkCreate,
+            "bookmarkdelete" => OperationType::BookmarkDelete,
+            "bookmarkforget" => OperationType::BookmarkForget,
+            "bookmarklist" => OperationType::BookmarkList,
+            "bookmarkmove" => OperationType::BookmarkMove,
+            "bookmarkrename" => OperationType::BookmarkRename,
+            "bookmarkset" => OperationType::BookmarkSet,
+            "bookmarktrack" => OperationType::BookmarkTrack,
+            "bookmarkuntrack" => OperationType::BookmarkUntrack,
+            "config" => OperationType::Config,
+            "evolog" => OperationType::Evolog,
+            "file" => OperationType::File,
+            "filechmod" => OperationType::FileChmod,
+            "filetrack" => OperationType::FileTrack,
+            "fileuntrack" => OperationType::FileUntrack,
+            "fix" => OperationType::Fix,
+            "gerritupload" => OperationType::GerritUpload,
+            "gitremote" => OperationType::GitRemote,
+            "help" => OperationType::Help,
+            "interdiff" => OperationType::Interdiff,
+            "metaedit" => OperationType::Metaedit,
+            "next" => OperationType::Next,
+            "prev" => OperationType::Prev,
+            "opabandon" => OperationType::OpAbandon,
+            "opdiff" => OperationType::OpDiff,
+            "oplog" => OperationType::OpLog,
+            "oprestore" => OperationType::OpRestore,
+            "oprevert" => OperationType::OpRevert,
+            "opshow" => OperationType::OpShow,
+            "parallelize" => OperationType::Parallelize,
+            "redo" => OperationType::Redo,
+            "revert" => OperationType::Revert,
+            "root" => OperationType::Root,
+            "show" => OperationType::Show,
+            "sign" => OperationType::Sign,
+            "simplifyparents" => OperationType::SimplifyParents,
+            "sparse" => OperationType::Sparse,
+            "tagdelete" => OperationType::TagDelete,
+            "taglist" => OperationType::TagList,
+            "unsign" => OperationType::Unsign,
+            "util" => OperationType::Util,
+            "version" => OperationType::Version,
+            "workspace" => OperationType::Workspace,
             _ => OperationType::Unknown,
         }
     }
@@ -243,7 +443,7 @@ impl OperationType {
 ///     .build();
 /// ```
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[napi(object)]
+#[cfg_attr(not(target_arch = "wasm32"), napi(object))]
 pub struct JJOperation {
     /// Unique operation ID (generated by wrapper)
     pub id: String,
@@ -292,6 +492,14 @@ pub struct JJOperation {
 
     /// Public key used for signature verification (hex-encoded, optional)
     pub signature_public_key: Option<String>,
+
+    /// Revisions (revsets) the command operated on
+    #[serde(default)]
+    pub revisions: Vec<String>,
+
+    /// Paths (filesets) the command operated on
+    #[serde(default)]
+    pub paths: Vec<String>,
 }
 
 impl JJOperation {
@@ -314,6 +522,8 @@ impl JJOperation {
             quantum_fingerprint: None,
             signature: None,
             signature_public_key: None,
+            revisions: Vec::new(),
+            paths: Vec::new(),
         }
     }
 
@@ -557,6 +767,8 @@ pub struct JJOperationBuilder {
     success: bool,
     error: Option<String>,
     quantum_fingerprint: Option<String>,
+    revisions: Vec<String>,
+    paths: Vec<String>,
 }
 
 impl Default for JJOperationBuilder {
@@ -574,6 +786,8 @@ impl Default for JJOperationBuilder {
             success: true, // Default to successful operations
             error: None,
             quantum_fingerprint: None,
+            revisions: Vec::new(),
+            paths: Vec::new(),
         }
     }
 }
@@ -652,6 +866,18 @@ impl JJOperationBuilder {
         self
     }
 
+    /// Set affected revisions
+    pub fn revisions(mut self, revisions: Vec<String>) -> Self {
+        self.revisions = revisions;
+        self
+    }
+
+    /// Set affected paths
+    pub fn paths(mut self, paths: Vec<String>) -> Self {
+        self.paths = paths;
+        self
+    }
+
     /// Build the operation
     pub fn build(self) -> JJOperation {
         JJOperation {
@@ -673,6 +899,8 @@ impl JJOperationBuilder {
             success: self.success,
             error: self.error,
             quantum_fingerprint: self.quantum_fingerprint,
+            revisions: self.revisions,
+            paths: self.paths,
         }
     }
 }
@@ -1179,6 +1407,34 @@ mod tests {
             OperationType::from_string("unknown_op"),
             OperationType::Unknown
         );
+
+        // Both the `as_string` and kebab-case forms round-trip
+        for op in [
+            OperationType::GitFetch,
+            OperationType::BranchDelete,
+            OperationType::BookmarkSet,
+            OperationType::OpRestore,
+            OperationType::SimplifyParents,
+        ] {
+            assert_eq!(OperationType::from_string(&op.as_string()), op);
+        }
+        assert_eq!(
+            OperationType::from_string("git-push"),
+            OperationType::GitPush
+        );
+        assert_eq!(
+            OperationType::from_string("simplify-parents"),
+            OperationType::SimplifyParents
+        );
+    }
+
+    #[test]
+    fn test_operation_type_discriminants_are_stable() {
+        // JS callers see these numbers through napi
+        assert_eq!(OperationType::Commit as i32, 0);
+        assert_eq!(OperationType::Diff as i32, 31);
+        assert_eq!(OperationType::Unknown as i32, 32);
+        assert_eq!(OperationType::Absorb as i32, 33);
     }
 
     #[test]
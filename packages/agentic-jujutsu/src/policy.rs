@@ -0,0 +1,663 @@
+//! Guardrails for mutating jj commands
+//!
+//! [`ProtectionPolicy`] checks every command run through
+//! [`JJWrapper`](crate::JJWrapper) against the `protected_revsets` and
+//! `protected_bookmarks` of [`JJConfig`]. Commands that would rewrite or
+//! abandon a protected revision, or delete, move, set or rename a protected
+//! bookmark, fail with [`JJError::ProtectedRevision`] or
+//! [`JJError::ProtectedBookmark`] before jj is invoked.
+//!
+//! Revision arguments are checked by asking jj whether they intersect the
+//! protected commits (`(<target>) & (<protected>)`), so commit IDs, ancestor
+//! ranges and other aliases of a protected revision are caught, while ranges
+//! that only start at one (`main..@`) are not. Protected bookmarks protect
+//! the commits they point to, locally and on remotes.
+//! Targets a command leaves at their default, like the `@` of a bare
+//! `jj describe`, are resolved first. `jj undo`, `jj redo` and operation
+//! restores are rejected when the protected commits differ between the
+//! restored operation and the one it replaces, compared with
+//! `at_operation()`.
+//!
+//! A violation can be overridden with a [`PolicyOverride`] signed by one of
+//! the configured `override_approvers`. The approver signs
+//! [`override_message`] for the exact command with their own key; the
+//! secret key never reaches this process. The override is recorded as a
+//! `PolicyOverride` entry in the operation log, and the executed operation
+//! references it.
+
+use crate::command_parser::ParsedCommand;
+use crate::config::JJConfig;
+use crate::crypto::verify_signature_internal;
+use crate::error::{JJError, Result};
+use crate::operations::OperationType;
+use napi_derive::napi;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+
+/// Approval to run a command that violates the protection policy
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[napi(object)]
+pub struct PolicyOverride {
+    /// Who approved the override
+    pub approved_by: String,
+    /// Why the protected target may be changed
+    pub reason: String,
+    /// Approver's public key (hex); must be one of `overrideApprovers`
+    pub public_key: String,
+    /// Approver's signature (hex) over `policyOverrideMessage` for the command
+    pub signature: String,
+}
+
+impl PolicyOverride {
+    /// Whether the signature approves running `args`
+    pub fn verify(&self, args: &[String]) -> Result<bool> {
+        let message = override_message(
+            args.to_vec(),
+            self.approved_by.clone(),
+            self.reason.clone(),
+        );
+        verify_signature_internal(&message, &self.signature, &self.public_key)
+    }
+}
+
+/// Message an approver signs to override the policy for one command
+///
+/// Covers the jj arguments, the approver and the reason, so the signature
+/// cannot be reused for a different command.
+#[napi(js_name = "policyOverrideMessage")]
+pub fn override_message(args: Vec<String>, approved_by: String, reason: String) -> Vec<u8> {
+    let mut message = b"agentic-jujutsu policy override\n".to_vec();
+    message.extend(serde_json::to_vec(&(args, approved_by, reason)).unwrap_or_default());
+    message
+}
+
+/// Protected revsets and bookmarks
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProtectionPolicy {
+    revsets: Vec<String>,
+    bookmarks: Vec<String>,
+}
+
+impl ProtectionPolicy {
+    /// Create a policy from protected revsets and bookmark patterns
+    pub fn new(revsets: Vec<String>, bookmarks: Vec<String>) -> Self {
+        Self { revsets, bookmarks }
+    }
+
+    /// Policy configured in `config`
+    pub fn from_config(config: &JJConfig) -> Self {
+        Self::new(
+            config.protected_revsets.clone().unwrap_or_default(),
+            config.protected_bookmarks.clone().unwrap_or_default(),
+        )
+    }
+
+    /// Whether nothing is protected
+    pub fn is_empty(&self) -> bool {
+        self.revsets.is_empty() && self.bookmarks.is_empty()
+    }
+
+    /// Check a command, returning the first violation
+    ///
+    /// `is_nonempty` evaluates a revset and reports whether it contains any
+    /// commit; the wrapper runs `jj log` for it. It is only called when
+    /// something is protected and the command rewrites revisions, moves
+    /// bookmarks by position or restores an earlier operation.
+    pub async fn check<F, Fut>(&self, command: &ParsedCommand, is_nonempty: F) -> Result<()>
+    where
+        F: Fn(String) -> Fut,
+        Fut: Future<Output = Result<bool>>,
+    {
+        if self.is_empty() {
+            return Ok(());
+        }
+        let operation = command.operation_type.as_string();
+
+        for bookmark in changed_bookmarks(command) {
+            if let Some(rule) = self.protecting_bookmark(&bookmark) {
+                return Err(JJError::ProtectedBookmark {
+                    operation,
+                    bookmark,
+                    rule: rule.to_string(),
+                });
+            }
+        }
+
+        let rules = self.protected_rules();
+
+        // Moving bookmarks by position moves every bookmark at the source
+        let sources = moved_bookmark_sources(command);
+        if !sources.is_empty() {
+            let bookmark_rules: Vec<(&str, String)> = self
+                .bookmarks
+                .iter()
+                .map(|rule| (rule.as_str(), format!("bookmarks(glob:{})", quote(rule))))
+                .collect();
+            let sources = union(sources.iter().map(String::as_str));
+            let query = |rules: &str| intersection(&sources, rules);
+            if let Some(rule) = violated_rule(&bookmark_rules, query, &is_nonempty).await? {
+                return Err(JJError::ProtectedBookmark {
+                    operation,
+                    bookmark: rule.clone(),
+                    rule,
+                });
+            }
+        }
+
+        // Undo and operation restores bring back whatever the protected
+        // commits were at another operation
+        if let Some((restored, replaced)) = restored_operations(command) {
+            let query = |rules: &str| state_change(&restored, &replaced, rules);
+            if let Some(rule) = violated_rule(&rules, query, &is_nonempty).await? {
+                return Err(JJError::ProtectedRevision {
+                    operation,
+                    revision: format!("operation {}", restored),
+                    rule,
+                });
+            }
+        }
+
+        let revisions = rewritten_revisions(command);
+        if revisions.is_empty() {
+            return Ok(());
+        }
+        let all_targets = union(revisions.iter().map(String::as_str));
+        let query = |rules: &str| intersection(&all_targets, rules);
+        let Some(rule) = violated_rule(&rules, query, &is_nonempty).await? else {
+            return Ok(());
+        };
+
+        // Narrow the violation down to one revision
+        if revisions.len() > 1 {
+            if let Some((_, revset)) = rules.iter().find(|(name, _)| *name == rule) {
+                for revision in &revisions {
+                    let query = intersection(&union([revision.as_str()]), &union([revset.as_str()]));
+                    if is_nonempty(query).await? {
+                        return Err(JJError::ProtectedRevision {
+                            operation,
+                            revision: revision.clone(),
+                            rule,
+                        });
+                    }
+                }
+            }
+        }
+        Err(JJError::ProtectedRevision {
+            operation,
+            revision: revisions.join(", "),
+            rule,
+        })
+    }
+
+    /// Protected bookmark pattern matching `name`
+    fn protecting_bookmark(&self, name: &str) -> Option<&str> {
+        let name = name
+            .strip_prefix("exact:")
+            .or_else(|| name.strip_prefix("glob:"))
+            .unwrap_or(name);
+        self.bookmarks
+            .iter()
+            .find(|rule| glob_matches(rule, name) || glob_matches(name, rule))
+            .map(String::as_str)
+    }
+
+    /// Each protected revset and bookmark pattern with the revset of commits
+    /// it protects
+    fn protected_rules(&self) -> Vec<(&str, String)> {
+        let revsets = self.revsets.iter().map(|rule| (rule.as_str(), rule.clone()));
+        let bookmarks = self.bookmarks.iter().map(|rule| {
+            let pattern = format!("glob:{}", quote(rule));
+            (
+                rule.as_str(),
+                format!("bookmarks({0}) | remote_bookmarks({0})", pattern),
+            )
+        });
+        revsets.chain(bookmarks).collect()
+    }
+}
+
+/// Revisions a command rewrites or abandons, as opposed to ones it only reads
+///
+/// Targets left at their default are resolved the way jj resolves them, so
+/// `jj describe` rewrites `@`, `jj squash` rewrites `@` and `@-`, and
+/// `jj rebase -d main` rewrites `@`'s branch.
+pub fn rewritten_revisions(command: &ParsedCommand) -> Vec<String> {
+    use OperationType as Op;
+
+    let given = |options: &[&str]| command.option_values(options);
+    let or_working_copy = |revisions: Vec<String>| {
+        if revisions.is_empty() {
+            vec!["@".to_string()]
+        } else {
+            revisions
+        }
+    };
+
+    match command.operation_type {
+        Op::Abandon | Op::Describe | Op::Metaedit | Op::Sign | Op::Unsign => {
+            or_working_copy(command.revisions.clone())
+        }
+        Op::Edit | Op::Parallelize => command.revisions.clone(),
+        Op::Commit => vec!["@".to_string()],
+        Op::Rebase => {
+            // Without -r, -s or -b jj rebases `-b @`
+            let mut revisions = or_working_copy(given(&[
+                "-r",
+                "--revisions",
+                "-s",
+                "--source",
+                "-b",
+                "--branch",
+            ]));
+            revisions.extend(given(&["-B", "--insert-before", "--before"]));
+            revisions
+        }
+        Op::Squash => squashed_revisions(command),
+        Op::Split | Op::Resolve => or_working_copy(given(&["-r", "--revision"])),
+        Op::Diffedit => or_working_copy(given(&["-r", "--revision", "-t", "--to"])),
+        Op::Restore => or_working_copy(given(&["-t", "--into", "--to", "-c", "--changes-in"])),
+        Op::Move => {
+            let mut revisions = or_working_copy(given(&["-f", "--from"]));
+            revisions.extend(or_working_copy(given(&["-t", "--to"])));
+            revisions.dedup();
+            revisions
+        }
+        Op::Absorb => {
+            let sources = or_working_copy(given(&["-f", "--from"]));
+            let mut revisions = given(&["-t", "--into", "--to"]);
+            if revisions.is_empty() {
+                // Absorbed into the mutable ancestors of the source
+                revisions = sources
+                    .iter()
+                    .map(|source| format!("::({}) & mutable()", source))
+                    .collect();
+            }
+            sources.into_iter().chain(revisions).collect()
+        }
+        Op::SimplifyParents => or_working_copy(given(&["-r", "--revisions", "-s", "--source"])),
+        Op::Fix => {
+            let sources = given(&["-s", "--source"]);
+            if sources.is_empty() {
+                vec!["reachable(@, mutable())".to_string()]
+            } else {
+                sources
+            }
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Sources and destinations of `jj squash` and `jj unsquash`
+///
+/// `-r X` moves X into its parent; otherwise `--from` defaults to `@` and
+/// `--into` to `@` when `--from` is given and to `@-` when it is not.
+fn squashed_revisions(command: &ParsedCommand) -> Vec<String> {
+    let given = |options: &[&str]| command.option_values(options);
+    let revisions = given(&["-r", "--revision"]);
+    let into = given(&["-t", "--into", "--to"]);
+    let before = given(&["-B", "--insert-before", "--before"]);
+
+    if !revisions.is_empty() {
+        let parents = revisions.iter().map(|revision| format!("({})-", revision));
+        let mut rewritten: Vec<String> = revisions.iter().cloned().chain(parents).collect();
+        rewritten.extend(into);
+        rewritten.extend(before);
+        return rewritten;
+    }
+
+    let from = given(&["-f", "--from"]);
+    let placed = !command
+        .option_values(&[
+            "-d",
+            "--destination",
+            "-o",
+            "--onto",
+            "-A",
+            "--insert-after",
+            "--after",
+        ])
+        .is_empty()
+        || !before.is_empty();
+    let into = match (into.is_empty(), placed, from.is_empty()) {
+        // A new commit receives the changes
+        (true, true, _) => Vec::new(),
+        (true, false, true) => vec!["@-".to_string()],
+        (true, false, false) => vec!["@".to_string()],
+        (false, _, _) => into,
+    };
+    let from = if from.is_empty() { vec!["@".to_string()] } else { from };
+    from.into_iter().chain(into).chain(before).collect()
+}
+
+/// Revisions whose bookmarks `jj bookmark move --from` moves
+pub fn moved_bookmark_sources(command: &ParsedCommand) -> Vec<String> {
+    match command.operation_type {
+        OperationType::BookmarkMove => command.option_values(&["-f", "--from"]),
+        _ => Vec::new(),
+    }
+}
+
+/// Operation whose repository state a command brings back, and the
+/// operation whose state it replaces
+///
+/// `jj undo` and `jj redo` are taken to restore the parent of the operation
+/// they act on, `jj op revert X` the parent of X.
+pub fn restored_operations(command: &ParsedCommand) -> Option<(String, String)> {
+    use OperationType as Op;
+
+    let operation = command.positionals.first().cloned();
+    match command.operation_type {
+        Op::OpRestore => Some((operation?, "@".to_string())),
+        Op::Undo | Op::Redo | Op::OpRevert => {
+            let operation = operation.unwrap_or_else(|| "@".to_string());
+            Some((format!("{}-", operation), operation))
+        }
+        _ => None,
+    }
+}
+
+/// Bookmarks a command deletes, forgets, moves, sets or renames
+pub fn changed_bookmarks(command: &ParsedCommand) -> Vec<String> {
+    let subcommand: Vec<&str> = command.subcommand.iter().map(String::as_str).collect();
+    match subcommand.as_slice() {
+        ["bookmark", "delete" | "forget" | "move" | "set" | "rename"] => {
+            command.positionals.clone()
+        }
+        _ => Vec::new(),
+    }
+}
+
+fn glob_matches(pattern: &str, name: &str) -> bool {
+    glob::Pattern::new(pattern)
+        .map(|p| p.matches(name))
+        .unwrap_or(pattern == name)
+}
+
+/// Union of revsets, each wrapped in `present()` so missing symbols count
+/// as empty
+fn union<'a>(revsets: impl IntoIterator<Item = &'a str>) -> String {
+    revsets
+        .into_iter()
+        .map(|revset| format!("present({})", revset))
+        .collect::<Vec<_>>()
+        .join(" | ")
+}
+
+fn intersection(a: &str, b: &str) -> String {
+    format!("({}) & ({})", a, b)
+}
+
+/// Commits in `revset` at one of two operations but not at the other
+fn state_change(a: &str, b: &str, revset: &str) -> String {
+    let at_a = format!("at_operation({}, {})", a, revset);
+    let at_b = format!("at_operation({}, {})", b, revset);
+    format!("({0} ~ {1}) | ({1} ~ {0})", at_a, at_b)
+}
+
+/// First rule whose protected commits `query` finds, if any
+///
+/// Asks about all rules at once, and only narrows a hit down rule by rule.
+async fn violated_rule<Q, F, Fut>(
+    rules: &[(&str, String)],
+    query: Q,
+    is_nonempty: &F,
+) -> Result<Option<String>>
+where
+    Q: Fn(&str) -> String,
+    F: Fn(String) -> Fut,
+    Fut: Future<Output = Result<bool>>,
+{
+    if rules.is_empty() {
+        return Ok(None);
+    }
+    let all_rules = union(rules.iter().map(|(_, revset)| revset.as_str()));
+    if !is_nonempty(query(&all_rules)).await? {
+        return Ok(None);
+    }
+    for (rule, revset) in rules {
+        if is_nonempty(query(&union([revset.as_str()]))).await? {
+            return Ok(Some(rule.to_string()));
+        }
+    }
+
+    // The repository changed between the queries; stay on the safe side
+    Ok(Some(all_rules))
+}
+
+/// Quote a string for a revset or fileset string literal
+pub(crate) fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command_parser::parse_jj_args;
+    use std::sync::Mutex;
+
+    fn policy() -> ProtectionPolicy {
+        ProtectionPolicy::new(
+            vec!["trunk()".to_string(), "main@origin".to_string()],
+            vec!["main".to_string(), "release/*".to_string()],
+        )
+    }
+
+    /// Check `args` against a repository where each (target, rule revset)
+    /// pair in `overlaps` shares a commit
+    async fn check_with(args: &str, overlaps: &[(&str, &str)]) -> Result<()> {
+        let args: Vec<&str> = args.split_whitespace().collect();
+        policy()
+            .check(&parse_jj_args(&args), |query| async move {
+                Ok(overlaps.iter().any(|(target, rule)| {
+                    query.contains(&format!("present({})", target))
+                        && query.contains(&format!("present({})", rule))
+                }))
+            })
+            .await
+    }
+
+    async fn check(args: &str) -> Result<()> {
+        check_with(args, &[]).await
+    }
+
+    const MAIN: &str = "bookmarks(glob:\"main\") | remote_bookmarks(glob:\"main\")";
+
+    #[test]
+    fn test_quote() {
+        assert_eq!(quote("release/*"), "\"release/*\"");
+        assert_eq!(quote("a\"b\\c"), "\"a\\\"b\\\\c\"");
+    }
+
+    #[tokio::test]
+    async fn test_query_intersects_targets_with_protected_set() {
+        let queries = Mutex::new(Vec::new());
+        let parsed = parse_jj_args(&["rebase", "-r", "main..@", "-d", "main"]);
+        policy()
+            .check(&parsed, |query| {
+                queries.lock().unwrap().push(query);
+                async { Ok(false) }
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(
+            queries.into_inner().unwrap(),
+            vec![format!(
+                "(present(main..@)) & (present(trunk()) | present(main@origin) | present({}) | \
+                 present(bookmarks(glob:\"release/*\") | remote_bookmarks(glob:\"release/*\")))",
+                MAIN
+            )]
+        );
+    }
+
+    #[test]
+    fn test_rewritten_revisions_skip_destinations() {
+        let parsed = parse_jj_args(&["rebase", "-s", "feat", "-d", "main"]);
+        assert_eq!(rewritten_revisions(&parsed), vec!["feat"]);
+
+        let parsed = parse_jj_args(&["squash", "--from", "a", "--into", "b", "src/"]);
+        assert_eq!(rewritten_revisions(&parsed), vec!["a", "b"]);
+
+        let parsed = parse_jj_args(&["new", "main"]);
+        assert!(rewritten_revisions(&parsed).is_empty());
+    }
+
+    #[test]
+    fn test_rewritten_revisions_resolve_defaults() {
+        let rewritten = |args: &str| {
+            let args: Vec<&str> = args.split_whitespace().collect();
+            rewritten_revisions(&parse_jj_args(&args))
+        };
+
+        assert_eq!(rewritten("describe -m msg"), vec!["@"]);
+        assert_eq!(rewritten("abandon"), vec!["@"]);
+        assert_eq!(rewritten("restore src/"), vec!["@"]);
+        assert_eq!(rewritten("restore -c feat"), vec!["feat"]);
+        assert_eq!(rewritten("rebase -d main"), vec!["@"]);
+        assert_eq!(rewritten("commit -m msg"), vec!["@"]);
+        assert_eq!(rewritten("split src/"), vec!["@"]);
+        assert_eq!(rewritten("squash"), vec!["@", "@-"]);
+        assert_eq!(rewritten("squash -r feat"), vec!["feat", "(feat)-"]);
+        assert_eq!(rewritten("unsquash"), vec!["@", "@-"]);
+        assert_eq!(rewritten("squash --from feat"), vec!["feat", "@"]);
+        assert_eq!(rewritten("squash --into feat"), vec!["@", "feat"]);
+        assert_eq!(rewritten("squash -B main"), vec!["@", "main"]);
+        assert_eq!(rewritten("absorb"), vec!["@", "::(@) & mutable()"]);
+        assert!(rewritten("new").is_empty());
+        assert!(rewritten("edit").is_empty());
+    }
+
+    #[test]
+    fn test_restored_operations() {
+        let restored = |args: &[&str]| restored_operations(&parse_jj_args(args));
+
+        assert_eq!(restored(&["undo"]), Some(("@-".to_string(), "@".to_string())));
+        assert_eq!(restored(&["redo"]), Some(("@-".to_string(), "@".to_string())));
+        assert_eq!(
+            restored(&["op", "revert", "abc"]),
+            Some(("abc-".to_string(), "abc".to_string()))
+        );
+        assert_eq!(
+            restored(&["op", "restore", "abc"]),
+            Some(("abc".to_string(), "@".to_string()))
+        );
+        assert_eq!(restored(&["op", "log"]), None);
+    }
+
+    #[tokio::test]
+    async fn test_restoring_operations_compares_protected_commits() {
+        let queries = Mutex::new(Vec::new());
+        let parsed = parse_jj_args(&["undo"]);
+        let result = ProtectionPolicy::new(vec!["trunk()".to_string()], Vec::new())
+            .check(&parsed, |query| {
+                let changed = query.contains("at_operation(@-, present(trunk()))");
+                queries.lock().unwrap().push(query);
+                async move { Ok(changed) }
+            })
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(JJError::ProtectedRevision { ref revision, ref rule, .. })
+                if revision == "operation @-" && rule == "trunk()"
+        ));
+        assert_eq!(
+            queries.into_inner().unwrap()[0],
+            "(at_operation(@-, present(trunk())) ~ at_operation(@, present(trunk()))) | \
+             (at_operation(@, present(trunk())) ~ at_operation(@-, present(trunk())))"
+        );
+
+        // Operations that left the protected commits alone can be undone
+        let result = policy().check(&parsed, |_| async { Ok(false) }).await;
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_protected_revisions() {
+        // A commit ID of main
+        assert!(matches!(
+            check_with("abandon 3f2a1b9c", &[("3f2a1b9c", MAIN)]).await,
+            Err(JJError::ProtectedRevision { ref revision, ref rule, .. })
+                if revision == "3f2a1b9c" && rule == "main"
+        ));
+        assert!(matches!(
+            check_with("describe -r ::trunk() -m msg", &[("::trunk()", "trunk()")]).await,
+            Err(JJError::ProtectedRevision { ref rule, .. }) if rule == "trunk()"
+        ));
+        assert!(check_with("rebase -b feat -d @", &[("feat", "main@origin")])
+            .await
+            .is_err());
+        assert!(matches!(
+            check_with("squash", &[("@-", MAIN)]).await,
+            Err(JJError::ProtectedRevision { ref revision, .. }) if revision == "@-"
+        ));
+        assert!(check_with("describe -m msg", &[("@", "trunk()")]).await.is_err());
+
+        // Ranges starting at a protected revision do not contain it
+        assert!(check("rebase -r main..@ -d main").await.is_ok());
+        assert!(check("edit maintenance").await.is_ok());
+        assert!(check_with("new main", &[("main", MAIN)]).await.is_ok());
+        assert!(check_with("log -r main", &[("main", MAIN)]).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_query_errors_reject_the_command() {
+        let parsed = parse_jj_args(&["abandon", "main"]);
+        let result = policy()
+            .check(&parsed, |_| async { Err(JJError::JJNotFound) })
+            .await;
+        assert_eq!(result, Err(JJError::JJNotFound));
+    }
+
+    #[tokio::test]
+    async fn test_protected_bookmarks() {
+        assert!(matches!(
+            check("bookmark delete main").await,
+            Err(JJError::ProtectedBookmark { ref bookmark, .. }) if bookmark == "main"
+        ));
+        assert!(check("branch delete release/1.0").await.is_err());
+        assert!(check("bookmark set main -r @").await.is_err());
+        assert!(check("bookmark delete glob:*").await.is_err());
+        assert!(check("bookmark rename feat main").await.is_err());
+        assert!(matches!(
+            check_with(
+                "bookmark move --from main --to @",
+                &[("main", "bookmarks(glob:\"main\")")]
+            )
+            .await,
+            Err(JJError::ProtectedBookmark { ref rule, .. }) if rule == "main"
+        ));
+        assert!(check("bookmark move --from feat --to @").await.is_ok());
+        assert!(check("bookmark delete feat").await.is_ok());
+        assert!(check("bookmark create feat -r main").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_empty_policy_allows_everything() {
+        let parsed = parse_jj_args(&["abandon", "main"]);
+        let result = ProtectionPolicy::default()
+            .check(&parsed, |_| async { Err(JJError::JJNotFound) })
+            .await;
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_override_signature_covers_the_command() {
+        let keypair = crate::crypto::generate_signing_keypair();
+        let args = vec!["abandon".to_string(), "main".to_string()];
+        let message = override_message(args.clone(), "rm".to_string(), "broken".to_string());
+        let approval = PolicyOverride {
+            approved_by: "rm".to_string(),
+            reason: "broken".to_string(),
+            public_key: keypair.public_key,
+            signature: crate::crypto::sign_message_internal(&message, &keypair.secret_key)
+                .unwrap(),
+        };
+
+        assert!(approval.verify(&args).unwrap());
+        assert_ne!(
+            message,
+            override_message(vec!["abandon".to_string()], "rm".to_string(), "broken".to_string())
+        );
+    }
+}
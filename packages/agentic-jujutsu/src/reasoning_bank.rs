@@ -598,6 +598,15 @@ impl ReasoningBank {
         Ok(patterns.clone())
     }
 
+    /// Get all stored trajectories, oldest first
+    pub fn get_trajectories(&self) -> Result<Vec<Trajectory>> {
+        let trajectories = self.trajectories.lock().map_err(|e| {
+            JJError::Unknown(format!("Failed to lock trajectories: {}", e))
+        })?;
+
+        Ok(trajectories.iter().cloned().collect())
+    }
+
     /// Query trajectories by task similarity
     pub fn query_trajectories(&self, task: &str, limit: usize) -> Result<Vec<Trajectory>> {
         let trajectories = self.trajectories.lock().map_err(|e| {
@@ -0,0 +1,800 @@
+//! Code review of jj changes
+//!
+//! A [`Review`] is opened for a change ID and collects line-anchored
+//! [`ReviewComment`]s and signed [`ReviewVerdict`]s from other agents. Because
+//! jj change IDs survive rewrites, a review follows its change across
+//! `describe`, `squash`, `rebase` and working-copy snapshots: whenever the
+//! change's commit ID differs from the one a comment was anchored to, the
+//! comment is re-anchored by finding its line again in the change's new diff.
+//!
+//! Comments anchor to a line on the new side of a hunk in `jj diff --git`
+//! output. Re-anchoring looks for the same line text in the same file,
+//! preferring candidates whose preceding lines also match and then the one
+//! closest to the old position. A comment whose line is gone is kept but
+//! marked `outdated`. Verdicts are bound to the commit they were given for and
+//! become `stale` once the change is rewritten.
+//!
+//! Reviews are stored as one JSON file per change under
+//! `.jj/agentic-jujutsu/reviews` in the repository, next to jj's own state
+//! and ignored by it. Every update holds an exclusive lock on the change's
+//! `.lock` file from loading the review until it is saved, so concurrent
+//! agents never overwrite each other's comments or verdicts.
+
+use crate::crypto::{sign_message_internal, verify_signature_internal};
+use crate::error::{JJError, Result};
+use chrono::Utc;
+use napi_derive::napi;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+/// Directory of review files, relative to the repository root
+pub const REVIEW_DIR: &str = ".jj/agentic-jujutsu/reviews";
+
+/// Preceding lines stored with a comment to disambiguate re-anchoring
+const ANCHOR_CONTEXT_LINES: usize = 2;
+
+/// A comment on one line of a change's diff
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[napi(object)]
+pub struct ReviewComment {
+    /// Comment ID
+    pub id: String,
+    /// Who wrote the comment
+    pub author: String,
+    /// File the comment is on
+    pub path: String,
+    /// Line number in the new version of the file
+    pub line: u32,
+    /// Header of the hunk containing the line, e.g. `@@ -3,4 +3,6 @@`
+    pub hunk: String,
+    /// Text of the commented line
+    pub anchor_text: String,
+    /// Lines preceding the commented line within its hunk
+    pub context: Vec<String>,
+    /// Comment text
+    pub body: String,
+    /// Commit the anchor was last resolved against
+    pub commit_id: String,
+    /// Whether the line no longer exists in the change
+    pub outdated: bool,
+    /// When the comment was added (RFC 3339)
+    pub created_at: String,
+}
+
+/// A reviewer's signed decision on a change
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[napi(object)]
+pub struct ReviewVerdict {
+    /// Who gave the verdict
+    pub reviewer: String,
+    /// `approve` or `request_changes`
+    pub verdict: String,
+    /// Optional summary
+    pub body: Option<String>,
+    /// Commit the verdict applies to
+    pub commit_id: String,
+    /// When the verdict was given (RFC 3339)
+    pub timestamp: String,
+    /// Signature (hex) over the verdict
+    pub signature: String,
+    /// Reviewer's public key (hex)
+    pub public_key: String,
+    /// Whether the change was rewritten after the verdict
+    pub stale: bool,
+}
+
+/// Review of a change
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[napi(object)]
+pub struct Review {
+    /// Change under review
+    pub change_id: String,
+    /// Commit of the change when the review was last refreshed
+    pub commit_id: String,
+    /// Who opened the review
+    pub opened_by: String,
+    /// When the review was opened (RFC 3339)
+    pub opened_at: String,
+    /// `open`, `approved` or `changes_requested`
+    pub status: String,
+    /// Line comments
+    pub comments: Vec<ReviewComment>,
+    /// Verdicts, oldest first
+    pub verdicts: Vec<ReviewVerdict>,
+}
+
+/// Reviewer decision
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    /// The change can land
+    Approve,
+    /// The change needs more work
+    RequestChanges,
+}
+
+impl Verdict {
+    /// Name stored in [`ReviewVerdict::verdict`]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Verdict::Approve => "approve",
+            Verdict::RequestChanges => "request_changes",
+        }
+    }
+
+    /// Parse a verdict name
+    pub fn parse(name: &str) -> Result<Self> {
+        match name {
+            "approve" | "approved" => Ok(Verdict::Approve),
+            "request_changes" | "request-changes" | "changes_requested" => {
+                Ok(Verdict::RequestChanges)
+            }
+            other => Err(JJError::InvalidConfig(format!(
+                "Unknown review verdict: {} (expected approve or request_changes)",
+                other
+            ))),
+        }
+    }
+}
+
+impl ReviewVerdict {
+    /// Check the verdict's signature
+    pub fn verify(&self) -> Result<bool> {
+        verify_signature_internal(
+            &verdict_message(
+                &self.commit_id,
+                &self.reviewer,
+                &self.verdict,
+                self.body.as_deref(),
+                &self.timestamp,
+            ),
+            &self.signature,
+            &self.public_key,
+        )
+    }
+}
+
+/// A change as it currently exists in the repository
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChangeSnapshot {
+    /// Full change ID
+    pub change_id: String,
+    /// Current commit ID
+    pub commit_id: String,
+    /// `jj diff --git` output of the change
+    pub diff: String,
+}
+
+/// Review files of one repository
+#[derive(Debug, Clone)]
+pub struct ReviewStore {
+    dir: PathBuf,
+}
+
+impl ReviewStore {
+    /// Store reviews in `dir`
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Store reviews in the repository's `.jj` directory
+    pub fn for_repo(repo_path: impl AsRef<Path>) -> Self {
+        Self::new(repo_path.as_ref().join(REVIEW_DIR))
+    }
+
+    /// Open a review for a change, or refresh the existing one
+    pub fn open(&self, change: &ChangeSnapshot, opened_by: &str) -> Result<Review> {
+        let _lock = self.lock(&change.change_id)?;
+        if let Some(mut review) = self.load(&change.change_id)? {
+            reanchor(&mut review, change);
+            self.save(&review)?;
+            return Ok(review);
+        }
+
+        let review = Review {
+            change_id: change.change_id.clone(),
+            commit_id: change.commit_id.clone(),
+            opened_by: opened_by.to_string(),
+            opened_at: Utc::now().to_rfc3339(),
+            status: "open".to_string(),
+            comments: Vec::new(),
+            verdicts: Vec::new(),
+        };
+        self.save(&review)?;
+        Ok(review)
+    }
+
+    /// Review of a change, if one was opened
+    pub fn load(&self, change_id: &str) -> Result<Option<Review>> {
+        let path = self.path(change_id)?;
+        if !path.exists() {
+            return Ok(None);
+        }
+        let json = std::fs::read_to_string(&path)?;
+        Ok(Some(serde_json::from_str(&json)?))
+    }
+
+    /// All stored reviews, ordered by change ID
+    pub fn list(&self) -> Result<Vec<Review>> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut reviews = Vec::new();
+        for entry in std::fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) == Some("json") {
+                let json = std::fs::read_to_string(&path)?;
+                reviews.push(serde_json::from_str::<Review>(&json)?);
+            }
+        }
+        reviews.sort_by(|a, b| a.change_id.cmp(&b.change_id));
+        Ok(reviews)
+    }
+
+    /// Re-anchor a review to the change's current commit and save it
+    pub fn refresh(&self, change: &ChangeSnapshot) -> Result<Review> {
+        let _lock = self.lock(&change.change_id)?;
+        let mut review = self.require(&change.change_id)?;
+        reanchor(&mut review, change);
+        self.save(&review)?;
+        Ok(review)
+    }
+
+    /// Comment on `line` of `path` in the change's current diff
+    pub fn comment(
+        &self,
+        change: &ChangeSnapshot,
+        author: &str,
+        path: &str,
+        line: u32,
+        body: &str,
+    ) -> Result<ReviewComment> {
+        let _lock = self.lock(&change.change_id)?;
+        let mut review = self.require(&change.change_id)?;
+        reanchor(&mut review, change);
+
+        let files = parse_hunks(&change.diff);
+        let (hunk, index) =
+            find_line(&files, path, line).ok_or_else(|| JJError::InvalidAnchor {
+                path: path.to_string(),
+                line,
+            })?;
+
+        let comment = ReviewComment {
+            id: uuid::Uuid::new_v4().to_string(),
+            author: author.to_string(),
+            path: path.to_string(),
+            line,
+            hunk: hunk.header.clone(),
+            anchor_text: hunk.lines[index].1.clone(),
+            context: context_before(hunk, index),
+            body: body.to_string(),
+            commit_id: change.commit_id.clone(),
+            outdated: false,
+            created_at: Utc::now().to_rfc3339(),
+        };
+        review.comments.push(comment.clone());
+        self.save(&review)?;
+        Ok(comment)
+    }
+
+    /// Record a verdict on the change's current commit, signed by the reviewer
+    pub fn submit_verdict(
+        &self,
+        change: &ChangeSnapshot,
+        reviewer: &str,
+        verdict: Verdict,
+        body: Option<&str>,
+        secret_key: &str,
+        public_key: &str,
+    ) -> Result<ReviewVerdict> {
+        let _lock = self.lock(&change.change_id)?;
+        let mut review = self.require(&change.change_id)?;
+        reanchor(&mut review, change);
+
+        let timestamp = Utc::now().to_rfc3339();
+        let message = verdict_message(
+            &change.commit_id,
+            reviewer,
+            verdict.as_str(),
+            body,
+            &timestamp,
+        );
+        let record = ReviewVerdict {
+            reviewer: reviewer.to_string(),
+            verdict: verdict.as_str().to_string(),
+            body: body.map(str::to_string),
+            commit_id: change.commit_id.clone(),
+            timestamp,
+            signature: sign_message_internal(&message, secret_key)?,
+            public_key: public_key.to_string(),
+            stale: false,
+        };
+        if !record.verify()? {
+            return Err(JJError::CryptoError(
+                "Verdict signature does not match the reviewer's public key".to_string(),
+            ));
+        }
+
+        review.verdicts.push(record.clone());
+        review.status = review_status(&review.verdicts).to_string();
+        self.save(&review)?;
+        Ok(record)
+    }
+
+    fn require(&self, change_id: &str) -> Result<Review> {
+        self.load(change_id)?
+            .ok_or_else(|| JJError::ReviewNotFound(change_id.to_string()))
+    }
+
+    fn path(&self, change_id: &str) -> Result<PathBuf> {
+        if change_id.is_empty() || !change_id.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(JJError::InvalidConfig(format!(
+                "Invalid change ID for a review: {}",
+                change_id
+            )));
+        }
+        Ok(self.dir.join(format!("{}.json", change_id)))
+    }
+
+    /// Exclusive lock on a change's review, released when the file is dropped
+    fn lock(&self, change_id: &str) -> Result<std::fs::File> {
+        std::fs::create_dir_all(&self.dir)?;
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(false)
+            .open(self.path(change_id)?.with_extension("lock"))?;
+        file.lock()?;
+        Ok(file)
+    }
+
+    /// Write a review; callers hold its lock
+    fn save(&self, review: &Review) -> Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        let path = self.path(&review.change_id)?;
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, serde_json::to_string_pretty(review)?)?;
+        std::fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+}
+
+/// Move comments and verdicts of a review to the change's current commit
+pub fn reanchor(review: &mut Review, change: &ChangeSnapshot) {
+    let files = parse_hunks(&change.diff);
+
+    for comment in review
+        .comments
+        .iter_mut()
+        .filter(|c| c.commit_id != change.commit_id)
+    {
+        match relocate(&files, comment) {
+            Some((hunk, index)) => {
+                comment.line = hunk.lines[index].0;
+                comment.hunk = hunk.header.clone();
+                comment.context = context_before(hunk, index);
+                comment.outdated = false;
+            }
+            None => comment.outdated = true,
+        }
+        comment.commit_id = change.commit_id.clone();
+    }
+
+    for verdict in &mut review.verdicts {
+        verdict.stale = verdict.commit_id != change.commit_id;
+    }
+    review.commit_id = change.commit_id.clone();
+    review.status = review_status(&review.verdicts).to_string();
+}
+
+/// Status from each reviewer's latest verdict on the current commit
+///
+/// Verdicts whose signature does not verify (e.g. edited in the review file)
+/// are ignored.
+fn review_status(verdicts: &[ReviewVerdict]) -> &'static str {
+    let mut latest: Vec<&ReviewVerdict> = Vec::new();
+    for verdict in verdicts
+        .iter()
+        .rev()
+        .filter(|v| !v.stale && v.verify().unwrap_or(false))
+    {
+        if !latest.iter().any(|v| v.reviewer == verdict.reviewer) {
+            latest.push(verdict);
+        }
+    }
+
+    if latest
+        .iter()
+        .any(|v| v.verdict == Verdict::RequestChanges.as_str())
+    {
+        "changes_requested"
+    } else if latest
+        .iter()
+        .any(|v| v.verdict == Verdict::Approve.as_str())
+    {
+        "approved"
+    } else {
+        "open"
+    }
+}
+
+fn verdict_message(
+    commit_id: &str,
+    reviewer: &str,
+    verdict: &str,
+    body: Option<&str>,
+    timestamp: &str,
+) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    for field in [commit_id, reviewer, verdict, body.unwrap_or(""), timestamp] {
+        hasher.update(field.as_bytes());
+        hasher.update([0]);
+    }
+    hasher.finalize().to_vec()
+}
+
+/// A hunk of a git diff, reduced to the lines of the new file
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Hunk {
+    header: String,
+    /// Context and added lines as `(line number, text)`
+    lines: Vec<(u32, String)>,
+}
+
+/// Hunks of a git diff by path of the new file
+fn parse_hunks(diff: &str) -> Vec<(String, Vec<Hunk>)> {
+    let mut files: Vec<(String, Vec<Hunk>)> = Vec::new();
+    let mut next_line = 0;
+
+    for line in diff.lines() {
+        if let Some(paths) = line.strip_prefix("diff --git ") {
+            let path = paths
+                .rsplit_once(" b/")
+                .map_or(paths, |(_, new)| new)
+                .to_string();
+            files.push((path, Vec::new()));
+        } else if line.starts_with("@@") {
+            let Some((_, hunks)) = files.last_mut() else {
+                continue;
+            };
+            next_line = hunk_new_start(line).unwrap_or(1);
+            hunks.push(Hunk {
+                header: hunk_header(line).to_string(),
+                lines: Vec::new(),
+            });
+        } else if let Some(hunk) = files.last_mut().and_then(|(_, hunks)| hunks.last_mut()) {
+            if let Some(text) = line.strip_prefix(' ').or_else(|| line.strip_prefix('+')) {
+                hunk.lines.push((next_line, text.to_string()));
+                next_line += 1;
+            }
+        }
+    }
+    files
+}
+
+/// `@@ -a,b +c,d @@` without any trailing section heading
+fn hunk_header(line: &str) -> &str {
+    match line[2..].find("@@") {
+        Some(end) => &line[..end + 4],
+        None => line,
+    }
+}
+
+fn hunk_new_start(line: &str) -> Option<u32> {
+    let new = line.split_whitespace().find(|part| part.starts_with('+'))?;
+    new[1..].split(',').next()?.parse().ok()
+}
+
+fn find_line<'a>(
+    files: &'a [(String, Vec<Hunk>)],
+    path: &str,
+    line: u32,
+) -> Option<(&'a Hunk, usize)> {
+    let (_, hunks) = files.iter().find(|(p, _)| p == path)?;
+    hunks.iter().find_map(|hunk| {
+        hunk.lines
+            .iter()
+            .position(|(number, _)| *number == line)
+            .map(|index| (hunk, index))
+    })
+}
+
+fn context_before(hunk: &Hunk, index: usize) -> Vec<String> {
+    hunk.lines[index.saturating_sub(ANCHOR_CONTEXT_LINES)..index]
+        .iter()
+        .map(|(_, text)| text.clone())
+        .collect()
+}
+
+/// Find a comment's line in a new diff
+fn relocate<'a>(
+    files: &'a [(String, Vec<Hunk>)],
+    comment: &ReviewComment,
+) -> Option<(&'a Hunk, usize)> {
+    let (_, hunks) = files.iter().find(|(p, _)| *p == comment.path)?;
+
+    let mut best: Option<(&Hunk, usize, usize, u32)> = None;
+    for hunk in hunks {
+        for (index, (number, text)) in hunk.lines.iter().enumerate() {
+            if *text != comment.anchor_text {
+                continue;
+            }
+            let context = context_before(hunk, index);
+            let matching = context
+                .iter()
+                .rev()
+                .zip(comment.context.iter().rev())
+                .take_while(|(a, b)| a == b)
+                .count();
+            let distance = number.abs_diff(comment.line);
+
+            let better = match best {
+                None => true,
+                Some((_, _, best_matching, best_distance)) => {
+                    matching > best_matching
+                        || (matching == best_matching && distance < best_distance)
+                }
+            };
+            if better {
+                best = Some((hunk, index, matching, distance));
+            }
+        }
+    }
+    best.map(|(hunk, index, _, _)| (hunk, index))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::generate_signing_keypair;
+    use crate::virtual_repo::VirtualRepo;
+
+    fn snapshot(repo: &mut VirtualRepo, revision: &str) -> ChangeSnapshot {
+        let diff = repo
+            .execute(&["diff", "--git", "-r", revision])
+            .unwrap()
+            .stdout;
+        let commit = repo.commit(revision).unwrap();
+        ChangeSnapshot {
+            change_id: commit.change_id.clone(),
+            commit_id: commit.commit_id.clone(),
+            diff,
+        }
+    }
+
+    #[test]
+    fn test_parse_hunks() {
+        let diff = "diff --git a/src/lib.rs b/src/lib.rs\n\
+                    index 1..2 100644\n\
+                    --- a/src/lib.rs\n\
+                    +++ b/src/lib.rs\n\
+                    @@ -1,3 +1,3 @@ fn main() {\n a\n-b\n+B\n c\n";
+        let files = parse_hunks(diff);
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].0, "src/lib.rs");
+        let hunk = &files[0].1[0];
+        assert_eq!(hunk.header, "@@ -1,3 +1,3 @@");
+        assert_eq!(
+            hunk.lines,
+            vec![
+                (1, "a".to_string()),
+                (2, "B".to_string()),
+                (3, "c".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_comments_follow_rewritten_change() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = ReviewStore::for_repo(dir.path());
+
+        let mut repo = VirtualRepo::new();
+        repo.write_file("src/lib.rs", "fn a() {}\nfn b() {}\nfn c() {}\n");
+        repo.execute(&["describe", "-m", "Add functions"]).unwrap();
+        let change = snapshot(&mut repo, "@");
+
+        store.open(&change, "author-agent").unwrap();
+        let on_c = store
+            .comment(&change, "reviewer", "src/lib.rs", 3, "c needs docs")
+            .unwrap();
+        assert_eq!(on_c.anchor_text, "fn c() {}");
+        store
+            .comment(&change, "reviewer", "src/lib.rs", 2, "drop b")
+            .unwrap();
+        assert!(matches!(
+            store.comment(&change, "reviewer", "README.md", 1, "?"),
+            Err(JJError::InvalidAnchor { .. })
+        ));
+
+        // Rewrite the change: insert two lines above `c` and remove `b`
+        repo.write_file("src/lib.rs", "fn a() {}\nfn x() {}\nfn y() {}\nfn c() {}\n");
+        let rewritten = snapshot(&mut repo, "@");
+        assert_eq!(rewritten.change_id, change.change_id);
+        assert_ne!(rewritten.commit_id, change.commit_id);
+
+        let review = store.refresh(&rewritten).unwrap();
+        assert_eq!(review.commit_id, rewritten.commit_id);
+        let c = &review.comments[0];
+        assert_eq!((c.line, c.outdated), (4, false));
+        assert_eq!(c.hunk, "@@ -0,0 +1,4 @@");
+        assert_eq!(c.commit_id, rewritten.commit_id);
+        let b = &review.comments[1];
+        assert!(b.outdated);
+
+        // Stored state survives a reload
+        assert_eq!(store.load(&change.change_id).unwrap().unwrap(), review);
+    }
+
+    #[test]
+    fn test_anchor_prefers_matching_context() {
+        let mut repo = VirtualRepo::new();
+        repo.write_file("a.py", "x = 1\nreturn x\ny = 2\nreturn y\n");
+        repo.execute(&["new"]).unwrap();
+        repo.write_file("a.py", "x = 1\nreturn x\ny = 2\nz = 3\nreturn y\n");
+        let change = snapshot(&mut repo, "@");
+
+        let dir = tempfile::tempdir().unwrap();
+        let store = ReviewStore::new(dir.path());
+        store.open(&change, "author").unwrap();
+        store
+            .comment(&change, "reviewer", "a.py", 5, "return z too")
+            .unwrap();
+
+        // Same text appears earlier; the preceding line picks the right one
+        repo.write_file(
+            "a.py",
+            "return y\nx = 1\nreturn x\ny = 2\nz = 3\nreturn y\n",
+        );
+        let review = store.refresh(&snapshot(&mut repo, "@")).unwrap();
+        assert_eq!(review.comments[0].line, 6);
+        assert!(!review.comments[0].outdated);
+    }
+
+    #[test]
+    fn test_signed_verdicts_go_stale_on_rewrite() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = ReviewStore::for_repo(dir.path());
+        let keys = generate_signing_keypair();
+
+        let mut repo = VirtualRepo::new();
+        repo.write_file("a.txt", "a\n");
+        let change = snapshot(&mut repo, "@");
+
+        assert!(matches!(
+            store.submit_verdict(
+                &change,
+                "r",
+                Verdict::Approve,
+                None,
+                &keys.secret_key,
+                &keys.public_key
+            ),
+            Err(JJError::ReviewNotFound(_))
+        ));
+        store.open(&change, "author").unwrap();
+
+        let verdict = store
+            .submit_verdict(
+                &change,
+                "reviewer",
+                Verdict::RequestChanges,
+                Some("needs tests"),
+                &keys.secret_key,
+                &keys.public_key,
+            )
+            .unwrap();
+        assert!(verdict.verify().unwrap());
+        assert_eq!(
+            store.load(&change.change_id).unwrap().unwrap().status,
+            "changes_requested"
+        );
+
+        store
+            .submit_verdict(
+                &change,
+                "reviewer",
+                Verdict::Approve,
+                None,
+                &keys.secret_key,
+                &keys.public_key,
+            )
+            .unwrap();
+        assert_eq!(store.refresh(&change).unwrap().status, "approved");
+
+        repo.write_file("a.txt", "b\n");
+        let review = store.refresh(&snapshot(&mut repo, "@")).unwrap();
+        assert!(review.verdicts.iter().all(|v| v.stale));
+        assert_eq!(review.status, "open");
+        assert_eq!(store.list().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_tampered_verdicts_fail_verification() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = ReviewStore::for_repo(dir.path());
+        let keys = generate_signing_keypair();
+        let other = generate_signing_keypair();
+
+        let mut repo = VirtualRepo::new();
+        repo.write_file("a.txt", "a\n");
+        let change = snapshot(&mut repo, "@");
+        store.open(&change, "author").unwrap();
+
+        assert!(matches!(
+            store.submit_verdict(
+                &change,
+                "reviewer",
+                Verdict::Approve,
+                None,
+                &keys.secret_key,
+                &other.public_key
+            ),
+            Err(JJError::CryptoError(_))
+        ));
+
+        let verdict = store
+            .submit_verdict(
+                &change,
+                "reviewer",
+                Verdict::RequestChanges,
+                Some("needs tests"),
+                &keys.secret_key,
+                &keys.public_key,
+            )
+            .unwrap();
+        assert!(verdict.verify().unwrap());
+
+        let mut approved = verdict.clone();
+        approved.verdict = Verdict::Approve.as_str().to_string();
+        assert!(!approved.verify().unwrap());
+        let mut moved = verdict.clone();
+        moved.commit_id = "0".repeat(40);
+        assert!(!moved.verify().unwrap());
+        let mut resigned = verdict.clone();
+        resigned.public_key = other.public_key.clone();
+        assert!(!resigned.verify().unwrap());
+
+        // Flipping the verdict in the review file does not approve the change
+        let mut review = store.load(&change.change_id).unwrap().unwrap();
+        review.verdicts = vec![approved];
+        store.save(&review).unwrap();
+        assert_eq!(store.refresh(&change).unwrap().status, "open");
+    }
+
+    #[test]
+    fn test_concurrent_comments_are_not_lost() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = ReviewStore::for_repo(dir.path());
+        let mut repo = VirtualRepo::new();
+        repo.write_file("a.txt", "a\n");
+        let change = snapshot(&mut repo, "@");
+        store.open(&change, "author").unwrap();
+
+        std::thread::scope(|scope| {
+            for i in 0..8 {
+                let (store, change) = (&store, &change);
+                scope.spawn(move || {
+                    store
+                        .comment(change, &format!("agent-{}", i), "a.txt", 1, "nit")
+                        .unwrap();
+                });
+            }
+        });
+
+        let review = store.load(&change.change_id).unwrap().unwrap();
+        assert_eq!(review.comments.len(), 8);
+    }
+
+    #[test]
+    fn test_verdict_names() {
+        assert_eq!(Verdict::parse("approve").unwrap(), Verdict::Approve);
+        assert_eq!(
+            Verdict::parse("request-changes").unwrap(),
+            Verdict::RequestChanges
+        );
+        assert!(Verdict::parse("lgtm").is_err());
+    }
+}
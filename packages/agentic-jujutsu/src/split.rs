@@ -0,0 +1,721 @@
+//! Splitting one change into several
+//!
+//! `jj split` is interactive unless given paths, and even then it only
+//! separates whole files into two commits. [`SplitPlan`] describes a split of
+//! a change into any number of groups, each a set of files or of individual
+//! hunks from the change's `jj diff --git` output, and
+//! [`JJWrapper::split_change`](crate::JJWrapper::split_change) carries it out
+//! without an editor:
+//!
+//! 1. A new change is created on the original's parents for every group but
+//!    the last. Files a group takes whole are restored from the original
+//!    commit; files split across groups are written with the hunks of all
+//!    groups so far applied.
+//! 2. The original change is rebased onto the last new change and restored
+//!    to its original tree, so it keeps its change ID, its descendants and
+//!    exactly the remaining hunks. It gets the last group's description.
+//!
+//! [`SplitPlan::auto`] builds a plan heuristically: formatting-only hunks go
+//! first, then the remaining hunks are grouped by file category (build,
+//! config, CI, source, tests, docs) and directory. Each group is described
+//! with [`generate_commit_message`] from its part of the diff.
+//!
+//! Intermediate changes are not guaranteed to build on their own: the
+//! heuristic does not know about dependencies between directories.
+
+use crate::commit_message::{
+    categorize, generate_commit_message, split_git_paths, FileCategory, MessageContext,
+};
+use crate::error::{JJError, Result};
+use napi_derive::napi;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// A hunk of a file in a change's diff
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[napi(object)]
+pub struct HunkRef {
+    /// File path (after the change)
+    pub path: String,
+    /// Index of the hunk within the file, from 0
+    pub index: u32,
+}
+
+/// One resulting change of a split
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[napi(object)]
+pub struct SplitGroup {
+    /// Description of the resulting change
+    pub description: String,
+    /// Files (or directories) that go into this change whole
+    pub paths: Option<Vec<String>>,
+    /// Individual hunks that go into this change
+    pub hunks: Option<Vec<HunkRef>>,
+}
+
+/// A hunk as listed for building a partition
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[napi(object)]
+pub struct DiffHunkInfo {
+    /// File path (after the change)
+    pub path: String,
+    /// Index of the hunk within the file, from 0
+    pub index: u32,
+    /// Hunk header, e.g. `@@ -3,4 +3,6 @@`
+    pub header: String,
+    /// Lines added
+    pub additions: u32,
+    /// Lines removed
+    pub deletions: u32,
+    /// Whether the hunk only changes whitespace
+    pub formatting_only: bool,
+}
+
+/// A change created by a split
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[napi(object)]
+pub struct SplitChange {
+    /// Change ID
+    pub change_id: String,
+    /// Description it was given
+    pub description: String,
+}
+
+/// How a file changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileKind {
+    /// New file
+    Added,
+    /// Existing file edited in place
+    Modified,
+    /// File removed
+    Deleted,
+    /// File moved, possibly with edits
+    Renamed,
+}
+
+/// A hunk of a git diff
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffHunk {
+    /// Header line, including any section heading
+    pub header: String,
+    /// First old line (1-based; 0 when nothing is removed at the start)
+    pub old_start: usize,
+    /// Number of old lines covered
+    pub old_len: usize,
+    /// Lines as `(' ' | '-' | '+', text)`, text including its line ending
+    pub lines: Vec<(char, String)>,
+}
+
+impl DiffHunk {
+    /// Lines added
+    pub fn additions(&self) -> usize {
+        self.lines.iter().filter(|(sign, _)| *sign == '+').count()
+    }
+
+    /// Lines removed
+    pub fn deletions(&self) -> usize {
+        self.lines.iter().filter(|(sign, _)| *sign == '-').count()
+    }
+
+    /// Whether removed and added lines differ only in whitespace
+    pub fn is_formatting_only(&self) -> bool {
+        let squash = |sign: char| -> String {
+            self.lines
+                .iter()
+                .filter(|(s, _)| *s == sign)
+                .flat_map(|(_, text)| text.chars())
+                .filter(|c| !c.is_whitespace())
+                .collect()
+        };
+        self.lines.iter().any(|(sign, _)| *sign != ' ') && squash('-') == squash('+')
+    }
+}
+
+/// One file of a git diff
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileDiff {
+    /// Path after the change
+    pub path: String,
+    /// Path before the change
+    pub old_path: String,
+    /// How the file changed
+    pub kind: FileKind,
+    /// Lines between `diff --git` and the first hunk
+    pub header: Vec<String>,
+    /// Hunks; empty for binary files and pure renames or mode changes
+    pub hunks: Vec<DiffHunk>,
+}
+
+impl FileDiff {
+    /// Number of units the file can be split into (at least one)
+    fn units(&self) -> usize {
+        self.hunks.len().max(1)
+    }
+
+    /// Paths touched by the file's change
+    pub fn paths(&self) -> Vec<String> {
+        let mut paths = vec![self.path.clone()];
+        if self.old_path != self.path {
+            paths.push(self.old_path.clone());
+        }
+        paths
+    }
+}
+
+/// Parse `jj diff --git` output into files and hunks
+pub fn parse_diff(diff: &str) -> Vec<FileDiff> {
+    let mut files: Vec<FileDiff> = Vec::new();
+
+    for line in diff.lines() {
+        if let Some(paths) = line.strip_prefix("diff --git ") {
+            let (old_path, path) = split_git_paths(paths);
+            let kind = if old_path == path {
+                FileKind::Modified
+            } else {
+                FileKind::Renamed
+            };
+            files.push(FileDiff {
+                path,
+                old_path,
+                kind,
+                header: vec![line.to_string()],
+                hunks: Vec::new(),
+            });
+            continue;
+        }
+        let Some(file) = files.last_mut() else {
+            continue;
+        };
+
+        if line.starts_with("@@") {
+            let (old_start, old_len) = old_range(line);
+            file.hunks.push(DiffHunk {
+                header: line.to_string(),
+                old_start,
+                old_len,
+                lines: Vec::new(),
+            });
+        } else if let Some(hunk) = file.hunks.last_mut() {
+            if line.starts_with('\\') {
+                // `\ No newline at end of file` applies to the previous line
+                if let Some((_, text)) = hunk.lines.last_mut() {
+                    text.pop();
+                }
+            } else if let Some(sign @ (' ' | '-' | '+')) = line.chars().next() {
+                hunk.lines.push((sign, format!("{}\n", &line[1..])));
+            }
+        } else {
+            if line.starts_with("new file mode") {
+                file.kind = FileKind::Added;
+            } else if line.starts_with("deleted file mode") {
+                file.kind = FileKind::Deleted;
+            }
+            file.header.push(line.to_string());
+        }
+    }
+    files
+}
+
+/// Hunks of parsed files, as listed to callers
+pub fn hunk_infos(files: &[FileDiff]) -> Vec<DiffHunkInfo> {
+    files
+        .iter()
+        .flat_map(|file| {
+            file.hunks
+                .iter()
+                .enumerate()
+                .map(move |(index, hunk)| DiffHunkInfo {
+                    path: file.path.clone(),
+                    index: index as u32,
+                    header: hunk.header.clone(),
+                    additions: hunk.additions() as u32,
+                    deletions: hunk.deletions() as u32,
+                    formatting_only: hunk.is_formatting_only(),
+                })
+        })
+        .collect()
+}
+
+/// `-a,b` of a hunk header
+fn old_range(header: &str) -> (usize, usize) {
+    let range = header
+        .split_whitespace()
+        .find_map(|part| part.strip_prefix('-'))
+        .unwrap_or("1");
+    let mut numbers = range.split(',').map(|n| n.parse().unwrap_or(0));
+    let start = numbers.next().unwrap_or(0);
+    let len = numbers.next().unwrap_or(1);
+    (start, len)
+}
+
+/// A group of a [`SplitPlan`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlannedGroup {
+    /// Description of the resulting change
+    pub description: String,
+    /// Hunk indexes by file index; files without hunks use index 0
+    pub units: BTreeMap<usize, Vec<usize>>,
+}
+
+/// Validated partition of a change's diff into ordered groups
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SplitPlan {
+    /// Groups in the order the changes are stacked
+    pub groups: Vec<PlannedGroup>,
+}
+
+impl SplitPlan {
+    /// Plan a split from caller-provided groups
+    ///
+    /// Every hunk of the diff must be in exactly one group. Added, deleted
+    /// and renamed files can only be assigned whole.
+    pub fn new(files: &[FileDiff], groups: &[SplitGroup]) -> Result<Self> {
+        if groups.len() < 2 {
+            return Err(JJError::InvalidSplit(
+                "A split needs at least two groups".to_string(),
+            ));
+        }
+
+        let mut owner: BTreeMap<(usize, usize), usize> = BTreeMap::new();
+        let mut assign = |unit: (usize, usize), group: usize| -> Result<()> {
+            match owner.insert(unit, group) {
+                Some(other) if other != group => Err(JJError::InvalidSplit(format!(
+                    "{} hunk {} is in groups {} and {}",
+                    files[unit.0].path,
+                    unit.1,
+                    other + 1,
+                    group + 1
+                ))),
+                _ => Ok(()),
+            }
+        };
+
+        for (group_index, group) in groups.iter().enumerate() {
+            for path in group.paths.iter().flatten() {
+                let matching: Vec<usize> = files
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, file)| file.paths().iter().any(|p| path_matches(path, p)))
+                    .map(|(index, _)| index)
+                    .collect();
+                if matching.is_empty() {
+                    return Err(JJError::InvalidSplit(format!(
+                        "{} is not changed in the revision",
+                        path
+                    )));
+                }
+                for file in matching {
+                    for hunk in 0..files[file].units() {
+                        assign((file, hunk), group_index)?;
+                    }
+                }
+            }
+
+            for hunk in group.hunks.iter().flatten() {
+                let file = files
+                    .iter()
+                    .position(|file| file.path == hunk.path)
+                    .ok_or_else(|| {
+                        JJError::InvalidSplit(format!(
+                            "{} is not changed in the revision",
+                            hunk.path
+                        ))
+                    })?;
+                let index = hunk.index as usize;
+                if index >= files[file].units() {
+                    return Err(JJError::InvalidSplit(format!(
+                        "{} has no hunk {}",
+                        hunk.path, hunk.index
+                    )));
+                }
+                assign((file, index), group_index)?;
+            }
+        }
+
+        let missing: Vec<String> = files
+            .iter()
+            .enumerate()
+            .flat_map(|(file, diff)| (0..diff.units()).map(move |hunk| (file, hunk)))
+            .filter(|unit| !owner.contains_key(unit))
+            .map(|(file, hunk)| format!("{} hunk {}", files[file].path, hunk))
+            .collect();
+        if !missing.is_empty() {
+            return Err(JJError::InvalidSplit(format!(
+                "Not assigned to any group: {}",
+                missing.join(", ")
+            )));
+        }
+
+        let mut planned: Vec<PlannedGroup> = groups
+            .iter()
+            .map(|group| PlannedGroup {
+                description: group.description.clone(),
+                units: BTreeMap::new(),
+            })
+            .collect();
+        for ((file, hunk), group) in owner {
+            planned[group].units.entry(file).or_default().push(hunk);
+        }
+        if let Some(index) = planned.iter().position(|g| g.units.is_empty()) {
+            return Err(JJError::InvalidSplit(format!(
+                "Group {} has no changes",
+                index + 1
+            )));
+        }
+
+        let plan = SplitPlan { groups: planned };
+        for file in plan.partial_files(files) {
+            if files[file].kind != FileKind::Modified {
+                return Err(JJError::InvalidSplit(format!(
+                    "{} is added, deleted or renamed and must stay in one group",
+                    files[file].path
+                )));
+            }
+        }
+        Ok(plan)
+    }
+
+    /// Plan a split by formatting, file category and directory
+    ///
+    /// Returns a single group when there is nothing to split.
+    pub fn auto(files: &[FileDiff]) -> Self {
+        // Formatting sorts first, then build, config, CI, source, tests, docs
+        let mut buckets: BTreeMap<(u8, String), BTreeMap<usize, Vec<usize>>> = BTreeMap::new();
+
+        for (index, file) in files.iter().enumerate() {
+            let category = category_rank(categorize(&file.path));
+            let directory = match file.path.rsplit_once('/') {
+                Some((directory, _)) => directory.to_string(),
+                None => String::new(),
+            };
+
+            for hunk in 0..file.units() {
+                let formatting = file.kind == FileKind::Modified
+                    && file
+                        .hunks
+                        .get(hunk)
+                        .is_some_and(DiffHunk::is_formatting_only);
+                let key = if formatting {
+                    (0, String::new())
+                } else {
+                    (category, directory.clone())
+                };
+                buckets
+                    .entry(key)
+                    .or_default()
+                    .entry(index)
+                    .or_default()
+                    .push(hunk);
+            }
+        }
+
+        let groups = buckets
+            .into_iter()
+            .map(|((rank, _), units)| {
+                let description = if rank == 0 {
+                    formatting_description(files, &units)
+                } else {
+                    generate_commit_message(&group_diff(files, &units), &MessageContext::default())
+                };
+                PlannedGroup { description, units }
+            })
+            .collect();
+        SplitPlan { groups }
+    }
+
+    /// Files whose hunks are spread over more than one group
+    pub fn partial_files(&self, files: &[FileDiff]) -> Vec<usize> {
+        (0..files.len())
+            .filter(|file| {
+                self.groups
+                    .iter()
+                    .filter(|group| group.units.contains_key(file))
+                    .count()
+                    > 1
+            })
+            .collect()
+    }
+
+    /// Files that group `index` takes whole
+    pub fn whole_files<'a>(&self, files: &'a [FileDiff], index: usize) -> Vec<&'a FileDiff> {
+        self.groups[index]
+            .units
+            .iter()
+            .filter(|(file, hunks)| hunks.len() == files[**file].units())
+            .map(|(file, _)| &files[*file])
+            .collect()
+    }
+
+    /// Content of a partially split file once groups `..=index` are applied
+    ///
+    /// `base` is the file's content in the original change's parent.
+    pub fn content_after(
+        &self,
+        files: &[FileDiff],
+        file: usize,
+        index: usize,
+        base: &str,
+    ) -> String {
+        let mut selected: Vec<usize> = self.groups[..=index]
+            .iter()
+            .filter_map(|group| group.units.get(&file))
+            .flatten()
+            .copied()
+            .collect();
+        selected.sort_unstable();
+        let hunks: Vec<&DiffHunk> = selected
+            .iter()
+            .filter_map(|hunk| files[file].hunks.get(*hunk))
+            .collect();
+        apply_hunks(base, &hunks)
+    }
+}
+
+/// Apply hunks (sorted by position) to the old content of a file
+pub fn apply_hunks(base: &str, hunks: &[&DiffHunk]) -> String {
+    let old: Vec<&str> = base.split_inclusive('\n').collect();
+    let mut out = String::with_capacity(base.len());
+    let mut position = 0;
+
+    for hunk in hunks {
+        let start = if hunk.old_len == 0 {
+            hunk.old_start
+        } else {
+            hunk.old_start.saturating_sub(1)
+        };
+        while position < start.min(old.len()) {
+            out.push_str(old[position]);
+            position += 1;
+        }
+        for (sign, text) in &hunk.lines {
+            match sign {
+                ' ' => {
+                    out.push_str(old.get(position).copied().unwrap_or(text));
+                    position += 1;
+                }
+                '-' => position += 1,
+                _ => out.push_str(text),
+            }
+        }
+    }
+    for line in old.iter().skip(position) {
+        out.push_str(line);
+    }
+    out
+}
+
+/// Git diff text of some hunks, for describing a group
+fn group_diff(files: &[FileDiff], units: &BTreeMap<usize, Vec<usize>>) -> String {
+    let mut out = String::new();
+    for (file, hunks) in units {
+        let file = &files[*file];
+        for line in &file.header {
+            out.push_str(line);
+            out.push('\n');
+        }
+        for hunk in hunks.iter().filter_map(|index| file.hunks.get(*index)) {
+            out.push_str(&hunk.header);
+            out.push('\n');
+            for (sign, text) in &hunk.lines {
+                out.push(*sign);
+                out.push_str(text);
+                if !text.ends_with('\n') {
+                    out.push_str("\n\\ No newline at end of file\n");
+                }
+            }
+        }
+    }
+    out
+}
+
+fn formatting_description(files: &[FileDiff], units: &BTreeMap<usize, Vec<usize>>) -> String {
+    let names: Vec<&str> = units
+        .keys()
+        .map(|file| {
+            let path = files[*file].path.as_str();
+            path.rsplit('/').next().unwrap_or(path)
+        })
+        .collect();
+    let subject = match names.as_slice() {
+        [one] => one.to_string(),
+        [first, second] => format!("{} and {}", first, second),
+        [first, rest @ ..] => format!("{} and {} more files", first, rest.len()),
+        [] => "files".to_string(),
+    };
+
+    let mut description = format!("style: format {}\n\n", subject);
+    for file in units.keys() {
+        description.push_str(&format!("- {}: whitespace only\n", files[*file].path));
+    }
+    description
+}
+
+fn category_rank(category: FileCategory) -> u8 {
+    match category {
+        FileCategory::Build => 1,
+        FileCategory::Config => 2,
+        FileCategory::Ci => 3,
+        FileCategory::Source => 4,
+        FileCategory::Test => 5,
+        FileCategory::Docs => 6,
+    }
+}
+
+fn path_matches(pattern: &str, path: &str) -> bool {
+    let pattern = pattern.trim_end_matches('/');
+    path == pattern || path.starts_with(&format!("{}/", pattern))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFF: &str = "\
+diff --git a/src/api/handler.rs b/src/api/handler.rs
+index 1111111..2222222 100644
+--- a/src/api/handler.rs
++++ b/src/api/handler.rs
+@@ -1,3 +1,3 @@
+ fn a() {
+-    let x=1;
++    let x = 1;
+ }
+@@ -10,3 +10,4 @@ fn b() {
+ fn b() {
+     run();
++    log();
+ }
+diff --git a/README.md b/README.md
+index 3333333..4444444 100644
+--- a/README.md
++++ b/README.md
+@@ -1 +1,2 @@
+ # Title
++Usage notes.
+diff --git a/src/new.rs b/src/new.rs
+new file mode 100644
+index 0000000..5555555
+--- /dev/null
++++ b/src/new.rs
+@@ -0,0 +1 @@
++pub fn new() {}
+\\ No newline at end of file
+";
+
+    fn group(description: &str, paths: &[&str], hunks: &[(&str, u32)]) -> SplitGroup {
+        SplitGroup {
+            description: description.to_string(),
+            paths: Some(paths.iter().map(|p| p.to_string()).collect()),
+            hunks: Some(
+                hunks
+                    .iter()
+                    .map(|(path, index)| HunkRef {
+                        path: path.to_string(),
+                        index: *index,
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    #[test]
+    fn test_parse_diff() {
+        let files = parse_diff(DIFF);
+        assert_eq!(files.len(), 3);
+        assert_eq!(files[0].hunks.len(), 2);
+        assert_eq!(
+            (files[0].hunks[1].old_start, files[0].hunks[1].old_len),
+            (10, 3)
+        );
+        assert!(files[0].hunks[0].is_formatting_only());
+        assert!(!files[0].hunks[1].is_formatting_only());
+        assert_eq!(files[2].kind, FileKind::Added);
+        assert_eq!(files[2].hunks[0].lines[0].1, "pub fn new() {}");
+
+        let infos = hunk_infos(&files);
+        assert_eq!(infos.len(), 4);
+        assert_eq!((infos[1].additions, infos[1].deletions), (1, 0));
+    }
+
+    #[test]
+    fn test_apply_hunks() {
+        let files = parse_diff(DIFF);
+        let base = "fn a() {\n    let x=1;\n}\n4\n5\n6\n7\n8\n9\nfn b() {\n    run();\n}\n";
+
+        let first = apply_hunks(base, &[&files[0].hunks[0]]);
+        assert!(first.starts_with("fn a() {\n    let x = 1;\n}\n"));
+        assert!(first.ends_with("    run();\n}\n"));
+
+        let both = apply_hunks(base, &[&files[0].hunks[0], &files[0].hunks[1]]);
+        assert!(both.ends_with("    run();\n    log();\n}\n"));
+
+        assert_eq!(apply_hunks("", &[&files[2].hunks[0]]), "pub fn new() {}");
+    }
+
+    #[test]
+    fn test_plan_validation() {
+        let files = parse_diff(DIFF);
+
+        let plan = SplitPlan::new(
+            &files,
+            &[
+                group("style", &[], &[("src/api/handler.rs", 0)]),
+                group("feature", &["src/"], &[]),
+                group("docs", &["README.md"], &[]),
+            ],
+        );
+        assert!(matches!(
+            plan,
+            Err(JJError::InvalidSplit(ref msg)) if msg.contains("in groups 1 and 2")
+        ));
+
+        let missing = SplitPlan::new(&files, &[group("a", &["src"], &[]), group("b", &[], &[])]);
+        assert!(matches!(
+            missing,
+            Err(JJError::InvalidSplit(ref msg)) if msg.contains("README.md hunk 0")
+        ));
+
+        let plan = SplitPlan::new(
+            &files,
+            &[
+                group("style", &[], &[("src/api/handler.rs", 0)]),
+                group("feature", &["src/new.rs"], &[("src/api/handler.rs", 1)]),
+                group("docs", &["README.md"], &[]),
+            ],
+        )
+        .unwrap();
+        assert_eq!(plan.partial_files(&files), vec![0]);
+        assert_eq!(plan.whole_files(&files, 1)[0].path, "src/new.rs");
+        assert_eq!(plan.whole_files(&files, 2)[0].path, "README.md");
+
+        let base = "fn a() {\n    let x=1;\n}\n4\n5\n6\n7\n8\n9\nfn b() {\n    run();\n}\n";
+        assert!(!plan.content_after(&files, 0, 0, base).contains("log();"));
+        assert!(plan.content_after(&files, 0, 1, base).contains("log();"));
+    }
+
+    #[test]
+    fn test_auto_plan_groups_by_kind() {
+        let files = parse_diff(DIFF);
+        let plan = SplitPlan::auto(&files);
+
+        let descriptions: Vec<&str> = plan
+            .groups
+            .iter()
+            .map(|g| g.description.lines().next().unwrap())
+            .collect();
+        assert_eq!(descriptions.len(), 4);
+        assert_eq!(descriptions[0], "style: format handler.rs");
+        assert!(descriptions[3].starts_with("docs"));
+
+        // The formatting hunk and the feature hunk of handler.rs are split
+        assert_eq!(plan.groups[0].units[&0], vec![0]);
+        assert_eq!(plan.partial_files(&files), vec![0]);
+
+        let single = parse_diff(
+            &DIFF[DIFF.find("diff --git a/README.md").unwrap()
+                ..DIFF.find("diff --git a/src/new.rs").unwrap()],
+        );
+        assert_eq!(SplitPlan::auto(&single).groups.len(), 1);
+    }
+}
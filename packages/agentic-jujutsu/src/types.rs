@@ -25,6 +25,7 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
+#[cfg(not(target_arch = "wasm32"))]
 use napi_derive::napi;
 
 use crate::error::{JJError, Result};
@@ -43,7 +44,7 @@ use crate::error::{JJError, Result};
 /// assert!(result.success());
 /// ```
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[napi(object)]
+#[cfg_attr(not(target_arch = "wasm32"), napi(object))]
 pub struct JJResult {
     /// Standard output from the command
     pub stdout: String,
@@ -91,7 +92,7 @@ impl JJResult {
         if self.success() {
             Ok(self.output())
         } else {
-            Err(JJError::CommandFailed(self.stderr.clone()))
+            Err(JJError::from_command_output(self.exit_code, &self.stderr))
         }
     }
 }
@@ -114,7 +115,7 @@ impl JJResult {
 /// assert_eq!(commit.message, "Add new feature");
 /// ```
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[napi(object)]
+#[cfg_attr(not(target_arch = "wasm32"), napi(object))]
 pub struct JJCommit {
     /// Commit ID (revision hash)
     pub id: String,
@@ -382,7 +383,7 @@ impl JJCommitBuilder {
 /// assert_eq!(branch.name, "feature/new-api");
 /// ```
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[napi(object)]
+#[cfg_attr(not(target_arch = "wasm32"), napi(object))]
 pub struct JJBranch {
     /// Branch name
     pub name: String,
@@ -461,7 +462,7 @@ impl JJBranch {
 ///     .build();
 /// ```
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[napi(object)]
+#[cfg_attr(not(target_arch = "wasm32"), napi(object))]
 pub struct JJConflict {
     /// Unique conflict identifier
     pub id: String,
@@ -593,7 +594,7 @@ impl JJConflictBuilder {
 
 /// Represents a diff between two commits
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[napi(object)]
+#[cfg_attr(not(target_arch = "wasm32"), napi(object))]
 pub struct JJDiff {
     /// Files added
     pub added: Vec<String>,
@@ -662,7 +663,7 @@ impl Default for JJDiff {
 /// let change = JJChange::new("src/main.rs".to_string());
 /// ```
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[napi(object)]
+#[cfg_attr(not(target_arch = "wasm32"), napi(object))]
 pub struct JJChange {
     /// File path
     pub file_path: String,
@@ -696,7 +697,7 @@ impl JJChange {
 
 /// Status of a file change
 #[derive(Debug, Serialize, Deserialize)]
-#[napi(string_enum)]
+#[cfg_attr(not(target_arch = "wasm32"), napi(string_enum))]
 pub enum ChangeStatus {
     /// File added
     Added,
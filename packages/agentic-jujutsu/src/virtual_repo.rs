@@ -0,0 +1,1588 @@
+//! In-memory jj repository
+//!
+//! [`VirtualRepo`] models the parts of a jj repository that agents work
+//! with: commits identified by change and commit IDs, a working copy that is
+//! snapshotted into `@` before every command, bookmarks, and a line-based
+//! diff. It backs the WASM build, where no `jj` binary can be spawned, and
+//! answers the same commands as [`JJWrapper`](crate::JJWrapper) does for the
+//! agent-facing subset: `status`, `new`, `describe`, `log`, `diff` and
+//! `bookmark create/set/delete/list`.
+//!
+//! Output follows jj's default templates, and failures are reported with the
+//! messages jj prints, so [`JJError::from_command_output`] classifies them the
+//! same way as native errors. Change IDs are derived from a counter rather
+//! than randomness, which makes a scenario reproducible like jj's own tests
+//! with a fixed `JJ_RANDOMNESS_SEED`.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use agentic_jujutsu::virtual_repo::VirtualRepo;
+//!
+//! let mut repo = VirtualRepo::new();
+//! repo.write_file("README.md", "hello\n");
+//! repo.execute(&["describe", "-m", "Add README"]).unwrap();
+//! repo.execute(&["new"]).unwrap();
+//!
+//! let log = repo.execute(&["log", "--no-graph"]).unwrap();
+//! assert!(log.stdout.contains("Add README"));
+//! ```
+
+use crate::command_parser::{parse_jj_args, ParsedCommand};
+use crate::error::{JJError, Result};
+use crate::operations::OperationType;
+use crate::types::{JJBranch, JJCommit, JJDiff, JJResult};
+use chrono::{DateTime, TimeZone, Utc};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+
+/// File contents by path
+pub type Tree = BTreeMap<String, String>;
+
+/// Commit ID of the root commit
+pub const ROOT_COMMIT_ID: &str = "0000000000000000000000000000000000000000";
+
+/// Change ID of the root commit
+pub const ROOT_CHANGE_ID: &str = "zzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzz";
+
+/// Length of IDs shown in command output
+const SHORT_ID_LEN: usize = 8;
+
+/// Length of blob hashes in `--git` diffs
+const BLOB_ID_LEN: usize = 10;
+
+/// Lines of context around diff hunks
+const CONTEXT_LINES: usize = 3;
+
+/// Default author, matching jj's test settings
+const DEFAULT_USER: &str = "Test User";
+const DEFAULT_EMAIL: &str = "test.user@example.com";
+
+/// A commit in a [`VirtualRepo`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VirtualCommit {
+    /// Content hash identifying this version of the change
+    pub commit_id: String,
+    /// Stable ID kept across rewrites
+    pub change_id: String,
+    /// Parent commit IDs
+    pub parents: Vec<String>,
+    /// Full description, with jj's trailing newline
+    pub description: String,
+    /// File contents
+    pub tree: Tree,
+    /// Author name
+    pub author: String,
+    /// Author email
+    pub email: String,
+    /// Committer timestamp
+    pub timestamp: DateTime<Utc>,
+    /// Whether the commit was rewritten and is no longer visible
+    pub hidden: bool,
+    /// Creation order, newest last
+    seq: u64,
+}
+
+/// Output of a successful command
+#[derive(Debug, Default)]
+struct Output {
+    stdout: String,
+    stderr: String,
+}
+
+/// Arguments of a command beyond what [`ParsedCommand`] records
+struct Invocation<'a> {
+    parsed: ParsedCommand,
+    args: &'a [&'a str],
+}
+
+impl Invocation<'_> {
+    /// Whether a flag (an option without a value) was given
+    fn flag(&self, names: &[&str]) -> bool {
+        self.args
+            .iter()
+            .take_while(|arg| **arg != "--")
+            .any(|arg| names.contains(arg))
+    }
+
+    fn values(&self, names: &[&str]) -> Vec<String> {
+        self.parsed.option_values(names)
+    }
+}
+
+/// In-memory jj repository
+#[derive(Debug, Clone)]
+pub struct VirtualRepo {
+    commits: HashMap<String, VirtualCommit>,
+    working_copy_id: String,
+    working_copy: Tree,
+    bookmarks: BTreeMap<String, String>,
+    user_name: String,
+    user_email: String,
+    timestamp: DateTime<Utc>,
+    next_seq: u64,
+}
+
+impl Default for VirtualRepo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl VirtualRepo {
+    /// Create a repository with an empty working-copy commit on the root
+    ///
+    /// Like `jj git init`, the repository starts with `@` as an empty child
+    /// of the root commit. The clock is fixed at 2001-02-03 04:05:06 UTC until
+    /// [`set_timestamp`](Self::set_timestamp) is called.
+    pub fn new() -> Self {
+        let timestamp = Utc
+            .with_ymd_and_hms(2001, 2, 3, 4, 5, 6)
+            .single()
+            .unwrap_or_default();
+
+        let root = VirtualCommit {
+            commit_id: ROOT_COMMIT_ID.to_string(),
+            change_id: ROOT_CHANGE_ID.to_string(),
+            parents: Vec::new(),
+            description: String::new(),
+            tree: Tree::new(),
+            author: String::new(),
+            email: String::new(),
+            timestamp: Utc.timestamp_opt(0, 0).single().unwrap_or_default(),
+            hidden: false,
+            seq: 0,
+        };
+
+        let mut repo = Self {
+            commits: HashMap::from([(root.commit_id.clone(), root)]),
+            working_copy_id: ROOT_COMMIT_ID.to_string(),
+            working_copy: Tree::new(),
+            bookmarks: BTreeMap::new(),
+            user_name: DEFAULT_USER.to_string(),
+            user_email: DEFAULT_EMAIL.to_string(),
+            timestamp,
+            next_seq: 1,
+        };
+        repo.working_copy_id = repo.create_commit(vec![ROOT_COMMIT_ID.to_string()], String::new());
+        repo
+    }
+
+    /// Set the author used for new and rewritten commits
+    pub fn set_user(&mut self, name: &str, email: &str) {
+        self.user_name = name.to_string();
+        self.user_email = email.to_string();
+    }
+
+    /// Set the clock used as committer timestamp
+    pub fn set_timestamp(&mut self, timestamp: DateTime<Utc>) {
+        self.timestamp = timestamp;
+    }
+
+    /// Write a file in the working copy
+    pub fn write_file(&mut self, path: &str, content: &str) {
+        self.working_copy
+            .insert(path.to_string(), content.to_string());
+    }
+
+    /// Read a file from the working copy
+    pub fn read_file(&self, path: &str) -> Option<&str> {
+        self.working_copy.get(path).map(String::as_str)
+    }
+
+    /// Remove a file from the working copy, returning whether it existed
+    pub fn remove_file(&mut self, path: &str) -> bool {
+        self.working_copy.remove(path).is_some()
+    }
+
+    /// Paths in the working copy
+    pub fn files(&self) -> Vec<String> {
+        self.working_copy.keys().cloned().collect()
+    }
+
+    /// The working-copy commit as of the last snapshot
+    pub fn working_copy_commit(&self) -> &VirtualCommit {
+        &self.commits[&self.working_copy_id]
+    }
+
+    /// Look up a visible commit by revision (`@`, `@-`, bookmark, ID prefix)
+    pub fn commit(&self, revision: &str) -> Result<&VirtualCommit> {
+        let id = self.resolve(revision)?;
+        Ok(&self.commits[&id])
+    }
+
+    /// Run a jj command line (with or without a leading `jj`)
+    ///
+    /// The working copy is snapshotted first, unless `--ignore-working-copy`
+    /// is given. A failing command leaves the repository as it was after the
+    /// snapshot.
+    pub fn execute(&mut self, args: &[&str]) -> Result<JJResult> {
+        let args = match args.first() {
+            Some(&"jj") => &args[1..],
+            _ => args,
+        };
+        let invocation = Invocation {
+            parsed: parse_jj_args(args),
+            args,
+        };
+
+        if !invocation.flag(&["--ignore-working-copy"]) {
+            self.snapshot();
+        }
+
+        let before = self.clone();
+        let result = self.dispatch(&invocation);
+        match result {
+            Ok(output) => {
+                self.working_copy = self.working_copy_commit().tree.clone();
+                Ok(JJResult::new(output.stdout, output.stderr, 0, 0))
+            }
+            Err(error) => {
+                *self = before;
+                Err(error)
+            }
+        }
+    }
+
+    /// Visible commits, newest first, at most `limit` of them
+    pub fn log_commits(&self, limit: Option<usize>) -> Vec<JJCommit> {
+        self.visible_commits()
+            .into_iter()
+            .take(limit.unwrap_or(usize::MAX))
+            .map(|commit| self.to_jj_commit(commit))
+            .collect()
+    }
+
+    /// Structured diff between two revisions
+    pub fn diff_between(&self, from: &str, to: &str) -> Result<JJDiff> {
+        let from = self.commits[&self.resolve(from)?].tree.clone();
+        let to = &self.commits[&self.resolve(to)?].tree;
+
+        let mut diff = JJDiff::new();
+        for (path, old, new) in tree_changes(&from, to, &[]) {
+            match (old, new) {
+                (None, Some(_)) => diff.added.push(path.to_string()),
+                (Some(_), None) => diff.deleted.push(path.to_string()),
+                _ => diff.modified.push(path.to_string()),
+            }
+            let old_lines = split_lines(old.unwrap_or(""));
+            let new_lines = split_lines(new.unwrap_or(""));
+            for edit in diff_lines(&old_lines, &new_lines) {
+                match edit.kind {
+                    EditKind::Insert => diff.additions += 1,
+                    EditKind::Delete => diff.deletions += 1,
+                    EditKind::Equal => {}
+                }
+            }
+        }
+        diff.content = format_git_diff(&from, to, &[]);
+        Ok(diff)
+    }
+
+    /// Local bookmarks
+    pub fn bookmark_list(&self) -> Vec<JJBranch> {
+        self.bookmarks
+            .iter()
+            .map(|(name, target)| JJBranch {
+                name: name.clone(),
+                target: target.clone(),
+                is_remote: false,
+                remote: None,
+                is_tracking: false,
+                is_current: *target == self.working_copy_id,
+                created_at: self.commits[target].timestamp.to_rfc3339(),
+            })
+            .collect()
+    }
+
+    fn dispatch(&mut self, invocation: &Invocation) -> Result<Output> {
+        let subcommand: Vec<&str> = invocation
+            .parsed
+            .subcommand
+            .iter()
+            .map(String::as_str)
+            .collect();
+
+        match subcommand.as_slice() {
+            ["status"] => Ok(self.status(invocation)),
+            ["new"] => self.new_change(invocation),
+            ["describe"] => self.describe(invocation),
+            ["log"] => self.log(invocation),
+            ["diff"] => self.diff(invocation),
+            ["bookmark", "create"] => self.bookmark_create(invocation),
+            ["bookmark", "set"] => self.bookmark_set(invocation),
+            ["bookmark", "delete"] => self.bookmark_delete(invocation),
+            ["bookmark", "list"] => Ok(self.bookmark_list_output()),
+            [] => Err(usage_error(
+                "jj requires a subcommand but one was not provided",
+            )),
+            [name] if invocation.parsed.operation_type == OperationType::Unknown => {
+                Err(usage_error(&format!("unrecognized subcommand '{}'", name)))
+            }
+            path => Err(JJError::CommandFailed(format!(
+                "`jj {}` is not supported by the in-memory repository",
+                path.join(" ")
+            ))),
+        }
+    }
+
+    // ----- commands -----
+
+    fn status(&self, invocation: &Invocation) -> Output {
+        let commit = self.working_copy_commit();
+        let parent_tree = self.parent_tree(commit);
+        let changes = tree_changes(&parent_tree, &commit.tree, &invocation.parsed.paths);
+
+        let mut stdout = String::new();
+        if changes.is_empty() {
+            stdout.push_str("The working copy has no changes.\n");
+        } else {
+            stdout.push_str("Working copy changes:\n");
+            for (path, old, new) in changes {
+                stdout.push_str(&format!("{} {}\n", status_letter(old, new), path));
+            }
+        }
+        stdout.push_str(&format!("Working copy  (@) : {}\n", self.summary(commit)));
+        for parent in &commit.parents {
+            stdout.push_str(&format!(
+                "Parent commit (@-): {}\n",
+                self.summary(&self.commits[parent])
+            ));
+        }
+
+        Output {
+            stdout,
+            stderr: String::new(),
+        }
+    }
+
+    fn new_change(&mut self, invocation: &Invocation) -> Result<Output> {
+        let mut revisions = invocation.parsed.revisions.clone();
+        if revisions.is_empty() {
+            revisions.push("@".to_string());
+        }
+        let mut parents = Vec::new();
+        for revision in &revisions {
+            let id = self.resolve(revision)?;
+            if !parents.contains(&id) {
+                parents.push(id);
+            }
+        }
+
+        let description = join_messages(&invocation.values(&["-m", "--message"]));
+        self.working_copy_id = self.create_commit(parents, description);
+
+        Ok(Output {
+            stdout: String::new(),
+            stderr: self.working_copy_report(),
+        })
+    }
+
+    fn describe(&mut self, invocation: &Invocation) -> Result<Output> {
+        let messages = invocation.values(&["-m", "--message"]);
+        if messages.is_empty() {
+            return Err(JJError::CommandFailed(
+                "`jj describe` needs a message (-m); the in-memory repository cannot open an editor"
+                    .to_string(),
+            ));
+        }
+        let description = join_messages(&messages);
+
+        let mut revisions = invocation.parsed.revisions.clone();
+        if revisions.is_empty() {
+            revisions.push("@".to_string());
+        }
+        let mut targets = Vec::new();
+        for revision in &revisions {
+            let id = self.resolve(revision)?;
+            if id == ROOT_COMMIT_ID {
+                return Err(fail(&format!(
+                    "The root commit {} is immutable",
+                    &ROOT_COMMIT_ID[..12]
+                )));
+            }
+            if !targets.contains(&id) {
+                targets.push(id);
+            }
+        }
+
+        let working_copy = self.working_copy_id.clone();
+        let mut rebased = 0;
+        let mut changed = false;
+        for target in targets {
+            // An earlier rewrite in this command may have rebased the target
+            let change_id = self.commits[&target].change_id.clone();
+            let id = self.visible_by_change_id(&change_id).unwrap_or(target);
+            if self.commits[&id].description == description {
+                continue;
+            }
+            changed = true;
+            let (_, count) = self.rewrite(&id, &|commit| commit.description = description.clone());
+            rebased += count;
+        }
+
+        if !changed {
+            return Ok(Output {
+                stdout: String::new(),
+                stderr: "Nothing changed.\n".to_string(),
+            });
+        }
+
+        let mut stderr = String::new();
+        if rebased > 0 {
+            stderr.push_str(&format!("Rebased {} descendant commits\n", rebased));
+        }
+        if self.working_copy_id != working_copy {
+            stderr.push_str(&self.working_copy_report());
+        }
+        Ok(Output {
+            stdout: String::new(),
+            stderr,
+        })
+    }
+
+    fn log(&self, invocation: &Invocation) -> Result<Output> {
+        if !invocation.values(&["-T", "--template"]).is_empty() {
+            return Err(JJError::CommandFailed(
+                "Templates are not supported by the in-memory repository".to_string(),
+            ));
+        }
+        let limit = match invocation.values(&["-n", "--limit"]).last() {
+            Some(value) => Some(value.parse::<usize>().map_err(|_| {
+                usage_error(&format!(
+                    "invalid value '{}' for '--limit <LIMIT>': invalid digit found in string",
+                    value
+                ))
+            })?),
+            None => None,
+        };
+
+        let mut commits = self.visible_commits();
+        let revsets = invocation.values(&["-r", "--revision", "--revisions"]);
+        if !revsets.is_empty() {
+            let mut selected = Vec::new();
+            for revset in &revsets {
+                selected.extend(self.evaluate(revset)?);
+            }
+            commits.retain(|commit| selected.contains(&commit.commit_id));
+        }
+        if !invocation.parsed.paths.is_empty() {
+            commits.retain(|commit| {
+                !tree_changes(
+                    &self.parent_tree(commit),
+                    &commit.tree,
+                    &invocation.parsed.paths,
+                )
+                .is_empty()
+            });
+        }
+        commits.truncate(limit.unwrap_or(usize::MAX));
+
+        let graph = !invocation.flag(&["--no-graph"]);
+        let shown: Vec<&str> = commits.iter().map(|c| c.commit_id.as_str()).collect();
+        let mut stdout = String::new();
+        for (index, commit) in commits.iter().enumerate() {
+            let (header, body) = self.log_entry(commit);
+            if !graph {
+                stdout.push_str(&format!("{}\n", header));
+                if let Some(body) = body {
+                    stdout.push_str(&format!("{}\n", body));
+                }
+                continue;
+            }
+
+            let node = if commit.commit_id == self.working_copy_id {
+                "@"
+            } else if commit.commit_id == ROOT_COMMIT_ID {
+                "◆"
+            } else {
+                "○"
+            };
+            stdout.push_str(&format!("{}  {}\n", node, header));
+            if let Some(body) = body {
+                stdout.push_str(&format!("│  {}\n", body));
+            }
+
+            let is_last = index + 1 == commits.len();
+            let elided = commit
+                .parents
+                .iter()
+                .any(|parent| !shown.contains(&parent.as_str()));
+            if is_last && elided {
+                stdout.push_str("~\n");
+            }
+        }
+
+        Ok(Output {
+            stdout,
+            stderr: String::new(),
+        })
+    }
+
+    fn diff(&self, invocation: &Invocation) -> Result<Output> {
+        let revisions = invocation.values(&["-r", "--revisions"]);
+        let from = invocation.values(&["-f", "--from"]);
+        let to = invocation.values(&["-t", "--to"]);
+
+        let (old, new) = if !from.is_empty() || !to.is_empty() {
+            let from = from.last().map_or("@", String::as_str);
+            let to = to.last().map_or("@", String::as_str);
+            (
+                self.commits[&self.resolve(from)?].tree.clone(),
+                self.commits[&self.resolve(to)?].tree.clone(),
+            )
+        } else {
+            let revision = revisions.last().map_or("@", String::as_str);
+            let commit = &self.commits[&self.resolve(revision)?];
+            (self.parent_tree(commit), commit.tree.clone())
+        };
+
+        let paths = &invocation.parsed.paths;
+        let stdout = if invocation.flag(&["--git"]) {
+            format_git_diff(&old, &new, paths)
+        } else if invocation.flag(&["-s", "--summary"]) {
+            tree_changes(&old, &new, paths)
+                .into_iter()
+                .map(|(path, o, n)| format!("{} {}\n", status_letter(o, n), path))
+                .collect()
+        } else if invocation.flag(&["--name-only"]) {
+            tree_changes(&old, &new, paths)
+                .into_iter()
+                .map(|(path, _, _)| format!("{}\n", path))
+                .collect()
+        } else {
+            format_color_words_diff(&old, &new, paths)
+        };
+
+        Ok(Output {
+            stdout,
+            stderr: String::new(),
+        })
+    }
+
+    fn bookmark_create(&mut self, invocation: &Invocation) -> Result<Output> {
+        let names = bookmark_names(invocation)?;
+        let target = self.bookmark_target(invocation)?;
+        for name in &names {
+            if self.bookmarks.contains_key(name) {
+                return Err(fail(&format!(
+                    "Bookmark already exists: {}\nHint: Use `jj bookmark set` to update it.",
+                    name
+                )));
+            }
+        }
+        for name in &names {
+            self.bookmarks.insert(name.clone(), target.clone());
+        }
+
+        Ok(Output {
+            stdout: String::new(),
+            stderr: format!(
+                "Created {} bookmarks pointing to {}\n",
+                names.len(),
+                self.summary(&self.commits[&target])
+            ),
+        })
+    }
+
+    fn bookmark_set(&mut self, invocation: &Invocation) -> Result<Output> {
+        let names = bookmark_names(invocation)?;
+        let target = self.bookmark_target(invocation)?;
+        let allow_backwards = invocation.flag(&["-B", "--allow-backwards"]);
+
+        let mut created = 0;
+        let mut moved = 0;
+        for name in &names {
+            match self.bookmarks.get(name) {
+                None => created += 1,
+                Some(current) if *current == target => {}
+                Some(current) => {
+                    if !allow_backwards && !self.is_ancestor(current, &target) {
+                        return Err(fail(&format!(
+                            "Refusing to move bookmark backwards or sideways: {}\nHint: Use --allow-backwards to allow it.",
+                            name
+                        )));
+                    }
+                    moved += 1;
+                }
+            }
+        }
+        if created == 0 && moved == 0 {
+            return Ok(Output {
+                stdout: String::new(),
+                stderr: "Nothing changed.\n".to_string(),
+            });
+        }
+        for name in &names {
+            self.bookmarks.insert(name.clone(), target.clone());
+        }
+
+        let summary = self.summary(&self.commits[&target]);
+        let mut stderr = String::new();
+        if created > 0 {
+            stderr.push_str(&format!(
+                "Created {} bookmarks pointing to {}\n",
+                created, summary
+            ));
+        }
+        if moved > 0 {
+            stderr.push_str(&format!("Moved {} bookmarks to {}\n", moved, summary));
+        }
+        Ok(Output {
+            stdout: String::new(),
+            stderr,
+        })
+    }
+
+    fn bookmark_delete(&mut self, invocation: &Invocation) -> Result<Output> {
+        let names = bookmark_names(invocation)?;
+        for name in &names {
+            if !self.bookmarks.contains_key(name) {
+                return Err(fail(&format!("No such bookmark: {}", name)));
+            }
+        }
+        for name in &names {
+            self.bookmarks.remove(name);
+        }
+
+        Ok(Output {
+            stdout: String::new(),
+            stderr: format!("Deleted {} bookmarks.\n", names.len()),
+        })
+    }
+
+    fn bookmark_list_output(&self) -> Output {
+        let stdout = self
+            .bookmarks
+            .iter()
+            .map(|(name, target)| format!("{}: {}\n", name, self.summary(&self.commits[target])))
+            .collect();
+        Output {
+            stdout,
+            stderr: String::new(),
+        }
+    }
+
+    fn bookmark_target(&self, invocation: &Invocation) -> Result<String> {
+        let revisions = invocation.values(&["-r", "--revision", "--revisions"]);
+        self.resolve(revisions.last().map_or("@", String::as_str))
+    }
+
+    // ----- repository model -----
+
+    /// Record working-copy changes in `@`
+    ///
+    /// [`execute`](Self::execute) does this before every command; the typed
+    /// accessors do not.
+    pub fn snapshot(&mut self) {
+        if self.working_copy_commit().tree == self.working_copy {
+            return;
+        }
+        let tree = self.working_copy.clone();
+        let id = self.working_copy_id.clone();
+        self.rewrite(&id, &|commit| commit.tree = tree.clone());
+    }
+
+    fn create_commit(&mut self, parents: Vec<String>, description: String) -> String {
+        let seq = self.take_seq();
+        let mut commit = VirtualCommit {
+            commit_id: String::new(),
+            change_id: change_id_for(seq),
+            parents,
+            description,
+            tree: Tree::new(),
+            author: self.user_name.clone(),
+            email: self.user_email.clone(),
+            timestamp: self.timestamp,
+            hidden: false,
+            seq,
+        };
+        commit.tree = self.parent_tree(&commit);
+        commit.commit_id = commit_id_for(&commit);
+
+        let id = commit.commit_id.clone();
+        self.commits.insert(id.clone(), commit);
+        id
+    }
+
+    /// Replace a commit with a modified copy and rebase its descendants
+    ///
+    /// Returns the new commit ID and the number of rebased descendants.
+    fn rewrite(&mut self, id: &str, change: &dyn Fn(&mut VirtualCommit)) -> (String, usize) {
+        let old = self.commits[id].clone();
+        let mut commit = old.clone();
+        change(&mut commit);
+        commit.timestamp = self.timestamp;
+        commit.seq = self.take_seq();
+        commit.commit_id = commit_id_for(&commit);
+
+        let new_id = commit.commit_id.clone();
+        let new_tree = commit.tree.clone();
+        self.commits.insert(new_id.clone(), commit);
+        if let Some(old) = self.commits.get_mut(id) {
+            old.hidden = true;
+        }
+        for target in self.bookmarks.values_mut() {
+            if target == id {
+                *target = new_id.clone();
+            }
+        }
+        if self.working_copy_id == id {
+            self.working_copy_id = new_id.clone();
+        }
+
+        let mut rebased = 0;
+        for child in self.children(id) {
+            let (_, count) = self.rewrite(&child, &|commit| {
+                for parent in commit.parents.iter_mut() {
+                    if parent == id {
+                        *parent = new_id.clone();
+                    }
+                }
+                commit.tree = apply_changes(&commit.tree, &old.tree, &new_tree);
+            });
+            rebased += 1 + count;
+        }
+
+        (new_id, rebased)
+    }
+
+    fn take_seq(&mut self) -> u64 {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        seq
+    }
+
+    /// Visible commits, newest first
+    fn visible_commits(&self) -> Vec<&VirtualCommit> {
+        let mut commits: Vec<&VirtualCommit> =
+            self.commits.values().filter(|c| !c.hidden).collect();
+        commits.sort_by_key(|c| std::cmp::Reverse(c.seq));
+        commits
+    }
+
+    /// Visible children of a commit, oldest first
+    fn children(&self, id: &str) -> Vec<String> {
+        let mut children: Vec<&VirtualCommit> = self
+            .commits
+            .values()
+            .filter(|c| !c.hidden && c.parents.iter().any(|p| p == id))
+            .collect();
+        children.sort_by_key(|c| c.seq);
+        children.into_iter().map(|c| c.commit_id.clone()).collect()
+    }
+
+    fn visible_by_change_id(&self, change_id: &str) -> Option<String> {
+        self.commits
+            .values()
+            .find(|c| !c.hidden && c.change_id == change_id)
+            .map(|c| c.commit_id.clone())
+    }
+
+    /// Whether `ancestor` is `descendant` or one of its ancestors
+    fn is_ancestor(&self, ancestor: &str, descendant: &str) -> bool {
+        let mut pending = vec![descendant.to_string()];
+        while let Some(id) = pending.pop() {
+            if id == ancestor {
+                return true;
+            }
+            pending.extend(self.commits[&id].parents.iter().cloned());
+        }
+        false
+    }
+
+    /// Tree a commit's changes are relative to
+    ///
+    /// For merges this is the union of the parent trees, with earlier parents
+    /// taking precedence; the model has no conflict representation.
+    fn parent_tree(&self, commit: &VirtualCommit) -> Tree {
+        let mut tree = Tree::new();
+        for parent in commit.parents.iter().rev() {
+            tree.extend(self.commits[parent].tree.clone());
+        }
+        tree
+    }
+
+    fn is_empty(&self, commit: &VirtualCommit) -> bool {
+        commit.commit_id != ROOT_COMMIT_ID && commit.tree == self.parent_tree(commit)
+    }
+
+    fn bookmarks_at(&self, id: &str) -> Vec<&str> {
+        self.bookmarks
+            .iter()
+            .filter(|(_, target)| *target == id)
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+
+    /// One-line commit summary as printed by `status`, `new` and `bookmark`
+    fn summary(&self, commit: &VirtualCommit) -> String {
+        let mut parts = vec![
+            short(&commit.change_id).to_string(),
+            short(&commit.commit_id).to_string(),
+        ];
+        let bookmarks = self.bookmarks_at(&commit.commit_id);
+        if !bookmarks.is_empty() {
+            parts.push(format!("{} |", bookmarks.join(" ")));
+        }
+        if commit.commit_id == ROOT_COMMIT_ID || self.is_empty(commit) {
+            parts.push("(empty)".to_string());
+        }
+        parts.push(first_line(&commit.description).to_string());
+        parts.join(" ")
+    }
+
+    /// Header and description lines of a `log` entry
+    fn log_entry(&self, commit: &VirtualCommit) -> (String, Option<String>) {
+        if commit.commit_id == ROOT_COMMIT_ID {
+            return (
+                format!(
+                    "{} root() {}",
+                    short(&commit.change_id),
+                    short(&commit.commit_id)
+                ),
+                None,
+            );
+        }
+
+        let mut header = vec![
+            short(&commit.change_id).to_string(),
+            commit.email.clone(),
+            commit.timestamp.format("%Y-%m-%d %H:%M:%S").to_string(),
+        ];
+        header.extend(
+            self.bookmarks_at(&commit.commit_id)
+                .iter()
+                .map(|b| b.to_string()),
+        );
+        header.push(short(&commit.commit_id).to_string());
+
+        let mut body = String::new();
+        if self.is_empty(commit) {
+            body.push_str("(empty) ");
+        }
+        body.push_str(first_line(&commit.description));
+        (header.join(" "), Some(body))
+    }
+
+    fn to_jj_commit(&self, commit: &VirtualCommit) -> JJCommit {
+        JJCommit {
+            id: commit.commit_id.clone(),
+            change_id: commit.change_id.clone(),
+            message: commit.description.trim_end().to_string(),
+            author: commit.author.clone(),
+            author_email: commit.email.clone(),
+            timestamp: commit.timestamp.to_rfc3339(),
+            parents: commit.parents.clone(),
+            children: self.children(&commit.commit_id),
+            branches: self
+                .bookmarks_at(&commit.commit_id)
+                .iter()
+                .map(|b| b.to_string())
+                .collect(),
+            tags: Vec::new(),
+            is_merge: commit.parents.len() > 1,
+            has_conflicts: false,
+            is_empty: self.is_empty(commit),
+        }
+    }
+
+    /// `Working copy  (@) now at:` report printed after `@` moves
+    fn working_copy_report(&self) -> String {
+        let commit = self.working_copy_commit();
+        let mut report = format!("Working copy  (@) now at: {}\n", self.summary(commit));
+        for parent in &commit.parents {
+            report.push_str(&format!(
+                "Parent commit (@-)      : {}\n",
+                self.summary(&self.commits[parent])
+            ));
+        }
+        report
+    }
+
+    // ----- revisions -----
+
+    /// Resolve a revision to a single visible commit ID
+    ///
+    /// Supports `@`, `root()`, bookmarks, change and commit ID prefixes, and
+    /// the postfix `-` (parent) and `+` (child) operators.
+    fn resolve(&self, revision: &str) -> Result<String> {
+        let revision = revision.trim();
+        if let Some(id) = self.resolve_symbol(revision)? {
+            return Ok(id);
+        }
+
+        let (base, step) = match revision.char_indices().last() {
+            Some((index, c @ ('-' | '+'))) if index > 0 => (&revision[..index], c),
+            _ => return Err(fail(&format!("Revision `{}` doesn't exist", revision))),
+        };
+        let id = self.resolve(base)?;
+        let candidates = if step == '-' {
+            self.commits[&id].parents.clone()
+        } else {
+            self.children(&id)
+        };
+        match candidates.as_slice() {
+            [single] => Ok(single.clone()),
+            [] => Err(fail(&format!(
+                "Revset `{}` didn't resolve to any revisions",
+                revision
+            ))),
+            _ => Err(fail(&format!(
+                "Revset `{}` resolved to more than one revision",
+                revision
+            ))),
+        }
+    }
+
+    fn resolve_symbol(&self, symbol: &str) -> Result<Option<String>> {
+        if symbol == "@" {
+            return Ok(Some(self.working_copy_id.clone()));
+        }
+        if symbol == "root()" {
+            return Ok(Some(ROOT_COMMIT_ID.to_string()));
+        }
+        if let Some(target) = self.bookmarks.get(symbol) {
+            return Ok(Some(target.clone()));
+        }
+        if symbol.is_empty() {
+            return Ok(None);
+        }
+
+        let (kind, matches): (&str, Vec<&VirtualCommit>) =
+            if symbol.chars().all(|c| ('k'..='z').contains(&c)) {
+                let matches = self
+                    .commits
+                    .values()
+                    .filter(|c| !c.hidden && c.change_id.starts_with(symbol))
+                    .collect();
+                ("Change ID", matches)
+            } else if symbol.chars().all(|c| c.is_ascii_hexdigit()) {
+                let matches = self
+                    .commits
+                    .values()
+                    .filter(|c| !c.hidden && c.commit_id.starts_with(symbol))
+                    .collect();
+                ("Commit ID", matches)
+            } else {
+                return Ok(None);
+            };
+
+        match matches.as_slice() {
+            [] => Ok(None),
+            [commit] => Ok(Some(commit.commit_id.clone())),
+            _ => Err(fail(&format!("{} prefix `{}` is ambiguous", kind, symbol))),
+        }
+    }
+
+    /// Evaluate the revsets `log -r` supports: `all()`, `::x`, `x::` and
+    /// single revisions
+    fn evaluate(&self, revset: &str) -> Result<Vec<String>> {
+        let revset = revset.trim();
+        let visible = self.visible_commits();
+
+        if revset == "all()" {
+            return Ok(visible.iter().map(|c| c.commit_id.clone()).collect());
+        }
+        if let Some(head) = revset.strip_prefix("::") {
+            let head = self.resolve(head)?;
+            return Ok(visible
+                .iter()
+                .filter(|c| self.is_ancestor(&c.commit_id, &head))
+                .map(|c| c.commit_id.clone())
+                .collect());
+        }
+        if let Some(base) = revset.strip_suffix("::") {
+            let base = self.resolve(base)?;
+            return Ok(visible
+                .iter()
+                .filter(|c| self.is_ancestor(&base, &c.commit_id))
+                .map(|c| c.commit_id.clone())
+                .collect());
+        }
+        Ok(vec![self.resolve(revset)?])
+    }
+}
+
+// ----- diffs -----
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EditKind {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// One line of an edit script, with the old and new line indices at it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Edit {
+    kind: EditKind,
+    old: usize,
+    new: usize,
+}
+
+/// Lines including their terminators
+fn split_lines(content: &str) -> Vec<&str> {
+    content.split_inclusive('\n').collect()
+}
+
+/// Line-based edit script from the longest common subsequence
+fn diff_lines(old: &[&str], new: &[&str]) -> Vec<Edit> {
+    // lcs[i][j] = length of the LCS of old[i..] and new[j..]
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut edits = Vec::with_capacity(old.len() + new.len());
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        let kind = if i < old.len() && j < new.len() && old[i] == new[j] {
+            EditKind::Equal
+        } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            EditKind::Delete
+        } else {
+            EditKind::Insert
+        };
+        edits.push(Edit {
+            kind,
+            old: i,
+            new: j,
+        });
+        match kind {
+            EditKind::Equal => {
+                i += 1;
+                j += 1;
+            }
+            EditKind::Delete => i += 1,
+            EditKind::Insert => j += 1,
+        }
+    }
+    edits
+}
+
+/// Group an edit script into hunks with `context` lines around changes
+fn hunks(edits: &[Edit], context: usize) -> Vec<&[Edit]> {
+    let changes: Vec<usize> = edits
+        .iter()
+        .enumerate()
+        .filter(|(_, edit)| edit.kind != EditKind::Equal)
+        .map(|(index, _)| index)
+        .collect();
+
+    let mut hunks = Vec::new();
+    let mut index = 0;
+    while index < changes.len() {
+        let start = changes[index].saturating_sub(context);
+        let mut end = changes[index];
+        while index + 1 < changes.len() && changes[index + 1] - end - 1 <= 2 * context {
+            index += 1;
+            end = changes[index];
+        }
+        index += 1;
+        hunks.push(&edits[start..edits.len().min(end + context + 1)]);
+    }
+    hunks
+}
+
+/// Changed paths between two trees, limited to `paths` (exact or directory
+/// prefixes) when given
+fn tree_changes<'a>(
+    old: &'a Tree,
+    new: &'a Tree,
+    paths: &[String],
+) -> Vec<(&'a str, Option<&'a str>, Option<&'a str>)> {
+    let mut all: Vec<&str> = old.keys().chain(new.keys()).map(String::as_str).collect();
+    all.sort_unstable();
+    all.dedup();
+
+    all.into_iter()
+        .filter(|path| matches_paths(path, paths))
+        .filter_map(|path| {
+            let before = old.get(path).map(String::as_str);
+            let after = new.get(path).map(String::as_str);
+            (before != after).then_some((path, before, after))
+        })
+        .collect()
+}
+
+fn matches_paths(path: &str, paths: &[String]) -> bool {
+    paths.is_empty()
+        || paths.iter().any(|prefix| {
+            let prefix = prefix.trim_end_matches('/');
+            prefix == "." || path == prefix || path.starts_with(&format!("{}/", prefix))
+        })
+}
+
+/// Apply the changes between `from` and `to` to `tree`
+///
+/// Paths the tree has modified itself keep its version.
+fn apply_changes(tree: &Tree, from: &Tree, to: &Tree) -> Tree {
+    let mut result = tree.clone();
+    for (path, before, after) in tree_changes(from, to, &[]) {
+        if tree.get(path).map(String::as_str) != before {
+            continue;
+        }
+        match after {
+            Some(content) => result.insert(path.to_string(), content.to_string()),
+            None => result.remove(path),
+        };
+    }
+    result
+}
+
+fn status_letter(old: Option<&str>, new: Option<&str>) -> char {
+    match (old, new) {
+        (None, _) => 'A',
+        (_, None) => 'D',
+        _ => 'M',
+    }
+}
+
+/// Git blob hash of file contents
+fn blob_id(content: &str) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(format!("blob {}\0", content.len()).as_bytes());
+    hasher.update(content.as_bytes());
+    hex::encode(hasher.finalize())[..BLOB_ID_LEN].to_string()
+}
+
+/// `jj diff --git` output
+fn format_git_diff(old: &Tree, new: &Tree, paths: &[String]) -> String {
+    let null_id = "0".repeat(BLOB_ID_LEN);
+    let mut out = String::new();
+
+    for (path, before, after) in tree_changes(old, new, paths) {
+        out.push_str(&format!("diff --git a/{} b/{}\n", path, path));
+        match (before, after) {
+            (None, Some(content)) => {
+                out.push_str("new file mode 100644\n");
+                out.push_str(&format!("index {}..{}\n", null_id, blob_id(content)));
+                out.push_str(&format!("--- /dev/null\n+++ b/{}\n", path));
+            }
+            (Some(content), None) => {
+                out.push_str("deleted file mode 100644\n");
+                out.push_str(&format!("index {}..{}\n", blob_id(content), null_id));
+                out.push_str(&format!("--- a/{}\n+++ /dev/null\n", path));
+            }
+            (before, after) => {
+                out.push_str(&format!(
+                    "index {}..{} 100644\n",
+                    blob_id(before.unwrap_or("")),
+                    blob_id(after.unwrap_or(""))
+                ));
+                out.push_str(&format!("--- a/{}\n+++ b/{}\n", path, path));
+            }
+        }
+
+        let old_lines = split_lines(before.unwrap_or(""));
+        let new_lines = split_lines(after.unwrap_or(""));
+        let edits = diff_lines(&old_lines, &new_lines);
+        for hunk in hunks(&edits, CONTEXT_LINES) {
+            let old_len = hunk.iter().filter(|e| e.kind != EditKind::Insert).count();
+            let new_len = hunk.iter().filter(|e| e.kind != EditKind::Delete).count();
+            let start = |index: usize, len: usize| if len == 0 { index } else { index + 1 };
+            out.push_str(&format!(
+                "@@ -{},{} +{},{} @@\n",
+                start(hunk[0].old, old_len),
+                old_len,
+                start(hunk[0].new, new_len),
+                new_len
+            ));
+            for edit in hunk {
+                let (sign, line) = match edit.kind {
+                    EditKind::Equal => (' ', old_lines[edit.old]),
+                    EditKind::Delete => ('-', old_lines[edit.old]),
+                    EditKind::Insert => ('+', new_lines[edit.new]),
+                };
+                out.push(sign);
+                out.push_str(line);
+                if !line.ends_with('\n') {
+                    out.push_str("\n\\ No newline at end of file\n");
+                }
+            }
+        }
+    }
+    out
+}
+
+/// jj's default (color-words) diff output, without colors
+fn format_color_words_diff(old: &Tree, new: &Tree, paths: &[String]) -> String {
+    let mut out = String::new();
+
+    for (path, before, after) in tree_changes(old, new, paths) {
+        let heading = match (before, after) {
+            (None, _) => "Added",
+            (_, None) => "Removed",
+            _ => "Modified",
+        };
+        out.push_str(&format!("{} regular file {}:\n", heading, path));
+
+        let old_lines = split_lines(before.unwrap_or(""));
+        let new_lines = split_lines(after.unwrap_or(""));
+        let edits = diff_lines(&old_lines, &new_lines);
+        for (index, hunk) in hunks(&edits, CONTEXT_LINES).into_iter().enumerate() {
+            if index > 0 {
+                out.push_str("    ...\n");
+            }
+            for edit in hunk {
+                let (old_number, new_number, line) = match edit.kind {
+                    EditKind::Equal => (Some(edit.old), Some(edit.new), old_lines[edit.old]),
+                    EditKind::Delete => (Some(edit.old), None, old_lines[edit.old]),
+                    EditKind::Insert => (None, Some(edit.new), new_lines[edit.new]),
+                };
+                let number =
+                    |n: Option<usize>| n.map_or("    ".to_string(), |n| format!("{:>4}", n + 1));
+                out.push_str(&format!(
+                    "{} {}: {}\n",
+                    number(old_number),
+                    number(new_number),
+                    line.trim_end_matches('\n')
+                ));
+            }
+        }
+    }
+    out
+}
+
+// ----- helpers -----
+
+/// Change ID for the `seq`-th commit, in jj's reverse-hex alphabet (`k`-`z`)
+fn change_id_for(seq: u64) -> String {
+    let digest = Sha256::digest(format!("change:{}", seq).as_bytes());
+    hex::encode(&digest[..16])
+        .chars()
+        .map(|c| {
+            let value = c.to_digit(16).unwrap_or(0) as u8;
+            char::from(b'z' - value)
+        })
+        .collect()
+}
+
+/// Commit ID as a hash of the commit's contents
+fn commit_id_for(commit: &VirtualCommit) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(commit.change_id.as_bytes());
+    for parent in &commit.parents {
+        hasher.update(b"\0parent ");
+        hasher.update(parent.as_bytes());
+    }
+    hasher.update(b"\0author ");
+    hasher.update(
+        format!(
+            "{} <{}> {}",
+            commit.author,
+            commit.email,
+            commit.timestamp.timestamp()
+        )
+        .as_bytes(),
+    );
+    for (path, content) in &commit.tree {
+        hasher.update(format!("\0file {} {}", path, blob_id(content)).as_bytes());
+    }
+    hasher.update(b"\0description ");
+    hasher.update(commit.description.as_bytes());
+    hex::encode(hasher.finalize())[..ROOT_COMMIT_ID.len()].to_string()
+}
+
+fn short(id: &str) -> &str {
+    &id[..SHORT_ID_LEN.min(id.len())]
+}
+
+fn first_line(description: &str) -> &str {
+    match description.lines().next() {
+        Some(line) if !line.is_empty() => line,
+        _ => "(no description set)",
+    }
+}
+
+/// Join `-m` values into a description the way jj does
+fn join_messages(messages: &[String]) -> String {
+    let description = messages.join("\n\n");
+    let description = description.trim_end();
+    if description.is_empty() {
+        String::new()
+    } else {
+        format!("{}\n", description)
+    }
+}
+
+fn bookmark_names(invocation: &Invocation) -> Result<Vec<String>> {
+    let names = invocation.parsed.positionals.clone();
+    if names.is_empty() {
+        return Err(usage_error(
+            "the following required arguments were not provided:\n  <NAMES>...",
+        ));
+    }
+    Ok(names)
+}
+
+/// Error as jj reports it for a failed command (exit code 1)
+fn fail(message: &str) -> JJError {
+    JJError::from_command_output(1, &format!("Error: {}\n", message))
+}
+
+/// Error as clap reports it for invalid arguments (exit code 2)
+fn usage_error(message: &str) -> JJError {
+    JJError::from_command_output(2, &format!("error: {}\n", message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    fn run(repo: &mut VirtualRepo, args: &[&str]) -> JJResult {
+        repo.execute(args)
+            .unwrap_or_else(|e| panic!("jj {} failed: {}", args.join(" "), e))
+    }
+
+    #[test]
+    fn test_fresh_repository() {
+        let mut repo = VirtualRepo::new();
+        let status = run(&mut repo, &["status"]);
+        let wc = repo.working_copy_commit().clone();
+
+        assert_eq!(
+            status.stdout,
+            format!(
+                "The working copy has no changes.\n\
+                 Working copy  (@) : {} {} (empty) (no description set)\n\
+                 Parent commit (@-): zzzzzzzz 00000000 (empty) (no description set)\n",
+                &wc.change_id[..8],
+                &wc.commit_id[..8]
+            )
+        );
+
+        let log = run(&mut repo, &["log"]);
+        assert_eq!(
+            log.stdout,
+            format!(
+                "@  {} test.user@example.com 2001-02-03 04:05:06 {}\n\
+                 │  (empty) (no description set)\n\
+                 ◆  zzzzzzzz root() 00000000\n",
+                &wc.change_id[..8],
+                &wc.commit_id[..8]
+            )
+        );
+    }
+
+    #[test]
+    fn test_snapshot_describe_and_new() {
+        let mut repo = VirtualRepo::new();
+        repo.write_file("a.txt", "one\n");
+
+        let status = run(&mut repo, &["st"]);
+        assert!(status
+            .stdout
+            .starts_with("Working copy changes:\nA a.txt\n"));
+        let change_id = repo.working_copy_commit().change_id.clone();
+
+        let described = run(&mut repo, &["describe", "-m", "Add a"]);
+        assert!(described.stderr.starts_with("Working copy  (@) now at: "));
+        assert!(described.stderr.contains(" Add a\n"));
+        assert_eq!(repo.working_copy_commit().change_id, change_id);
+
+        let new = run(&mut repo, &["new", "-m", "next"]);
+        assert!(new.stderr.contains("(empty) next\n"));
+        assert!(new.stderr.contains("Parent commit (@-)      : "));
+        assert_eq!(repo.commit("@-").unwrap().change_id, change_id);
+        assert_eq!(repo.read_file("a.txt"), Some("one\n"));
+
+        let again = run(&mut repo, &["describe", "-m", "next"]);
+        assert_eq!(again.stderr, "Nothing changed.\n");
+    }
+
+    #[test]
+    fn test_describe_ancestor_rebases_descendants() {
+        let mut repo = VirtualRepo::new();
+        repo.write_file("a.txt", "one\n");
+        run(&mut repo, &["new"]);
+        repo.write_file("b.txt", "two\n");
+        run(&mut repo, &["new"]);
+
+        let result = run(&mut repo, &["describe", "-r", "@--", "-m", "first"]);
+        assert!(result.stderr.starts_with("Rebased 2 descendant commits\n"));
+        assert!(result.stderr.contains("Working copy  (@) now at: "));
+        assert_eq!(repo.commit("@--").unwrap().description, "first\n");
+        assert_eq!(repo.files(), vec!["a.txt", "b.txt"]);
+
+        let root = repo.execute(&["describe", "-r", "root()", "-m", "x"]);
+        assert!(matches!(root, Err(JJError::ImmutableCommit { .. })));
+    }
+
+    #[test]
+    fn test_git_diff() {
+        let mut repo = VirtualRepo::new();
+        repo.write_file("a.txt", "1\n2\n3\n4\n5\n6\n7\n8\n9\n");
+        repo.write_file("gone.txt", "bye");
+        run(&mut repo, &["new"]);
+        repo.write_file("a.txt", "1\n2\n3\n4\nfive\n6\n7\n8\n9\n");
+        repo.remove_file("gone.txt");
+        repo.write_file("new.txt", "hi\n");
+
+        let diff = run(&mut repo, &["diff", "--git"]);
+        assert_eq!(
+            diff.stdout,
+            format!(
+                "diff --git a/a.txt b/a.txt\n\
+                 index {}..{} 100644\n\
+                 --- a/a.txt\n\
+                 +++ b/a.txt\n\
+                 @@ -2,7 +2,7 @@\n 2\n 3\n 4\n-5\n+five\n 6\n 7\n 8\n\
+                 diff --git a/gone.txt b/gone.txt\n\
+                 deleted file mode 100644\n\
+                 index {}..0000000000\n\
+                 --- a/gone.txt\n\
+                 +++ /dev/null\n\
+                 @@ -1,1 +0,0 @@\n-bye\n\\ No newline at end of file\n\
+                 diff --git a/new.txt b/new.txt\n\
+                 new file mode 100644\n\
+                 index 0000000000..{}\n\
+                 --- /dev/null\n\
+                 +++ b/new.txt\n\
+                 @@ -0,0 +1,1 @@\n+hi\n",
+                blob_id("1\n2\n3\n4\n5\n6\n7\n8\n9\n"),
+                blob_id("1\n2\n3\n4\nfive\n6\n7\n8\n9\n"),
+                blob_id("bye"),
+                blob_id("hi\n"),
+            )
+        );
+        // Same hash as `git hash-object`
+        assert_eq!(blob_id("hi\n"), "45b983be36");
+
+        let summary = run(&mut repo, &["diff", "--summary"]);
+        assert_eq!(summary.stdout, "M a.txt\nD gone.txt\nA new.txt\n");
+
+        let words = run(&mut repo, &["diff", "new.txt", "a.txt"]);
+        assert_eq!(
+            words.stdout,
+            "Modified regular file a.txt:\n   2    2: 2\n   3    3: 3\n   4    4: 4\n   5     : 5\n        5: five\n   6    6: 6\n   7    7: 7\n   8    8: 8\n\
+             Added regular file new.txt:\n        1: hi\n"
+        );
+
+        let structured = repo.diff_between("@-", "@").unwrap();
+        assert_eq!(structured.added, vec!["new.txt"]);
+        assert_eq!(structured.modified, vec!["a.txt"]);
+        assert_eq!(structured.deleted, vec!["gone.txt"]);
+        assert_eq!((structured.additions, structured.deletions), (2, 2));
+    }
+
+    #[test]
+    fn test_bookmarks() {
+        let mut repo = VirtualRepo::new();
+        repo.write_file("a.txt", "a\n");
+        run(&mut repo, &["describe", "-m", "base"]);
+        run(&mut repo, &["bookmark", "create", "main"]);
+        run(&mut repo, &["new"]);
+
+        let exists = repo.execute(&["bookmark", "create", "main"]);
+        assert!(matches!(
+            exists,
+            Err(JJError::CommandExited { exit_code: 1, .. })
+        ));
+
+        // Snapshots move bookmarks along with the rewritten commit
+        repo.write_file("b.txt", "b\n");
+        let moved = run(&mut repo, &["bookmark", "set", "main", "-r", "@"]);
+        assert!(moved.stderr.starts_with("Moved 1 bookmarks to "));
+        repo.write_file("b.txt", "changed\n");
+        run(&mut repo, &["status"]);
+        assert_eq!(
+            repo.bookmark_list()[0].target,
+            repo.working_copy_commit().commit_id
+        );
+
+        let backwards = repo.execute(&["branch", "set", "main", "-r", "@-"]);
+        assert!(backwards.is_err());
+        run(
+            &mut repo,
+            &["bookmark", "set", "main", "-r", "@-", "--allow-backwards"],
+        );
+
+        let list = run(&mut repo, &["bookmark", "list"]);
+        assert!(list.stdout.starts_with("main: "));
+        assert!(list.stdout.ends_with(" base\n"));
+
+        run(&mut repo, &["bookmark", "delete", "main"]);
+        let missing = repo.execute(&["bookmark", "delete", "main"]);
+        assert!(matches!(
+            missing,
+            Err(JJError::RevisionNotFound { ref revision, .. }) if revision == "main"
+        ));
+        assert!(repo.bookmark_list().is_empty());
+    }
+
+    #[test]
+    fn test_revisions_and_log() {
+        let mut repo = VirtualRepo::new();
+        run(&mut repo, &["describe", "-m", "one"]);
+        run(&mut repo, &["new", "-m", "two"]);
+        run(&mut repo, &["bookmark", "create", "feature", "-r", "@-"]);
+
+        let wc = repo.working_copy_commit().clone();
+        assert_eq!(
+            repo.commit(&wc.change_id[..4]).unwrap().commit_id,
+            wc.commit_id
+        );
+        assert_eq!(
+            repo.commit(&wc.commit_id[..6]).unwrap().commit_id,
+            wc.commit_id
+        );
+        assert_eq!(repo.commit("feature+").unwrap().commit_id, wc.commit_id);
+
+        let missing = repo.execute(&["log", "-r", "nope"]);
+        assert!(matches!(
+            missing,
+            Err(JJError::RevisionNotFound { ref revision, .. }) if revision == "nope"
+        ));
+
+        let log = run(&mut repo, &["log", "--no-graph", "-r", "::@-"]);
+        let lines: Vec<&str> = log.stdout.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].contains(" feature "));
+        assert_eq!(lines[1], "(empty) one");
+        assert_eq!(lines[2], "zzzzzzzz root() 00000000");
+
+        let limited = run(&mut repo, &["log", "-n", "1"]);
+        assert!(limited.stdout.ends_with("│  (empty) two\n~\n"));
+
+        let commits = repo.log_commits(Some(2));
+        assert_eq!(commits[0].message, "two");
+        assert_eq!(commits[1].branches, vec!["feature"]);
+        assert_eq!(commits[1].children, vec![wc.commit_id.clone()]);
+    }
+
+    #[test]
+    fn test_unsupported_and_unknown_commands() {
+        let mut repo = VirtualRepo::new();
+        assert!(matches!(
+            repo.execute(&["frobnicate"]),
+            Err(JJError::CommandExited { exit_code: 2, .. })
+        ));
+        assert!(matches!(
+            repo.execute(&["rebase", "-d", "@-"]),
+            Err(JJError::CommandFailed(_))
+        ));
+    }
+
+    #[test]
+    fn test_failed_command_keeps_state() {
+        let mut repo = VirtualRepo::new();
+        run(&mut repo, &["new"]);
+        let before = repo.working_copy_commit().clone();
+
+        // Nothing is rewritten when one of the revisions does not resolve
+        let result = repo.execute(&["describe", "-r", "@", "-r", "nope", "-m", "x"]);
+        assert!(result.is_err());
+        assert_eq!(repo.working_copy_commit(), &before);
+    }
+}
@@ -1,122 +1,194 @@
-//! WASM implementation with simulated command execution
+//! WASM bindings backed by an in-memory repository
 //!
-//! ## Architecture Note
+//! Browser and edge runtimes cannot spawn the `jj` binary, so the WASM build
+//! runs commands against a [`VirtualRepo`]: the same commit, change and
+//! bookmark model as jj, with the working copy kept in memory. The exported
+//! `JJWrapper` class mirrors the agent-facing subset of the native
+//! [`JJWrapper`](crate::JJWrapper) (`status`, `newCommit`, `describe`, `log`,
+//! `diff` and the `branch*` methods) and adds file access for the in-memory
+//! working copy.
 //!
-//! This module provides **simulated** jj command execution for WASM/browser environments
-//! where direct CLI execution is not possible. For real jj operations, use:
+//! Errors are thrown with the same `[CODE] message` reasons as the native
+//! module, so `errorCode()` works on both.
 //!
-//! - **Node.js/CLI**: The `bin/cli.js` executes the real `jj` binary if installed
-//! - **Native Rust**: The `native.rs` module uses async-process for real command execution
-//! - **Browser/WASM**: This module returns realistic mock data for demos and testing
-//!
-//! To use real jujutsu operations, install the jj CLI:
-//! - Cargo: `cargo install --git https://github.com/martinvonz/jj jj-cli`
-//! - Homebrew: `brew install jj`
-//! - From source: https://github.com/martinvonz/jj#installation
+//! Parity tests live next to the model in `virtual_repo.rs` and run under
+//! node with `wasm-pack test --node`.
 
 #![cfg(target_arch = "wasm32")]
 
-use crate::error::{JJError, Result};
-use std::time::Duration;
+use crate::error::JJError;
+use crate::virtual_repo::VirtualRepo;
+use chrono::{TimeZone, Utc};
+use serde::Serialize;
 use wasm_bindgen::prelude::*;
 
-/// Execute a jj command in WASM environment (simulated for browser contexts)
-///
-/// **Important**: This returns simulated data for browser/WASM environments.
-/// For real jj operations, use Node.js CLI or native Rust implementation.
-///
-/// Note: jj_path and timeout are ignored in WASM as we simulate commands
-pub async fn execute_jj_command(
-    _jj_path: &str,
-    args: &[&str],
-    _command_timeout: Duration,
-) -> Result<String> {
-    // Log to browser console with clear indication this is simulated
-    web_sys::console::warn_1(
-        &format!("WASM Simulation: jj {} (Browser environment - not real jj execution)", args.join(" ")).into()
-    );
+/// jj wrapper over an in-memory repository
+#[wasm_bindgen(js_name = JJWrapper)]
+pub struct WasmJJWrapper {
+    repo: VirtualRepo,
+}
 
-    // Return simulated responses for browser/WASM demonstrations
-    let response = match args.first() {
-        Some(&"version") | Some(&"--version") => {
-            "jj 0.12.0 (WASM simulation)".to_string()
-        }
-        Some(&"status") => {
-            "The working copy is clean\nWorking copy : qpvuntsm 12345678 (empty) (no description set)\nParent commit: zzzzzzzz 00000000 (empty) (no description set)".to_string()
-        }
-        Some(&"log") => {
-            "@  qpvuntsm test@example.com 2024-01-01 12:00:00.000 12345678\n│  (empty) (no description set)\n◉  zzzzzzzz root() 00000000".to_string()
-        }
-        Some(&"branch") => {
-            if args.len() > 1 && args[1] == "list" {
-                "main: abc123def456\nfeature: def789abc012".to_string()
-            } else {
-                "Branch operation simulated".to_string()
-            }
-        }
-        Some(&"diff") => {
-            "+++ b/file.txt\n--- a/file.txt\n+Added line\n-Removed line".to_string()
-        }
-        Some(&"resolve") => {
-            if args.contains(&"--list") {
-                "No conflicts found".to_string()
-            } else {
-                "Conflicts resolved".to_string()
-            }
-        }
-        Some(&"describe") => {
-            "Working copy now at: qpvuntsm 12345678 Updated description".to_string()
-        }
-        Some(&"new") => {
-            "Working copy now at: abcdefgh 87654321 (empty) (no description set)".to_string()
-        }
-        Some(&"edit") => {
-            "Working copy now at: requested commit".to_string()
-        }
-        Some(&"abandon") => {
-            "Abandoned commit".to_string()
-        }
-        Some(&"squash") => {
-            "Squashed commits".to_string()
-        }
-        Some(&"rebase") => {
-            "Rebased commits".to_string()
-        }
-        Some(&"undo") => {
-            "Undid operation".to_string()
-        }
-        Some(&"restore") => {
-            "Restored files".to_string()
-        }
-        Some(&"git") => {
-            if args.len() > 1 && args[1] == "fetch" {
-                "Fetched from remote".to_string()
-            } else if args.len() > 1 && args[1] == "push" {
-                "Pushed to remote".to_string()
-            } else {
-                "Git operation simulated".to_string()
-            }
+impl Default for WasmJJWrapper {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[wasm_bindgen(js_class = JJWrapper)]
+impl WasmJJWrapper {
+    /// Create a wrapper with a fresh repository (`@` empty on the root)
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> WasmJJWrapper {
+        WasmJJWrapper {
+            repo: VirtualRepo::new(),
         }
-        _ => {
-            return Err(JJError::CommandFailed(format!(
-                "Unknown command in WASM simulation: {}",
-                args.join(" ")
-            )));
+    }
+
+    /// Set the author of new and rewritten commits
+    #[wasm_bindgen(js_name = setUser)]
+    pub fn set_user(&mut self, name: &str, email: &str) {
+        self.repo.set_user(name, email);
+    }
+
+    /// Execute a jj command, e.g. `["log", "-r", "::@"]`
+    pub fn execute(&mut self, args: Vec<String>) -> Result<JsValue, JsError> {
+        self.run(args)
+    }
+
+    /// Get repository status
+    pub fn status(&mut self) -> Result<JsValue, JsError> {
+        self.run(vec!["status".to_string()])
+    }
+
+    /// Create a new change on top of `@`
+    #[wasm_bindgen(js_name = newCommit)]
+    pub fn new_commit(&mut self, message: Option<String>) -> Result<JsValue, JsError> {
+        let mut args = vec!["new".to_string()];
+        if let Some(message) = message {
+            args.push("-m".to_string());
+            args.push(message);
+        }
+        self.run(args)
+    }
+
+    /// Describe the working-copy change with a message
+    pub fn describe(&mut self, message: String) -> Result<JsValue, JsError> {
+        self.run(vec!["describe".to_string(), "-m".to_string(), message])
+    }
+
+    /// Visible commits, newest first
+    pub fn log(&mut self, limit: Option<u32>) -> Result<JsValue, JsError> {
+        self.snapshot();
+        to_js(&self.repo.log_commits(limit.map(|l| l as usize)))
+    }
+
+    /// Get diff between two revisions
+    pub fn diff(&mut self, from: String, to: String) -> Result<JsValue, JsError> {
+        self.snapshot();
+        let diff = self.repo.diff_between(&from, &to).map_err(to_js_error)?;
+        to_js(&diff)
+    }
+
+    /// Create a bookmark at `revision` (default `@`)
+    #[wasm_bindgen(js_name = branchCreate)]
+    pub fn branch_create(
+        &mut self,
+        name: String,
+        revision: Option<String>,
+    ) -> Result<JsValue, JsError> {
+        let mut args = vec!["bookmark".to_string(), "create".to_string(), name];
+        if let Some(revision) = revision {
+            args.push("-r".to_string());
+            args.push(revision);
+        }
+        self.run(args)
+    }
+
+    /// Delete a bookmark
+    #[wasm_bindgen(js_name = branchDelete)]
+    pub fn branch_delete(&mut self, name: String) -> Result<JsValue, JsError> {
+        self.run(vec!["bookmark".to_string(), "delete".to_string(), name])
+    }
+
+    /// List bookmarks
+    #[wasm_bindgen(js_name = branchList)]
+    pub fn branch_list(&self) -> Result<JsValue, JsError> {
+        to_js(&self.repo.bookmark_list())
+    }
+
+    /// Write a file in the working copy
+    #[wasm_bindgen(js_name = writeFile)]
+    pub fn write_file(&mut self, path: &str, content: &str) {
+        self.repo.write_file(path, content);
+    }
+
+    /// Read a file from the working copy
+    #[wasm_bindgen(js_name = readFile)]
+    pub fn read_file(&self, path: &str) -> Option<String> {
+        self.repo.read_file(path).map(str::to_string)
+    }
+
+    /// Remove a file from the working copy
+    #[wasm_bindgen(js_name = removeFile)]
+    pub fn remove_file(&mut self, path: &str) -> bool {
+        self.repo.remove_file(path)
+    }
+
+    /// Paths in the working copy
+    pub fn files(&self) -> Vec<String> {
+        self.repo.files()
+    }
+}
+
+impl WasmJJWrapper {
+    fn run(&mut self, args: Vec<String>) -> Result<JsValue, JsError> {
+        self.tick();
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        let result = self.repo.execute(&args).map_err(to_js_error)?;
+        to_js(&result)
+    }
+
+    /// Record working-copy changes, as any native jj command would
+    fn snapshot(&mut self) {
+        self.tick();
+        self.repo.snapshot();
+    }
+
+    /// Use the current time for commits created by the next command
+    fn tick(&mut self) {
+        if let Some(now) = Utc
+            .timestamp_millis_opt(js_sys::Date::now() as i64)
+            .single()
+        {
+            self.repo.set_timestamp(now);
         }
-    };
+    }
+}
 
-    Ok(response)
+fn to_js<T: Serialize>(value: &T) -> Result<JsValue, JsError> {
+    let json = serde_json::to_string(value)?;
+    js_sys::JSON::parse(&json).map_err(|_| JsError::new("Failed to convert result to JSON"))
 }
 
-// No stub needed - this module is only compiled for WASM targets
+/// Same reason format as the native module's `napi::Error`
+fn to_js_error(err: JJError) -> JsError {
+    let mut reason = format!("[{}] {}", err.code(), err);
+    if let Some(stderr) = err.stderr().map(str::trim).filter(|s| !s.is_empty()) {
+        if !reason.contains(stderr) {
+            reason.push('\n');
+            reason.push_str(stderr);
+        }
+    }
+    JsError::new(&reason)
+}
 
 /// Initialize WASM module
 #[wasm_bindgen(start)]
 pub fn wasm_init() {
     console_error_panic_hook::set_once();
-    web_sys::console::log_1(&"agentic-jujutsu WASM module initialized".into());
-    web_sys::console::log_1(&"Note: Browser WASM environment uses simulated jj commands".into());
-    web_sys::console::log_1(&"For real jj operations, use Node.js CLI or install jj binary".into());
+    web_sys::console::log_1(
+        &"agentic-jujutsu WASM module initialized (in-memory repository)".into(),
+    );
 }
 
 #[cfg(test)]
@@ -124,25 +196,26 @@ mod tests {
     use super::*;
     use wasm_bindgen_test::*;
 
-    wasm_bindgen_test_configure!(run_in_browser);
-
-    #[wasm_bindgen_test]
-    async fn test_wasm_version() {
-        let result = execute_jj_command(&["version"]).await;
-        assert!(result.is_ok());
-        assert!(result.unwrap().contains("WASM simulation"));
-    }
-
     #[wasm_bindgen_test]
-    async fn test_wasm_status() {
-        let result = execute_jj_command(&["status"]).await;
-        assert!(result.is_ok());
-        assert!(result.unwrap().contains("Working copy"));
+    fn test_wrapper_round_trip() {
+        let mut wrapper = WasmJJWrapper::new();
+        wrapper.write_file("a.txt", "a\n");
+        assert!(wrapper.describe("Add a".to_string()).is_ok());
+        assert!(wrapper.new_commit(None).is_ok());
+        assert!(wrapper
+            .branch_create("main".to_string(), Some("@-".to_string()))
+            .is_ok());
+        assert_eq!(wrapper.read_file("a.txt").as_deref(), Some("a\n"));
     }
 
     #[wasm_bindgen_test]
-    async fn test_wasm_unknown_command() {
-        let result = execute_jj_command(&["unknown_command"]).await;
-        assert!(result.is_err());
+    fn test_errors_carry_codes() {
+        let mut wrapper = WasmJJWrapper::new();
+        let err = match wrapper.branch_delete("missing".to_string()) {
+            Err(err) => JsValue::from(err),
+            Ok(_) => panic!("deleting a missing bookmark should fail"),
+        };
+        let message = err.unchecked_into::<js_sys::Error>().message();
+        assert!(String::from(message).starts_with("[REVISION_NOT_FOUND]"));
     }
 }
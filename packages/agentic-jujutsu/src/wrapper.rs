@@ -2,85 +2,39 @@
 
 use crate::{
     agent_coordination::AgentCoordination,
+    command_parser,
+    commit_message::{generate_commit_message, MessageContext},
     config::JJConfig,
+    embedded::extract_embedded_binary,
     error::{JJError, Result},
-    operations::{JJOperation, JJOperationLog, OperationType},
+    metrics,
+    operations::{JJOperation, JJOperationLog},
+    policy::{quote, PolicyOverride, ProtectionPolicy},
     reasoning_bank::{ReasoningBank, Trajectory},
+    review::{ChangeSnapshot, Review, ReviewComment, ReviewStore, ReviewVerdict, Verdict},
+    split::{hunk_infos, parse_diff, DiffHunkInfo, FileDiff, SplitChange, SplitGroup, SplitPlan},
     types::{JJBranch, JJCommit, JJConflict, JJDiff, JJResult},
-    native::execute_jj_command,
+    native::{run_jj_command, run_jj_command_with_retry, RetryPolicy},
 };
 use chrono::Utc;
 use napi_derive::napi;
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use std::time::Instant;
-use std::path::PathBuf;
-use std::fs;
-use std::io::Write;
-
-// Include the embedded jj binary
-const JJ_BINARY: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/jj"));
-
-/// Extract embedded jj binary to cache directory
-fn extract_embedded_binary() -> Result<PathBuf> {
-    // Get cache directory path
-    let home = std::env::var("HOME")
-        .or_else(|_| std::env::var("USERPROFILE"))
-        .unwrap_or_else(|_| ".".to_string());
-
-    let cache_dir = PathBuf::from(home)
-        .join(".cache")
-        .join("agentic-jujutsu");
-
-    // Create cache directory if it doesn't exist
-    fs::create_dir_all(&cache_dir)
-        .map_err(|e| JJError::IoError(format!("Failed to create cache directory: {}", e)))?;
-
-    let binary_path = cache_dir.join("jj");
-
-    // Check if binary is already extracted and valid
-    if binary_path.exists() {
-        // Verify it's not a placeholder
-        if let Ok(content) = fs::read(&binary_path) {
-            if content.len() > 100 && content != b"SYSTEM_JJ" {
-                return Ok(binary_path);
-            }
-        }
-    }
-
-    // Check if this is a placeholder indicating system jj should be used
-    if JJ_BINARY == b"SYSTEM_JJ" {
-        // Return "jj" to use system PATH
-        return Ok(PathBuf::from("jj"));
-    }
-
-    // Extract embedded binary
-    let mut file = fs::File::create(&binary_path)
-        .map_err(|e| JJError::IoError(format!("Failed to create jj binary file: {}", e)))?;
-
-    file.write_all(JJ_BINARY)
-        .map_err(|e| JJError::IoError(format!("Failed to write jj binary: {}", e)))?;
-
-    // Make executable on Unix
-    #[cfg(unix)]
-    {
-        use std::os::unix::fs::PermissionsExt;
-        let mut perms = fs::metadata(&binary_path)
-            .map_err(|e| JJError::IoError(format!("Failed to get permissions: {}", e)))?
-            .permissions();
-        perms.set_mode(0o755);
-        fs::set_permissions(&binary_path, perms)
-            .map_err(|e| JJError::IoError(format!("Failed to set permissions: {}", e)))?;
-    }
-
-    Ok(binary_path)
-}
+use tracing::Instrument;
 
 /// Validate command arguments to prevent command injection
+///
+/// Commit messages (`-m`/`--message` values) are passed to jj verbatim and
+/// may contain any character except NUL.
 fn validate_command_args(args: &[&str]) -> Result<()> {
+    let mut after_message_flag = false;
     for arg in args {
+        let is_message = std::mem::take(&mut after_message_flag) || arg.starts_with("--message=");
+        after_message_flag = matches!(*arg, "-m" | "--message");
+
         // Block shell metacharacters that could enable command injection
-        if arg.contains(&['$', '`', '&', '|', ';', '\n', '>', '<'][..]) {
+        if !is_message && arg.contains(&['$', '`', '&', '|', ';', '\n', '>', '<'][..]) {
             return Err(JJError::InvalidConfig(format!(
                 "Invalid character in argument: {}. Shell metacharacters are not allowed.",
                 arg
@@ -160,8 +114,35 @@ impl JJWrapper {
     }
 
     /// Execute a jj command and return the result
+    ///
+    /// Commands that violate the protection policy (`protectedRevsets`,
+    /// `protectedBookmarks`) are rejected before jj runs.
     #[napi]
     pub async fn execute(&self, args: Vec<String>) -> napi::Result<JJResult> {
+        self.execute_with_policy(args, None).await
+    }
+
+    /// Execute a jj command, overriding the protection policy
+    ///
+    /// The approver must be one of `overrideApprovers` and sign
+    /// `policyOverrideMessage(args, approvedBy, reason)` with their own key.
+    /// If the command violates the policy, a `PolicyOverride` entry carrying
+    /// that signature is added to the operation log first, and the executed
+    /// operation references it in its `policy_override` metadata.
+    #[napi(js_name = "executeWithOverride")]
+    pub async fn execute_with_override(
+        &self,
+        args: Vec<String>,
+        approval: PolicyOverride,
+    ) -> napi::Result<JJResult> {
+        self.execute_with_policy(args, Some(approval)).await
+    }
+
+    async fn execute_with_policy(
+        &self,
+        args: Vec<String>,
+        approval: Option<PolicyOverride>,
+    ) -> napi::Result<JJResult> {
         // Convert Vec<String> to Vec<&str> for internal processing
         let args_refs: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
 
@@ -171,15 +152,63 @@ impl JJWrapper {
 
         let start = Instant::now();
         let command = format!("jj {}", args.join(" "));
+        let parsed = command_parser::parse_jj_args(&args_refs);
+        let operation_type = parsed.operation_type.as_string();
+
+        // Enforce the protection policy before jj is invoked
+        let check = ProtectionPolicy::from_config(&self.config)
+            .check(&parsed, |revset| self.revset_is_nonempty(revset))
+            .await;
+        let override_id = match (check, approval) {
+            (Ok(()), _) => None,
+            (
+                Err(violation @ (JJError::ProtectedRevision { .. } | JJError::ProtectedBookmark { .. })),
+                Some(approval),
+            ) => Some(self.record_override(&args, &command, &violation, &approval)?),
+            (Err(violation), _) => {
+                metrics::global().record_operation(&operation_type, violation.code(), start.elapsed());
+                return Err(violation.into());
+            }
+        };
+        let span = tracing::info_span!(
+            "jj_command",
+            command = %command,
+            operation_type = %operation_type,
+            revisions = ?parsed.revisions,
+            duration_ms = tracing::field::Empty,
+            outcome = tracing::field::Empty,
+        );
         let hostname = std::env::var("HOSTNAME").unwrap_or_else(|_| "unknown".to_string());
         let username = std::env::var("USER").unwrap_or_else(|_| "unknown".to_string());
 
         // Execute command with timeout
         let timeout = std::time::Duration::from_millis(self.config.timeout_ms as u64);
-        let result = execute_jj_command(&self.config.jj_path, &args_refs, timeout).await;
+        let repo_path = std::path::Path::new(&self.config.repo_path);
+        let retry_policy = match self.config.max_retries {
+            Some(retries) => RetryPolicy::default().with_max_retries(retries),
+            None => RetryPolicy::default(),
+        };
+        let result = run_jj_command_with_retry(
+            &self.config.jj_path,
+            Some(repo_path),
+            &args_refs,
+            timeout,
+            &retry_policy,
+        )
+        .instrument(span.clone())
+        .await;
 
         // Log the operation (ALWAYS, even if failed)
-        let duration_ms = start.elapsed().as_millis() as u64;
+        let elapsed = start.elapsed();
+        let duration_ms = elapsed.as_millis() as u64;
+        let outcome = match &result {
+            Ok(_) => "success",
+            Err(e) => e.code(),
+        };
+        span.record("duration_ms", duration_ms);
+        span.record("outcome", outcome);
+        metrics::global().record_operation(&operation_type, outcome, elapsed);
+
         let mut operation = JJOperation::new(
             format!("{}@{}", Utc::now().timestamp(), hostname),
             command.clone(),
@@ -187,13 +216,23 @@ impl JJWrapper {
             hostname.clone(),
         );
 
-        operation.operation_type = Self::detect_operation_type(&args_refs).as_string();
+        operation.operation_type = operation_type;
+        operation.revisions = parsed.revisions;
+        operation.paths = parsed.paths;
         operation.duration_ms = duration_ms as u32;
+        if let Some(id) = override_id {
+            operation.set_metadata("policy_override".to_string(), id);
+        }
 
         match &result {
             Ok(output) => {
                 operation.success = true;
-                let jj_result = JJResult::new(output.clone(), String::new(), 0, duration_ms);
+                let jj_result = JJResult::new(
+                    output.stdout.clone(),
+                    output.stderr.clone(),
+                    output.exit_code,
+                    duration_ms,
+                );
                 self.operation_log.lock().unwrap().add_operation(operation);
                 Ok(jj_result)
             }
@@ -201,36 +240,78 @@ impl JJWrapper {
                 operation.success = false;
                 operation.error = Some(e.to_string());
                 self.operation_log.lock().unwrap().add_operation(operation);
-                Err(napi::Error::from_reason(format!("Command failed: {}", e)))
+                Err(e.clone().into())
             }
         }
     }
 
-    /// Detect operation type from command arguments
-    fn detect_operation_type(args: &[&str]) -> OperationType {
-        if args.is_empty() {
-            return OperationType::Unknown;
+    /// Whether `revset` contains any commit, for the protection policy
+    async fn revset_is_nonempty(&self, revset: String) -> Result<bool> {
+        let output = run_jj_command(
+            &self.config.jj_path,
+            Some(std::path::Path::new(&self.config.repo_path)),
+            &[
+                "log",
+                "--no-graph",
+                "--ignore-working-copy",
+                "-n",
+                "1",
+                "-r",
+                &revset,
+                "-T",
+                "commit_id",
+            ],
+            std::time::Duration::from_millis(self.config.timeout_ms as u64),
+        )
+        .await?;
+        Ok(!output.stdout.trim().is_empty())
+    }
+
+    /// Add a record of an approved policy override to the log
+    ///
+    /// Returns the ID of the record.
+    fn record_override(
+        &self,
+        args: &[String],
+        command: &str,
+        violation: &JJError,
+        approval: &PolicyOverride,
+    ) -> napi::Result<String> {
+        // Without configured approvers nobody may override
+        let approvers = self.config.override_approvers.as_deref().unwrap_or_default();
+        if !approvers.contains(&approval.public_key) {
+            return Err(JJError::CryptoError(format!(
+                "{} is not an authorized override approver",
+                approval.approved_by
+            ))
+            .into());
         }
-
-        match args[0] {
-            "describe" => OperationType::Describe,
-            "new" => OperationType::New,
-            "edit" => OperationType::Edit,
-            "abandon" => OperationType::Abandon,
-            "rebase" => OperationType::Rebase,
-            "squash" => OperationType::Squash,
-            "resolve" => OperationType::Resolve,
-            "branch" => OperationType::Branch,
-            "bookmark" => OperationType::Bookmark,
-            "git" if args.len() > 1 && args[1] == "fetch" => OperationType::GitFetch,
-            "git" if args.len() > 1 && args[1] == "push" => OperationType::GitPush,
-            "undo" => OperationType::Undo,
-            "restore" => OperationType::Restore,
-            "status" => OperationType::Status,
-            "log" => OperationType::Log,
-            "diff" => OperationType::Diff,
-            _ => OperationType::Unknown,
+        if !approval.verify(args)? {
+            return Err(JJError::CryptoError(
+                "Override signature does not match the command and the approver's key".to_string(),
+            )
+            .into());
         }
+
+        let hostname = std::env::var("HOSTNAME").unwrap_or_else(|_| "unknown".to_string());
+        let mut record = JJOperation::new(
+            format!("override-{}", uuid::Uuid::new_v4()),
+            format!("override {} [{}]: {}", command, violation, approval.reason),
+            approval.approved_by.clone(),
+            hostname,
+        );
+        record.operation_type = "PolicyOverride".to_string();
+        record.tags.push("policy-override".to_string());
+        record.set_metadata("command".to_string(), command.to_string());
+        record.set_metadata("violation".to_string(), violation.code().to_string());
+        record.set_metadata("reason".to_string(), approval.reason.clone());
+        record.set_metadata("approved_by".to_string(), approval.approved_by.clone());
+        record.set_metadata("approver_public_key".to_string(), approval.public_key.clone());
+        record.set_metadata("approval_signature".to_string(), approval.signature.clone());
+
+        let id = record.id.clone();
+        self.operation_log.lock().unwrap().add_operation(record);
+        Ok(id)
     }
 
     /// Get operations from the operation log
@@ -259,8 +340,10 @@ impl JJWrapper {
         };
 
         let result = self.execute(args).await?;
-        Self::parse_conflicts(&result.stdout)
-            .map_err(|e| napi::Error::from_reason(format!("Failed to parse conflicts: {}", e)))
+        let conflicts = Self::parse_conflicts(&result.stdout)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to parse conflicts: {}", e)))?;
+        metrics::global().record_conflicts("working_copy", conflicts.len());
+        Ok(conflicts)
     }
 
     /// Parse conflict list output
@@ -318,6 +401,24 @@ impl JJWrapper {
             .ok_or_else(|| napi::Error::from_reason("No operations found"))
     }
 
+    /// Generate a commit message for the working-copy change from its diff
+    ///
+    /// The message is built offline from `jj diff --git -r @` and the active
+    /// trajectory's task and operations.
+    #[napi(js_name = "generateDescription")]
+    pub async fn generate_description(&self) -> napi::Result<String> {
+        let context = self.message_context(None)?;
+        self.generate_description_with(&context).await
+    }
+
+    /// Describe the working-copy change with a generated message
+    #[napi(js_name = "describeAuto")]
+    pub async fn describe_auto(&self) -> napi::Result<JJOperation> {
+        let context = self.message_context(None)?;
+        let message = self.generate_description_with(&context).await?;
+        self.describe(message).await
+    }
+
     /// Get repository status
     #[napi]
     pub async fn status(&self) -> napi::Result<JJResult> {
@@ -560,6 +661,122 @@ impl JJWrapper {
         self.operation_log.lock().unwrap().clear();
     }
 
+    // ========== REVIEW METHODS ==========
+
+    /// Open a review for a change, or refresh the existing one
+    ///
+    /// Reviews are keyed by change ID and stored under `.jj/agentic-jujutsu/reviews`.
+    #[napi(js_name = "openReview")]
+    pub async fn open_review(&self, change_id: String, opened_by: String) -> napi::Result<Review> {
+        let change = self.change_snapshot(&change_id).await?;
+        Ok(self.review_store().open(&change, &opened_by)?)
+    }
+
+    /// Get the review of a change, with comments re-anchored to its current commit
+    #[napi(js_name = "getReview")]
+    pub async fn get_review(&self, change_id: String) -> napi::Result<Review> {
+        let change = self.change_snapshot(&change_id).await?;
+        Ok(self.review_store().refresh(&change)?)
+    }
+
+    /// List stored reviews as of their last refresh
+    #[napi(js_name = "listReviews")]
+    pub fn list_reviews(&self) -> napi::Result<Vec<Review>> {
+        Ok(self.review_store().list()?)
+    }
+
+    /// Comment on a line of a change's diff (line number in the new file)
+    #[napi(js_name = "addReviewComment")]
+    pub async fn add_review_comment(
+        &self,
+        change_id: String,
+        author: String,
+        path: String,
+        line: u32,
+        body: String,
+    ) -> napi::Result<ReviewComment> {
+        let change = self.change_snapshot(&change_id).await?;
+        Ok(self.review_store().comment(&change, &author, &path, line, &body)?)
+    }
+
+    /// Approve a change or request changes, signed with the reviewer's key
+    ///
+    /// `verdict` is `approve` or `request_changes`. The verdict applies to the
+    /// change's current commit and becomes stale when the change is rewritten.
+    #[napi(js_name = "submitReviewVerdict")]
+    pub async fn submit_review_verdict(
+        &self,
+        change_id: String,
+        reviewer: String,
+        verdict: String,
+        body: Option<String>,
+        secret_key: String,
+        public_key: String,
+    ) -> napi::Result<ReviewVerdict> {
+        let verdict = Verdict::parse(&verdict)?;
+        let change = self.change_snapshot(&change_id).await?;
+        Ok(self.review_store().submit_verdict(
+            &change,
+            &reviewer,
+            verdict,
+            body.as_deref(),
+            &secret_key,
+            &public_key,
+        )?)
+    }
+
+    /// Verify the signatures of all verdicts on a change
+    #[napi(js_name = "verifyReview")]
+    pub async fn verify_review(&self, change_id: String) -> napi::Result<bool> {
+        let review = self.get_review(change_id).await?;
+        for verdict in &review.verdicts {
+            if !verdict.verify()? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    // ========== SPLIT METHODS ==========
+
+    /// List the hunks of a revision's diff, for building a split partition
+    #[napi(js_name = "diffHunks")]
+    pub async fn diff_hunks(&self, revision: String) -> napi::Result<Vec<DiffHunkInfo>> {
+        let change = self.change_snapshot(&revision).await?;
+        Ok(hunk_infos(&parse_diff(&change.diff)))
+    }
+
+    /// Split a change into one change per group, without an editor
+    ///
+    /// Groups are stacked in order on the original's parents. The last group
+    /// keeps the original change ID, so descendants stay on top of it.
+    #[napi(js_name = "splitChange")]
+    pub async fn split_change(
+        &self,
+        revision: String,
+        groups: Vec<SplitGroup>,
+    ) -> napi::Result<Vec<SplitChange>> {
+        let change = self.change_snapshot(&revision).await?;
+        let files = parse_diff(&change.diff);
+        let plan = SplitPlan::new(&files, &groups)?;
+        self.apply_split(&change, &files, &plan).await
+    }
+
+    /// Split a change by formatting, file category and directory
+    ///
+    /// Returns an empty list (and leaves the change alone) when everything
+    /// falls into one group.
+    #[napi(js_name = "autoSplit")]
+    pub async fn auto_split(&self, revision: String) -> napi::Result<Vec<SplitChange>> {
+        let change = self.change_snapshot(&revision).await?;
+        let files = parse_diff(&change.diff);
+        let plan = SplitPlan::auto(&files);
+        if plan.groups.len() < 2 {
+            return Ok(Vec::new());
+        }
+        self.apply_split(&change, &files, &plan).await
+    }
+
     // ========== REASONING BANK METHODS ==========
 
     /// Start a learning trajectory for a task
@@ -769,10 +986,15 @@ impl JJWrapper {
     // ===== Agent Coordination Methods =====
 
     /// Enable agent coordination with QuantumDAG
+    ///
+    /// Conflict rules are loaded from `conflict_rules_path` when configured.
     #[napi(js_name = "enableAgentCoordination")]
     pub async fn enable_agent_coordination(&self) -> napi::Result<()> {
+        let rules = self.config.load_conflict_rules()
+            .map_err(|e| napi::Error::from_reason(format!("Failed to load conflict rules: {}", e)))?;
+
         let mut coord = self.agent_coordination.lock().await;
-        *coord = Some(AgentCoordination::new());
+        *coord = Some(AgentCoordination::with_conflict_rules(rules));
         Ok(())
     }
 
@@ -790,6 +1012,12 @@ impl JJWrapper {
     }
 
     /// Register an agent operation in the coordination system
+    ///
+    /// The operation is recorded even when a conflict rule with action
+    /// `block` or `require-approval` fires against another agent's
+    /// operation; it is then flagged and the call fails with
+    /// `OPERATION_BLOCKED` or `APPROVAL_REQUIRED`, so it can be undone or
+    /// approved.
     #[napi(js_name = "registerAgentOperation")]
     pub async fn register_agent_operation(
         &self,
@@ -809,8 +1037,7 @@ impl JJWrapper {
                     .map_err(|e| napi::Error::from_reason(format!("Failed to get operation: {}", e)))?
             };
 
-            let vertex_id = coordination.register_operation(&agent_id, &operation, affected_files).await
-                .map_err(|e| napi::Error::from_reason(format!("Failed to register operation: {}", e)))?;
+            let vertex_id = coordination.register_operation(&agent_id, &operation, affected_files).await?;
 
             Ok(vertex_id)
         } else {
@@ -818,6 +1045,18 @@ impl JJWrapper {
         }
     }
 
+    /// Approve an operation flagged by a `require-approval` conflict rule
+    #[napi(js_name = "approveAgentOperation")]
+    pub async fn approve_agent_operation(&self, operation_id: String) -> napi::Result<()> {
+        let coord = self.agent_coordination.lock().await;
+        if let Some(ref coordination) = *coord {
+            coordination.approve_operation(&operation_id).await;
+            Ok(())
+        } else {
+            Err(napi::Error::from_reason("Agent coordination not enabled. Call enableAgentCoordination() first."))
+        }
+    }
+
     /// Check for conflicts with proposed operation
     #[napi(js_name = "checkAgentConflicts")]
     pub async fn check_agent_conflicts(
@@ -830,6 +1069,7 @@ impl JJWrapper {
         if let Some(ref coordination) = *coord {
             let conflicts = coordination.check_conflicts(&operation_id, &operation_type, affected_files).await
                 .map_err(|e| napi::Error::from_reason(format!("Failed to check conflicts: {}", e)))?;
+            metrics::global().record_conflicts("agents", conflicts.len());
 
             serde_json::to_string(&conflicts)
                 .map_err(|e| napi::Error::from_reason(format!("Failed to serialize conflicts: {}", e)))
@@ -1221,6 +1461,222 @@ impl JJWrapper {
             agent_coordination,
         })
     }
+
+    /// Reasoning bank holding this wrapper's trajectories and patterns
+    pub fn reasoning_bank(&self) -> &ReasoningBank {
+        &self.reasoning_bank
+    }
+
+    /// Message context from the active trajectory
+    ///
+    /// `task` is used when no trajectory is active.
+    pub fn message_context(&self, task: Option<String>) -> napi::Result<MessageContext> {
+        let current = self.current_trajectory.lock().map_err(|e| {
+            napi::Error::from_reason(format!("Failed to lock trajectory: {}", e))
+        })?;
+
+        Ok(match current.as_ref() {
+            Some(trajectory) => MessageContext {
+                task: Some(trajectory.task.clone()),
+                operations: trajectory
+                    .operations
+                    .iter()
+                    .map(|op| op.operation_type.clone())
+                    .collect(),
+            },
+            None => MessageContext {
+                task,
+                operations: Vec::new(),
+            },
+        })
+    }
+
+    /// Review store of the configured repository
+    pub fn review_store(&self) -> ReviewStore {
+        ReviewStore::for_repo(&self.config.repo_path)
+    }
+
+    /// Full change ID, current commit ID and git diff of a single revision
+    pub async fn change_snapshot(&self, revision: &str) -> napi::Result<ChangeSnapshot> {
+        let args = vec![
+            "log".to_string(),
+            "--no-graph".to_string(),
+            "-r".to_string(),
+            revision.to_string(),
+            "-T".to_string(),
+            r#"change_id ++ " " ++ commit_id ++ "\n""#.to_string(),
+        ];
+        let output = self.execute(args).await?.stdout;
+        let lines: Vec<&str> = output.lines().filter(|l| !l.trim().is_empty()).collect();
+        let ids = match lines.as_slice() {
+            [line] => line.trim().split_once(' '),
+            _ => None,
+        };
+        let Some((change_id, commit_id)) = ids else {
+            return Err(JJError::ParseError(format!(
+                "Expected a single change for {}, got: {}",
+                revision,
+                output.trim()
+            ))
+            .into());
+        };
+
+        let args = vec![
+            "diff".to_string(),
+            "--git".to_string(),
+            "-r".to_string(),
+            commit_id.to_string(),
+        ];
+        let diff = self.execute(args).await?.stdout;
+        Ok(ChangeSnapshot {
+            change_id: change_id.to_string(),
+            commit_id: commit_id.to_string(),
+            diff,
+        })
+    }
+
+    /// Carry out a validated split of `change`
+    ///
+    /// A failure part-way through restores the operation the split started
+    /// from, so the repository never keeps a half-built stack.
+    pub async fn apply_split(
+        &self,
+        change: &ChangeSnapshot,
+        files: &[FileDiff],
+        plan: &SplitPlan,
+    ) -> napi::Result<Vec<SplitChange>> {
+        // Check the split as a whole, so a protected change is rejected
+        // before any of its steps runs
+        let split = command_parser::parse_jj_args(&["split", "-r", &change.commit_id]);
+        ProtectionPolicy::from_config(&self.config)
+            .check(&split, |revset| self.revset_is_nonempty(revset))
+            .await?;
+
+        let jj = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+        // Reading the change ID snapshots the working copy before the
+        // operation to restore is recorded
+        let working_copy = self
+            .execute(jj(&["log", "--no-graph", "-r", "@", "-T", "change_id"]))
+            .await?
+            .stdout
+            .trim()
+            .to_string();
+        let operation = self
+            .execute(jj(&["op", "log", "--no-graph", "-n", "1", "-T", "id"]))
+            .await?
+            .stdout
+            .trim()
+            .to_string();
+
+        match self.split_steps(change, files, plan, &working_copy).await {
+            Ok(created) => Ok(created),
+            Err(err) => match self.execute(jj(&["op", "restore", &operation])).await {
+                Ok(_) => Err(err),
+                Err(restore) => Err(JJError::CommandFailed(format!(
+                    "{}; restoring operation {} also failed: {}",
+                    err.reason, operation, restore.reason
+                ))
+                .into()),
+            },
+        }
+    }
+
+    async fn split_steps(
+        &self,
+        change: &ChangeSnapshot,
+        files: &[FileDiff],
+        plan: &SplitPlan,
+        working_copy: &str,
+    ) -> napi::Result<Vec<SplitChange>> {
+        let jj = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+        let partial = plan.partial_files(files);
+        let mut bases = HashMap::new();
+        let mut created = Vec::new();
+        let Some((last, groups)) = plan.groups.split_last() else {
+            return Ok(created);
+        };
+
+        for (index, group) in groups.iter().enumerate() {
+            if index == 0 {
+                let parents = format!("{}-", change.commit_id);
+                self.execute(jj(&["new", &parents, "-m", &group.description]))
+                    .await?;
+                for &file in &partial {
+                    let path = file_pattern(&files[file].path);
+                    let base = self.execute(jj(&["file", "show", "-r", "@", &path])).await?;
+                    bases.insert(file, base.stdout);
+                }
+            } else {
+                self.execute(jj(&["new", "-m", &group.description])).await?;
+            }
+
+            let whole: Vec<String> = plan
+                .whole_files(files, index)
+                .into_iter()
+                .flat_map(FileDiff::paths)
+                .map(|path| file_pattern(&path))
+                .collect();
+            if !whole.is_empty() {
+                let mut args = jj(&["restore", "--from", &change.commit_id, "--into", "@"]);
+                args.extend(whole);
+                self.execute(args).await?;
+            }
+            for &file in partial.iter().filter(|file| group.units.contains_key(file)) {
+                let content = plan.content_after(files, file, index, &bases[&file]);
+                let path = std::path::Path::new(&self.config.repo_path).join(&files[file].path);
+                std::fs::write(path, content).map_err(JJError::from)?;
+            }
+
+            // Reading the change ID also snapshots the files written above
+            let change_id = self
+                .execute(jj(&["log", "--no-graph", "-r", "@", "-T", "change_id"]))
+                .await?
+                .stdout
+                .trim()
+                .to_string();
+            created.push(SplitChange {
+                change_id,
+                description: group.description.clone(),
+            });
+        }
+
+        self.execute(jj(&["rebase", "-s", &change.change_id, "-d", "@"]))
+            .await?;
+        self.execute(jj(&[
+            "restore",
+            "--from",
+            &change.commit_id,
+            "--into",
+            &change.change_id,
+        ]))
+        .await?;
+        self.execute(jj(&["describe", "-r", &change.change_id, "-m", &last.description]))
+            .await?;
+        self.execute(jj(&["edit", working_copy])).await?;
+
+        created.push(SplitChange {
+            change_id: change.change_id.clone(),
+            description: last.description.clone(),
+        });
+        Ok(created)
+    }
+
+    /// Generate a commit message for the working-copy change with `context`
+    pub async fn generate_description_with(&self, context: &MessageContext) -> napi::Result<String> {
+        let args = vec![
+            "diff".to_string(),
+            "--git".to_string(),
+            "-r".to_string(),
+            "@".to_string(),
+        ];
+        let result = self.execute(args).await?;
+        Ok(generate_commit_message(&result.stdout, context))
+    }
+}
+
+/// Fileset matching exactly `path`, however it is spelled
+fn file_pattern(path: &str) -> String {
+    format!("file:{}", quote(path))
 }
 
 impl Default for JJWrapper {
@@ -1232,6 +1688,477 @@ impl Default for JJWrapper {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::operations::OperationType;
+    use std::path::Path;
+
+    /// Wrapper whose jj is a shell script, run in a fresh repository directory
+    ///
+    /// `script` is the body after the shebang; it sees the directory as `$dir`.
+    #[cfg(unix)]
+    fn fake_jj(script: &str) -> (tempfile::TempDir, JJWrapper) {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("jj");
+        std::fs::write(
+            &path,
+            format!("#!/bin/sh\ndir='{}'\n{}", dir.path().display(), script),
+        )
+        .unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let config = JJConfig::default()
+            .with_jj_path(path.to_string_lossy().into_owned())
+            .with_repo_path(dir.path().to_string_lossy().into_owned());
+        (dir, JJWrapper::with_config(config).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_execute_records_metrics() {
+        let wrapper = JJWrapper::with_config(JJConfig::default().with_jj_path("echo".into())).unwrap();
+        wrapper
+            .execute(vec!["abandon".into(), "-r".into(), "metrics-test".into()])
+            .await
+            .unwrap();
+
+        let wrapper = JJWrapper::with_config(JJConfig::default().with_jj_path("false".into())).unwrap();
+        assert!(wrapper.execute(vec!["squash".into()]).await.is_err());
+
+        let text = metrics::global().render();
+        assert!(text.contains(
+            "agentic_jujutsu_operations_total{operation_type=\"Abandon\",outcome=\"success\"}"
+        ));
+        assert!(text.contains(
+            "agentic_jujutsu_operations_total{operation_type=\"Squash\",outcome=\"COMMAND_FAILED\"}"
+        ));
+        assert!(text.contains("agentic_jujutsu_operation_duration_seconds_count{operation_type=\"Abandon\"}"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_generate_description_from_diff() {
+        let fixture = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/golden/commit_messages/python_fix.diff");
+        let (_dir, wrapper) =
+            fake_jj(&format!("[ \"$1\" = diff ] && cat '{}'\n", fixture.display()));
+        let context = wrapper
+            .message_context(Some("Fix crash on missing config".to_string()))
+            .unwrap();
+        let message = wrapper.generate_description_with(&context).await.unwrap();
+
+        assert!(message.starts_with("fix(app): update `load_config`\n"));
+        assert!(message.ends_with("Task: Fix crash on missing config\n"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_review_follows_rewritten_change() {
+        // A fake jj printing the change's IDs and diff from files
+        let (dir, wrapper) = fake_jj(
+            "case \"$1\" in\n  log) cat \"$dir/ids\" ;;\n  diff) cat \"$dir/diff\" ;;\nesac\n",
+        );
+        let rewrite = |commit_id: &str, lines: &[&str]| {
+            std::fs::write(dir.path().join("ids"), format!("kxqpmwyz {}\n", commit_id)).unwrap();
+            let body: String = lines.iter().map(|line| format!("+{}\n", line)).collect();
+            std::fs::write(
+                dir.path().join("diff"),
+                format!(
+                    "diff --git a/app.py b/app.py\nnew file mode 100644\n--- /dev/null\n+++ b/app.py\n@@ -0,0 +1,{} @@\n{}",
+                    lines.len(),
+                    body
+                ),
+            )
+            .unwrap();
+        };
+
+        rewrite("1111", &["import os", "def main():", "    run()"]);
+        wrapper.open_review("kx".into(), "coder".into()).await.unwrap();
+        wrapper
+            .add_review_comment("kx".into(), "reviewer".into(), "app.py".into(), 3, "handle errors".into())
+            .await
+            .unwrap();
+
+        rewrite("2222", &["import os", "import sys", "", "def main():", "    run()"]);
+        let review = wrapper.get_review("kx".into()).await.unwrap();
+        assert_eq!(review.change_id, "kxqpmwyz");
+        assert_eq!(review.commit_id, "2222");
+        assert_eq!(review.comments[0].line, 5);
+        assert!(!review.comments[0].outdated);
+        assert!(dir.path().join(".jj/agentic-jujutsu/reviews/kxqpmwyz.json").exists());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_auto_split_stacks_groups_under_original() {
+        // A fake jj recording its arguments one command per line; `-T
+        // change_id` queries get a fresh ID per call
+        let (dir, wrapper) = fake_jj(
+            "printf '%s' \"$*\" | tr '\\n' '~' >> \"$dir/commands\"\necho >> \"$dir/commands\"\ncase \"$1\" in\n  log) if [ \"$6\" = change_id ]; then echo \"c$(grep -c . \"$dir/commands\")\"; else echo 'orig 1111'; fi ;;\n  diff) cat \"$dir/diff\" ;;\n  file) cat \"$dir/base\" ;;\n  op) echo op1 ;;\nesac\n",
+        );
+        std::fs::write(
+            dir.path().join("diff"),
+            "diff --git a/src/lib.rs b/src/lib.rs\n--- a/src/lib.rs\n+++ b/src/lib.rs\n@@ -1,3 +1,3 @@\n fn a() {\n-    let x=1;\n+    let x = 1;\n }\n@@ -5,2 +5,3 @@\n fn b() {\n+    log();\n }\ndiff --git a/README.md b/README.md\n--- a/README.md\n+++ b/README.md\n@@ -1 +1,2 @@\n # Title\n+Usage notes.\n",
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("base"), "fn a() {\n    let x=1;\n}\n\nfn b() {\n}\n").unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+
+        let changes = wrapper.auto_split("orig".into()).await.unwrap();
+
+        assert_eq!(changes.len(), 3);
+        assert!(changes[0].description.starts_with("style: format lib.rs"));
+        assert_eq!(changes[2].change_id, "orig");
+        assert!(changes[2].description.starts_with("docs"));
+
+        // The feature group leaves lib.rs with both hunks applied
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("src/lib.rs")).unwrap(),
+            "fn a() {\n    let x = 1;\n}\n\nfn b() {\n    log();\n}\n"
+        );
+        let commands = std::fs::read_to_string(dir.path().join("commands")).unwrap();
+        let commands: Vec<&str> = commands.lines().filter(|c| !c.starts_with("log")).collect();
+        assert_eq!(commands[1], "op log --no-graph -n 1 -T id");
+        assert!(commands[2].starts_with("new 1111- -m style: format lib.rs"));
+        assert_eq!(commands[3], "file show -r @ file:\"src/lib.rs\"");
+        assert!(commands[4].starts_with("new -m "));
+        assert_eq!(commands[5..7], ["rebase -s orig -d @", "restore --from 1111 --into orig"]);
+        assert!(commands[7].starts_with("describe -r orig -m docs"));
+        assert_eq!(commands[8], "edit c3");
+        assert!(!commands.iter().any(|c| c.starts_with("op restore")));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_failed_split_restores_operation() {
+        // A fake jj whose rebase fails after the new changes were created
+        let (dir, wrapper) = fake_jj(
+            "echo \"$*\" >> \"$dir/commands\"\ncase \"$1\" in\n  log) if [ \"$6\" = change_id ]; then echo c1; else echo 'orig 1111'; fi ;;\n  diff) cat \"$dir/diff\" ;;\n  op) echo op1 ;;\n  rebase) echo 'Error: rebase failed' >&2; exit 1 ;;\nesac\n",
+        );
+        std::fs::write(
+            dir.path().join("diff"),
+            "diff --git a/my \"file\".rs b/my \"file\".rs\n--- a/my \"file\".rs\n+++ b/my \"file\".rs\n@@ -1 +1 @@\n-a\n+b\ndiff --git a/README.md b/README.md\n--- a/README.md\n+++ b/README.md\n@@ -1 +1,2 @@\n # Title\n+Usage notes.\n",
+        )
+        .unwrap();
+
+        let groups = vec![
+            SplitGroup {
+                description: "feat: code".to_string(),
+                paths: Some(vec!["my \"file\".rs".to_string()]),
+                hunks: None,
+            },
+            SplitGroup {
+                description: "docs: usage".to_string(),
+                paths: Some(vec!["README.md".to_string()]),
+                hunks: None,
+            },
+        ];
+        assert!(wrapper.split_change("orig".into(), groups).await.is_err());
+
+        let commands = std::fs::read_to_string(dir.path().join("commands")).unwrap();
+        assert!(commands.contains("restore --from 1111 --into @ file:\"my \\\"file\\\".rs\"\n"));
+        assert!(commands.ends_with("rebase -s orig -d @\nop restore op1\n"));
+    }
+
+    /// Wrapper protecting `trunk()`, `main` and `release/*`
+    ///
+    /// Its fake jj finds every policy query non-empty unless it targets
+    /// `feat` or `@`; `@` counts as protected once `$dir/protect-at` exists,
+    /// and other operations differ in protected commits until
+    /// `$dir/history-unchanged` exists. `log -r X` prints change `X` and
+    /// `diff` prints `$dir/diff`; other commands are recorded in `$dir/ran`.
+    #[cfg(unix)]
+    fn protected_wrapper(approvers: Vec<String>) -> (tempfile::TempDir, JJWrapper) {
+        let (dir, wrapper) = fake_jj(
+            "if [ \"$1 $3\" = 'log --ignore-working-copy' ]; then\n\
+             target=$(printf '%s' \"$7\" | sed 's/) & (.*//')\n\
+             case \"$target\" in\n\
+             *at_operation*) [ -f \"$dir/history-unchanged\" ] || echo 1111 ;;\n\
+             *feat*) ;;\n\
+             '(present(@)') if [ -f \"$dir/protect-at\" ]; then echo 1111; fi ;;\n\
+             *) echo 1111 ;;\n\
+             esac\n\
+             elif [ \"$1\" = log ]; then\n  echo \"$4 1111\"\n\
+             elif [ \"$1\" = diff ]; then\n  cat \"$dir/diff\"\n\
+             else\n  echo \"$*\" >> \"$dir/ran\"\nfi\n",
+        );
+        let config = wrapper
+            .get_config()
+            .with_protected_revsets(vec!["trunk()".to_string()])
+            .with_protected_bookmarks(vec!["main".to_string(), "release/*".to_string()])
+            .with_override_approvers(approvers);
+        (dir, JJWrapper::with_config(config).unwrap())
+    }
+
+    fn assert_blocked<T: std::fmt::Debug>(result: napi::Result<T>, code: &str) {
+        let err = result.expect_err("command should be rejected");
+        assert_eq!(crate::error::error_code(err.reason.clone()).as_deref(), Some(code), "{}", err.reason);
+    }
+
+    fn approve(args: &[&str], keypair: &crate::crypto::SigningKeypair) -> PolicyOverride {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        let (approved_by, reason) = ("release-manager".to_string(), "Drop a broken release commit".to_string());
+        let message = crate::policy::override_message(args, approved_by.clone(), reason.clone());
+        PolicyOverride {
+            approved_by,
+            reason,
+            public_key: keypair.public_key.clone(),
+            signature: crate::crypto::sign_message_internal(&message, &keypair.secret_key).unwrap(),
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_protected_targets_rejected_before_jj_runs() {
+        let (dir, wrapper) = protected_wrapper(Vec::new());
+
+        assert_blocked(wrapper.abandon("main".to_string()).await, "PROTECTED_REVISION");
+        assert_blocked(wrapper.edit("3f2a1b9c".to_string()).await, "PROTECTED_REVISION");
+        assert_blocked(wrapper.squash(Some("main-".to_string()), None).await, "PROTECTED_REVISION");
+        assert_blocked(wrapper.squash(None, Some("trunk()".to_string())).await, "PROTECTED_REVISION");
+        assert_blocked(
+            wrapper.rebase("main".to_string(), "@".to_string()).await,
+            "PROTECTED_REVISION",
+        );
+        assert_blocked(wrapper.branch_delete("main".to_string()).await, "PROTECTED_BOOKMARK");
+        assert_blocked(
+            wrapper
+                .execute(vec!["describe".into(), "-r".into(), "::@".into(), "-m".into(), "x".into()])
+                .await,
+            "PROTECTED_REVISION",
+        );
+        assert_blocked(
+            wrapper
+                .execute(vec!["bookmark".into(), "set".into(), "main".into(), "-r".into(), "@".into()])
+                .await,
+            "PROTECTED_BOOKMARK",
+        );
+        assert!(!dir.path().join("ran").exists());
+        assert!(wrapper.get_operations(100).unwrap().is_empty());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_default_targets_are_checked() {
+        let (dir, wrapper) = protected_wrapper(Vec::new());
+        let execute = |args: &[&str]| wrapper.execute(args.iter().map(|arg| arg.to_string()).collect());
+
+        // `@-` is protected, `@` is not yet
+        assert_blocked(wrapper.squash(None, None).await, "PROTECTED_REVISION");
+        assert_blocked(wrapper.squash(Some("@".to_string()), None).await, "PROTECTED_REVISION");
+        assert!(wrapper.squash(Some("feat".to_string()), None).await.is_ok());
+        assert!(wrapper.describe("msg".to_string()).await.is_ok());
+        assert!(wrapper.restore(vec!["src/".to_string()]).await.is_ok());
+
+        std::fs::write(dir.path().join("protect-at"), "").unwrap();
+        assert_blocked(wrapper.describe("msg".to_string()).await, "PROTECTED_REVISION");
+        assert_blocked(wrapper.restore(vec!["src/".to_string()]).await, "PROTECTED_REVISION");
+        assert_blocked(wrapper.resolve(None).await, "PROTECTED_REVISION");
+        assert_blocked(execute(&["abandon"]).await, "PROTECTED_REVISION");
+        assert_blocked(execute(&["rebase", "-d", "feat"]).await, "PROTECTED_REVISION");
+        assert_blocked(execute(&["commit", "-m", "msg"]).await, "PROTECTED_REVISION");
+
+        // A new change on top of a protected one rewrites nothing
+        assert!(wrapper.new_commit(Some("msg".to_string())).await.is_ok());
+        let ran = std::fs::read_to_string(dir.path().join("ran")).unwrap();
+        assert_eq!(ran.lines().collect::<Vec<_>>(), [
+            "squash -r feat",
+            "describe -m msg",
+            "restore src/",
+            "new -m msg",
+        ]);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_bookmark_moves_and_undo_are_checked() {
+        let (dir, wrapper) = protected_wrapper(Vec::new());
+        let execute = |args: &[&str]| wrapper.execute(args.iter().map(|arg| arg.to_string()).collect());
+
+        assert_blocked(
+            execute(&["bookmark", "move", "--from", "main", "--to", "@"]).await,
+            "PROTECTED_BOOKMARK",
+        );
+        assert_blocked(wrapper.undo().await, "PROTECTED_REVISION");
+        assert_blocked(execute(&["redo"]).await, "PROTECTED_REVISION");
+        assert_blocked(execute(&["op", "restore", "abc123"]).await, "PROTECTED_REVISION");
+        assert_blocked(execute(&["op", "revert", "abc123"]).await, "PROTECTED_REVISION");
+        assert!(!dir.path().join("ran").exists());
+
+        // Operations that left the protected commits alone can be undone
+        std::fs::write(dir.path().join("history-unchanged"), "").unwrap();
+        assert!(wrapper.undo().await.is_ok());
+        assert!(execute(&["bookmark", "move", "--from", "feat", "--to", "@"]).await.is_ok());
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("ran")).unwrap(),
+            "undo\nbookmark move --from feat --to @\n"
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_split_of_protected_change_is_rejected_before_any_step() {
+        let (dir, wrapper) = protected_wrapper(Vec::new());
+        std::fs::write(
+            dir.path().join("diff"),
+            "diff --git a/a.rs b/a.rs\n--- a/a.rs\n+++ b/a.rs\n@@ -1 +1 @@\n-a\n+b\ndiff --git a/b.rs b/b.rs\n--- a/b.rs\n+++ b/b.rs\n@@ -1 +1 @@\n-a\n+b\n",
+        )
+        .unwrap();
+        let groups = vec![
+            SplitGroup {
+                description: "feat: a".to_string(),
+                paths: Some(vec!["a.rs".to_string()]),
+                hunks: None,
+            },
+            SplitGroup {
+                description: "feat: b".to_string(),
+                paths: Some(vec!["b.rs".to_string()]),
+                hunks: None,
+            },
+        ];
+
+        assert_blocked(wrapper.split_change("main".to_string(), groups).await, "PROTECTED_REVISION");
+        assert!(!dir.path().join("ran").exists());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_unprotected_targets_reach_jj() {
+        let (dir, wrapper) = protected_wrapper(Vec::new());
+
+        assert!(wrapper.rebase("feat".to_string(), "main".to_string()).await.is_ok());
+        assert!(wrapper.abandon("feat".to_string()).await.is_ok());
+        assert!(wrapper.branch_create("feat".to_string(), Some("main".to_string())).await.is_ok());
+        assert!(wrapper.new_commit(None).await.is_ok());
+        assert!(wrapper.describe("msg".to_string()).await.is_ok());
+        assert_eq!(wrapper.get_operations(100).unwrap().len(), 5);
+        let ran = std::fs::read_to_string(dir.path().join("ran")).unwrap();
+        assert_eq!(ran.lines().count(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_policy_queries_fail_closed() {
+        let config = JJConfig::default()
+            .with_jj_path("/nonexistent/jj".to_string())
+            .with_protected_bookmarks(vec!["main".to_string()]);
+        let wrapper = JJWrapper::with_config(config).unwrap();
+        assert_blocked(wrapper.abandon("feat".to_string()).await, "JJ_NOT_FOUND");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_override_is_signed_and_recorded() {
+        let keypair = crate::crypto::generate_signing_keypair();
+        let (_dir, wrapper) = protected_wrapper(vec![keypair.public_key.clone()]);
+        let approval = approve(&["abandon", "main"], &keypair);
+
+        wrapper
+            .execute_with_override(vec!["abandon".into(), "main".into()], approval.clone())
+            .await
+            .unwrap();
+
+        let operations = wrapper.get_operations(10).unwrap();
+        let (executed, record) = (&operations[0], &operations[1]);
+        assert_eq!(record.operation_type, "PolicyOverride");
+        assert_eq!(record.get_metadata("violation").as_deref(), Some("PROTECTED_REVISION"));
+        assert_eq!(record.get_metadata("approved_by").as_deref(), Some("release-manager"));
+        assert_eq!(record.get_metadata("approval_signature"), Some(approval.signature.clone()));
+        assert_eq!(executed.operation_type, "Abandon");
+        assert_eq!(executed.get_metadata("policy_override"), Some(record.id.clone()));
+
+        // Commands within the policy are not recorded as overrides
+        wrapper
+            .execute_with_override(vec!["abandon".into(), "feat".into()], approval.clone())
+            .await
+            .unwrap();
+        assert_eq!(wrapper.get_operations(10).unwrap().len(), 3);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_override_requires_configured_approver() {
+        let keypair = crate::crypto::generate_signing_keypair();
+        let approval = approve(&["abandon", "main"], &keypair);
+        let abandon_main = || vec!["abandon".to_string(), "main".to_string()];
+
+        // No approvers configured
+        let (_dir, wrapper) = protected_wrapper(Vec::new());
+        assert!(wrapper.execute_with_override(abandon_main(), approval.clone()).await.is_err());
+        assert!(wrapper.get_operations(10).unwrap().is_empty());
+
+        // Someone else's key
+        let (_dir, wrapper) = protected_wrapper(vec!["someone-else".to_string()]);
+        assert!(wrapper.execute_with_override(abandon_main(), approval).await.is_err());
+        assert!(wrapper.get_operations(10).unwrap().is_empty());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_override_rejects_forged_signatures() {
+        let keypair = crate::crypto::generate_signing_keypair();
+        let (_dir, wrapper) = protected_wrapper(vec![keypair.public_key.clone()]);
+        let abandon = |target: &str| vec!["abandon".to_string(), target.to_string()];
+        let approval = approve(&["abandon", "main"], &keypair);
+
+        let forged = PolicyOverride {
+            signature: "ab".repeat(64),
+            ..approval.clone()
+        };
+        let reworded = PolicyOverride {
+            reason: "Anything goes".to_string(),
+            ..approval.clone()
+        };
+        for (args, approval) in [
+            (abandon("main"), forged),
+            (abandon("main"), reworded),
+            (abandon("release/1"), approval),
+        ] {
+            assert_blocked(wrapper.execute_with_override(args, approval).await, "CRYPTO_ERROR");
+        }
+        assert!(wrapper.get_operations(10).unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    #[ignore = "requires jj on PATH"]
+    async fn test_protection_evaluates_revsets_with_jj() {
+        let dir = tempfile::tempdir().unwrap();
+        let jj = |args: &[&str]| {
+            let output = std::process::Command::new("jj")
+                .args(args)
+                .current_dir(dir.path())
+                .output()
+                .unwrap();
+            assert!(output.status.success(), "jj {:?}: {}", args, String::from_utf8_lossy(&output.stderr));
+            String::from_utf8(output.stdout).unwrap()
+        };
+        jj(&["git", "init"]);
+        jj(&["config", "set", "--repo", "user.name", "Agent"]);
+        jj(&["config", "set", "--repo", "user.email", "agent@example.com"]);
+        jj(&["describe", "-m", "base"]);
+        jj(&["bookmark", "create", "main", "-r", "@"]);
+        jj(&["new", "-m", "work"]);
+        let main_id = jj(&["log", "--no-graph", "-r", "main", "-T", "commit_id"]);
+
+        let config = JJConfig::default()
+            .with_jj_path("jj".to_string())
+            .with_repo_path(dir.path().to_string_lossy().into_owned())
+            .with_protected_bookmarks(vec!["main".to_string()]);
+        let wrapper = JJWrapper::with_config(config).unwrap();
+
+        // A commit ID and an ancestor range of main
+        assert_blocked(wrapper.abandon(main_id).await, "PROTECTED_REVISION");
+        assert_blocked(
+            wrapper
+                .execute(vec!["describe".into(), "-r".into(), "::@".into(), "-m".into(), "x".into()])
+                .await,
+            "PROTECTED_REVISION",
+        );
+
+        // A range that starts at main does not contain it
+        wrapper
+            .execute(vec!["rebase".into(), "-r".into(), "main..@".into(), "-d".into(), "main".into()])
+            .await
+            .unwrap();
+    }
 
     #[test]
     fn test_wrapper_creation() {
@@ -1246,15 +2173,15 @@ mod tests {
     #[test]
     fn test_detect_operation_type() {
         assert_eq!(
-            JJWrapper::detect_operation_type(&["describe", "-m", "test"]),
+            command_parser::detect_operation_type(&["describe", "-m", "test"]),
             OperationType::Describe
         );
         assert_eq!(
-            JJWrapper::detect_operation_type(&["new"]),
+            command_parser::detect_operation_type(&["new"]),
             OperationType::New
         );
         assert_eq!(
-            JJWrapper::detect_operation_type(&["git", "fetch"]),
+            command_parser::detect_operation_type(&["git", "fetch"]),
             OperationType::GitFetch
         );
     }
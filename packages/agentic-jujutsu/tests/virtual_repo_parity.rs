@@ -0,0 +1,217 @@
+//! Parity of the in-memory repository with jj
+//!
+//! Each scenario runs against a [`VirtualRepo`] and against a `jj git init`
+//! repository under jj's test environment (fixed user, timestamp and
+//! randomness seed), and compares exit codes, stdout and stderr. Change and
+//! commit IDs differ between the two, so both sides replace them with their
+//! position in `jj log -r 'all()'` first.
+
+use agentic_jujutsu::VirtualRepo;
+use std::process::Command;
+
+/// Exit code, stdout and stderr of one command
+type Output = (i32, String, String);
+
+struct Parity {
+    virtual_repo: VirtualRepo,
+    dir: tempfile::TempDir,
+    config: tempfile::TempDir,
+}
+
+impl Parity {
+    fn new() -> Self {
+        let config = tempfile::tempdir().unwrap();
+        std::fs::write(
+            config.path().join("config.toml"),
+            "[ui]\ncolor = \"never\"\npaginate = \"never\"\n",
+        )
+        .unwrap();
+        let parity = Self {
+            virtual_repo: VirtualRepo::new(),
+            dir: tempfile::tempdir().unwrap(),
+            config,
+        };
+        let (code, _, stderr) = parity.jj(&["git", "init"]);
+        assert_eq!(code, 0, "jj git init: {}", stderr);
+        parity
+    }
+
+    fn jj(&self, args: &[&str]) -> Output {
+        let output = Command::new("jj")
+            .args(args)
+            .current_dir(self.dir.path())
+            .env("JJ_CONFIG", self.config.path().join("config.toml"))
+            .env("JJ_USER", "Test User")
+            .env("JJ_EMAIL", "test.user@example.com")
+            .env("JJ_TIMESTAMP", "2001-02-03T04:05:06+00:00")
+            .env("JJ_OP_TIMESTAMP", "2001-02-03T04:05:06+00:00")
+            .env("JJ_RANDOMNESS_SEED", "0")
+            .env("JJ_OP_HOSTNAME", "host.example.com")
+            .env("JJ_OP_USERNAME", "test-username")
+            .output()
+            .expect("jj should be on PATH");
+        (
+            output.status.code().unwrap_or(-1),
+            String::from_utf8_lossy(&output.stdout).into_owned(),
+            String::from_utf8_lossy(&output.stderr).into_owned(),
+        )
+    }
+
+    fn write_file(&mut self, path: &str, content: &str) {
+        self.virtual_repo.write_file(path, content);
+        let path = self.dir.path().join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    fn remove_file(&mut self, path: &str) {
+        self.virtual_repo.remove_file(path);
+        std::fs::remove_file(self.dir.path().join(path)).unwrap();
+    }
+
+    /// Run `args` on both sides and assert the normalized output matches
+    fn run(&mut self, args: &[&str]) -> Output {
+        let real = self.jj(args);
+        let simulated = match self.virtual_repo.execute(args) {
+            Ok(result) => (result.exit_code, result.stdout, result.stderr),
+            Err(e) => (
+                e.exit_code().unwrap_or(1),
+                String::new(),
+                e.stderr().unwrap_or_default().to_string(),
+            ),
+        };
+
+        let real = normalize(real, &self.real_ids());
+        let simulated = normalize(simulated, &self.virtual_ids());
+        assert_eq!(simulated, real, "jj {}", args.join(" "));
+        real
+    }
+
+    /// (change ID, commit ID) of every visible commit, newest first
+    fn real_ids(&self) -> Vec<(String, String)> {
+        let (_, stdout, _) = self.jj(&[
+            "--ignore-working-copy",
+            "log",
+            "--no-graph",
+            "-r",
+            "all()",
+            "-T",
+            "change_id ++ \" \" ++ commit_id ++ \"\\n\"",
+        ]);
+        stdout
+            .lines()
+            .filter_map(|line| line.split_once(' '))
+            .map(|(change, commit)| (change.to_string(), commit.to_string()))
+            .collect()
+    }
+
+    fn virtual_ids(&self) -> Vec<(String, String)> {
+        self.virtual_repo
+            .log_commits(None)
+            .into_iter()
+            .map(|commit| (commit.change_id, commit.id))
+            .collect()
+    }
+}
+
+/// Replace the short change and commit IDs with `<change N>`/`<commit N>`
+fn normalize((code, stdout, stderr): Output, ids: &[(String, String)]) -> Output {
+    let replace = |mut text: String| {
+        for (index, (change, commit)) in ids.iter().enumerate() {
+            text = text.replace(short(change), &format!("<change {}>", index));
+            text = text.replace(short(commit), &format!("<commit {}>", index));
+        }
+        text
+    };
+    (code, replace(stdout), replace(stderr))
+}
+
+fn short(id: &str) -> &str {
+    &id[..id.len().min(8)]
+}
+
+#[test]
+#[ignore = "requires jj on PATH"]
+fn test_fresh_repository() {
+    let mut parity = Parity::new();
+    parity.run(&["status"]);
+    parity.run(&["log"]);
+    parity.run(&["log", "--no-graph"]);
+}
+
+#[test]
+#[ignore = "requires jj on PATH"]
+fn test_snapshot_describe_and_new() {
+    let mut parity = Parity::new();
+    parity.write_file("a.txt", "one\n");
+    parity.run(&["st"]);
+    parity.run(&["describe", "-m", "Add a"]);
+    parity.run(&["new", "-m", "next"]);
+    parity.run(&["describe", "-m", "next"]);
+    parity.run(&["log"]);
+}
+
+#[test]
+#[ignore = "requires jj on PATH"]
+fn test_describe_ancestor_rebases_descendants() {
+    let mut parity = Parity::new();
+    parity.write_file("a.txt", "one\n");
+    parity.run(&["new"]);
+    parity.write_file("b.txt", "two\n");
+    parity.run(&["new"]);
+    parity.run(&["describe", "-r", "@--", "-m", "first"]);
+    parity.run(&["describe", "-r", "root()", "-m", "x"]);
+    parity.run(&["log"]);
+}
+
+#[test]
+#[ignore = "requires jj on PATH"]
+fn test_diffs() {
+    let mut parity = Parity::new();
+    parity.write_file("a.txt", "1\n2\n3\n4\n5\n6\n7\n8\n9\n");
+    parity.write_file("gone.txt", "bye");
+    parity.run(&["new"]);
+    parity.write_file("a.txt", "1\n2\n3\n4\nfive\n6\n7\n8\n9\n");
+    parity.remove_file("gone.txt");
+    parity.write_file("new.txt", "hi\n");
+
+    parity.run(&["diff", "--git"]);
+    parity.run(&["diff", "--summary"]);
+    parity.run(&["diff", "new.txt", "a.txt"]);
+    parity.run(&["status"]);
+}
+
+#[test]
+#[ignore = "requires jj on PATH"]
+fn test_bookmarks() {
+    let mut parity = Parity::new();
+    parity.write_file("a.txt", "a\n");
+    parity.run(&["describe", "-m", "base"]);
+    parity.run(&["bookmark", "create", "main"]);
+    parity.run(&["new"]);
+    parity.run(&["bookmark", "create", "main"]);
+
+    parity.write_file("b.txt", "b\n");
+    parity.run(&["bookmark", "set", "main", "-r", "@"]);
+    parity.run(&["bookmark", "set", "main", "-r", "@-"]);
+    parity.run(&["bookmark", "set", "main", "-r", "@-", "--allow-backwards"]);
+    parity.run(&["bookmark", "list"]);
+    parity.run(&["log", "--no-graph", "-r", "::main"]);
+
+    parity.run(&["bookmark", "delete", "main"]);
+    parity.run(&["bookmark", "delete", "main"]);
+    parity.run(&["bookmark", "list"]);
+}
+
+#[test]
+#[ignore = "requires jj on PATH"]
+fn test_revisions() {
+    let mut parity = Parity::new();
+    parity.run(&["describe", "-m", "one"]);
+    parity.run(&["new", "-m", "two"]);
+    parity.run(&["bookmark", "create", "feature", "-r", "@-"]);
+    parity.run(&["log", "-r", "nope"]);
+    parity.run(&["log", "--no-graph", "-r", "::@-"]);
+    parity.run(&["log", "-n", "1"]);
+    parity.run(&["log", "-r", "feature+"]);
+}